@@ -0,0 +1,31 @@
+//! Embeds the git commit and the kubernetes version selected for k8s-openapi
+//! into the binary for the `version` command.
+
+use std::process::Command;
+
+fn main() {
+    let git_sha = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map_or_else(|| "unknown".to_string(), |sha| sha.trim().to_string());
+
+    println!("cargo:rustc-env=GIT_SHA={git_sha}");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+
+    // k8s-openapi sets the selected version as `0x00_MM_NN_00`.
+    let k8s_openapi_version = std::env::vars()
+        .find_map(|(key, value)| {
+            (key.starts_with("DEP_K8S_OPENAPI_") && key.ends_with("_VERSION")).then_some(value)
+        })
+        .and_then(|value| value.parse::<u32>().ok())
+        .map_or_else(
+            || "unknown".to_string(),
+            |version| format!("{}.{}", version >> 16 & 0xff, version >> 8 & 0xff),
+        );
+
+    println!("cargo:rustc-env=K8S_OPENAPI_VERSION={k8s_openapi_version}");
+}
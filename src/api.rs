@@ -1,12 +1,49 @@
+//! Access to the kubernetes api and the resource types used by the commands.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    future::Future,
+    num::NonZeroUsize,
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+
 use bytesize::ByteSize;
 use eyre::eyre;
 use eyre::{Context, Result};
 use k8s_openapi::{
-    api::{apps::v1::ReplicaSet, batch::v1::Job, core::v1::Pod},
+    api::{
+        apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet},
+        autoscaling::{v1::CrossVersionObjectReference, v2::HorizontalPodAutoscaler},
+        batch::v1::{CronJob, Job},
+        core::v1::{
+            ConfigMap, Event, Namespace, Node, PersistentVolumeClaim, Pod, Secret, Service,
+            ServiceAccount,
+        },
+        discovery::v1::EndpointSlice,
+        networking::v1::Ingress,
+        rbac::v1::{ClusterRole, ClusterRoleBinding, RoleBinding},
+    },
     apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::OwnerReference},
+    chrono::{self, DateTime, Utc},
+};
+use kube::{
+    api::{ApiResource, DynamicObject, ListParams},
+    config::KubeConfigOptions,
+    core::{GroupVersion, ObjectMeta},
+    Api, Client, Config,
+};
+use log::{info, warn};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject, StringValidation, SubschemaValidation},
+    JsonSchema,
 };
-use kube::{api::ListParams, core::ObjectMeta, Api, Client};
 use serde::Serialize;
+use tokio::sync::Semaphore;
+
+use crate::document::NamespaceError;
 
 #[derive(Debug, thiserror::Error)]
 enum ApiError {
@@ -15,40 +52,256 @@ enum ApiError {
 
     #[error("failed to list pods: {0}")]
     ListPods(kube::Error),
+
+    #[error("failed to list nodes: {0}")]
+    ListNodes(kube::Error),
+
+    #[error("failed to list namespaces: {0}")]
+    ListNamespaces(kube::Error),
+
+    #[error("failed to list statefulsets: {0}")]
+    ListStatefulSets(kube::Error),
+
+    #[error("failed to list daemonsets: {0}")]
+    ListDaemonSets(kube::Error),
+
+    #[error("failed to list jobs: {0}")]
+    ListJobs(kube::Error),
+
+    #[error("failed to list ingresses: {0}")]
+    ListIngresses(kube::Error),
+
+    #[error("failed to list cronjobs: {0}")]
+    ListCronJobs(kube::Error),
+
+    #[error("failed to list services: {0}")]
+    ListServices(kube::Error),
+
+    #[error("failed to list secrets: {0}")]
+    ListSecrets(kube::Error),
+
+    #[error("failed to list service accounts: {0}")]
+    ListServiceAccounts(kube::Error),
+
+    #[error("failed to list persistent volume claims: {0}")]
+    ListPersistentVolumeClaims(kube::Error),
+
+    #[error("failed to list endpoint slices: {0}")]
+    ListEndpointSlices(kube::Error),
+
+    #[error("failed to list config maps: {0}")]
+    ListConfigMaps(kube::Error),
+
+    #[error("failed to list replica sets: {0}")]
+    ListReplicaSets(kube::Error),
+
+    #[error("failed to list deployments: {0}")]
+    ListDeployments(kube::Error),
+
+    #[error("failed to list horizontal pod autoscalers: {0}")]
+    ListHorizontalPodAutoscalers(kube::Error),
+
+    #[error("failed to list vertical pod autoscalers: {0}")]
+    ListVerticalPodAutoscalers(kube::Error),
+
+    #[error("failed to list cluster role bindings: {0}")]
+    ListClusterRoleBindings(kube::Error),
+
+    #[error("failed to list cluster roles: {0}")]
+    ListClusterRoles(kube::Error),
+
+    #[error("failed to list role bindings: {0}")]
+    ListRoleBindings(kube::Error),
+
+    #[error("failed to list events: {0}")]
+    ListEvents(kube::Error),
+
+    #[error("failed to get api server version: {0}")]
+    ServerVersion(kube::Error),
+
+    #[error("failed to list api groups: {0}")]
+    ListApiGroups(kube::Error),
 }
 
+/// Amount of memory in bytes.
 #[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Default)]
-pub(crate) struct Memory(u64);
+pub struct Memory(u64);
 
+/// How amounts of [`Memory`] are rendered in the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MemoryUnits {
+    /// Binary units like `GiB`.
+    #[default]
+    Iec,
+
+    /// Decimal units like `GB`.
+    Si,
+
+    /// Plain number of bytes.
+    Bytes,
+}
+
+impl MemoryUnits {
+    /// Name of the units as used on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Iec => "iec",
+            Self::Si => "si",
+            Self::Bytes => "bytes",
+        }
+    }
+}
+
+impl fmt::Display for MemoryUnits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MemoryUnits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Self::Iec, Self::Si, Self::Bytes]
+            .into_iter()
+            .find(|units| units.as_str() == s)
+            .ok_or_else(|| format!("invalid memory units {s}, expected one of iec, si, bytes"))
+    }
+}
+
+static MEMORY_UNITS: OnceLock<MemoryUnits> = OnceLock::new();
+
+/// Set the units memory is rendered in. Has to be called before the first
+/// report is serialized, otherwise [`MemoryUnits::Iec`] is used.
+pub fn set_memory_units(units: MemoryUnits) -> Result<()> {
+    MEMORY_UNITS
+        .set(units)
+        .map_err(|_| eyre!("memory units are already set"))
+}
+
+/// Amount of cpu in millicores.
 #[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Default)]
-pub(crate) struct Cpu(u64);
+pub struct Cpu(u64);
+
+/// How amounts of [`Cpu`] are rendered in the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CpuUnits {
+    /// Millicores like `1500m`.
+    #[default]
+    Millicores,
+
+    /// Cores rounded to two decimals like `1.5`.
+    Cores,
+}
+
+impl CpuUnits {
+    /// Name of the units as used on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Millicores => "millicores",
+            Self::Cores => "cores",
+        }
+    }
+}
+
+impl fmt::Display for CpuUnits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
-#[derive(serde::Deserialize, Clone, Debug)]
-pub(crate) struct PodMetricsContainer {
-    pub(crate) name: String,
-    pub(crate) usage: PodMetricsContainerUsage,
+impl FromStr for CpuUnits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Self::Millicores, Self::Cores]
+            .into_iter()
+            .find(|units| units.as_str() == s)
+            .ok_or_else(|| format!("invalid cpu units {s}, expected one of millicores, cores"))
+    }
+}
+
+static CPU_UNITS: OnceLock<CpuUnits> = OnceLock::new();
+
+/// Set the units cpu is rendered in. Has to be called before the first report
+/// is serialized, otherwise [`CpuUnits::Millicores`] is used.
+pub fn set_cpu_units(units: CpuUnits) -> Result<()> {
+    CPU_UNITS
+        .set(units)
+        .map_err(|_| eyre!("cpu units are already set"))
+}
+
+/// Resource usage of a single container from the metrics api.
+#[derive(serde::Deserialize, Serialize, Clone, Debug)]
+pub struct PodMetricsContainer {
+    /// Name of the container.
+    pub name: String,
+    /// Current resource usage of the container.
+    pub usage: PodMetricsContainerUsage,
 }
 
-#[derive(serde::Deserialize, Clone, Debug)]
-pub(crate) struct PodMetricsContainerUsage {
-    pub(crate) cpu: Quantity,
-    pub(crate) memory: Quantity,
+/// Cpu and memory usage of a container.
+#[derive(serde::Deserialize, Serialize, Clone, Debug)]
+pub struct PodMetricsContainerUsage {
+    /// Used cpu.
+    pub cpu: Quantity,
+    /// Used memory.
+    pub memory: Quantity,
 }
 
-#[derive(serde::Deserialize, Clone, Debug)]
-pub(crate) struct PodMetrics {
-    pub(crate) metadata: ObjectMeta,
+/// Resource usage of a pod from the `metrics.k8s.io` api.
+#[derive(serde::Deserialize, Serialize, Clone, Debug)]
+pub struct PodMetrics {
+    /// Metadata of the pod the metrics belong to.
+    pub metadata: ObjectMeta,
+    /// Time the metrics were collected.
     #[allow(unused)]
-    pub(crate) timestamp: String,
+    pub timestamp: String,
+    /// Time window the metrics were collected over.
     #[allow(unused)]
-    pub(crate) window: String,
-    pub(crate) containers: Vec<PodMetricsContainer>,
+    pub window: String,
+    /// Resource usage of the containers of the pod.
+    pub containers: Vec<PodMetricsContainer>,
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, Clone, Default)]
-pub(crate) struct Owner {
-    pub(crate) name: String,
-    pub(crate) kind: String,
+/// Object that controls a pod.
+#[derive(
+    Debug,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Serialize,
+    serde::Deserialize,
+    JsonSchema,
+    Clone,
+    Default,
+)]
+pub struct Owner {
+    /// Name of the owner.
+    pub name: String,
+    /// Kind of the owner, for example `Deployment`.
+    pub kind: String,
+    /// Uid of the owner. Tells apart owners that were deleted and recreated
+    /// with the same name.
+    pub uid: String,
+    /// Namespace of the owned object. Owners live in the namespace of the
+    /// objects they own unless they are cluster scoped like the `Node` of a
+    /// static pod.
+    pub namespace: String,
+}
+
+impl Owner {
+    /// Owner from the owner reference of an object in the given namespace.
+    pub fn new(owner_reference: &OwnerReference, namespace: &str) -> Self {
+        Self {
+            name: owner_reference.name.clone(),
+            kind: owner_reference.kind.clone(),
+            uid: owner_reference.uid.clone(),
+            namespace: namespace.to_string(),
+        }
+    }
 }
 
 impl k8s_openapi::Resource for PodMetrics {
@@ -73,33 +326,884 @@ impl k8s_openapi::Metadata for PodMetrics {
     }
 }
 
-pub(crate) async fn get_pods(namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>> {
-    let client = Client::try_default()
+/// Vertical pod autoscaler from the `autoscaling.k8s.io` api with the
+/// resources it recommends for the containers of its target.
+#[derive(serde::Deserialize, Serialize, Clone, Debug, Default)]
+pub struct VerticalPodAutoscaler {
+    /// Metadata of the autoscaler.
+    pub metadata: ObjectMeta,
+    /// Workload the autoscaler targets.
+    #[serde(default)]
+    pub spec: VerticalPodAutoscalerSpec,
+    /// Current recommendation, missing until the recommender ran.
+    pub status: Option<VerticalPodAutoscalerStatus>,
+}
+
+/// Spec of a vertical pod autoscaler.
+#[derive(serde::Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VerticalPodAutoscalerSpec {
+    /// Workload whose pods are autoscaled, for example a `Deployment`.
+    pub target_ref: Option<CrossVersionObjectReference>,
+}
+
+/// Status of a vertical pod autoscaler.
+#[derive(serde::Deserialize, Serialize, Clone, Debug, Default)]
+pub struct VerticalPodAutoscalerStatus {
+    /// Recommended resources of the containers.
+    pub recommendation: Option<RecommendedPodResources>,
+}
+
+/// Recommended resources of the containers of a pod.
+#[derive(serde::Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendedPodResources {
+    /// Recommendation per container.
+    #[serde(default)]
+    pub container_recommendations: Vec<RecommendedContainerResources>,
+}
+
+/// Recommended resources of a single container.
+#[derive(serde::Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendedContainerResources {
+    /// Name of the container.
+    pub container_name: String,
+    /// Recommended requests, keyed by the resource name like `cpu`.
+    #[serde(default)]
+    pub target: BTreeMap<String, Quantity>,
+}
+
+impl k8s_openapi::Resource for VerticalPodAutoscaler {
+    const GROUP: &'static str = "autoscaling.k8s.io";
+    const KIND: &'static str = "VerticalPodAutoscaler";
+    const VERSION: &'static str = "v1";
+    const API_VERSION: &'static str = "autoscaling.k8s.io/v1";
+    const URL_PATH_SEGMENT: &'static str = "verticalpodautoscalers";
+
+    type Scope = k8s_openapi::NamespaceResourceScope;
+}
+
+impl k8s_openapi::Metadata for VerticalPodAutoscaler {
+    type Ty = ObjectMeta;
+
+    fn metadata(&self) -> &Self::Ty {
+        &self.metadata
+    }
+
+    fn metadata_mut(&mut self) -> &mut Self::Ty {
+        &mut self.metadata
+    }
+}
+
+/// Default number of kubernetes api requests that can be in flight at the same
+/// time.
+pub const DEFAULT_CONCURRENCY: NonZeroUsize = match NonZeroUsize::new(16) {
+    Some(concurrency) => concurrency,
+    None => unreachable!(),
+};
+
+/// Shared limit for all requests made to the kubernetes api during the run.
+static LIMITER: OnceLock<Semaphore> = OnceLock::new();
+
+/// Set the number of kubernetes api requests that can be in flight at the same
+/// time. Has to be called before the first request is made, otherwise
+/// [`DEFAULT_CONCURRENCY`] is used.
+pub fn set_concurrency(concurrency: NonZeroUsize) -> Result<()> {
+    LIMITER
+        .set(Semaphore::new(concurrency.get()))
+        .map_err(|_| eyre!("concurrency limit is already set"))
+}
+
+/// Run the request while holding a permit of the shared limit.
+async fn limit<F: Future>(request: F) -> F::Output {
+    let limiter = LIMITER.get_or_init(|| Semaphore::new(DEFAULT_CONCURRENCY.get()));
+    let _permit = limiter.acquire().await.expect("limiter is never closed");
+
+    request.await
+}
+
+/// User and groups the requests to the api server are sent as, like
+/// `kubectl --as` and `--as-group`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Impersonation {
+    /// User to impersonate, for example
+    /// `system:serviceaccount:team-a:auditor`.
+    pub user: Option<String>,
+
+    /// Groups to impersonate.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+}
+
+impl Impersonation {
+    /// Whether requests are sent as the user of the kubeconfig.
+    pub fn is_empty(&self) -> bool {
+        self.user.is_none() && self.groups.is_empty()
+    }
+}
+
+/// Load the kubernetes configuration of the given context of the kubeconfig.
+/// Uses the current context or the in-cluster configuration if no context is
+/// given. Every request made with the configuration impersonates the given
+/// user and groups.
+pub async fn config(context: Option<&str>, impersonation: &Impersonation) -> Result<Config> {
+    let mut config = match context {
+        None => Config::infer()
+            .await
+            .context("failed to infer kubernetes configuration")?,
+
+        Some(context) => {
+            let options = KubeConfigOptions {
+                context: Some(context.to_string()),
+                ..Default::default()
+            };
+
+            Config::from_kubeconfig(&options)
+                .await
+                .with_context(|| format!("failed to load kubeconfig context {context}"))?
+        }
+    };
+
+    if let Some(user) = &impersonation.user {
+        config.auth_info.impersonate = Some(user.clone());
+    }
+
+    if !impersonation.groups.is_empty() {
+        config.auth_info.impersonate_groups = Some(impersonation.groups.clone());
+    }
+
+    Ok(config)
+}
+
+/// Create a kubernetes client for the given context of the kubeconfig. Uses
+/// the current context or the in-cluster configuration if no context is given.
+pub async fn client(context: Option<&str>, impersonation: &Impersonation) -> Result<Client> {
+    let config = config(context, impersonation).await?;
+    let client = Client::try_from(config).map_err(ApiError::CreateClient)?;
+
+    Ok(client)
+}
+
+/// Returns the name of the current context from the kubeconfig if there is
+/// one.
+pub fn current_context() -> Option<String> {
+    kube::config::Kubeconfig::read()
+        .ok()
+        .and_then(|config| config.current_context)
+}
+
+/// Returns the names of all contexts in the kubeconfig.
+pub fn contexts() -> Result<Vec<String>> {
+    let config = kube::config::Kubeconfig::read().context("failed to read kubeconfig")?;
+
+    Ok(config
+        .contexts
+        .into_iter()
+        .map(|context| context.name)
+        .collect())
+}
+
+/// Version and served apis of an api server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerInfo {
+    /// Version of the api server, for example `v1.29.2`.
+    pub version: String,
+
+    /// Served api group versions like `apps/v1`, the core api is `v1`.
+    pub group_versions: BTreeSet<String>,
+}
+
+/// Query the version of the api server, for example `v1.29.2`.
+pub async fn get_server_version(client: &Client) -> Result<String> {
+    let version = limit(client.apiserver_version())
+        .await
+        .map_err(ApiError::ServerVersion)?;
+
+    Ok(version.git_version)
+}
+
+/// Query the version and the served api group versions of the api server.
+pub async fn server_info(client: &Client) -> Result<ServerInfo> {
+    let version = limit(client.apiserver_version())
+        .await
+        .map_err(ApiError::ServerVersion)?;
+
+    let core = limit(client.list_core_api_versions())
+        .await
+        .map_err(ApiError::ListApiGroups)?;
+
+    let groups = limit(client.list_api_groups())
         .await
-        .map_err(ApiError::CreateClient)?;
+        .map_err(ApiError::ListApiGroups)?;
+
+    let group_versions = core
+        .versions
+        .into_iter()
+        .chain(groups.groups.into_iter().flat_map(|group| {
+            group
+                .versions
+                .into_iter()
+                .map(|version| version.group_version)
+        }))
+        .collect();
+
+    Ok(ServerInfo {
+        version: version.git_version,
+        group_versions,
+    })
+}
+
+/// Api group versions a check reads besides the core api.
+pub fn required_apis(check: &str) -> &'static [&'static str] {
+    match check {
+        "resource-requests" | "pod-cpu-throttling-indicator" => &["metrics.k8s.io/v1beta1"],
+        "daemonset-node-coverage"
+        | "statefulset-pod-management-policy"
+        | "orphan-replicasets"
+        | "update-strategies" => &["apps/v1"],
+        "job-parallelism-check" | "leaking-cronjobs" => &["batch/v1"],
+        "ingress-tls-check" => &["networking.k8s.io/v1"],
+        "endpoint-readiness" => &["discovery.k8s.io/v1"],
+        _ => &[],
+    }
+}
+
+/// Warnings for the apis the check needs that the api server does not serve.
+pub fn missing_apis(check: &str, server: &ServerInfo) -> Vec<String> {
+    required_apis(check)
+        .iter()
+        .filter(|api| !server.group_versions.contains(**api))
+        .map(|api| {
+            let hint = match *api {
+                "metrics.k8s.io/v1beta1" => ", is metrics-server installed?",
+                _ => "",
+            };
+
+            format!(
+                "{check} needs the api {api} which the api server {} does not serve{hint}",
+                server.version
+            )
+        })
+        .collect()
+}
+
+/// Context of the kubeconfig.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct KubeContext {
+    /// Name of the context.
+    pub name: String,
+    /// Name of the cluster the context uses.
+    pub cluster: Option<String>,
+    /// Address of the api server of the cluster.
+    pub server: Option<String>,
+    /// Default namespace of the context.
+    pub namespace: Option<String>,
+    /// Name of the user the context authenticates as.
+    pub user: Option<String>,
+    /// Whether this is the current context of the kubeconfig.
+    pub is_current: bool,
+}
+
+/// Returns all contexts of the kubeconfig together with their cluster, user
+/// and default namespace.
+pub fn context_list() -> Result<Vec<KubeContext>> {
+    let config = kube::config::Kubeconfig::read().context("failed to read kubeconfig")?;
+
+    Ok(kube_contexts(&config))
+}
+
+fn kube_contexts(config: &kube::config::Kubeconfig) -> Vec<KubeContext> {
+    config
+        .contexts
+        .iter()
+        .map(|named| {
+            let context = named.context.as_ref();
+            let cluster = context.map(|context| context.cluster.clone());
+
+            KubeContext {
+                name: named.name.clone(),
+                server: config
+                    .clusters
+                    .iter()
+                    .find(|named| Some(&named.name) == cluster.as_ref())
+                    .and_then(|named| named.cluster.as_ref())
+                    .and_then(|cluster| cluster.server.clone()),
+                cluster,
+                namespace: context.and_then(|context| context.namespace.clone()),
+                user: context.map(|context| context.user.clone()),
+                is_current: config.current_context.as_ref() == Some(&named.name),
+            }
+        })
+        .collect()
+}
+
+/// Objects listed from the namespaces that could be read and the namespaces
+/// that were forbidden.
+pub type Listed<K> = (Vec<K>, Vec<NamespaceError>);
+
+/// Get the pods of the given namespaces, the current namespace if none are
+/// given or of all namespaces.
+pub async fn get_pods(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<Pod>> {
+    list_namespaced(client, namespaces, all_namespaces, ApiError::ListPods).await
+}
+
+/// Get the statefulsets of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_statefulsets(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<StatefulSet>> {
+    list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListStatefulSets,
+    )
+    .await
+}
+
+/// Get the daemonsets of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_daemonsets(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<DaemonSet>> {
+    list_namespaced(client, namespaces, all_namespaces, ApiError::ListDaemonSets).await
+}
+
+/// Get the jobs of the given namespaces, the current namespace if none are
+/// given or of all namespaces.
+pub async fn get_jobs(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<Job>> {
+    list_namespaced(client, namespaces, all_namespaces, ApiError::ListJobs).await
+}
+
+/// Get the ingresses of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_ingresses(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<Ingress>> {
+    list_namespaced(client, namespaces, all_namespaces, ApiError::ListIngresses).await
+}
+
+/// Get the cronjobs of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_cronjobs(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<CronJob>> {
+    list_namespaced(client, namespaces, all_namespaces, ApiError::ListCronJobs).await
+}
+
+/// Get the services of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_services(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<Service>> {
+    list_namespaced(client, namespaces, all_namespaces, ApiError::ListServices).await
+}
+
+/// Get the secrets of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_secrets(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<Secret>> {
+    list_namespaced(client, namespaces, all_namespaces, ApiError::ListSecrets).await
+}
+
+/// Get the service accounts of the given namespaces, the current namespace if
+/// none are given or of all namespaces.
+pub async fn get_service_accounts(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<ServiceAccount>> {
+    list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListServiceAccounts,
+    )
+    .await
+}
 
-    let apis = if all_namespaces {
-        vec![Api::all(client)]
-    } else if namespaces.is_empty() {
-        vec![Api::default_namespaced(client)]
+/// Get the persistent volume claims of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_persistent_volume_claims(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<PersistentVolumeClaim>> {
+    list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListPersistentVolumeClaims,
+    )
+    .await
+}
+
+/// Get the endpoint slices of the given namespaces, the current namespace if
+/// none are given or of all namespaces.
+pub async fn get_endpoint_slices(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<EndpointSlice>> {
+    list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListEndpointSlices,
+    )
+    .await
+}
+
+/// Get the config maps of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_config_maps(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<ConfigMap>> {
+    list_namespaced(client, namespaces, all_namespaces, ApiError::ListConfigMaps).await
+}
+
+/// Get the replica sets of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_replica_sets(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<ReplicaSet>> {
+    list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListReplicaSets,
+    )
+    .await
+}
+
+/// Get the deployments of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_deployments(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<Deployment>> {
+    list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListDeployments,
+    )
+    .await
+}
+
+/// Get the horizontal pod autoscalers of the given namespaces, the current
+/// namespace if none are given or of all namespaces.
+pub async fn get_horizontal_pod_autoscalers(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<HorizontalPodAutoscaler>> {
+    list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListHorizontalPodAutoscalers,
+    )
+    .await
+}
+
+/// Get the vertical pod autoscalers of the given namespaces, the current
+/// namespace if none are given or of all namespaces. Returns none if the
+/// vertical pod autoscaler is not installed in the cluster.
+pub async fn get_vertical_pod_autoscalers(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<VerticalPodAutoscaler>> {
+    let listed = list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListVerticalPodAutoscalers,
+    )
+    .await;
+
+    match listed {
+        Err(err) if err.downcast_ref().is_some_and(is_not_installed) => {
+            warn!("The vertical pod autoscaler is not installed: {err}");
+            Ok((Vec::new(), Vec::new()))
+        }
+
+        listed => listed,
+    }
+}
+
+/// The api of a custom resource does not exist if its definition is not
+/// installed.
+fn is_not_installed(err: &ApiError) -> bool {
+    matches!(
+        err,
+        ApiError::ListVerticalPodAutoscalers(kube::Error::Api(response)) if response.code == 404
+    )
+}
+
+/// Get the role bindings of the given namespaces, the current namespace if none
+/// are given or of all namespaces.
+pub async fn get_role_bindings(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Listed<RoleBinding>> {
+    list_namespaced(
+        client,
+        namespaces,
+        all_namespaces,
+        ApiError::ListRoleBindings,
+    )
+    .await
+}
+
+async fn list_namespaced<K>(
+    client: &Client,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    error: fn(kube::Error) -> ApiError,
+) -> Result<Listed<K>>
+where
+    K: kube::Resource<Scope = k8s_openapi::NamespaceResourceScope>
+        + Clone
+        + serde::de::DeserializeOwned
+        + fmt::Debug,
+    <K as kube::Resource>::DynamicType: Default,
+{
+    let namespaces = if namespaces.is_empty() {
+        vec![client.default_namespace().to_string()]
     } else {
         namespaces
-            .iter()
-            .map(|namespace| Api::namespaced(client.clone(), namespace))
-            .collect()
     };
 
-    let lp = ListParams::default();
-    let mut pods = Vec::new();
+    let list = |namespace: Option<String>| {
+        let api = match namespace {
+            Some(namespace) => Api::<K>::namespaced(client.clone(), &namespace),
+            None => Api::<K>::all(client.clone()),
+        };
+
+        async move {
+            paginate(|token| {
+                let api = api.clone();
+
+                async move {
+                    let mut lp = ListParams::default().limit(LIST_PAGE_SIZE);
+                    if let Some(token) = &token {
+                        lp = lp.continue_token(token);
+                    }
+
+                    let page = limit(api.list(&lp)).await?;
+                    Ok((page.items, page.metadata.continue_))
+                }
+            })
+            .await
+        }
+    };
+
+    list_tolerating(
+        namespaces,
+        all_namespaces,
+        list,
+        || get_namespaces(client),
+        error,
+    )
+    .await
+}
+
+/// Number of objects requested per page when listing.
+const LIST_PAGE_SIZE: u32 = 500;
+
+/// Fetch all pages of a list. The managed fields often make up most of an
+/// object and are not used by any check, they are dropped after every page so
+/// only the trimmed objects are kept while the next page is fetched.
+async fn paginate<K, L, F>(fetch: L) -> std::result::Result<Vec<K>, kube::Error>
+where
+    K: kube::Resource,
+    L: Fn(Option<String>) -> F,
+    F: Future<Output = std::result::Result<(Vec<K>, Option<String>), kube::Error>>,
+{
+    let mut objects = Vec::new();
+    let mut token = None;
+    let mut pages = 0;
+
+    loop {
+        let (page, next) = fetch(token).await?;
+        pages += 1;
+
+        objects.extend(page.into_iter().map(|mut object| {
+            object.meta_mut().managed_fields = None;
+            object
+        }));
+
+        match next {
+            Some(next) if !next.is_empty() => token = Some(next),
+            _ => break,
+        }
+    }
+
+    info!(
+        "Listed {} objects in {pages} pages, dropped their managed fields to save memory",
+        objects.len()
+    );
+
+    Ok(objects)
+}
+
+/// List the objects of every namespace and skip the namespaces the token is
+/// not allowed to read instead of failing. If listing all namespaces at once is
+/// forbidden they are listed one by one. Any other error still fails.
+async fn list_tolerating<K, L, F, N, G>(
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    list: L,
+    cluster_namespaces: N,
+    error: fn(kube::Error) -> ApiError,
+) -> Result<Listed<K>>
+where
+    L: Fn(Option<String>) -> F,
+    F: Future<Output = std::result::Result<Vec<K>, kube::Error>>,
+    N: FnOnce() -> G,
+    G: Future<Output = Result<Vec<String>>>,
+{
+    let namespaces = if all_namespaces {
+        match list(None).await {
+            Ok(objects) => return Ok((objects, Vec::new())),
+            Err(err) if is_forbidden(&err) => {
+                warn!("Listing all namespaces at once is forbidden, listing them one by one");
+                cluster_namespaces().await?
+            }
+            Err(err) => return Err(error(err).into()),
+        }
+    } else {
+        namespaces
+    };
+
+    let mut objects = Vec::new();
+    let mut errors = Vec::new();
+
+    for namespace in namespaces {
+        match list(Some(namespace.clone())).await {
+            Ok(listed) => objects.extend(listed),
+            Err(err) if is_forbidden(&err) => {
+                let err = error(err);
+                warn!("Skipping namespace {namespace}: {err}");
+
+                errors.push(NamespaceError {
+                    context: None,
+                    namespace,
+                    error: err.to_string(),
+                });
+            }
+            Err(err) => return Err(error(err).into()),
+        }
+    }
+
+    Ok((objects, errors))
+}
+
+fn is_forbidden(err: &kube::Error) -> bool {
+    matches!(err, kube::Error::Api(response) if response.code == 403)
+}
+
+/// Get all nodes of the cluster.
+pub async fn get_nodes(client: &Client) -> Result<Vec<Node>> {
+    let api: Api<Node> = Api::all(client.clone());
+
+    let nodes = limit(api.list(&ListParams::default()))
+        .await
+        .map_err(ApiError::ListNodes)?
+        .items;
+
+    Ok(nodes)
+}
+
+/// Get all cluster role bindings of the cluster.
+pub async fn get_cluster_role_bindings(client: &Client) -> Result<Vec<ClusterRoleBinding>> {
+    let api: Api<ClusterRoleBinding> = Api::all(client.clone());
+
+    let cluster_role_bindings = limit(api.list(&ListParams::default()))
+        .await
+        .map_err(ApiError::ListClusterRoleBindings)?
+        .items;
+
+    Ok(cluster_role_bindings)
+}
+
+/// Get all cluster roles of the cluster.
+pub async fn get_cluster_roles(client: &Client) -> Result<Vec<ClusterRole>> {
+    let api: Api<ClusterRole> = Api::all(client.clone());
+
+    let cluster_roles = limit(api.list(&ListParams::default()))
+        .await
+        .map_err(ApiError::ListClusterRoles)?
+        .items;
+
+    Ok(cluster_roles)
+}
+
+/// Get the names of all namespaces of the cluster.
+pub async fn get_namespaces(client: &Client) -> Result<Vec<String>> {
+    let namespaces = get_namespace_list(client)
+        .await?
+        .into_iter()
+        .filter_map(|namespace| namespace.metadata.name)
+        .collect();
+
+    Ok(namespaces)
+}
+
+/// Get all namespaces with their status.
+pub async fn get_namespace_list(client: &Client) -> Result<Vec<Namespace>> {
+    let api: Api<Namespace> = Api::all(client.clone());
+
+    let namespaces = limit(api.list(&ListParams::default()))
+        .await
+        .map_err(ApiError::ListNamespaces)?
+        .items;
+
+    Ok(namespaces)
+}
+
+/// Get the events of the pod that last happened between `since` and `until`.
+/// Events without any timestamp are only returned without bounds.
+pub async fn get_pod_events(
+    client: &Client,
+    namespace: &str,
+    pod_name: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<Vec<Event>> {
+    let api: Api<Event> = Api::namespaced(client.clone(), namespace);
+
+    let params = ListParams::default().fields(&format!(
+        "involvedObject.kind=Pod,involvedObject.name={pod_name}"
+    ));
+
+    let events = limit(api.list(&params))
+        .await
+        .map_err(ApiError::ListEvents)?
+        .items;
+
+    Ok(events
+        .into_iter()
+        .filter(|event| in_time_window(event, since, until))
+        .collect())
+}
+
+/// Get the warning events of all namespaces that happened on a node, either
+/// about the node itself or reported by the kubelet of a node about one of its
+/// pods, like evictions.
+pub async fn get_node_events(client: &Client) -> Result<Vec<Event>> {
+    let api: Api<Event> = Api::all(client.clone());
+
+    let events = paginate(|token| {
+        let api = api.clone();
+
+        async move {
+            let mut lp = ListParams::default()
+                .fields("type=Warning")
+                .limit(LIST_PAGE_SIZE);
+            if let Some(token) = &token {
+                lp = lp.continue_token(token);
+            }
+
+            let page = limit(api.list(&lp)).await?;
+            Ok((page.items, page.metadata.continue_))
+        }
+    })
+    .await
+    .map_err(ApiError::ListEvents)?;
+
+    Ok(events
+        .into_iter()
+        .filter(|event| event_node(event).is_some())
+        .collect())
+}
+
+/// Name of the node the event happened on. Events of other objects only name
+/// their node in the source the kubelet reports them with.
+pub fn event_node(event: &Event) -> Option<&str> {
+    if event.involved_object.kind.as_deref() == Some("Node") {
+        return event.involved_object.name.as_deref();
+    }
+
+    event
+        .source
+        .as_ref()
+        .and_then(|source| source.host.as_deref())
+}
+
+/// Time the event last happened. Events created through the `events.k8s.io`
+/// api only set `eventTime`.
+pub fn event_timestamp(event: &Event) -> Option<DateTime<Utc>> {
+    event
+        .last_timestamp
+        .as_ref()
+        .map(|time| time.0)
+        .or_else(|| event.event_time.as_ref().map(|time| time.0))
+}
+
+fn in_time_window(
+    event: &Event,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> bool {
+    if since.is_none() && until.is_none() {
+        return true;
+    }
+
+    event_timestamp(event).is_some_and(|time| {
+        since.into_iter().all(|since| time >= since) && until.into_iter().all(|until| time <= until)
+    })
+}
 
-    for api in apis {
-        pods.extend(api.list(&lp).await.map_err(ApiError::ListPods)?);
+/// Parse a bound of a time window, either an RFC 3339 timestamp like
+/// `2024-01-01T12:00:00Z` or a duration like `1h` or `30m` before `now`.
+pub fn parse_time_bound(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
     }
 
-    Ok(pods)
+    let duration = humantime::parse_duration(value).with_context(|| {
+        format!("{value} is neither an RFC 3339 timestamp nor a duration like 1h")
+    })?;
+
+    let duration = chrono::Duration::from_std(duration).context("duration is too long")?;
+
+    Ok(now - duration)
 }
 
-pub(crate) fn get_sync<T>(namespace: &str, name: &str) -> Result<T>
+/// Blocking version of [`get`] for use in synchronous code running on a multi
+/// threaded tokio runtime.
+pub fn get_sync<T>(client: &Client, namespace: &str, name: &str) -> Result<T>
 where
     T: k8s_openapi::Resource<Scope = k8s_openapi::NamespaceResourceScope>
         + Clone
@@ -109,11 +1213,12 @@ where
 {
     tokio::task::block_in_place(|| {
         let handle = tokio::runtime::Handle::current();
-        handle.block_on(get::<T>(namespace, name))
+        handle.block_on(get::<T>(client, namespace, name))
     })
 }
 
-pub(crate) async fn get<T>(namespace: &str, name: &str) -> Result<T>
+/// Get a single namespaced object by its name.
+pub async fn get<T>(client: &Client, namespace: &str, name: &str) -> Result<T>
 where
     T: k8s_openapi::Resource<Scope = k8s_openapi::NamespaceResourceScope>
         + Clone
@@ -121,15 +1226,10 @@ where
         + std::fmt::Debug
         + k8s_openapi::Metadata<Ty = ObjectMeta>,
 {
-    let client = Client::try_default()
-        .await
-        .map_err(ApiError::CreateClient)?;
-
-    let api: Api<T> = Api::namespaced(client, namespace);
+    let api: Api<T> = Api::namespaced(client.clone(), namespace);
     let lp = ListParams::default().fields(&format!("metadata.name={name}"));
 
-    let mut out = api
-        .list(&lp)
+    let mut out = limit(api.list(&lp))
         .await
         .with_context(|| "failed to get from kubernetes api")?
         .items;
@@ -139,7 +1239,53 @@ where
     Ok(out.remove(0))
 }
 
-pub(crate) fn get_pod_owner(pod: &Pod) -> Option<Owner> {
+/// Controlling owners of the `ReplicaSet`s and `Job`s looked up while resolving
+/// pod owners. Pods of the same deployment share their replica set, so it only
+/// has to be fetched once. Keyed by kind, namespace and name as a replica set
+/// and a job can have the same name.
+#[derive(Debug, Default)]
+pub struct OwnerCache {
+    owners: Mutex<HashMap<(String, String, String), Option<OwnerReference>>>,
+}
+
+impl OwnerCache {
+    /// Controlling owner reference of the object, `fetch` is only called the
+    /// first time the object is looked up.
+    fn get_or_fetch(
+        &self,
+        kind: &str,
+        namespace: &str,
+        name: &str,
+        fetch: impl FnOnce() -> Option<OwnerReference>,
+    ) -> Option<OwnerReference> {
+        let key = (kind.to_string(), namespace.to_string(), name.to_string());
+
+        if let Some(owner) = self.owners.lock().expect("failed to lock owners").get(&key) {
+            return owner.clone();
+        }
+
+        let owner = fetch();
+
+        self.owners
+            .lock()
+            .expect("failed to lock owners")
+            .insert(key, owner.clone());
+
+        owner
+    }
+}
+
+/// Get the top level owner of the pod. `ReplicaSet` and `Job` owners are
+/// resolved to the object controlling them, the lookups are cached in `cache`.
+/// If the lookup fails, for example because it is forbidden, the direct owner
+/// is used.
+pub fn get_pod_owner(client: &Client, cache: &OwnerCache, pod: &Pod) -> Option<Owner> {
+    let namespace = pod
+        .metadata
+        .namespace
+        .as_ref()
+        .expect("failed to get namespace");
+
     pod.metadata
         .owner_references
         .as_ref()
@@ -147,45 +1293,103 @@ pub(crate) fn get_pod_owner(pod: &Pod) -> Option<Owner> {
             owner_references
                 .iter()
                 .find(|owner_reference| owner_reference.controller.unwrap_or(false))
-                .map(|owner_reference| match owner_reference.kind.as_str() {
-                    "ReplicaSet" => {
-                        let replica_set = get_sync::<ReplicaSet>(
-                            pod.metadata
-                                .namespace
-                                .as_ref()
-                                .expect("failed to get namespace"),
-                            &owner_reference.name,
-                        )
-                        .expect("failed to get replica set");
-
-                        extract_owner(&replica_set)
-                            .unwrap_or(owner_reference)
-                            .clone()
-                    }
+                .map(|owner_reference| {
+                    let kind = owner_reference.kind.as_str();
+                    let name = &owner_reference.name;
+
+                    let owner =
+                        match kind {
+                            "ReplicaSet" => cache.get_or_fetch(kind, namespace, name, || {
+                                match get_sync::<ReplicaSet>(client, namespace, name) {
+                                    Ok(replica_set) => extract_owner(&replica_set).cloned(),
+                                    Err(err) => {
+                                        warn!(
+                                            "Failed to get replica set {namespace}/{name}: {err:?}"
+                                        );
+                                        None
+                                    }
+                                }
+                            }),
+
+                            "Job" => cache.get_or_fetch(kind, namespace, name, || {
+                                match get_sync::<Job>(client, namespace, name) {
+                                    Ok(job) => extract_owner(&job).cloned(),
+                                    Err(err) => {
+                                        warn!("Failed to get job {namespace}/{name}: {err:?}");
+                                        None
+                                    }
+                                }
+                            }),
+
+                            _ => None,
+                        };
+
+                    owner.unwrap_or_else(|| owner_reference.clone())
+                })
+                .map(|owner_reference| Owner::new(&owner_reference, namespace))
+        })
+}
 
-                    "Job" => {
-                        let job = get_sync::<Job>(
-                            pod.metadata
-                                .namespace
-                                .as_ref()
-                                .expect("failed to get namespace"),
-                            &owner_reference.name,
-                        )
-                        .expect("failed to get job");
-
-                        extract_owner(&job).unwrap_or(owner_reference).clone()
-                    }
+/// Maximum number of owners followed when resolving an owner chain. Protects
+/// against owner references that form a cycle.
+const MAX_OWNER_CHAIN_LENGTH: usize = 16;
 
-                    _ => owner_reference.clone(),
-                })
-                .map(|owner_reference| Owner {
-                    name: owner_reference.name,
-                    kind: owner_reference.kind,
+/// Blocking version of [`get_owner_chain`] for use in synchronous code running
+/// on a multi threaded tokio runtime.
+pub fn get_owner_chain_sync(client: &Client, pod: &Pod) -> Result<Vec<Owner>> {
+    tokio::task::block_in_place(|| {
+        let handle = tokio::runtime::Handle::current();
+        handle.block_on(get_owner_chain(client, pod))
+    })
+}
+
+/// Get all controlling owners of the pod starting with the direct owner up to
+/// the top level owner, for example `[ReplicaSet/app-1234, Deployment/app]`.
+pub async fn get_owner_chain(client: &Client, pod: &Pod) -> Result<Vec<Owner>> {
+    let Some(namespace) = pod.metadata.namespace.as_deref() else {
+        return Ok(Vec::new());
+    };
+
+    let mut chain = Vec::new();
+    let mut next = extract_owner(pod).cloned();
+
+    while let Some(owner_reference) = next {
+        chain.push(Owner::new(&owner_reference, namespace));
+
+        if chain.len() >= MAX_OWNER_CHAIN_LENGTH {
+            break;
+        }
+
+        let gvk = owner_reference
+            .api_version
+            .parse::<GroupVersion>()
+            .with_context(|| format!("invalid api version {}", owner_reference.api_version))?
+            .with_kind(&owner_reference.kind);
+
+        let api: Api<DynamicObject> =
+            Api::namespaced_with(client.clone(), namespace, &ApiResource::from_gvk(&gvk));
+
+        let object = limit(api.get_opt(&owner_reference.name))
+            .await
+            .with_context(|| format!("failed to get owner {}", owner_reference.name))?;
+
+        next = object.and_then(|object| {
+            object
+                .metadata
+                .owner_references
+                .and_then(|owner_references| {
+                    owner_references
+                        .into_iter()
+                        .find(|owner_reference| owner_reference.controller.unwrap_or(false))
                 })
-        })
+        });
+    }
+
+    Ok(chain)
 }
 
-pub(crate) fn extract_owner<T>(object: &T) -> Option<&OwnerReference>
+/// Get the controlling owner reference of the object.
+pub fn extract_owner<T>(object: &T) -> Option<&OwnerReference>
 where
     T: k8s_openapi::Resource<Scope = k8s_openapi::NamespaceResourceScope>
         + Clone
@@ -204,22 +1408,24 @@ where
         })
 }
 
-pub(crate) async fn get_pod_resource_usage(
+/// Get the current resource usage of the pod from the metrics api. The usage
+/// is unknown if reading the metrics is forbidden.
+pub async fn get_pod_resource_usage(
+    client: &Client,
     namespace: &str,
     pod: &str,
 ) -> Result<Option<PodMetrics>> {
-    let client = Client::try_default()
-        .await
-        .map_err(ApiError::CreateClient)?;
-
     let api: Api<PodMetrics> = Api::namespaced(client.clone(), namespace);
     let lp = ListParams::default().fields(&format!("metadata.name={pod}"));
 
-    let mut out = api
-        .list(&lp)
-        .await
-        .expect("failed to list pod metrics")
-        .items;
+    let mut out = match limit(api.list(&lp)).await {
+        Ok(list) => list.items,
+        Err(err) if is_forbidden(&err) => {
+            warn!("Skipping resource usage of pod {namespace}/{pod}: {err}");
+            return Ok(None);
+        }
+        Err(err) => return Err(err).context("failed to list pod metrics"),
+    };
 
     if out.len() != 1 {
         return Ok(None);
@@ -228,12 +1434,45 @@ pub(crate) async fn get_pod_resource_usage(
     Ok(Some(out.remove(0)))
 }
 
+/// Get the current resource usage of all pods of the cluster with a single
+/// request to the metrics api, keyed by the namespace and name of the pod. No
+/// usage is returned if reading the metrics is forbidden.
+pub async fn get_all_pod_metrics(client: &Client) -> Result<HashMap<(String, String), PodMetrics>> {
+    let api: Api<PodMetrics> = Api::all(client.clone());
+
+    let metrics = match limit(api.list(&ListParams::default())).await {
+        Ok(list) => list.items,
+        Err(err) if is_forbidden(&err) => {
+            warn!("Skipping resource usage of pods: {err}");
+            Vec::new()
+        }
+        Err(err) => return Err(err).context("failed to list pod metrics"),
+    };
+
+    Ok(index_pod_metrics(metrics))
+}
+
+/// Key the resource usage of pods by their namespace and name.
+pub fn index_pod_metrics(
+    metrics: impl IntoIterator<Item = PodMetrics>,
+) -> HashMap<(String, String), PodMetrics> {
+    metrics
+        .into_iter()
+        .filter_map(|metrics| {
+            let namespace = metrics.metadata.namespace.clone()?;
+            let name = metrics.metadata.name.clone()?;
+
+            Some(((namespace, name), metrics))
+        })
+        .collect()
+}
+
 impl Serialize for Cpu {
     fn serialize<S>(&self, serializer: S) -> std::prelude::v1::Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&format!("{}m", self.0))
+        serializer.serialize_str(&self.to_string_as(CPU_UNITS.get().copied().unwrap_or_default()))
     }
 }
 
@@ -242,15 +1481,65 @@ impl Serialize for Memory {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&ByteSize(self.0).to_string_as(true))
+        match MEMORY_UNITS.get().copied().unwrap_or_default() {
+            MemoryUnits::Bytes => serializer.serialize_u64(self.0),
+            units => serializer.serialize_str(&self.to_string_as(units)),
+        }
     }
 }
 
+impl JsonSchema for Cpu {
+    fn schema_name() -> String {
+        "Cpu".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_schema(r"^[0-9]+(m|\.[0-9]{1,2})?$")
+    }
+}
+
+impl JsonSchema for Memory {
+    fn schema_name() -> String {
+        "Memory".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![
+                    string_schema(r"^[0-9]+(\.[0-9]+)? (B|[kKMGTPE]i?B)$"),
+                    SchemaObject {
+                        instance_type: Some(InstanceType::Integer.into()),
+                        ..Default::default()
+                    }
+                    .into(),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+fn string_schema(pattern: &str) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        string: Some(Box::new(StringValidation {
+            pattern: Some(pattern.to_string()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
 impl TryFrom<&Quantity> for Cpu {
     type Error = eyre::Error;
 
     fn try_from(value: &Quantity) -> Result<Self, Self::Error> {
-        let number = quantity_to_number(value).wrap_err("failed to convert quantity to number")?;
+        let number =
+            quantity_to_number(value, 1000).wrap_err("failed to convert quantity to number")?;
 
         Ok(Self(number))
     }
@@ -260,52 +1549,86 @@ impl TryFrom<&Quantity> for Memory {
     type Error = eyre::Error;
 
     fn try_from(value: &Quantity) -> Result<Self, Self::Error> {
-        let number = quantity_to_number(value).wrap_err("failed to convert quantity to number")?;
+        let number =
+            quantity_to_number(value, 1).wrap_err("failed to convert quantity to number")?;
 
         Ok(Self(number))
     }
 }
 
-fn quantity_to_number(input: &Quantity) -> Result<u64> {
-    let mut number = String::new();
-    let mut suffix = String::new();
+impl FromStr for Cpu {
+    type Err = String;
 
-    // accumulate number until char is not a number then use the rest as suffix
-    // to make the memory stuff (MiB, GiB) work
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(&Quantity(s.to_string())).map_err(|err| format!("invalid cpu {s}: {err}"))
+    }
+}
 
-    let number_acc = true;
+impl FromStr for Memory {
+    type Err = String;
 
-    for ch in input.0.chars() {
-        if number_acc {
-            if ch.is_numeric() {
-                number.push(ch);
-            } else {
-                suffix.push(ch);
-            }
-        } else {
-            suffix.push(ch);
-        }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(&Quantity(s.to_string())).map_err(|err| format!("invalid memory {s}: {err}"))
     }
+}
 
-    let number = number.parse().expect("failed to parse number");
+/// Convert a quantity like `1500m`, `0.5`, `262144k` or `1Gi` into a whole
+/// number of `scale` units per base unit, `1000` to get millicores from cores
+/// and `1` to get bytes. Fractions that are left over are cut off.
+fn quantity_to_number(input: &Quantity, scale: u64) -> Result<u64> {
+    // split the quantity into the decimal number and the suffix after it
+    let split = input
+        .0
+        .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+        .unwrap_or(input.0.len());
+
+    let (number, suffix) = input.0.split_at(split);
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(eyre!("failed to parse number of {}", input.0));
+    }
 
-    let number = if suffix.is_empty() {
-        number * 1000
-    } else {
-        match suffix.as_str() {
-            "n" => number / 1000 / 1000,
-            "m" => number,
-            "k" => number * 1000 * 1000,
-            "Ki" => number * 1024,
-            "Mi" => number * 1024 * 1024,
-            "Gi" => number * 1024 * 1024 * 1024,
-
-            _ => {
-                return Err(eyre!("invalid suffix {suffix}"));
-            }
+    let digits: u128 = format!("{whole}{fraction}")
+        .parse()
+        .wrap_err_with(|| format!("failed to parse number of {}", input.0))?;
+
+    let (multiplier, divisor): (u128, u128) = match suffix {
+        "n" => (1, 1_000_000_000),
+        "u" => (1, 1_000_000),
+        "m" => (1, 1000),
+        "" => (1, 1),
+        "k" => (1000, 1),
+        "M" => (1000_u128.pow(2), 1),
+        "G" => (1000_u128.pow(3), 1),
+        "T" => (1000_u128.pow(4), 1),
+        "P" => (1000_u128.pow(5), 1),
+        "E" => (1000_u128.pow(6), 1),
+        "Ki" => (1 << 10, 1),
+        "Mi" => (1 << 20, 1),
+        "Gi" => (1 << 30, 1),
+        "Ti" => (1 << 40, 1),
+        "Pi" => (1 << 50, 1),
+        "Ei" => (1 << 60, 1),
+
+        _ => {
+            return Err(eyre!("invalid suffix {suffix}"));
         }
     };
 
+    let exponent = u32::try_from(fraction.len()).wrap_err("too many decimal places")?;
+    let divisor = 10_u128
+        .checked_pow(exponent)
+        .and_then(|decimals| decimals.checked_mul(divisor))
+        .ok_or_else(|| eyre!("too many decimal places in {}", input.0))?;
+
+    let number = digits
+        .checked_mul(multiplier)
+        .and_then(|number| number.checked_mul(u128::from(scale)))
+        .map(|number| number / divisor)
+        .and_then(|number| u64::try_from(number).ok())
+        .ok_or_else(|| eyre!("quantity {} is too large", input.0))?;
+
     Ok(number)
 }
 
@@ -315,26 +1638,497 @@ impl From<u64> for Cpu {
     }
 }
 
+impl From<u64> for Memory {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
 #[cfg(test)]
-#[allow(clippy::unwrap_used)]
+#[allow(clippy::unwrap_used, clippy::panic)]
 mod tests {
+    use std::collections::BTreeSet;
+
     use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 
     #[test]
     fn quantity_to_number() {
+        // cpu in millicores
         let testcases = vec![
             ("1500m", 1500),
             ("1k", 1_000_000),
             ("1", 1000),
+            ("0.5", 500),
+            ("1.25", 1250),
+            (".1", 100),
+            ("250000000n", 250),
+            ("1Ki", 1_024_000),
+        ];
+
+        for (input, expected) in testcases {
+            let input: Quantity = Quantity(input.to_string());
+
+            let output = super::quantity_to_number(&input, 1000).unwrap();
+            assert_eq!(expected, output, "{}", input.0);
+        }
+
+        // memory in bytes
+        let testcases = vec![
             ("1Ki", 1024),
+            ("262144k", 262_144_000),
+            ("134217728", 134_217_728),
+            ("1M", 1_000_000),
+            ("1G", 1_000_000_000),
+            ("2T", 2_000_000_000_000),
+            ("1.5Gi", 1_610_612_736),
+            ("1500m", 1),
         ];
 
         for (input, expected) in testcases {
             let input: Quantity = Quantity(input.to_string());
 
-            let output = super::quantity_to_number(&input).unwrap();
-            assert_eq!(expected, output);
+            let output = super::quantity_to_number(&input, 1).unwrap();
+            assert_eq!(expected, output, "{}", input.0);
+        }
+
+        for input in [
+            "",
+            ".",
+            "1.2.3",
+            "1x",
+            "-1",
+            "1e3",
+            "100000000000000000000000Ei",
+        ] {
+            let input: Quantity = Quantity(input.to_string());
+
+            assert!(super::quantity_to_number(&input, 1).is_err(), "{}", input.0);
+        }
+    }
+
+    #[test]
+    fn owner_from_owner_reference() {
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+
+        let reference = |uid: &str| OwnerReference {
+            kind: "ReplicaSet".to_string(),
+            name: "app-1234".to_string(),
+            uid: uid.to_string(),
+            ..Default::default()
+        };
+
+        let owner = super::Owner::new(&reference("a"), "web");
+        assert_eq!("a", owner.uid);
+        assert_eq!("web", owner.namespace);
+
+        assert_eq!(owner, super::Owner::new(&reference("a"), "web"));
+        assert_ne!(owner, super::Owner::new(&reference("b"), "web"));
+        assert_ne!(owner, super::Owner::new(&reference("a"), "batch"));
+    }
+
+    #[test]
+    fn owner_cache() {
+        use std::cell::Cell;
+
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+
+        let deployment = OwnerReference {
+            kind: "Deployment".to_string(),
+            name: "app".to_string(),
+            ..Default::default()
+        };
+
+        let cache = super::OwnerCache::default();
+        let fetches = Cell::new(0);
+
+        let fetch = |owner: Option<OwnerReference>| {
+            let fetches = &fetches;
+            move || {
+                fetches.set(fetches.get() + 1);
+                owner
+            }
+        };
+
+        for _ in 0..3 {
+            let owner = cache.get_or_fetch(
+                "ReplicaSet",
+                "web",
+                "app-1234",
+                fetch(Some(deployment.clone())),
+            );
+            assert_eq!(Some(&deployment), owner.as_ref());
         }
+        assert_eq!(1, fetches.get());
+
+        // Objects without a controller are cached as well.
+        for _ in 0..3 {
+            assert_eq!(
+                None,
+                cache.get_or_fetch("Job", "web", "app-1234", fetch(None))
+            );
+        }
+        assert_eq!(2, fetches.get());
+
+        cache.get_or_fetch("ReplicaSet", "batch", "app-1234", fetch(None));
+        assert_eq!(3, fetches.get());
+    }
+
+    fn forbidden() -> kube::Error {
+        kube::Error::Api(kube::core::ErrorResponse {
+            status: "Failure".to_string(),
+            message: "pods is forbidden".to_string(),
+            reason: "Forbidden".to_string(),
+            code: 403,
+        })
+    }
+
+    #[tokio::test]
+    async fn paginate() {
+        use k8s_openapi::{
+            api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::ManagedFieldsEntry,
+        };
+
+        let pod = |name: &str| {
+            let mut pod = Pod::default();
+            pod.metadata.name = Some(name.to_string());
+            pod.metadata.managed_fields = Some(vec![ManagedFieldsEntry::default()]);
+            pod
+        };
+
+        let fetch = |token: Option<String>| {
+            let page = match token.as_deref() {
+                None => (vec![pod("a"), pod("b")], Some("page-2".to_string())),
+                Some("page-2") => (vec![pod("c")], Some(String::new())),
+                Some(token) => panic!("unexpected continue token {token}"),
+            };
+
+            async move { Ok(page) }
+        };
+
+        let pods = super::paginate(fetch).await.unwrap();
+
+        assert_eq!(
+            vec![Some("a"), Some("b"), Some("c")],
+            pods.iter()
+                .map(|pod| pod.metadata.name.as_deref())
+                .collect::<Vec<_>>()
+        );
+        assert!(pods.iter().all(|pod| pod.metadata.managed_fields.is_none()));
+    }
+
+    #[tokio::test]
+    async fn list_tolerating_forbidden_namespace() {
+        let list = |namespace: Option<String>| async move {
+            match namespace.as_deref() {
+                Some("team-b") => Err(forbidden()),
+                Some(namespace) => Ok(vec![format!("{namespace}/pod")]),
+                None => panic!("all namespaces are not listed"),
+            }
+        };
+
+        let (pods, errors) = super::list_tolerating(
+            vec![
+                "team-a".to_string(),
+                "team-b".to_string(),
+                "team-c".to_string(),
+            ],
+            false,
+            list,
+            || async { unreachable!() },
+            super::ApiError::ListPods,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(vec!["team-a/pod", "team-c/pod"], pods);
+        assert_eq!(
+            vec![("team-b", None)],
+            errors
+                .iter()
+                .map(|error| (error.namespace.as_str(), error.context.as_deref()))
+                .collect::<Vec<_>>()
+        );
+        assert!(errors[0].error.starts_with("failed to list pods: "));
+    }
+
+    #[tokio::test]
+    async fn list_tolerating_forbidden_all_namespaces() {
+        let list = |namespace: Option<String>| async move {
+            match namespace.as_deref() {
+                None | Some("kube-system") => Err(forbidden()),
+                Some(namespace) => Ok(vec![format!("{namespace}/pod")]),
+            }
+        };
+
+        let (pods, errors) = super::list_tolerating(
+            Vec::new(),
+            true,
+            list,
+            || async { Ok(vec!["default".to_string(), "kube-system".to_string()]) },
+            super::ApiError::ListPods,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(vec!["default/pod"], pods);
+        assert_eq!("kube-system", errors[0].namespace);
+    }
+
+    #[tokio::test]
+    async fn list_tolerating_other_errors() {
+        let list = |_: Option<String>| async {
+            Err::<Vec<String>, _>(kube::Error::Api(kube::core::ErrorResponse {
+                status: "Failure".to_string(),
+                message: "etcdserver: request timed out".to_string(),
+                reason: "InternalError".to_string(),
+                code: 500,
+            }))
+        };
+
+        let result = super::list_tolerating(
+            vec!["team-a".to_string()],
+            false,
+            list,
+            || async { unreachable!() },
+            super::ApiError::ListPods,
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn missing_apis() {
+        let server = super::ServerInfo {
+            version: "v1.29.2".to_string(),
+            group_versions: ["v1", "apps/v1", "batch/v1"]
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+        };
+
+        assert_eq!(
+            vec![
+                "resource-requests needs the api metrics.k8s.io/v1beta1 which the api server \
+                 v1.29.2 does not serve, is metrics-server installed?"
+                    .to_string()
+            ],
+            super::missing_apis("resource-requests", &server)
+        );
+
+        assert!(super::missing_apis("daemonset-node-coverage", &server).is_empty());
+        assert!(super::missing_apis("missing-health-probes", &server).is_empty());
+
+        let server = super::ServerInfo {
+            version: "v1.29.2".to_string(),
+            group_versions: BTreeSet::from(["v1".to_string()]),
+        };
+
+        assert_eq!(
+            vec![
+                "job-parallelism-check needs the api batch/v1 which the api server v1.29.2 does \
+                 not serve"
+                    .to_string()
+            ],
+            super::missing_apis("job-parallelism-check", &server)
+        );
+    }
+
+    #[test]
+    fn kube_contexts() {
+        use kube::config::{Cluster, Context, Kubeconfig, NamedCluster, NamedContext};
+
+        let config = Kubeconfig {
+            current_context: Some("staging".to_string()),
+            clusters: vec![NamedCluster {
+                name: "staging-cluster".to_string(),
+                cluster: Some(Cluster {
+                    server: Some("https://staging.example.com".to_string()),
+                    ..Default::default()
+                }),
+            }],
+            contexts: vec![
+                NamedContext {
+                    name: "staging".to_string(),
+                    context: Some(Context {
+                        cluster: "staging-cluster".to_string(),
+                        user: "admin".to_string(),
+                        namespace: Some("web".to_string()),
+                        ..Default::default()
+                    }),
+                },
+                NamedContext {
+                    name: "empty".to_string(),
+                    context: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            vec![
+                super::KubeContext {
+                    name: "staging".to_string(),
+                    cluster: Some("staging-cluster".to_string()),
+                    server: Some("https://staging.example.com".to_string()),
+                    namespace: Some("web".to_string()),
+                    user: Some("admin".to_string()),
+                    is_current: true,
+                },
+                super::KubeContext {
+                    name: "empty".to_string(),
+                    cluster: None,
+                    server: None,
+                    namespace: None,
+                    user: None,
+                    is_current: false,
+                },
+            ],
+            super::kube_contexts(&config)
+        );
+    }
+
+    #[test]
+    fn memory_to_string_as() {
+        use super::{Memory, MemoryUnits};
+
+        let memory = Memory(1_500_000_000);
+
+        assert_eq!("1.4 GiB", memory.to_string_as(MemoryUnits::Iec));
+        assert_eq!("1.5 GB", memory.to_string_as(MemoryUnits::Si));
+        assert_eq!("1500000000", memory.to_string_as(MemoryUnits::Bytes));
+
+        assert_eq!(Ok(MemoryUnits::Si), "si".parse());
+        assert!("gib".parse::<MemoryUnits>().is_err());
+    }
+
+    #[test]
+    fn cpu_to_string_as() {
+        use super::{Cpu, CpuUnits};
+
+        let testcases = [
+            (1500, "1500m", "1.5"),
+            (1333, "1333m", "1.33"),
+            (1335, "1335m", "1.34"),
+            (2000, "2000m", "2"),
+            (1999, "1999m", "2"),
+            (50, "50m", "0.05"),
+            (4, "4m", "0"),
+        ];
+
+        for (millicores, expected_millicores, expected_cores) in testcases {
+            let cpu = Cpu(millicores);
+
+            assert_eq!(expected_millicores, cpu.to_string_as(CpuUnits::Millicores));
+            assert_eq!(expected_cores, cpu.to_string_as(CpuUnits::Cores));
+        }
+
+        assert_eq!(Ok(CpuUnits::Cores), "cores".parse());
+        assert!("m".parse::<CpuUnits>().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn limit() {
+        use std::{
+            num::NonZeroUsize,
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+            time::Duration,
+        };
+
+        // The limit is shared by the whole process, no other test may make
+        // requests through it.
+        let concurrency = NonZeroUsize::new(3).unwrap();
+        super::set_concurrency(concurrency).unwrap();
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let tasks = (0..20)
+            .map(|_| {
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+
+                tokio::spawn(async move {
+                    super::limit(async {
+                        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(current, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                    })
+                    .await;
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let max_in_flight = max_in_flight.load(Ordering::SeqCst);
+        assert!(max_in_flight > 0);
+        assert!(max_in_flight <= concurrency.get(), "{max_in_flight}");
+        assert!(super::set_concurrency(concurrency).is_err());
+    }
+
+    #[test]
+    fn in_time_window() {
+        let event = |last_timestamp: Option<&str>, event_time: Option<&str>| {
+            serde_json::from_value::<k8s_openapi::api::core::v1::Event>(serde_json::json!({
+                "metadata": { "namespace": "web", "name": "api-0.1" },
+                "involvedObject": { "kind": "Pod", "name": "api-0" },
+                "lastTimestamp": last_timestamp,
+                "eventTime": event_time,
+            }))
+            .unwrap()
+        };
+
+        let time = |value: &str| Some(value.parse().unwrap());
+
+        let since = time("2024-01-01T10:00:00Z");
+        let until = time("2024-01-01T12:00:00Z");
+
+        let inside = event(Some("2024-01-01T11:00:00Z"), None);
+        let before = event(Some("2024-01-01T09:00:00Z"), None);
+        let event_time = event(None, Some("2024-01-01T11:30:00.000000Z"));
+        let untimed = event(None, None);
+
+        assert!(super::in_time_window(&inside, since, until));
+        assert!(!super::in_time_window(&before, since, until));
+        assert!(super::in_time_window(&before, None, until));
+        assert!(super::in_time_window(&event_time, since, until));
+        assert!(!super::in_time_window(
+            &event_time,
+            since,
+            time("2024-01-01T11:00:00Z")
+        ));
+        assert!(!super::in_time_window(&untimed, since, None));
+        assert!(super::in_time_window(&untimed, None, None));
+    }
+
+    #[test]
+    fn parse_time_bound() {
+        let now = "2024-01-01T12:00:00Z"
+            .parse::<k8s_openapi::chrono::DateTime<k8s_openapi::chrono::Utc>>()
+            .unwrap();
+
+        assert_eq!(
+            "2024-01-01T11:00:00+00:00",
+            super::parse_time_bound("1h", now).unwrap().to_rfc3339()
+        );
+        assert_eq!(
+            "2024-01-01T11:30:00+00:00",
+            super::parse_time_bound("30m", now).unwrap().to_rfc3339()
+        );
+        assert_eq!(
+            "2023-12-31T22:00:00+00:00",
+            super::parse_time_bound("2024-01-01T00:00:00+02:00", now)
+                .unwrap()
+                .to_rfc3339()
+        );
+        assert!(super::parse_time_bound("yesterday", now).is_err());
     }
 }
 
@@ -383,13 +2177,42 @@ impl num::traits::SaturatingSub for Memory {
 }
 
 impl Cpu {
-    pub(crate) fn to_milliseconds(self) -> u64 {
+    /// Cpu in millicores.
+    pub fn to_milliseconds(self) -> u64 {
         self.0
     }
+
+    /// Render the cpu for humans in the given units.
+    pub fn to_string_as(self, units: CpuUnits) -> String {
+        match units {
+            CpuUnits::Millicores => format!("{}m", self.0),
+            CpuUnits::Cores => {
+                let hundredths = (self.0 + 5) / 10;
+
+                match hundredths % 100 {
+                    0 => format!("{}", hundredths / 100),
+                    fraction if fraction % 10 == 0 => {
+                        format!("{}.{}", hundredths / 100, fraction / 10)
+                    }
+                    fraction => format!("{}.{fraction:02}", hundredths / 100),
+                }
+            }
+        }
+    }
 }
 
 impl Memory {
-    pub(crate) fn to_bytes(self) -> u64 {
+    /// Memory in bytes.
+    pub fn to_bytes(self) -> u64 {
         self.0
     }
+
+    /// Render the memory for humans in the given units.
+    pub fn to_string_as(self, units: MemoryUnits) -> String {
+        match units {
+            MemoryUnits::Iec => ByteSize(self.0).to_string_as(true),
+            MemoryUnits::Si => ByteSize(self.0).to_string_as(false),
+            MemoryUnits::Bytes => self.0.to_string(),
+        }
+    }
 }
@@ -0,0 +1,562 @@
+//! On-disk cache of api responses for repeated runs against the same cluster.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use eyre::{eyre, Context, Result};
+use k8s_openapi::{
+    api::{
+        apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet},
+        autoscaling::v2::HorizontalPodAutoscaler,
+        batch::v1::{CronJob, Job},
+        core::v1::{
+            ConfigMap, Event, Namespace, Node, PersistentVolumeClaim, Pod, Secret, Service,
+            ServiceAccount,
+        },
+        discovery::v1::EndpointSlice,
+        networking::v1::Ingress,
+        rbac::v1::{ClusterRole, ClusterRoleBinding, RoleBinding},
+    },
+    chrono::{self, DateTime, Utc},
+};
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    api::{index_pod_metrics, Owner, PodMetrics, VerticalPodAutoscaler},
+    source::Source,
+};
+
+/// How long cached responses are used and whether they are refreshed.
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct CacheOptions {
+    /// Directory the caches of all contexts are stored in.
+    pub root: PathBuf,
+
+    /// Cached responses older than this are fetched again.
+    pub ttl: Duration,
+
+    /// Ignore the cached responses and replace them with fresh ones.
+    pub refresh: bool,
+}
+
+/// Directory the cache is stored in by default, `$XDG_CACHE_HOME/k8s-tools`
+/// or `~/.cache/k8s-tools`.
+pub fn default_root() -> Result<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .map(|dir| dir.join("k8s-tools"))
+        .ok_or_else(|| eyre!("failed to find the cache directory, set XDG_CACHE_HOME"))
+}
+
+/// Cached response together with the time it was fetched.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry<T> {
+    fetched_at: String,
+    value: T,
+}
+
+/// Responses of a single context stored as one json file per request.
+#[derive(Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    ttl: chrono::Duration,
+    refresh: bool,
+
+    /// Fetch time of the oldest response read from the cache.
+    oldest: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl Cache {
+    /// Open the cache of the given context.
+    pub fn new(options: &CacheOptions, context: &str) -> Result<Self> {
+        let dir = options.root.join(file_name(context));
+
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create cache directory {}", dir.display()))?;
+
+        Ok(Self {
+            dir,
+            ttl: chrono::Duration::from_std(options.ttl).context("invalid cache ttl")?,
+            refresh: options.refresh,
+            oldest: Mutex::default(),
+        })
+    }
+
+    /// Time the oldest response used from the cache was fetched in RFC 3339
+    /// format, `None` if every response was fetched fresh.
+    pub fn fetched_at(&self) -> Option<String> {
+        self.oldest
+            .lock()
+            .expect("failed to lock cache")
+            .map(|fetched_at| fetched_at.to_rfc3339())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", file_name(key)))
+    }
+
+    /// Cached response of the request if it is younger than the ttl.
+    fn get<T: DeserializeOwned>(&self, key: &str, now: DateTime<Utc>) -> Option<T> {
+        if self.refresh {
+            return None;
+        }
+
+        let path = self.path(key);
+        let data = fs::read(&path).ok()?;
+
+        let entry = match serde_json::from_slice::<Entry<T>>(&data) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!("Ignoring invalid cache file {}: {err}", path.display());
+                return None;
+            }
+        };
+
+        let fetched_at = DateTime::parse_from_rfc3339(&entry.fetched_at)
+            .ok()?
+            .with_timezone(&Utc);
+
+        if now - fetched_at >= self.ttl {
+            debug!("Cached response {key} expired");
+            return None;
+        }
+
+        let mut oldest = self.oldest.lock().expect("failed to lock cache");
+        *oldest = Some(oldest.map_or(fetched_at, |oldest| oldest.min(fetched_at)));
+
+        Some(entry.value)
+    }
+
+    /// Store the response of the request, failures only skip caching.
+    fn put<T: Serialize>(&self, key: &str, value: &T, now: DateTime<Utc>) {
+        let entry = Entry {
+            fetched_at: now.to_rfc3339(),
+            value,
+        };
+
+        let path = self.path(key);
+        let result = serde_json::to_vec(&entry)
+            .map_err(eyre::Report::from)
+            .and_then(|data| fs::write(&path, data).map_err(eyre::Report::from));
+
+        if let Err(err) = result {
+            warn!("Failed to write cache file {}: {err}", path.display());
+        }
+    }
+}
+
+/// Only keep characters that are safe in file names.
+fn file_name(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ',') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Wraps another source and caches its pods, owners and metrics on disk.
+pub struct CachedSource<'a> {
+    inner: &'a dyn Source,
+    cache: Cache,
+}
+
+impl<'a> CachedSource<'a> {
+    /// Cache the responses of the given source.
+    pub fn new(inner: &'a dyn Source, cache: Cache) -> Self {
+        Self { inner, cache }
+    }
+
+    /// See [`Cache::fetched_at`].
+    pub fn fetched_at(&self) -> Option<String> {
+        self.cache.fetched_at()
+    }
+}
+
+impl std::fmt::Debug for CachedSource<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedSource")
+            .field("cache", &self.cache)
+            .finish_non_exhaustive()
+    }
+}
+
+fn pod_key(prefix: &str, pod: &Pod) -> String {
+    format!(
+        "{prefix}-{}-{}",
+        pod.metadata.namespace.as_deref().unwrap_or_default(),
+        pod.metadata.name.as_deref().unwrap_or_default()
+    )
+}
+
+#[async_trait]
+impl Source for CachedSource<'_> {
+    async fn pods(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>> {
+        let key = if all_namespaces {
+            "pods-all".to_string()
+        } else {
+            format!("pods-{}", namespaces.join(","))
+        };
+
+        if let Some(pods) = self.cache.get(&key, Utc::now()) {
+            return Ok(pods);
+        }
+
+        let pods = self.inner.pods(namespaces, all_namespaces).await?;
+        self.cache.put(&key, &pods, Utc::now());
+
+        Ok(pods)
+    }
+
+    fn pod_owner(&self, pod: &Pod) -> Option<Owner> {
+        let key = pod_key("owner", pod);
+
+        if let Some(owner) = self.cache.get(&key, Utc::now()) {
+            return owner;
+        }
+
+        let owner = self.inner.pod_owner(pod);
+        self.cache.put(&key, &owner, Utc::now());
+
+        owner
+    }
+
+    fn owner_chain(&self, pod: &Pod) -> Vec<Owner> {
+        let key = pod_key("owner-chain", pod);
+
+        if let Some(chain) = self.cache.get(&key, Utc::now()) {
+            return chain;
+        }
+
+        let chain = self.inner.owner_chain(pod);
+        self.cache.put(&key, &chain, Utc::now());
+
+        chain
+    }
+
+    fn checks_container(&self, name: &str) -> bool {
+        self.inner.checks_container(name)
+    }
+
+    async fn pod_resource_usage(&self, namespace: &str, pod: &str) -> Result<Option<PodMetrics>> {
+        let key = format!("metrics-{namespace}-{pod}");
+
+        if let Some(usage) = self.cache.get(&key, Utc::now()) {
+            return Ok(usage);
+        }
+
+        let usage = self.inner.pod_resource_usage(namespace, pod).await?;
+        self.cache.put(&key, &usage, Utc::now());
+
+        Ok(usage)
+    }
+
+    /// Stored as a list, json objects can not be keyed by the namespace and
+    /// name of the pod.
+    async fn all_pod_resource_usage(&self) -> Result<HashMap<(String, String), PodMetrics>> {
+        let key = "metrics-all";
+
+        if let Some(usage) = self.cache.get::<Vec<PodMetrics>>(key, Utc::now()) {
+            return Ok(index_pod_metrics(usage));
+        }
+
+        let usage = self.inner.all_pod_resource_usage().await?;
+        self.cache
+            .put(key, &usage.values().collect::<Vec<_>>(), Utc::now());
+
+        Ok(usage)
+    }
+
+    async fn nodes(&self) -> Result<Vec<Node>> {
+        self.inner.nodes().await
+    }
+
+    async fn server_version(&self) -> Result<String> {
+        self.inner.server_version().await
+    }
+
+    async fn namespaces(&self) -> Result<Vec<String>> {
+        self.inner.namespaces().await
+    }
+
+    async fn namespace_list(&self) -> Result<Vec<Namespace>> {
+        self.inner.namespace_list().await
+    }
+
+    async fn node_events(&self) -> Result<Vec<Event>> {
+        self.inner.node_events().await
+    }
+
+    async fn statefulsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<StatefulSet>> {
+        self.inner.statefulsets(namespaces, all_namespaces).await
+    }
+
+    async fn daemonsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<DaemonSet>> {
+        self.inner.daemonsets(namespaces, all_namespaces).await
+    }
+
+    async fn jobs(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Job>> {
+        self.inner.jobs(namespaces, all_namespaces).await
+    }
+
+    async fn ingresses(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Ingress>> {
+        self.inner.ingresses(namespaces, all_namespaces).await
+    }
+
+    async fn cronjobs(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<CronJob>> {
+        self.inner.cronjobs(namespaces, all_namespaces).await
+    }
+
+    async fn services(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Service>> {
+        self.inner.services(namespaces, all_namespaces).await
+    }
+
+    async fn secrets(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Secret>> {
+        self.inner.secrets(namespaces, all_namespaces).await
+    }
+
+    async fn service_accounts(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ServiceAccount>> {
+        self.inner
+            .service_accounts(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn persistent_volume_claims(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<PersistentVolumeClaim>> {
+        self.inner
+            .persistent_volume_claims(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn endpoint_slices(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<EndpointSlice>> {
+        self.inner.endpoint_slices(namespaces, all_namespaces).await
+    }
+
+    async fn config_maps(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ConfigMap>> {
+        self.inner.config_maps(namespaces, all_namespaces).await
+    }
+
+    async fn role_bindings(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<RoleBinding>> {
+        self.inner.role_bindings(namespaces, all_namespaces).await
+    }
+
+    async fn cluster_roles(&self) -> Result<Vec<ClusterRole>> {
+        self.inner.cluster_roles().await
+    }
+
+    async fn cluster_role_bindings(&self) -> Result<Vec<ClusterRoleBinding>> {
+        self.inner.cluster_role_bindings().await
+    }
+
+    async fn vertical_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<VerticalPodAutoscaler>> {
+        self.inner
+            .vertical_pod_autoscalers(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn horizontal_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<HorizontalPodAutoscaler>> {
+        self.inner
+            .horizontal_pod_autoscalers(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn deployments(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Deployment>> {
+        self.inner.deployments(namespaces, all_namespaces).await
+    }
+
+    async fn replica_sets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ReplicaSet>> {
+        self.inner.replica_sets(namespaces, all_namespaces).await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::{path::PathBuf, time::Duration};
+
+    use k8s_openapi::{
+        api::core::v1::Pod,
+        chrono::{self, TimeZone, Utc},
+    };
+
+    use super::{Cache, CacheOptions, CachedSource};
+    use crate::{
+        api::Owner,
+        source::{FileSource, Source},
+    };
+
+    fn options(name: &str, refresh: bool) -> CacheOptions {
+        let root: PathBuf = std::env::temp_dir().join(format!(
+            "k8s-tools-cache-test-{name}-{}",
+            std::process::id()
+        ));
+
+        let _ = std::fs::remove_dir_all(&root);
+
+        CacheOptions {
+            root,
+            ttl: Duration::from_secs(120),
+            refresh,
+        }
+    }
+
+    #[test]
+    fn ttl() {
+        let options = options("ttl", false);
+        let cache = Cache::new(&options, "kind-dev").unwrap();
+
+        let fetched = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let owner = Some(Owner {
+            kind: "ReplicaSet".to_string(),
+            name: "frontend-7d9c8b7f5".to_string(),
+            namespace: "web".to_string(),
+            ..Default::default()
+        });
+
+        cache.put("owner-web/frontend", &owner, fetched);
+        assert!(options
+            .root
+            .join("kind-dev")
+            .join("owner-web_frontend.json")
+            .exists());
+
+        assert_eq!(None, cache.fetched_at());
+        assert_eq!(
+            Some(owner),
+            cache.get::<Option<Owner>>(
+                "owner-web/frontend",
+                fetched + chrono::Duration::seconds(119)
+            )
+        );
+        assert_eq!(Some(fetched.to_rfc3339()), cache.fetched_at());
+
+        assert_eq!(
+            None,
+            cache.get::<Option<Owner>>(
+                "owner-web/frontend",
+                fetched + chrono::Duration::seconds(120)
+            )
+        );
+        assert_eq!(None, cache.get::<Option<Owner>>("owner-web/other", fetched));
+
+        let refreshing = Cache::new(
+            &CacheOptions {
+                refresh: true,
+                ..options.clone()
+            },
+            "kind-dev",
+        )
+        .unwrap();
+        assert_eq!(
+            None,
+            refreshing.get::<Option<Owner>>("owner-web/frontend", fetched)
+        );
+
+        std::fs::remove_dir_all(options.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cached_source() {
+        const PODS: &str = include_str!("../resources/fixtures/pods.json");
+        const METRICS: &str = include_str!("../resources/fixtures/metrics.json");
+
+        let options = options("source", false);
+        let source = FileSource::from_readers(PODS.as_bytes(), Some(METRICS.as_bytes())).unwrap();
+
+        let first = CachedSource::new(&source, Cache::new(&options, "kind-dev").unwrap());
+        let pods = first.pods(Vec::new(), true).await.unwrap();
+        let usage = first
+            .pod_resource_usage("web", "frontend-7d9c8b7f5-abcde")
+            .await
+            .unwrap();
+        assert_eq!(None, first.fetched_at());
+
+        // The second run reads everything from the cache, even though the
+        // source it wraps is empty.
+        let empty = FileSource::default();
+        let second = CachedSource::new(&empty, Cache::new(&options, "kind-dev").unwrap());
+
+        let cached: Vec<Pod> = second.pods(Vec::new(), true).await.unwrap();
+        assert_eq!(pods, cached);
+
+        let cached = second
+            .pod_resource_usage("web", "frontend-7d9c8b7f5-abcde")
+            .await
+            .unwrap();
+        assert_eq!(
+            usage.map(|usage| usage.containers.len()),
+            cached.map(|usage| usage.containers.len())
+        );
+        assert!(second.fetched_at().is_some());
+
+        std::fs::remove_dir_all(options.root).unwrap();
+    }
+}
@@ -0,0 +1,534 @@
+//! Find required pod affinity terms that can never match.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use eyre::Result;
+use k8s_openapi::{
+    api::core::v1::{Pod, PodAffinityTerm},
+    apimachinery::pkg::apis::meta::v1::LabelSelector,
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{matches_selector, Finding, Severity},
+    source::Source,
+};
+
+/// Required affinity or anti affinity term of a workload that matches
+/// nothing.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct DeadAffinityTerm {
+    namespace: String,
+    owner: Option<Owner>,
+
+    /// First pod of the workload with the term.
+    pod_name: String,
+
+    /// Either `podAffinity` or `podAntiAffinity`.
+    affinity: String,
+
+    /// The term in a short form, for example
+    /// `app=db topologyKey=kubernetes.io/hostname`.
+    term: String,
+
+    /// Why the term can not match.
+    reason: String,
+
+    severity: Severity,
+}
+
+impl Finding for DeadAffinityTerm {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the required `podAffinity` and `podAntiAffinity` terms whose label
+/// selector matches no pod in the namespaces of the term or whose topology key
+/// is not a label of any node. Dead affinity terms are reported with a high
+/// severity as the pods stay pending once they are rescheduled, dead anti
+/// affinity terms only with a low severity as they have no effect.
+///
+/// Pods of namespaces the term selects outside of the given namespaces are
+/// read as well so the term is not reported because they were not read.
+pub async fn affinity_check(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<DeadAffinityTerm>> {
+    let pods = source.pods(namespaces.clone(), all_namespaces).await?;
+
+    let terms = pods
+        .iter()
+        .flat_map(|pod| {
+            required_terms(pod)
+                .into_iter()
+                .map(move |(affinity, term)| (pod, affinity, term))
+        })
+        .collect::<Vec<_>>();
+
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let nodes = source.nodes().await?;
+
+    let node_labels = nodes
+        .iter()
+        .filter_map(|node| node.metadata.labels.as_ref())
+        .flat_map(BTreeMap::keys)
+        .map(String::as_str)
+        .collect::<BTreeSet<_>>();
+
+    let namespace_list = if terms
+        .iter()
+        .any(|(_, _, term)| term.namespace_selector.is_some())
+    {
+        source.namespace_list().await?
+    } else {
+        Vec::new()
+    };
+
+    let namespace_labels = namespace_list
+        .iter()
+        .filter_map(|namespace| {
+            Some((
+                namespace.metadata.name.as_deref()?,
+                namespace.metadata.labels.as_ref(),
+            ))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let targets = terms
+        .iter()
+        .map(|(pod, _, term)| target_namespaces(pod, term, &namespace_labels))
+        .collect::<Vec<_>>();
+
+    let read = if all_namespaces {
+        None
+    } else if namespaces.is_empty() {
+        Some(
+            pods.iter()
+                .filter_map(|pod| pod.metadata.namespace.clone())
+                .collect(),
+        )
+    } else {
+        Some(namespaces.into_iter().collect())
+    };
+
+    let other_pods = unread_pods(source, read, &targets).await?;
+    let candidates = pods.iter().chain(&other_pods).collect::<Vec<_>>();
+
+    let mut seen = BTreeSet::new();
+    let mut findings = Vec::new();
+
+    for ((pod, affinity, term), targets) in terms.into_iter().zip(targets) {
+        let Some(reason) = dead_reason(term, &targets, &candidates, &node_labels) else {
+            continue;
+        };
+
+        let namespace = pod
+            .metadata
+            .namespace
+            .clone()
+            .expect("failed to get namespace");
+
+        let pod_name = pod.metadata.name.clone().expect("failed to get name");
+        let owner = source.pod_owner(pod);
+        let term = describe_term(term);
+
+        let workload = owner
+            .as_ref()
+            .map_or_else(|| pod_name.clone(), |owner| owner.name.clone());
+
+        if !seen.insert((
+            namespace.clone(),
+            workload,
+            affinity,
+            term.clone(),
+            reason.clone(),
+        )) {
+            continue;
+        }
+
+        findings.push(DeadAffinityTerm {
+            namespace,
+            owner,
+            pod_name,
+            affinity: affinity.to_string(),
+            term,
+            reason,
+            severity: if affinity == "podAffinity" {
+                Severity::High
+            } else {
+                Severity::Low
+            },
+        });
+    }
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+/// Pods of the target namespaces that are not part of the namespaces that
+/// were `read` already, `None` if all namespaces were read.
+async fn unread_pods(
+    source: &dyn Source,
+    read: Option<BTreeSet<String>>,
+    targets: &[BTreeSet<String>],
+) -> Result<Vec<Pod>> {
+    let Some(read) = read else {
+        return Ok(Vec::new());
+    };
+
+    let unread = targets
+        .iter()
+        .flatten()
+        .filter(|namespace| !read.contains(*namespace))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    if unread.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    source.pods(unread.into_iter().collect(), false).await
+}
+
+fn required_terms(pod: &Pod) -> Vec<(&'static str, &PodAffinityTerm)> {
+    let Some(affinity) = pod.spec.as_ref().and_then(|spec| spec.affinity.as_ref()) else {
+        return Vec::new();
+    };
+
+    let pod_affinity = affinity
+        .pod_affinity
+        .as_ref()
+        .and_then(|affinity| {
+            affinity
+                .required_during_scheduling_ignored_during_execution
+                .as_ref()
+        })
+        .into_iter()
+        .flatten()
+        .map(|term| ("podAffinity", term));
+
+    let pod_anti_affinity = affinity
+        .pod_anti_affinity
+        .as_ref()
+        .and_then(|affinity| {
+            affinity
+                .required_during_scheduling_ignored_during_execution
+                .as_ref()
+        })
+        .into_iter()
+        .flatten()
+        .map(|term| ("podAntiAffinity", term));
+
+    pod_affinity.chain(pod_anti_affinity).collect()
+}
+
+/// Namespaces the term selects pods from. Without `namespaces` and
+/// `namespaceSelector` this is the namespace of the pod, an empty
+/// `namespaceSelector` selects all namespaces.
+fn target_namespaces(
+    pod: &Pod,
+    term: &PodAffinityTerm,
+    namespace_labels: &BTreeMap<&str, Option<&BTreeMap<String, String>>>,
+) -> BTreeSet<String> {
+    let mut targets = term
+        .namespaces
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    if let Some(selector) = &term.namespace_selector {
+        targets.extend(
+            namespace_labels
+                .iter()
+                .filter(|(_, labels)| matches_selector(selector, **labels))
+                .map(|(name, _)| (*name).to_string()),
+        );
+    } else if targets.is_empty() {
+        targets.extend(pod.metadata.namespace.clone());
+    }
+
+    targets
+}
+
+fn dead_reason(
+    term: &PodAffinityTerm,
+    targets: &BTreeSet<String>,
+    candidates: &[&Pod],
+    node_labels: &BTreeSet<&str>,
+) -> Option<String> {
+    if !node_labels.contains(term.topology_key.as_str()) {
+        return Some(format!("no node has the label {}", term.topology_key));
+    }
+
+    if targets.is_empty() {
+        return Some("the namespace selector matches no namespace".to_string());
+    }
+
+    // A term without a label selector matches no pods.
+    let Some(selector) = &term.label_selector else {
+        return Some("the term has no label selector".to_string());
+    };
+
+    let matched = candidates.iter().any(|pod| {
+        pod.metadata
+            .namespace
+            .as_ref()
+            .is_some_and(|namespace| targets.contains(namespace))
+            && matches_selector(selector, pod.metadata.labels.as_ref())
+    });
+
+    (!matched).then(|| {
+        format!(
+            "the label selector matches no pod in the namespaces {}",
+            targets.iter().cloned().collect::<Vec<_>>().join(", ")
+        )
+    })
+}
+
+/// Term in the notation of `kubectl get -l` followed by the topology key and
+/// the namespaces.
+fn describe_term(term: &PodAffinityTerm) -> String {
+    let mut parts = vec![term
+        .label_selector
+        .as_ref()
+        .map_or_else(|| "<none>".to_string(), describe_selector)];
+
+    parts.push(format!("topologyKey={}", term.topology_key));
+
+    if let Some(namespaces) = term.namespaces.as_ref().filter(|list| !list.is_empty()) {
+        parts.push(format!("namespaces={}", namespaces.join(",")));
+    }
+
+    if let Some(selector) = &term.namespace_selector {
+        parts.push(format!("namespaceSelector={}", describe_selector(selector)));
+    }
+
+    parts.join(" ")
+}
+
+fn describe_selector(selector: &LabelSelector) -> String {
+    let labels = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .map(|(key, value)| format!("{key}={value}"));
+
+    let expressions = selector
+        .match_expressions
+        .iter()
+        .flatten()
+        .map(|expression| {
+            let values = expression.values.as_deref().unwrap_or_default().join(",");
+
+            match expression.operator.as_str() {
+                "Exists" => expression.key.clone(),
+                "DoesNotExist" => format!("!{}", expression.key),
+                operator => format!("{} {} ({values})", expression.key, operator.to_lowercase()),
+            }
+        });
+
+    let selector = labels.chain(expressions).collect::<Vec<_>>().join(",");
+
+    if selector.is_empty() {
+        "<all>".to_string()
+    } else {
+        selector
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    #[allow(clippy::too_many_lines)]
+    async fn affinity_check() {
+        let term = |selector: serde_json::Value, topology_key: &str| json!({ "labelSelector": selector, "topologyKey": topology_key });
+
+        let pod = |namespace: &str, name: &str, app: &str, affinity: serde_json::Value| {
+            json!({
+                "metadata": {
+                    "namespace": namespace,
+                    "name": name,
+                    "labels": { "app": app },
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "ReplicaSet",
+                        "name": app,
+                        "uid": app,
+                        "controller": true,
+                    }],
+                },
+                "spec": { "containers": [{ "name": app }], "affinity": affinity },
+            })
+        };
+
+        let hostname = "kubernetes.io/hostname";
+
+        let pods = json!({
+            "items": [
+                // Co-located with the cache, which only exists in another
+                // namespace that is selected by its labels.
+                pod(
+                    "web",
+                    "api-0",
+                    "api",
+                    json!({
+                        "podAffinity": {
+                            "requiredDuringSchedulingIgnoredDuringExecution": [{
+                                "labelSelector": { "matchLabels": { "app": "cache" } },
+                                "namespaceSelector": { "matchLabels": { "team": "web" } },
+                                "topologyKey": hostname,
+                            }],
+                        },
+                        "podAntiAffinity": {
+                            "requiredDuringSchedulingIgnoredDuringExecution": [
+                                term(json!({ "matchLabels": { "app": "api" } }), hostname),
+                            ],
+                        },
+                    }),
+                ),
+                // Both replicas have the same dead terms.
+                pod(
+                    "web",
+                    "frontend-0",
+                    "frontend",
+                    json!({
+                        "podAffinity": {
+                            "requiredDuringSchedulingIgnoredDuringExecution": [
+                                term(json!({ "matchLabels": { "app": "backend" } }), hostname),
+                                term(json!({ "matchLabels": { "app": "api" } }), "rack"),
+                            ],
+                        },
+                    }),
+                ),
+                pod(
+                    "web",
+                    "frontend-1",
+                    "frontend",
+                    json!({
+                        "podAffinity": {
+                            "requiredDuringSchedulingIgnoredDuringExecution": [
+                                term(json!({ "matchLabels": { "app": "backend" } }), hostname),
+                                term(json!({ "matchLabels": { "app": "api" } }), "rack"),
+                            ],
+                        },
+                    }),
+                ),
+                pod(
+                    "web",
+                    "batch-0",
+                    "batch",
+                    json!({
+                        "podAntiAffinity": {
+                            "requiredDuringSchedulingIgnoredDuringExecution": [{
+                                "labelSelector": {
+                                    "matchExpressions": [
+                                        { "key": "app", "operator": "In", "values": ["etl"] },
+                                    ],
+                                },
+                                "namespaces": ["jobs"],
+                                "topologyKey": hostname,
+                            }],
+                        },
+                    }),
+                ),
+                pod("cache", "cache-0", "cache", json!(null)),
+                pod("jobs", "report-0", "report", json!(null)),
+            ],
+        });
+
+        let nodes = json!({
+            "items": [{ "metadata": { "name": "node-a", "labels": { hostname: "node-a" } } }],
+        });
+
+        let namespaces = json!({
+            "items": [
+                { "metadata": { "name": "web" } },
+                { "metadata": { "name": "cache", "labels": { "team": "web" } } },
+                { "metadata": { "name": "jobs" } },
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(nodes.to_string().as_bytes())
+            .unwrap()
+            .with_namespaces(namespaces.to_string().as_bytes())
+            .unwrap();
+
+        let findings = super::affinity_check(&source, vec!["web".to_string()], false)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.pod_name.as_str(),
+                    finding.affinity.as_str(),
+                    finding.term.as_str(),
+                    finding.reason.as_str(),
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "batch-0",
+                    "podAntiAffinity",
+                    "app in (etl) topologyKey=kubernetes.io/hostname namespaces=jobs",
+                    "the label selector matches no pod in the namespaces jobs",
+                    Severity::Low
+                ),
+                (
+                    "frontend-0",
+                    "podAffinity",
+                    "app=api topologyKey=rack",
+                    "no node has the label rack",
+                    Severity::High
+                ),
+                (
+                    "frontend-0",
+                    "podAffinity",
+                    "app=backend topologyKey=kubernetes.io/hostname",
+                    "the label selector matches no pod in the namespaces web",
+                    Severity::High
+                ),
+            ],
+            findings
+        );
+    }
+}
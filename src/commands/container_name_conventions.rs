@@ -0,0 +1,233 @@
+//! Find containers whose names violate the naming conventions.
+
+use std::collections::BTreeSet;
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Container with a name that does not follow the naming conventions.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ContainerNameViolation {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    violations: Vec<String>,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ContainerNameViolation {
+    const DEFAULT_SEVERITY: Severity = Severity::Low;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Naming conventions container names have to follow.
+#[derive(Debug, Clone, Default)]
+pub struct NameConventions {
+    /// The whole name has to match this pattern.
+    pub pattern: Option<Regex>,
+
+    /// Maximum length of the name.
+    pub max_length: Option<usize>,
+}
+
+impl NameConventions {
+    /// Create conventions from an unanchored pattern. The pattern is anchored
+    /// so it has to match the whole name.
+    pub fn new(pattern: Option<&str>, max_length: Option<usize>) -> Result<Self> {
+        let pattern = pattern
+            .map(|pattern| Regex::new(&format!("^(?:{pattern})$")))
+            .transpose()?;
+
+        Ok(Self {
+            pattern,
+            max_length,
+        })
+    }
+
+    fn violations(&self, name: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(name) {
+                violations.push(format!("does not match pattern {pattern}"));
+            }
+        }
+
+        if let Some(max_length) = self.max_length {
+            if name.len() > max_length {
+                violations.push(format!("longer than {max_length} characters"));
+            }
+        }
+
+        violations
+    }
+}
+
+/// Get containers whose names do not match the pattern or are longer than
+/// the maximum length.
+pub async fn container_name_conventions(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    conventions: &NameConventions,
+    details: DetailOptions,
+) -> Result<Vec<ContainerNameViolation>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let violations = pods
+        .iter()
+        .flat_map(|pod| {
+            pod_name_violations(source, pod, conventions, &details)
+                .expect("failed to get container name violations")
+        })
+        .collect::<Vec<_>>();
+
+    Ok(violations)
+}
+
+fn pod_name_violations(
+    source: &dyn Source,
+    pod: &Pod,
+    conventions: &NameConventions,
+    details: &DetailOptions,
+) -> Result<BTreeSet<ContainerNameViolation>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let violations = spec
+        .containers
+        .iter()
+        .filter(|container| source.checks_container(&container.name))
+        .map(|container| (container, conventions.violations(&container.name)))
+        .filter(|(_, violations)| !violations.is_empty())
+        .map(|(container, violations)| ContainerNameViolation {
+            namespace: pod
+                .metadata
+                .namespace
+                .as_ref()
+                .expect("failed to get namespace")
+                .clone(),
+
+            owner: source.pod_owner(pod),
+
+            pod_name: pod
+                .metadata
+                .name
+                .as_ref()
+                .expect("failed to get name")
+                .clone(),
+
+            container_name: container.name.clone(),
+            violations,
+
+            severity: Severity::Low,
+            details: PodDetails::new(source, pod, details),
+        })
+        .collect();
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::api::core::v1::{Container, Pod, PodSpec};
+
+    use super::NameConventions;
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    #[test]
+    fn violations() {
+        let conventions = NameConventions::new(Some("[a-z][a-z0-9-]*"), Some(10)).unwrap();
+
+        assert!(conventions.violations("app").is_empty());
+        assert!(conventions.violations("app-server").is_empty());
+        assert_eq!(1, conventions.violations("app_server").len());
+        assert_eq!(1, conventions.violations("application-server").len());
+        assert_eq!(2, conventions.violations("Application_Server").len());
+    }
+
+    #[test]
+    fn pattern_is_anchored() {
+        let conventions = NameConventions::new(Some("[a-z]+"), None).unwrap();
+
+        assert!(conventions.violations("app").is_empty());
+        assert_eq!(1, conventions.violations("app-server").len());
+    }
+
+    #[test]
+    fn pod_name_violations() {
+        let pod = Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("pod".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(PodSpec {
+                containers: vec![
+                    Container {
+                        name: "kebab-case".to_string(),
+                        ..Default::default()
+                    },
+                    Container {
+                        name: "snake_case".to_string(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let conventions = NameConventions::new(Some("[a-z0-9]+(-[a-z0-9]+)*"), None).unwrap();
+
+        let output = super::pod_name_violations(
+            &FileSource::default(),
+            &pod,
+            &conventions,
+            &DetailOptions::default(),
+        )
+        .unwrap();
+
+        let names = output
+            .iter()
+            .map(|violation| violation.container_name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["snake_case"], names);
+    }
+}
@@ -0,0 +1,197 @@
+//! Find container ports without a name.
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Container port that has no name.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ContainerPortNameCheck {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    port_number: i32,
+    protocol: String,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ContainerPortNameCheck {
+    const DEFAULT_SEVERITY: Severity = Severity::Low;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the ports of containers that have no name. Service monitors and
+/// services can only refer to named ports by name. With `required_ports` only
+/// unnamed ports with one of the given numbers are returned, otherwise all
+/// unnamed ports are.
+pub async fn container_port_name_check(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    required_ports: Vec<i32>,
+    details: DetailOptions,
+) -> Result<Vec<ContainerPortNameCheck>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut findings = pods
+        .iter()
+        .map(|pod| unnamed_ports(source, pod, &required_ports, &details))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn unnamed_ports(
+    source: &dyn Source,
+    pod: &Pod,
+    required_ports: &[i32],
+    details: &DetailOptions,
+) -> Result<Vec<ContainerPortNameCheck>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let findings = spec
+        .containers
+        .iter()
+        .filter(|container| source.checks_container(&container.name))
+        .flat_map(|container| {
+            container
+                .ports
+                .iter()
+                .flatten()
+                .map(move |port| (container, port))
+        })
+        .filter(|(_, port)| port.name.as_deref().map_or(true, str::is_empty))
+        .filter(|(_, port)| {
+            required_ports.is_empty() || required_ports.contains(&port.container_port)
+        })
+        .map(|(container, port)| ContainerPortNameCheck {
+            namespace: pod
+                .metadata
+                .namespace
+                .as_ref()
+                .expect("failed to get namespace")
+                .clone(),
+
+            owner: source.pod_owner(pod),
+
+            pod_name: pod
+                .metadata
+                .name
+                .as_ref()
+                .expect("failed to get name")
+                .clone(),
+
+            container_name: container.name.clone(),
+            port_number: port.container_port,
+            protocol: port.protocol.clone().unwrap_or_else(|| "TCP".to_string()),
+            severity: Severity::Low,
+            details: PodDetails::new(source, pod, details),
+        })
+        .collect();
+
+    Ok(findings)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    #[tokio::test]
+    async fn container_port_name_check() {
+        let pods = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api-0" },
+                "spec": {
+                    "containers": [
+                        {
+                            "name": "api",
+                            "ports": [
+                                { "containerPort": 8080, "name": "http" },
+                                { "containerPort": 9090 },
+                            ],
+                        },
+                        {
+                            "name": "dns",
+                            "ports": [{ "containerPort": 53, "protocol": "UDP" }],
+                        },
+                        { "name": "sidecar" },
+                    ],
+                },
+            }],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let run = |required_ports| {
+            super::container_port_name_check(
+                &source,
+                Vec::new(),
+                true,
+                required_ports,
+                DetailOptions::default(),
+            )
+        };
+
+        let findings = run(Vec::new()).await.unwrap();
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.container_name.as_str(),
+                    finding.port_number,
+                    finding.protocol.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![("api", 9090, "TCP"), ("dns", 53, "UDP")], findings);
+
+        let findings = run(vec![8080, 9090]).await.unwrap();
+        let ports = findings
+            .iter()
+            .map(|finding| finding.port_number)
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![9090], ports);
+    }
+}
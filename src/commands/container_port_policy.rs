@@ -0,0 +1,269 @@
+//! Find container ports outside of the allowed port ranges.
+
+use std::{fmt, str::FromStr};
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Port of a container checked against the allowed port ranges.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ContainerPort {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    port: i32,
+    protocol: String,
+
+    /// Whether the port is outside of all allowed ranges.
+    out_of_range: bool,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ContainerPort {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Inclusive range of port numbers containers are allowed to expose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortRange {
+    min: i32,
+    max: i32,
+}
+
+impl PortRange {
+    fn contains(self, port: i32) -> bool {
+        (self.min..=self.max).contains(&port)
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.min, self.max)
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    /// Parse a range like `8000-8999`, a single port like `443` is a range of
+    /// one port.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (min, max) = s.split_once('-').unwrap_or((s, s));
+
+        let parse = |port: &str| {
+            port.trim()
+                .parse::<i32>()
+                .ok()
+                .filter(|port| (1..=65535).contains(port))
+                .ok_or_else(|| format!("invalid port {port:?} in range {s:?}"))
+        };
+
+        let (min, max) = (parse(min)?, parse(max)?);
+
+        if min > max {
+            return Err(format!("start of port range {s:?} is after its end"));
+        }
+
+        Ok(Self { min, max })
+    }
+}
+
+/// Get the ports of all containers and flag the ones outside of every allowed
+/// range. Ports inside an allowed range are reported as informational.
+pub async fn container_port_policy(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    allowed: &[PortRange],
+    details: DetailOptions,
+) -> Result<Vec<ContainerPort>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let ports = pods
+        .iter()
+        .map(|pod| container_ports(source, pod, allowed, &details))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    Ok(ports)
+}
+
+fn container_ports(
+    source: &dyn Source,
+    pod: &Pod,
+    allowed: &[PortRange],
+    details: &DetailOptions,
+) -> Result<Vec<ContainerPort>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let ports = spec
+        .containers
+        .iter()
+        .filter(|container| source.checks_container(&container.name))
+        .flat_map(|container| {
+            container
+                .ports
+                .iter()
+                .flatten()
+                .map(move |port| (container, port))
+        })
+        .map(|(container, port)| {
+            let out_of_range = !allowed
+                .iter()
+                .any(|range| range.contains(port.container_port));
+
+            ContainerPort {
+                namespace: pod
+                    .metadata
+                    .namespace
+                    .as_ref()
+                    .expect("failed to get namespace")
+                    .clone(),
+
+                owner: source.pod_owner(pod),
+
+                pod_name: pod
+                    .metadata
+                    .name
+                    .as_ref()
+                    .expect("failed to get name")
+                    .clone(),
+
+                container_name: container.name.clone(),
+                port: port.container_port,
+                protocol: port.protocol.clone().unwrap_or_else(|| "TCP".to_string()),
+                out_of_range,
+                severity: if out_of_range {
+                    Severity::Medium
+                } else {
+                    Severity::Info
+                },
+                details: PodDetails::new(source, pod, details),
+            }
+        })
+        .collect();
+
+    Ok(ports)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use super::PortRange;
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    #[test]
+    fn port_range() {
+        assert_eq!(
+            PortRange {
+                min: 8000,
+                max: 8999
+            },
+            "8000-8999".parse().unwrap()
+        );
+        assert_eq!(PortRange { min: 443, max: 443 }, "443".parse().unwrap());
+
+        assert!("9000-8000".parse::<PortRange>().is_err());
+        assert!("0-100".parse::<PortRange>().is_err());
+        assert!("8000-70000".parse::<PortRange>().is_err());
+        assert!("http".parse::<PortRange>().is_err());
+    }
+
+    #[tokio::test]
+    async fn container_port_policy() {
+        let pods = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api-0" },
+                "spec": {
+                    "containers": [
+                        {
+                            "name": "api",
+                            "ports": [
+                                { "containerPort": 8080 },
+                                { "containerPort": 22, "protocol": "TCP" },
+                            ],
+                        },
+                        {
+                            "name": "dns",
+                            "ports": [{ "containerPort": 53, "protocol": "UDP" }],
+                        },
+                        { "name": "worker" },
+                    ],
+                },
+            }],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let allowed = ["8000-8999".parse().unwrap(), "53".parse().unwrap()];
+
+        let ports = super::container_port_policy(
+            &source,
+            Vec::new(),
+            true,
+            &allowed,
+            DetailOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let ports = ports
+            .iter()
+            .map(|port| {
+                (
+                    port.container_name.as_str(),
+                    port.port,
+                    port.protocol.as_str(),
+                    port.out_of_range,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("api", 8080, "TCP", false),
+                ("api", 22, "TCP", true),
+                ("dns", 53, "UDP", false),
+            ],
+            ports
+        );
+    }
+}
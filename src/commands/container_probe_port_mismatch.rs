@@ -0,0 +1,283 @@
+//! Find http probes targeting ports the container does not expose.
+
+use std::collections::BTreeSet;
+
+use eyre::{bail, Result};
+use k8s_openapi::{
+    api::core::v1::{Container, Pod, Probe},
+    apimachinery::pkg::util::intstr::IntOrString,
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Http probe of a container targeting a port that is not in the port list of
+/// the container.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ProbePortMismatch {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    probe_type: String,
+    probe_port: String,
+    available_ports: Vec<String>,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ProbePortMismatch {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get liveness and readiness http probes that target a port number or name
+/// that is not defined in the ports of the container. A named port that does
+/// not exist makes the probe fail and is reported as high. The port list is
+/// only informational, so a port number that is missing from it is reported
+/// as info.
+pub async fn container_probe_port_mismatch(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    details: DetailOptions,
+) -> Result<Vec<ProbePortMismatch>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mismatches = pods
+        .iter()
+        .flat_map(|pod| {
+            pod_probe_port_mismatches(source, pod, &details)
+                .expect("failed to get probe port mismatches")
+        })
+        .collect::<Vec<_>>();
+
+    Ok(mismatches)
+}
+
+fn pod_probe_port_mismatches(
+    source: &dyn Source,
+    pod: &Pod,
+    details: &DetailOptions,
+) -> Result<BTreeSet<ProbePortMismatch>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let mismatches = spec
+        .containers
+        .iter()
+        .filter(|container| source.checks_container(&container.name))
+        .flat_map(|container| {
+            [
+                ("liveness", container.liveness_probe.as_ref()),
+                ("readiness", container.readiness_probe.as_ref()),
+            ]
+            .into_iter()
+            .filter_map(|(probe_type, probe)| {
+                mismatched_probe_port(container, probe?)
+                    .map(|(port, severity)| (probe_type, port, severity))
+            })
+            .map(|(probe_type, probe_port, severity)| ProbePortMismatch {
+                namespace: pod
+                    .metadata
+                    .namespace
+                    .as_ref()
+                    .expect("failed to get namespace")
+                    .clone(),
+
+                owner: source.pod_owner(pod),
+
+                pod_name: pod
+                    .metadata
+                    .name
+                    .as_ref()
+                    .expect("failed to get name")
+                    .clone(),
+
+                container_name: container.name.clone(),
+                probe_type: probe_type.to_string(),
+                probe_port,
+                available_ports: available_ports(container),
+
+                severity,
+                details: PodDetails::new(source, pod, details),
+            })
+        })
+        .collect();
+
+    Ok(mismatches)
+}
+
+/// Returns the port of the http probe and the severity of the mismatch if the
+/// container does not define it.
+fn mismatched_probe_port(container: &Container, probe: &Probe) -> Option<(String, Severity)> {
+    let port = &probe.http_get.as_ref()?.port;
+    let ports = container.ports.iter().flatten();
+
+    let found = match port {
+        IntOrString::Int(number) => ports.into_iter().any(|port| port.container_port == *number),
+
+        IntOrString::String(name) => ports
+            .into_iter()
+            .any(|port| port.name.as_ref() == Some(name)),
+    };
+
+    if found {
+        return None;
+    }
+
+    let mismatch = match port {
+        IntOrString::Int(number) => (number.to_string(), Severity::Info),
+        IntOrString::String(name) => (name.clone(), Severity::High),
+    };
+
+    Some(mismatch)
+}
+
+fn available_ports(container: &Container) -> Vec<String> {
+    container
+        .ports
+        .iter()
+        .flatten()
+        .map(|port| match &port.name {
+            Some(name) => format!("{name}:{}", port.container_port),
+            None => port.container_port.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::{
+        api::core::v1::{Container, ContainerPort, HTTPGetAction, Pod, PodSpec, Probe},
+        apimachinery::pkg::util::intstr::IntOrString,
+    };
+
+    use crate::{
+        commands::{DetailOptions, Severity},
+        source::FileSource,
+    };
+
+    #[allow(clippy::unnecessary_wraps)]
+    fn http_probe(port: IntOrString) -> Option<Probe> {
+        Some(Probe {
+            http_get: Some(HTTPGetAction {
+                port,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn probe_port_mismatches() {
+        let ports = Some(vec![ContainerPort {
+            container_port: 8080,
+            name: Some("http".to_string()),
+            ..Default::default()
+        }]);
+
+        let pod = Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("pod".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(PodSpec {
+                containers: vec![
+                    Container {
+                        name: "matching".to_string(),
+                        ports: ports.clone(),
+                        liveness_probe: http_probe(IntOrString::Int(8080)),
+                        readiness_probe: http_probe(IntOrString::String("http".to_string())),
+                        ..Default::default()
+                    },
+                    Container {
+                        name: "mismatching".to_string(),
+                        ports,
+                        liveness_probe: http_probe(IntOrString::Int(9090)),
+                        readiness_probe: http_probe(IntOrString::String("metrics".to_string())),
+                        ..Default::default()
+                    },
+                    Container {
+                        name: "no-ports".to_string(),
+                        readiness_probe: http_probe(IntOrString::Int(8080)),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let output = super::pod_probe_port_mismatches(
+            &FileSource::default(),
+            &pod,
+            &DetailOptions::default(),
+        )
+        .unwrap();
+
+        let output = output
+            .iter()
+            .map(|mismatch| {
+                (
+                    mismatch.container_name.as_str(),
+                    mismatch.probe_type.as_str(),
+                    mismatch.probe_port.as_str(),
+                    mismatch.available_ports.clone(),
+                    mismatch.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "mismatching",
+                    "liveness",
+                    "9090",
+                    vec!["http:8080".to_string()],
+                    Severity::Info
+                ),
+                (
+                    "mismatching",
+                    "readiness",
+                    "metrics",
+                    vec!["http:8080".to_string()],
+                    Severity::High
+                ),
+                ("no-ports", "readiness", "8080", vec![], Severity::Info),
+            ],
+            output
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Inventory the containers that likely run scratch or distroless images.
+
+use eyre::Result;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{Finding, Severity},
+    source::{glob_to_regex, Source},
+};
+
+/// Container together with a guess whether its image has a shell and the
+/// usual debugging utilities.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ScratchImage {
+    namespace: String,
+    pod_name: String,
+    owner: Option<Owner>,
+    container_name: String,
+    image: String,
+
+    /// Whether the image name matches one of the distroless patterns. Such
+    /// containers need `kubectl debug` with an ephemeral container instead of
+    /// `kubectl exec`.
+    is_likely_distroless: bool,
+
+    severity: Severity,
+}
+
+impl Finding for ScratchImage {
+    const DEFAULT_SEVERITY: Severity = Severity::Info;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// List the containers and init containers of all pods and mark the ones whose
+/// image matches one of the `distroless_patterns`. The patterns are globs
+/// supporting `*` and `?` that have to match the whole image name.
+pub async fn container_scratch_image(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    distroless_patterns: &[String],
+) -> Result<Vec<ScratchImage>> {
+    let patterns = distroless_patterns
+        .iter()
+        .map(|pattern| glob_to_regex(pattern))
+        .collect::<Vec<_>>();
+
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut inventory = pods
+        .iter()
+        .flat_map(|pod| {
+            let containers = pod.spec.iter().flat_map(|spec| {
+                spec.init_containers
+                    .iter()
+                    .flatten()
+                    .chain(&spec.containers)
+                    .filter(|container| source.checks_container(&container.name))
+            });
+
+            containers.map(|container| {
+                let image = container.image.clone().unwrap_or_default();
+
+                ScratchImage {
+                    namespace: pod
+                        .metadata
+                        .namespace
+                        .clone()
+                        .expect("failed to get namespace"),
+                    pod_name: pod.metadata.name.clone().expect("failed to get name"),
+                    owner: source.pod_owner(pod),
+                    container_name: container.name.clone(),
+                    is_likely_distroless: is_likely_distroless(&patterns, &image),
+                    image,
+                    severity: Severity::Info,
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    inventory.sort();
+
+    Ok(inventory)
+}
+
+fn is_likely_distroless(patterns: &[Regex], image: &str) -> bool {
+    patterns.iter().any(|pattern| pattern.is_match(image))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::source::FileSource;
+
+    #[tokio::test]
+    async fn container_scratch_image() {
+        let pods = json!({
+            "items": [
+                {
+                    "metadata": { "namespace": "web", "name": "api-0" },
+                    "spec": {
+                        "initContainers": [{ "name": "migrate", "image": "alpine:3.19" }],
+                        "containers": [{
+                            "name": "api",
+                            "image": "gcr.io/distroless/static-debian12:nonroot",
+                        }],
+                    },
+                },
+                {
+                    "metadata": { "namespace": "web", "name": "proxy-0" },
+                    "spec": {
+                        "containers": [
+                            { "name": "proxy", "image": "envoyproxy/envoy:v1.29.1" },
+                            { "name": "agent", "image": "scratch" },
+                        ],
+                    },
+                },
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let patterns = ["*/distroless/*".to_string(), "scratch".to_string()];
+
+        let inventory = super::container_scratch_image(&source, Vec::new(), true, &patterns)
+            .await
+            .unwrap();
+
+        let inventory = inventory
+            .iter()
+            .map(|container| {
+                (
+                    container.pod_name.as_str(),
+                    container.container_name.as_str(),
+                    container.is_likely_distroless,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("api-0", "api", true),
+                ("api-0", "migrate", false),
+                ("proxy-0", "agent", true),
+                ("proxy-0", "proxy", false),
+            ],
+            inventory
+        );
+    }
+}
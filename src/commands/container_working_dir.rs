@@ -0,0 +1,268 @@
+//! Find containers whose working directory is at a privileged path.
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Container with its working directory at or below a forbidden path.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ContainerWorkingDir {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    working_dir: String,
+
+    /// Forbidden path the working directory matched.
+    forbidden_path: String,
+
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ContainerWorkingDir {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get containers and init containers whose `workingDir` is one of the
+/// forbidden paths or below one of them. A forbidden path of `/` only matches
+/// the root itself. Containers without a `workingDir` use the one of their
+/// image and are not checked. With `flag_root_workdir` only containers working
+/// in `/` are returned.
+pub async fn container_working_dir(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    forbidden_paths: Vec<String>,
+    flag_root_workdir: bool,
+    details: DetailOptions,
+) -> Result<Vec<ContainerWorkingDir>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let forbidden_paths = if flag_root_workdir {
+        vec!["/".to_string()]
+    } else {
+        forbidden_paths
+    };
+
+    let mut findings = pods
+        .iter()
+        .map(|pod| forbidden_working_dirs(source, pod, &forbidden_paths, &details))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn forbidden_working_dirs(
+    source: &dyn Source,
+    pod: &Pod,
+    forbidden_paths: &[String],
+    details: &DetailOptions,
+) -> Result<Vec<ContainerWorkingDir>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let findings = spec
+        .init_containers
+        .iter()
+        .flatten()
+        .chain(&spec.containers)
+        .filter(|container| source.checks_container(&container.name))
+        .filter_map(|container| {
+            let working_dir = container.working_dir.as_deref()?;
+            let forbidden_path = forbidden_path(working_dir, forbidden_paths)?;
+
+            Some(ContainerWorkingDir {
+                namespace: pod
+                    .metadata
+                    .namespace
+                    .as_ref()
+                    .expect("failed to get namespace")
+                    .clone(),
+
+                owner: source.pod_owner(pod),
+
+                pod_name: pod
+                    .metadata
+                    .name
+                    .as_ref()
+                    .expect("failed to get name")
+                    .clone(),
+
+                container_name: container.name.clone(),
+                working_dir: working_dir.to_string(),
+                forbidden_path: forbidden_path.to_string(),
+
+                // Many images work in the root, other paths are picked on
+                // purpose.
+                severity: if forbidden_path == "/" {
+                    Severity::Low
+                } else {
+                    Severity::Medium
+                },
+
+                details: PodDetails::new(source, pod, details),
+            })
+        })
+        .collect();
+
+    Ok(findings)
+}
+
+/// The forbidden path the working directory is equal to or below.
+fn forbidden_path<'a>(working_dir: &str, forbidden_paths: &'a [String]) -> Option<&'a str> {
+    let working_dir = working_dir.trim_end_matches('/');
+
+    forbidden_paths
+        .iter()
+        .find(|forbidden| {
+            let forbidden = forbidden.trim_end_matches('/');
+
+            // Every path is below `/`, so it only matches the root itself.
+            if forbidden.is_empty() {
+                return working_dir.is_empty();
+            }
+
+            working_dir == forbidden
+                || working_dir
+                    .strip_prefix(forbidden)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        commands::{DetailOptions, Severity},
+        source::FileSource,
+    };
+
+    fn forbidden_paths() -> Vec<String> {
+        ["/", "/etc", "/proc", "/sys"]
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn forbidden_path() {
+        let forbidden_paths = forbidden_paths();
+
+        let testcases = [
+            ("/", Some("/")),
+            ("//", Some("/")),
+            ("/etc", Some("/etc")),
+            ("/etc/nginx/", Some("/etc")),
+            ("/proc/self", Some("/proc")),
+            ("/sys", Some("/sys")),
+            ("/app", None),
+            ("/etcd", None),
+            ("/system", None),
+        ];
+
+        for (working_dir, expected) in testcases {
+            assert_eq!(
+                expected,
+                super::forbidden_path(working_dir, &forbidden_paths),
+                "{working_dir}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn container_working_dir() {
+        let pods = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api-0" },
+                "spec": {
+                    "initContainers": [{ "name": "setup", "workingDir": "/etc/app" }],
+                    "containers": [
+                        { "name": "api", "workingDir": "/app" },
+                        { "name": "debug", "workingDir": "/" },
+                        { "name": "sidecar" },
+                    ],
+                },
+            }],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let run = |flag_root_workdir| {
+            super::container_working_dir(
+                &source,
+                Vec::new(),
+                true,
+                forbidden_paths(),
+                flag_root_workdir,
+                DetailOptions::default(),
+            )
+        };
+
+        let findings = run(false).await.unwrap();
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.container_name.as_str(),
+                    finding.forbidden_path.as_str(),
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("debug", "/", Severity::Low),
+                ("setup", "/etc", Severity::Medium)
+            ],
+            findings
+        );
+
+        let findings = run(true).await.unwrap();
+        let containers = findings
+            .iter()
+            .map(|finding| finding.container_name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["debug"], containers);
+    }
+}
@@ -0,0 +1,313 @@
+//! Find workload pods that run on control plane nodes.
+
+use std::collections::BTreeMap;
+
+use eyre::Result;
+use k8s_openapi::api::core::v1::{Node, Pod, Taint, Toleration};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{tolerates, Finding, Severity},
+    source::Source,
+};
+
+/// Labels that mark control plane nodes, `master` is used by older clusters.
+const CONTROL_PLANE_LABELS: &[&str] = &[
+    "node-role.kubernetes.io/control-plane",
+    "node-role.kubernetes.io/master",
+];
+
+/// Pod scheduled on a control plane node.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ControlPlanePod {
+    namespace: String,
+    pod_name: String,
+    owner: Option<Owner>,
+    node_name: String,
+
+    /// Tolerations of the pod that match the taints of the node, for example
+    /// `node-role.kubernetes.io/control-plane:NoSchedule`. A toleration for
+    /// every taint is shown as `*`. Empty if the node has no taints keeping
+    /// pods off it.
+    tolerations: Vec<String>,
+
+    severity: Severity,
+}
+
+impl Finding for ControlPlanePod {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the pods outside of `ignore_namespaces` that are scheduled on nodes
+/// with a control plane label. Pods of daemonsets are reported with a low
+/// severity as they run on every node by design.
+pub async fn control_plane_pods(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    ignore_namespaces: &[String],
+) -> Result<Vec<ControlPlanePod>> {
+    let nodes = source.nodes().await?;
+
+    let control_plane = nodes
+        .iter()
+        .filter(|node| is_control_plane(node))
+        .filter_map(|node| Some((node.metadata.name.as_deref()?, node)))
+        .collect::<BTreeMap<_, _>>();
+
+    if control_plane.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut findings = pods
+        .iter()
+        .filter(|pod| {
+            pod.metadata
+                .namespace
+                .as_ref()
+                .is_some_and(|namespace| !ignore_namespaces.contains(namespace))
+        })
+        .filter_map(|pod| {
+            let node_name = pod.spec.as_ref()?.node_name.as_deref()?;
+            let node = control_plane.get(node_name)?;
+
+            Some(control_plane_pod(source, pod, node_name, node))
+        })
+        .collect::<Vec<_>>();
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn is_control_plane(node: &Node) -> bool {
+    node.metadata.labels.as_ref().is_some_and(|labels| {
+        CONTROL_PLANE_LABELS
+            .iter()
+            .any(|label| labels.contains_key(*label))
+    })
+}
+
+fn control_plane_pod(
+    source: &dyn Source,
+    pod: &Pod,
+    node_name: &str,
+    node: &Node,
+) -> ControlPlanePod {
+    let owner = source.pod_owner(pod);
+
+    let pod_tolerations = pod
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.tolerations.as_deref())
+        .unwrap_or_default();
+
+    let mut tolerations = node
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.taints.as_ref())
+        .into_iter()
+        .flatten()
+        .filter(|taint| taint.effect == "NoSchedule" || taint.effect == "NoExecute")
+        .flat_map(|taint| matching_tolerations(pod_tolerations, taint))
+        .collect::<Vec<_>>();
+
+    tolerations.sort();
+    tolerations.dedup();
+
+    let severity = if owner
+        .as_ref()
+        .is_some_and(|owner| owner.kind == "DaemonSet")
+    {
+        Severity::Low
+    } else {
+        Severity::Medium
+    };
+
+    ControlPlanePod {
+        namespace: pod
+            .metadata
+            .namespace
+            .clone()
+            .expect("failed to get namespace"),
+        pod_name: pod.metadata.name.clone().expect("failed to get name"),
+        owner,
+        node_name: node_name.to_string(),
+        tolerations,
+        severity,
+    }
+}
+
+fn matching_tolerations<'a>(
+    tolerations: &'a [Toleration],
+    taint: &'a Taint,
+) -> impl Iterator<Item = String> + 'a {
+    tolerations
+        .iter()
+        .filter(|toleration| tolerates(toleration, taint))
+        .map(describe_toleration)
+}
+
+/// Toleration in the `key=value:effect` notation of `kubectl taint`.
+fn describe_toleration(toleration: &Toleration) -> String {
+    let key = toleration.key.as_deref().unwrap_or_default();
+
+    let mut description = match (toleration.operator.as_deref(), key) {
+        (Some("Exists"), "") => "*".to_string(),
+        (Some("Exists"), key) => key.to_string(),
+        (_, key) => format!("{key}={}", toleration.value.as_deref().unwrap_or_default()),
+    };
+
+    if let Some(effect) = toleration
+        .effect
+        .as_deref()
+        .filter(|effect| !effect.is_empty())
+    {
+        description.push(':');
+        description.push_str(effect);
+    }
+
+    description
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    async fn control_plane_pods() {
+        let node = |name: &str, role: &str, taints: serde_json::Value| {
+            json!({
+                "metadata": {
+                    "name": name,
+                    "labels": { format!("node-role.kubernetes.io/{role}"): "" },
+                },
+                "spec": { "taints": taints },
+            })
+        };
+
+        let nodes = json!({
+            "items": [
+                node(
+                    "cp-0",
+                    "control-plane",
+                    json!([{
+                        "key": "node-role.kubernetes.io/control-plane",
+                        "effect": "NoSchedule",
+                    }]),
+                ),
+                node("master-0", "master", json!([])),
+                node("worker-0", "worker", json!([])),
+            ],
+        });
+
+        let pod = |namespace: &str, name: &str, node: &str, owner: &str, tolerations| {
+            json!({
+                "metadata": {
+                    "namespace": namespace,
+                    "name": name,
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": owner,
+                        "name": name,
+                        "uid": name,
+                        "controller": true,
+                    }],
+                },
+                "spec": {
+                    "nodeName": node,
+                    "containers": [{ "name": "app" }],
+                    "tolerations": tolerations,
+                },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod(
+                    "web",
+                    "api-0",
+                    "cp-0",
+                    "ReplicaSet",
+                    json!([
+                        { "operator": "Exists" },
+                        {
+                            "key": "node-role.kubernetes.io/control-plane",
+                            "operator": "Exists",
+                            "effect": "NoSchedule",
+                        },
+                        { "key": "node.kubernetes.io/not-ready", "operator": "Exists" },
+                    ]),
+                ),
+                pod("monitoring", "exporter", "master-0", "DaemonSet", json!([])),
+                pod("web", "api-1", "worker-0", "ReplicaSet", json!([])),
+                pod("kube-system", "etcd", "cp-0", "Node", json!([{ "operator": "Exists" }])),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(nodes.to_string().as_bytes())
+            .unwrap();
+
+        let findings =
+            super::control_plane_pods(&source, Vec::new(), true, &["kube-system".to_string()])
+                .await
+                .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.pod_name.as_str(),
+                    finding.node_name.as_str(),
+                    finding.tolerations.clone(),
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("exporter", "master-0", Vec::new(), Severity::Low),
+                (
+                    "api-0",
+                    "cp-0",
+                    vec![
+                        "*".to_string(),
+                        "node-role.kubernetes.io/control-plane:NoSchedule".to_string()
+                    ],
+                    Severity::Medium
+                ),
+            ],
+            findings
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Find nodes a daemonset has no running pod on.
+
+use std::collections::BTreeMap;
+
+use eyre::{bail, Result};
+use k8s_openapi::api::{
+    apps::v1::DaemonSet,
+    core::v1::{Node, Pod, Taint, Toleration},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{tolerates, Finding, Severity},
+    source::Source,
+};
+
+/// Taints the daemonset controller tolerates on its own for every daemonset
+/// pod.
+const DEFAULT_TOLERATED_TAINTS: &[&str] = &[
+    "node.kubernetes.io/not-ready",
+    "node.kubernetes.io/unreachable",
+    "node.kubernetes.io/disk-pressure",
+    "node.kubernetes.io/memory-pressure",
+    "node.kubernetes.io/pid-pressure",
+    "node.kubernetes.io/unschedulable",
+    "node.kubernetes.io/network-unavailable",
+];
+
+/// Node without a running pod of a daemonset.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct UncoveredNode {
+    namespace: String,
+    daemonset_name: String,
+    node_name: String,
+
+    /// Why the daemonset has no running pod on the node as far as it can be
+    /// told from the pods, the node taints and the node selector.
+    reason: String,
+
+    severity: Severity,
+}
+
+impl Finding for UncoveredNode {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the nodes each daemonset has no running pod on. Nodes excluded by the
+/// node selector of the daemonset are reported with a low severity as that is
+/// usually intended.
+pub async fn daemonset_node_coverage(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<UncoveredNode>> {
+    let daemonsets = source
+        .daemonsets(namespaces.clone(), all_namespaces)
+        .await?;
+    let pods = source.pods(namespaces, all_namespaces).await?;
+    let nodes = source.nodes().await?;
+
+    let mut findings = Vec::new();
+
+    for daemonset in &daemonsets {
+        findings.extend(uncovered_nodes(daemonset, &pods, &nodes)?);
+    }
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn uncovered_nodes(
+    daemonset: &DaemonSet,
+    pods: &[Pod],
+    nodes: &[Node],
+) -> Result<Vec<UncoveredNode>> {
+    let Some(spec) = &daemonset.spec else {
+        bail!("DaemonSet has no spec");
+    };
+
+    let namespace = daemonset
+        .metadata
+        .namespace
+        .as_ref()
+        .expect("failed to get namespace");
+
+    let name = daemonset
+        .metadata
+        .name
+        .as_ref()
+        .expect("failed to get name");
+
+    // Pods of the daemonset by the node they are on, a node can have more
+    // than one while a pod is replaced.
+    let mut pods_by_node: BTreeMap<&str, Vec<&Pod>> = BTreeMap::new();
+
+    for pod in pods.iter().filter(|pod| owned_by(pod, daemonset)) {
+        if let Some(node_name) = pod.spec.as_ref().and_then(|spec| spec.node_name.as_ref()) {
+            pods_by_node.entry(node_name).or_default().push(pod);
+        }
+    }
+
+    let pod_spec = spec.template.spec.as_ref();
+    let tolerations = pod_spec
+        .and_then(|spec| spec.tolerations.as_deref())
+        .unwrap_or_default();
+    let node_selector = pod_spec.and_then(|spec| spec.node_selector.as_ref());
+
+    let mut findings = Vec::new();
+
+    for node in nodes {
+        let node_name = node.metadata.name.as_ref().expect("failed to get name");
+        let node_pods = pods_by_node.get(node_name.as_str());
+
+        if node_pods.is_some_and(|pods| pods.iter().any(|pod| pod_phase(pod) == "Running")) {
+            continue;
+        }
+
+        let (reason, severity) = if let Some(pod) = node_pods.and_then(|pods| pods.first()) {
+            (
+                format!(
+                    "pod {} is {}",
+                    pod.metadata.name.as_deref().unwrap_or_default(),
+                    pod_phase(pod)
+                ),
+                Severity::High,
+            )
+        } else if let Some((key, value)) = node_selector.and_then(|selector| {
+            selector
+                .iter()
+                .find(|(key, value)| node_label(node, key) != Some(value.as_str()))
+        }) {
+            (
+                format!("node does not match the node selector {key}={value}"),
+                Severity::Low,
+            )
+        } else if let Some(taint) = untolerated_taint(node, tolerations) {
+            (
+                format!(
+                    "node has the taint {}={}:{} which is not tolerated",
+                    taint.key,
+                    taint.value.as_deref().unwrap_or_default(),
+                    taint.effect
+                ),
+                Severity::Medium,
+            )
+        } else {
+            (
+                "no pod was scheduled on the node".to_string(),
+                Severity::High,
+            )
+        };
+
+        findings.push(UncoveredNode {
+            namespace: namespace.clone(),
+            daemonset_name: name.clone(),
+            node_name: node_name.clone(),
+            reason,
+            severity,
+        });
+    }
+
+    Ok(findings)
+}
+
+fn owned_by(pod: &Pod, daemonset: &DaemonSet) -> bool {
+    pod.metadata.namespace == daemonset.metadata.namespace
+        && pod.metadata.owner_references.iter().flatten().any(|owner| {
+            owner.kind == "DaemonSet"
+                && daemonset.metadata.uid.as_ref().map_or_else(
+                    || daemonset.metadata.name.as_ref() == Some(&owner.name),
+                    |uid| *uid == owner.uid,
+                )
+        })
+}
+
+fn pod_phase(pod: &Pod) -> &str {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.phase.as_deref())
+        .unwrap_or("Unknown")
+}
+
+fn node_label<'a>(node: &'a Node, key: &str) -> Option<&'a str> {
+    node.metadata
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get(key))
+        .map(String::as_str)
+}
+
+/// First taint of the node that keeps the daemonset pod from being scheduled
+/// or running on it.
+fn untolerated_taint<'a>(node: &'a Node, tolerations: &[Toleration]) -> Option<&'a Taint> {
+    node.spec
+        .as_ref()
+        .and_then(|spec| spec.taints.as_ref())
+        .into_iter()
+        .flatten()
+        .filter(|taint| taint.effect == "NoSchedule" || taint.effect == "NoExecute")
+        .filter(|taint| !DEFAULT_TOLERATED_TAINTS.contains(&taint.key.as_str()))
+        .find(|taint| {
+            !tolerations
+                .iter()
+                .any(|toleration| tolerates(toleration, taint))
+        })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    fn node(
+        name: &str,
+        labels: &serde_json::Value,
+        taints: &serde_json::Value,
+    ) -> serde_json::Value {
+        json!({
+            "metadata": { "name": name, "labels": labels },
+            "spec": { "taints": taints },
+        })
+    }
+
+    fn pod(name: &str, node: &str, phase: &str) -> serde_json::Value {
+        json!({
+            "metadata": {
+                "namespace": "monitoring",
+                "name": name,
+                "ownerReferences": [{
+                    "apiVersion": "apps/v1",
+                    "kind": "DaemonSet",
+                    "name": "exporter",
+                    "uid": "1234",
+                    "controller": true,
+                }],
+            },
+            "spec": { "nodeName": node, "containers": [{ "name": "exporter" }] },
+            "status": { "phase": phase },
+        })
+    }
+
+    #[tokio::test]
+    async fn daemonset_node_coverage() {
+        let pods = json!({
+            "items": [pod("exporter-a", "node-a", "Running"), pod("exporter-b", "node-b", "Pending")],
+        });
+
+        let nodes = json!({
+            "items": [
+                node("node-a", &json!({ "pool": "default" }), &json!([])),
+                node("node-b", &json!({ "pool": "default" }), &json!([])),
+                node(
+                    "node-c",
+                    &json!({ "pool": "default" }),
+                    &json!([
+                        { "key": "node.kubernetes.io/unschedulable", "effect": "NoSchedule" },
+                        { "key": "dedicated", "value": "gpu", "effect": "NoSchedule" },
+                    ]),
+                ),
+                node(
+                    "node-d",
+                    &json!({ "pool": "default" }),
+                    &json!([{ "key": "tolerated", "value": "yes", "effect": "NoExecute" }]),
+                ),
+                node("node-e", &json!({ "pool": "batch" }), &json!([])),
+            ],
+        });
+
+        let daemonsets = json!({
+            "items": [{
+                "metadata": { "namespace": "monitoring", "name": "exporter", "uid": "1234" },
+                "spec": {
+                    "selector": {},
+                    "template": {
+                        "spec": {
+                            "containers": [{ "name": "exporter" }],
+                            "nodeSelector": { "pool": "default" },
+                            "tolerations": [{ "key": "tolerated", "operator": "Exists" }],
+                        },
+                    },
+                },
+            }],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(nodes.to_string().as_bytes())
+            .unwrap()
+            .with_daemonsets(daemonsets.to_string().as_bytes())
+            .unwrap();
+
+        let findings = super::daemonset_node_coverage(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.node_name.as_str(),
+                    finding.reason.as_str(),
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("node-b", "pod exporter-b is Pending", Severity::High),
+                (
+                    "node-c",
+                    "node has the taint dedicated=gpu:NoSchedule which is not tolerated",
+                    Severity::Medium
+                ),
+                ("node-d", "no pod was scheduled on the node", Severity::High),
+                (
+                    "node-e",
+                    "node does not match the node selector pool=default",
+                    Severity::Low
+                ),
+            ],
+            findings
+        );
+    }
+}
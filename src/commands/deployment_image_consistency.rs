@@ -0,0 +1,205 @@
+//! Find deployments whose pods run different images for the same container.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use eyre::Result;
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, PodPhase, Severity},
+    source::Source,
+};
+
+/// Label the deployment controller sets on the replica sets and pods it
+/// creates, the name of a replica set is the deployment name followed by it.
+const POD_TEMPLATE_HASH_LABEL: &str = "pod-template-hash";
+
+/// Container of a deployment that runs with more than one image.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct InconsistentImages {
+    namespace: String,
+    deployment_name: String,
+    container_name: String,
+    images_found: Vec<String>,
+    pod_count_per_image: BTreeMap<String, usize>,
+    severity: Severity,
+}
+
+impl Finding for InconsistentImages {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Group the running pods by their deployment and get the containers that do
+/// not run the same image in every pod, for example during a stuck rolling
+/// update.
+pub async fn deployment_image_consistency(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<InconsistentImages>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut images: BTreeMap<(String, String, String), BTreeMap<String, BTreeSet<&str>>> =
+        BTreeMap::new();
+
+    for pod in pods
+        .iter()
+        .filter(|pod| PodPhase::matches(pod, &[PodPhase::Running]))
+    {
+        let Some(deployment) = deployment_name(source, pod) else {
+            continue;
+        };
+
+        let Some(spec) = &pod.spec else {
+            continue;
+        };
+
+        let namespace = pod
+            .metadata
+            .namespace
+            .clone()
+            .expect("failed to get namespace");
+
+        let pod_name = pod.metadata.name.as_deref().expect("failed to get name");
+
+        for container in &spec.containers {
+            if !source.checks_container(&container.name) {
+                continue;
+            }
+
+            images
+                .entry((
+                    namespace.clone(),
+                    deployment.clone(),
+                    container.name.clone(),
+                ))
+                .or_default()
+                .entry(container.image.clone().unwrap_or_default())
+                .or_default()
+                .insert(pod_name);
+        }
+    }
+
+    let inconsistent = images
+        .into_iter()
+        .filter(|(_, pods_per_image)| pods_per_image.len() > 1)
+        .map(
+            |((namespace, deployment_name, container_name), pods_per_image)| InconsistentImages {
+                namespace,
+                deployment_name,
+                container_name,
+                images_found: pods_per_image.keys().cloned().collect(),
+                pod_count_per_image: pods_per_image
+                    .into_iter()
+                    .map(|(image, pods)| (image, pods.len()))
+                    .collect(),
+                severity: Severity::Medium,
+            },
+        )
+        .collect();
+
+    Ok(inconsistent)
+}
+
+/// Name of the deployment of the pod. Sources that do not resolve owners past
+/// the replica set derive it from the replica set name and the pod template
+/// hash.
+fn deployment_name(source: &dyn Source, pod: &Pod) -> Option<String> {
+    let owner = source.pod_owner(pod)?;
+
+    match owner.kind.as_str() {
+        "Deployment" => Some(owner.name),
+
+        "ReplicaSet" => {
+            let hash = pod.metadata.labels.as_ref()?.get(POD_TEMPLATE_HASH_LABEL)?;
+
+            owner
+                .name
+                .strip_suffix(hash.as_str())?
+                .strip_suffix('-')
+                .map(ToString::to_string)
+        }
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use crate::source::FileSource;
+
+    #[tokio::test]
+    async fn deployment_image_consistency() {
+        let pod = |name: &str, hash: &str, image: &str, phase: &str| {
+            json!({
+                "metadata": {
+                    "namespace": "web",
+                    "name": name,
+                    "labels": { "pod-template-hash": hash },
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "ReplicaSet",
+                        "name": format!("api-{hash}"),
+                        "uid": hash,
+                        "controller": true,
+                    }],
+                },
+                "spec": {
+                    "containers": [
+                        { "name": "api", "image": image },
+                        { "name": "proxy", "image": "envoy:1.29" },
+                    ],
+                },
+                "status": { "phase": phase },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("api-5d8f-a", "5d8f", "api:1.0", "Running"),
+                pod("api-5d8f-b", "5d8f", "api:1.0", "Running"),
+                pod("api-7c9b-a", "7c9b", "api:1.1", "Running"),
+                pod("api-7c9b-b", "7c9b", "api:1.2", "Pending"),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let inconsistent = super::deployment_image_consistency(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        assert_eq!(1, inconsistent.len());
+
+        let images = &inconsistent[0];
+        assert_eq!("api", images.deployment_name);
+        assert_eq!("api", images.container_name);
+        assert_eq!(
+            vec!["api:1.0".to_string(), "api:1.1".to_string()],
+            images.images_found
+        );
+        assert_eq!(
+            BTreeMap::from([("api:1.0".to_string(), 2), ("api:1.1".to_string(), 1)]),
+            images.pod_count_per_image
+        );
+    }
+}
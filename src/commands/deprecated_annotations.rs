@@ -0,0 +1,276 @@
+//! Find objects using annotations that were replaced by typed fields.
+
+use std::collections::BTreeMap;
+
+use eyre::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Annotation that was replaced by a field and stops working or already
+/// stopped working.
+struct DeprecatedAnnotation {
+    /// Key of the annotation, a trailing `*` matches any suffix.
+    annotation: &'static str,
+
+    /// What to use instead.
+    replacement: &'static str,
+
+    severity: Severity,
+}
+
+/// The checked annotations, extend this to check for more of them.
+const DEPRECATED_ANNOTATIONS: &[DeprecatedAnnotation] = &[
+    DeprecatedAnnotation {
+        annotation: "kubernetes.io/ingress.class",
+        replacement: "spec.ingressClassName",
+        severity: Severity::Low,
+    },
+    DeprecatedAnnotation {
+        annotation: "seccomp.security.alpha.kubernetes.io/*",
+        replacement: "securityContext.seccompProfile",
+        severity: Severity::Medium,
+    },
+    DeprecatedAnnotation {
+        annotation: "container.seccomp.security.alpha.kubernetes.io/*",
+        replacement: "securityContext.seccompProfile of the container",
+        severity: Severity::Medium,
+    },
+    DeprecatedAnnotation {
+        annotation: "container.apparmor.security.beta.kubernetes.io/*",
+        replacement: "securityContext.appArmorProfile of the container",
+        severity: Severity::Low,
+    },
+    DeprecatedAnnotation {
+        annotation: "service.beta.kubernetes.io/external-traffic",
+        replacement: "spec.externalTrafficPolicy",
+        severity: Severity::Medium,
+    },
+];
+
+impl DeprecatedAnnotation {
+    fn matches(&self, key: &str) -> bool {
+        match self.annotation.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix),
+            None => key == self.annotation,
+        }
+    }
+}
+
+/// Object with an annotation that has a typed replacement.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct DeprecatedAnnotationUsage {
+    namespace: String,
+    kind: String,
+    name: String,
+
+    /// Owner of pods, the workload the annotation has to be changed in.
+    owner: Option<Owner>,
+
+    annotation: String,
+    replacement: String,
+    severity: Severity,
+}
+
+impl Finding for DeprecatedAnnotationUsage {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get pods, services and ingresses using one of the
+/// [`DEPRECATED_ANNOTATIONS`]. Workloads are checked through their pods as the
+/// annotations of the pod template end up on them, the owner of the pod points
+/// at the workload.
+pub async fn deprecated_annotations(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<DeprecatedAnnotationUsage>> {
+    let pods = source.pods(namespaces.clone(), all_namespaces).await?;
+    let services = source.services(namespaces.clone(), all_namespaces).await?;
+    let ingresses = source.ingresses(namespaces, all_namespaces).await?;
+
+    let pods = pods
+        .iter()
+        .map(|pod| ("Pod", &pod.metadata, source.pod_owner(pod)));
+    let services = services
+        .iter()
+        .map(|service| ("Service", &service.metadata, None));
+    let ingresses = ingresses
+        .iter()
+        .map(|ingress| ("Ingress", &ingress.metadata, None));
+
+    let usages = pods
+        .chain(services)
+        .chain(ingresses)
+        .flat_map(|(kind, metadata, owner)| {
+            let namespace = metadata
+                .namespace
+                .as_ref()
+                .expect("failed to get namespace");
+
+            let name = metadata.name.as_ref().expect("failed to get name");
+
+            deprecated_usages(metadata.annotations.as_ref()).map(move |(key, deprecated)| {
+                DeprecatedAnnotationUsage {
+                    namespace: namespace.clone(),
+                    kind: kind.to_string(),
+                    name: name.clone(),
+                    owner: owner.clone(),
+                    annotation: key.clone(),
+                    replacement: deprecated.replacement.to_string(),
+                    severity: deprecated.severity,
+                }
+            })
+        })
+        .collect();
+
+    Ok(usages)
+}
+
+fn deprecated_usages(
+    annotations: Option<&BTreeMap<String, String>>,
+) -> impl Iterator<Item = (&String, &'static DeprecatedAnnotation)> {
+    annotations.into_iter().flatten().filter_map(|(key, _)| {
+        DEPRECATED_ANNOTATIONS
+            .iter()
+            .find(|deprecated| deprecated.matches(key))
+            .map(|deprecated| (key, deprecated))
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    async fn deprecated_annotations() {
+        let pods = json!({
+            "items": [
+                {
+                    "metadata": {
+                        "namespace": "web",
+                        "name": "api-0",
+                        "annotations": {
+                            "seccomp.security.alpha.kubernetes.io/pod": "runtime/default",
+                            "container.apparmor.security.beta.kubernetes.io/api": "runtime/default",
+                            "prometheus.io/scrape": "true",
+                        },
+                    },
+                    "spec": { "containers": [] },
+                },
+                {
+                    "metadata": { "namespace": "web", "name": "api-1" },
+                    "spec": { "containers": [] },
+                },
+            ],
+        });
+
+        let services = json!({
+            "items": [{
+                "metadata": {
+                    "namespace": "web",
+                    "name": "api",
+                    "annotations": { "service.beta.kubernetes.io/external-traffic": "OnlyLocal" },
+                },
+            }],
+        });
+
+        let ingresses = json!({
+            "items": [
+                {
+                    "metadata": {
+                        "namespace": "web",
+                        "name": "api",
+                        "annotations": { "kubernetes.io/ingress.class": "nginx" },
+                    },
+                },
+                {
+                    "metadata": { "namespace": "web", "name": "shop" },
+                    "spec": { "ingressClassName": "nginx" },
+                },
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_services(services.to_string().as_bytes())
+            .unwrap()
+            .with_ingresses(ingresses.to_string().as_bytes())
+            .unwrap();
+
+        let usages = super::deprecated_annotations(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let usages = usages
+            .iter()
+            .map(|usage| {
+                (
+                    usage.kind.as_str(),
+                    usage.name.as_str(),
+                    usage.annotation.as_str(),
+                    usage.replacement.as_str(),
+                    usage.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "Pod",
+                    "api-0",
+                    "container.apparmor.security.beta.kubernetes.io/api",
+                    "securityContext.appArmorProfile of the container",
+                    Severity::Low
+                ),
+                (
+                    "Pod",
+                    "api-0",
+                    "seccomp.security.alpha.kubernetes.io/pod",
+                    "securityContext.seccompProfile",
+                    Severity::Medium
+                ),
+                (
+                    "Service",
+                    "api",
+                    "service.beta.kubernetes.io/external-traffic",
+                    "spec.externalTrafficPolicy",
+                    Severity::Medium
+                ),
+                (
+                    "Ingress",
+                    "api",
+                    "kubernetes.io/ingress.class",
+                    "spec.ingressClassName",
+                    Severity::Low
+                ),
+            ],
+            usages
+        );
+    }
+}
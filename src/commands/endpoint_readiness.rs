@@ -0,0 +1,396 @@
+//! Find services that route around a large part of their endpoints because
+//! they are not ready.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use eyre::{Context, Result};
+use k8s_openapi::{
+    api::{core::v1::Pod, discovery::v1::Endpoint},
+    chrono::{self, DateTime, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{pod_condition, Finding, Severity},
+    humanize,
+    source::Source,
+};
+
+/// Label of endpoint slices naming the service they belong to.
+const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// Service with too many endpoints that are not ready or terminating.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ServiceReadiness {
+    namespace: String,
+    service_name: String,
+    endpoints: usize,
+    ready: usize,
+    not_ready: usize,
+    not_ready_pct: u64,
+
+    /// Pods behind the endpoints that have been not ready or terminating for
+    /// longer than the duration.
+    pods: Vec<NotReadyPod>,
+
+    severity: Severity,
+}
+
+/// Pod behind a not ready endpoint.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct NotReadyPod {
+    /// Name of the pod, the address of the endpoint if it has no pod.
+    pod_name: String,
+    owner: Option<Owner>,
+
+    /// Whether the endpoint is `not-ready` or `terminating`.
+    state: String,
+
+    /// How long the pod has been in that state, empty if it is unknown.
+    since: Option<String>,
+}
+
+impl Finding for ServiceReadiness {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Endpoints of a service deduplicated over its endpoint slices.
+#[derive(Default)]
+struct ServiceEndpoints<'a> {
+    ready: usize,
+    not_ready: BTreeMap<String, &'a Endpoint>,
+}
+
+/// Get services where at least `min_not_ready_pct` percent of the endpoints
+/// have been not ready or terminating for longer than `not_ready_for`. How
+/// long comes from the pod behind the endpoint, endpoints without a pod count
+/// as not ready for long.
+pub async fn endpoint_readiness(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    not_ready_for: Duration,
+    min_not_ready_pct: u64,
+) -> Result<Vec<ServiceReadiness>> {
+    let not_ready_for = chrono::Duration::from_std(not_ready_for).context("invalid duration")?;
+    let now = Utc::now();
+
+    let slices = source
+        .endpoint_slices(namespaces.clone(), all_namespaces)
+        .await?;
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let pods = pods
+        .iter()
+        .filter_map(|pod| {
+            Some((
+                (
+                    pod.metadata.namespace.as_deref()?,
+                    pod.metadata.name.as_deref()?,
+                ),
+                pod,
+            ))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let mut services: BTreeMap<(&str, &str), ServiceEndpoints<'_>> = BTreeMap::new();
+
+    for slice in &slices {
+        let Some(service) = slice
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(SERVICE_NAME_LABEL))
+        else {
+            continue;
+        };
+
+        let namespace = slice
+            .metadata
+            .namespace
+            .as_deref()
+            .expect("failed to get namespace");
+
+        let endpoints = services.entry((namespace, service)).or_default();
+
+        for endpoint in &slice.endpoints {
+            if is_ready(endpoint) {
+                endpoints.ready += 1;
+            } else {
+                endpoints.not_ready.insert(endpoint_key(endpoint), endpoint);
+            }
+        }
+    }
+
+    let readiness = services
+        .into_iter()
+        .filter_map(|((namespace, service), endpoints)| {
+            let not_ready_pods = endpoints
+                .not_ready
+                .into_iter()
+                .map(|(key, endpoint)| {
+                    let pod = endpoint
+                        .target_ref
+                        .as_ref()
+                        .filter(|target| target.kind.as_deref() == Some("Pod"))
+                        .and_then(|target| target.name.as_deref())
+                        .and_then(|name| pods.get(&(namespace, name)).copied());
+
+                    not_ready_pod(source, key, endpoint, pod, now)
+                })
+                .collect::<Vec<_>>();
+
+            let not_ready = not_ready_pods.len();
+            let total = endpoints.ready + not_ready;
+
+            let pods = not_ready_pods
+                .into_iter()
+                .filter(|(_, duration)| !duration.is_some_and(|duration| duration <= not_ready_for))
+                .map(|(pod, _)| pod)
+                .collect::<Vec<_>>();
+
+            let stale_pct = percentage(pods.len(), total);
+
+            if pods.is_empty() || stale_pct < min_not_ready_pct {
+                return None;
+            }
+
+            Some(ServiceReadiness {
+                namespace: namespace.to_string(),
+                service_name: service.to_string(),
+                endpoints: total,
+                ready: endpoints.ready,
+                not_ready,
+                not_ready_pct: percentage(not_ready, total),
+                pods,
+                severity: if endpoints.ready == 0 {
+                    Severity::High
+                } else {
+                    Severity::Medium
+                },
+            })
+        })
+        .collect();
+
+    Ok(readiness)
+}
+
+/// Unset conditions mean ready, terminating endpoints are never counted as
+/// ready even if they still serve.
+fn is_ready(endpoint: &Endpoint) -> bool {
+    let conditions = endpoint.conditions.as_ref();
+
+    conditions
+        .and_then(|conditions| conditions.ready)
+        .unwrap_or(true)
+        && !conditions
+            .and_then(|conditions| conditions.terminating)
+            .unwrap_or(false)
+}
+
+/// Endpoints of dual stack services are listed in one slice per address
+/// family, they are told apart by their pod.
+fn endpoint_key(endpoint: &Endpoint) -> String {
+    endpoint
+        .target_ref
+        .as_ref()
+        .and_then(|target| target.name.clone())
+        .or_else(|| endpoint.addresses.first().cloned())
+        .unwrap_or_default()
+}
+
+fn not_ready_pod(
+    source: &dyn Source,
+    key: String,
+    endpoint: &Endpoint,
+    pod: Option<&Pod>,
+    now: DateTime<Utc>,
+) -> (NotReadyPod, Option<chrono::Duration>) {
+    let terminating = endpoint
+        .conditions
+        .as_ref()
+        .and_then(|conditions| conditions.terminating)
+        .unwrap_or(false);
+
+    let since = pod.and_then(|pod| {
+        if terminating {
+            pod.metadata.deletion_timestamp.as_ref().map(|time| time.0)
+        } else {
+            pod_condition(pod, "Ready")
+                .and_then(|condition| condition.last_transition_time.as_ref())
+                .map(|time| time.0)
+        }
+    });
+
+    let duration = since.map(|since: DateTime<Utc>| now - since);
+
+    let pod = NotReadyPod {
+        pod_name: key,
+        owner: pod.and_then(|pod| source.pod_owner(pod)),
+        state: if terminating {
+            "terminating"
+        } else {
+            "not-ready"
+        }
+        .to_string(),
+        since: duration.map(|duration| humanize::duration(duration.to_std().unwrap_or_default())),
+    };
+
+    (pod, duration)
+}
+
+fn percentage(part: usize, total: usize) -> u64 {
+    if total == 0 {
+        return 0;
+    }
+
+    (part * 100 / total) as u64
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use k8s_openapi::chrono::{self, SecondsFormat, Utc};
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    #[allow(clippy::too_many_lines)]
+    async fn endpoint_readiness() {
+        let ago = |minutes: i64| {
+            (Utc::now() - chrono::Duration::minutes(minutes))
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+
+        let pod = |name: &str, ready_since: i64, deleted: Option<i64>| {
+            json!({
+                "metadata": {
+                    "namespace": "web",
+                    "name": name,
+                    "deletionTimestamp": deleted.map(ago),
+                },
+                "spec": { "containers": [] },
+                "status": {
+                    "conditions": [{
+                        "type": "Ready",
+                        "status": "False",
+                        "lastTransitionTime": ago(ready_since),
+                    }],
+                },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("api-0", 30, None),
+                pod("api-1", 1, None),
+                pod("api-2", 60, Some(20)),
+                pod("cache-0", 30, None),
+            ],
+        });
+
+        let endpoint = |pod: &str, ready: bool, terminating: bool| {
+            json!({
+                "addresses": [format!("10.0.0.{}", pod.len())],
+                "conditions": { "ready": ready, "terminating": terminating },
+                "targetRef": { "kind": "Pod", "namespace": "web", "name": pod },
+            })
+        };
+
+        let slice = |name: &str, service: &str, endpoints: serde_json::Value| {
+            json!({
+                "metadata": {
+                    "namespace": "web",
+                    "name": name,
+                    "labels": { "kubernetes.io/service-name": service },
+                },
+                "addressType": "IPv4",
+                "endpoints": endpoints,
+            })
+        };
+
+        let slices = json!({
+            "items": [
+                slice(
+                    "api-ipv4",
+                    "api",
+                    json!([
+                        endpoint("api-0", false, false),
+                        endpoint("api-1", false, false),
+                        endpoint("api-2", false, true),
+                        endpoint("api-3", true, false),
+                    ]),
+                ),
+                slice("api-ipv6", "api", json!([endpoint("api-0", false, false)])),
+                slice(
+                    "cache",
+                    "cache",
+                    json!([
+                        endpoint("cache-0", false, false),
+                        endpoint("cache-1", true, false),
+                        endpoint("cache-2", true, false),
+                        endpoint("cache-3", true, false),
+                        endpoint("cache-4", true, false),
+                    ]),
+                ),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_endpoint_slices(slices.to_string().as_bytes())
+            .unwrap();
+
+        let readiness =
+            super::endpoint_readiness(&source, Vec::new(), true, Duration::from_secs(300), 25)
+                .await
+                .unwrap();
+
+        assert_eq!(1, readiness.len());
+
+        let api = &readiness[0];
+        assert_eq!("api", api.service_name);
+        assert_eq!(
+            (4, 1, 3, 75),
+            (api.endpoints, api.ready, api.not_ready, api.not_ready_pct)
+        );
+        assert_eq!(Severity::Medium, api.severity);
+
+        let pods = api
+            .pods
+            .iter()
+            .map(|pod| (pod.pod_name.as_str(), pod.state.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![("api-0", "not-ready"), ("api-2", "terminating")], pods);
+
+        let readiness =
+            super::endpoint_readiness(&source, Vec::new(), true, Duration::from_secs(300), 10)
+                .await
+                .unwrap();
+
+        assert_eq!(
+            vec!["api", "cache"],
+            readiness
+                .iter()
+                .map(|service| service.service_name.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! Estimate how many pods of a given size still fit on the nodes of the
+//! cluster.
+
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use eyre::{bail, Context, Result};
+use k8s_openapi::api::core::v1::{Node, Pod};
+use num::traits::SaturatingSub;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{Cpu, Memory},
+    commands::{
+        node_allocatable_summary::{node_allocatable, node_requests},
+        Finding, PodPhase, Report, Severity,
+    },
+    source::Source,
+};
+
+/// Number of pods of the given size that fit on the nodes.
+#[derive(Debug, Serialize, PartialEq, JsonSchema)]
+pub struct Output {
+    pod_cpu: Cpu,
+    pod_memory: Memory,
+
+    /// Number of pods that fit on all candidate nodes together.
+    fitting: u64,
+
+    /// Number of pods asked for with `--replicas`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replicas: Option<u64>,
+
+    /// Whether `replicas` pods fit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fits: Option<bool>,
+
+    nodes: Vec<NodeFit>,
+}
+
+/// Number of pods of the given size that fit on a node.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+#[allow(clippy::module_name_repetitions)]
+pub struct NodeFit {
+    node_name: String,
+
+    /// Allocatable cpu that is not requested yet.
+    available_cpu: Cpu,
+
+    /// Allocatable memory that is not requested yet.
+    available_memory: Memory,
+
+    /// Number of pods the node can still run, missing if the node does not
+    /// limit it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    available_pods: Option<u64>,
+
+    fitting: u64,
+
+    /// Resource that runs out first on the node.
+    constraint: Constraint,
+
+    severity: Severity,
+}
+
+/// Resource that limits how many pods fit on a node.
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Constraint {
+    /// The node runs out of cpu first.
+    Cpu,
+
+    /// The node runs out of memory first.
+    Memory,
+
+    /// The node can not run more pods.
+    Pods,
+}
+
+impl Report for Output {
+    type Finding = NodeFit;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.nodes.iter().collect()
+    }
+
+    /// The total only counts the nodes that are kept.
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        self.nodes.retain_mut(|node| keep(node));
+        self.fitting = self.nodes.iter().map(|node| node.fitting).sum();
+        self.fits = self.replicas.map(|replicas| self.fitting >= replicas);
+    }
+
+    fn sort_findings(
+        &mut self,
+        compare: &mut dyn FnMut(&Self::Finding, &Self::Finding) -> Ordering,
+    ) {
+        self.nodes.sort_by(|a, b| compare(a, b));
+    }
+}
+
+impl Finding for NodeFit {
+    const DEFAULT_SEVERITY: Severity = Severity::Info;
+
+    /// Nodes are not namespaced.
+    fn namespace(&self) -> &'static str {
+        ""
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Count how many pods requesting `cpu` and `memory` fit on every node next to
+/// the requests of the pods already running on it, the same way
+/// `node-allocatable-summary` calculates the available resources. The
+/// requests of the pods of all namespaces are counted. The number of pods is
+/// also limited by the allocatable pods of the node minus the pods that are
+/// already on it. Only nodes whose labels match all `key=value` pairs of
+/// `node_selector` are candidates, cordoned nodes are skipped. Taints are not
+/// considered. With `replicas` the output tells if that many pods fit.
+pub async fn fit(
+    source: &dyn Source,
+    cpu: Cpu,
+    memory: Memory,
+    replicas: Option<u64>,
+    node_selector: Vec<String>,
+) -> Result<Output> {
+    if cpu == Cpu::default() && memory == Memory::default() {
+        bail!("the pod has to request cpu or memory");
+    }
+
+    let node_selector = parse_node_selector(&node_selector)?;
+
+    let nodes = source.nodes().await?;
+    let pods = source.pods(Vec::new(), true).await?;
+
+    // Every container of the pods takes up room on the node, including the
+    // ones left out with `--filter-container`.
+    let requests = node_requests(&pods, |_| true)?;
+    let node_pods = node_pods(&pods);
+
+    let mut fits = Vec::new();
+    for node in &nodes {
+        let Some(node_name) = node.metadata.name.as_deref() else {
+            continue;
+        };
+
+        let labels = node.metadata.labels.as_ref();
+        let selected = node_selector
+            .iter()
+            .all(|(key, value)| labels.and_then(|labels| labels.get(key)) == Some(value));
+
+        let cordoned = node
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.unschedulable)
+            .unwrap_or(false);
+
+        if !selected || cordoned {
+            continue;
+        }
+
+        let (allocatable_cpu, allocatable_memory) = node_allocatable(node)?;
+        let (requested_cpu, requested_memory) =
+            requests.get(node_name).copied().unwrap_or_default();
+
+        let available_cpu = allocatable_cpu.saturating_sub(&requested_cpu);
+        let available_memory = allocatable_memory.saturating_sub(&requested_memory);
+
+        let available_pods = allocatable_pods(node)
+            .with_context(|| format!("invalid allocatable pods of node {node_name}"))?
+            .map(|pods| pods.saturating_sub(node_pods.get(node_name).copied().unwrap_or_default()));
+
+        let (fitting, constraint) = fitting(
+            (available_cpu.to_milliseconds(), cpu.to_milliseconds()),
+            (available_memory.to_bytes(), memory.to_bytes()),
+        );
+
+        let (fitting, constraint) = match available_pods {
+            Some(pods) if pods < fitting => (pods, Constraint::Pods),
+            _ => (fitting, constraint),
+        };
+
+        fits.push(NodeFit {
+            node_name: node_name.to_string(),
+            available_cpu,
+            available_memory,
+            available_pods,
+            fitting,
+            constraint,
+            severity: Severity::Info,
+        });
+    }
+
+    fits.sort();
+
+    let fitting = fits.iter().map(|node| node.fitting).sum();
+
+    Ok(Output {
+        pod_cpu: cpu,
+        pod_memory: memory,
+        fitting,
+        replicas,
+        fits: replicas.map(|replicas| fitting >= replicas),
+        nodes: fits,
+    })
+}
+
+/// Number of pods that fit into the available cpu and memory and the resource
+/// that runs out first, cpu if both run out at the same time. Resources the
+/// pod does not request do not limit the number.
+fn fitting(
+    (available_cpu, cpu): (u64, u64),
+    (available_memory, memory): (u64, u64),
+) -> (u64, Constraint) {
+    let by_cpu = available_cpu.checked_div(cpu);
+    let by_memory = available_memory.checked_div(memory);
+
+    match (by_cpu, by_memory) {
+        (Some(by_cpu), Some(by_memory)) if by_memory < by_cpu => (by_memory, Constraint::Memory),
+        (Some(by_cpu), _) => (by_cpu, Constraint::Cpu),
+        (None, Some(by_memory)) => (by_memory, Constraint::Memory),
+        (None, None) => (0, Constraint::Cpu),
+    }
+}
+
+/// Number of pods the node can run at most, `None` if it is not known.
+fn allocatable_pods(node: &Node) -> Result<Option<u64>> {
+    node.status
+        .as_ref()
+        .and_then(|status| status.allocatable.as_ref())
+        .and_then(|allocatable| allocatable.get("pods"))
+        .map(|pods| pods.0.parse().context("failed to parse number of pods"))
+        .transpose()
+}
+
+/// Number of pods that take up a slot on their node, keyed by the name of the
+/// node. Pods that finished do not count.
+fn node_pods(pods: &[Pod]) -> BTreeMap<&str, u64> {
+    let mut node_pods = BTreeMap::new();
+
+    for pod in pods
+        .iter()
+        .filter(|pod| !PodPhase::matches(pod, &[PodPhase::Succeeded, PodPhase::Failed]))
+    {
+        if let Some(node_name) = pod.spec.as_ref().and_then(|spec| spec.node_name.as_deref()) {
+            *node_pods.entry(node_name).or_default() += 1;
+        }
+    }
+
+    node_pods
+}
+
+/// Parse `key=value` pairs into the labels a node needs to have.
+fn parse_node_selector(selectors: &[String]) -> Result<BTreeMap<String, String>> {
+    selectors
+        .iter()
+        .map(|selector| match selector.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => bail!("invalid node selector {selector}, expected key=value"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use super::Constraint;
+    use crate::{
+        api::{Cpu, Memory},
+        source::FileSource,
+    };
+
+    fn source() -> FileSource {
+        let node = |name: &str, pool: &str, cpu: &str, memory: &str, cordoned: bool| {
+            json!({
+                "metadata": { "name": name, "labels": { "pool": pool } },
+                "spec": { "unschedulable": cordoned },
+                "status": { "allocatable": { "cpu": cpu, "memory": memory } },
+            })
+        };
+
+        let nodes = json!({
+            "items": [
+                node("node-a", "general", "4", "16Gi", false),
+                node("node-b", "general", "8", "4Gi", false),
+                node("node-c", "general", "8", "32Gi", true),
+                node("node-d", "gpu", "16", "64Gi", false),
+            ],
+        });
+
+        let pod = |name: &str, node: &str, cpu: &str, memory: &str| {
+            json!({
+                "metadata": { "namespace": "web", "name": name },
+                "spec": {
+                    "nodeName": node,
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": cpu, "memory": memory } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("api-0", "node-a", "1500m", "2Gi"),
+                pod("api-1", "node-b", "1", "1Gi"),
+            ],
+        });
+
+        FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(nodes.to_string().as_bytes())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn fit() {
+        let source = source();
+
+        let output = super::fit(
+            &source,
+            "500m".parse::<Cpu>().unwrap(),
+            "1Gi".parse::<Memory>().unwrap(),
+            Some(10),
+            vec!["pool=general".to_string()],
+        )
+        .await
+        .unwrap();
+
+        let nodes = output
+            .nodes
+            .iter()
+            .map(|node| (node.node_name.as_str(), node.fitting, node.constraint))
+            .collect::<Vec<_>>();
+
+        // node-a has 2500m and 14Gi left, node-b 7 cores but only 3Gi. The
+        // cordoned node and the gpu node are not candidates.
+        assert_eq!(
+            vec![
+                ("node-a", 5, Constraint::Cpu),
+                ("node-b", 3, Constraint::Memory),
+            ],
+            nodes
+        );
+
+        assert_eq!(8, output.fitting);
+        assert_eq!(Some(false), output.fits);
+    }
+
+    #[tokio::test]
+    async fn pod_slots() {
+        let nodes = json!({
+            "items": [{
+                "metadata": { "name": "node-a" },
+                "status": { "allocatable": { "cpu": "8", "memory": "32Gi", "pods": "4" } },
+            }],
+        });
+
+        let pod = |name: &str, phase: &str| {
+            json!({
+                "metadata": { "namespace": "web", "name": name },
+                "spec": { "nodeName": "node-a", "containers": [{ "name": "app" }] },
+                "status": { "phase": phase },
+            })
+        };
+
+        // Finished pods do not take up a slot.
+        let pods = json!({
+            "items": [
+                pod("api-0", "Running"),
+                pod("api-1", "Pending"),
+                pod("job", "Succeeded"),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(nodes.to_string().as_bytes())
+            .unwrap();
+
+        let output = super::fit(&source, Cpu::from(100), Memory::default(), None, Vec::new())
+            .await
+            .unwrap();
+
+        let nodes = output
+            .nodes
+            .iter()
+            .map(|node| (node.available_pods, node.fitting, node.constraint))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![(Some(2), 2, Constraint::Pods)], nodes);
+    }
+
+    #[tokio::test]
+    async fn invalid_node_selector() {
+        let source = source();
+
+        let output = super::fit(
+            &source,
+            Cpu::from(100),
+            Memory::default(),
+            None,
+            vec!["pool".to_string()],
+        )
+        .await;
+
+        assert!(output.is_err());
+    }
+
+    #[test]
+    fn fitting() {
+        assert_eq!((3, Constraint::Memory), super::fitting((1000, 100), (3, 1)));
+        assert_eq!((2, Constraint::Cpu), super::fitting((200, 100), (3, 1)));
+        assert_eq!((3, Constraint::Memory), super::fitting((200, 0), (3, 1)));
+        assert_eq!((0, Constraint::Cpu), super::fitting((0, 100), (3, 0)));
+    }
+}
@@ -0,0 +1,180 @@
+//! Find ingresses that serve traffic without tls.
+
+use std::collections::BTreeSet;
+
+use eyre::Result;
+use k8s_openapi::api::networking::v1::Ingress;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Ingress without tls or with tls entries that have no certificate secret.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct IngressWithoutTls {
+    namespace: String,
+    ingress_name: String,
+
+    /// Hosts of the rules and tls entries of the ingress.
+    hosts: BTreeSet<String>,
+
+    has_tls: bool,
+
+    /// Secret of the first tls entry that has one.
+    tls_secret_name: Option<String>,
+
+    severity: Severity,
+}
+
+impl Finding for IngressWithoutTls {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get ingresses without `spec.tls` entries and ingresses with tls entries
+/// without a `secretName`. The latter are reported with a low severity as the
+/// ingress controller serves them with its default certificate.
+pub async fn ingress_tls_check(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<IngressWithoutTls>> {
+    let ingresses = source.ingresses(namespaces, all_namespaces).await?;
+
+    Ok(ingresses.iter().filter_map(ingress_without_tls).collect())
+}
+
+fn ingress_without_tls(ingress: &Ingress) -> Option<IngressWithoutTls> {
+    let spec = ingress.spec.as_ref();
+    let tls = spec
+        .and_then(|spec| spec.tls.as_deref())
+        .unwrap_or_default();
+
+    let has_tls = !tls.is_empty();
+    let missing_secret = tls.iter().any(|tls| tls.secret_name.is_none());
+
+    if has_tls && !missing_secret {
+        return None;
+    }
+
+    let hosts = spec
+        .and_then(|spec| spec.rules.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|rule| rule.host.clone())
+        .chain(
+            tls.iter()
+                .flat_map(|tls| tls.hosts.iter().flatten().cloned()),
+        )
+        .collect();
+
+    Some(IngressWithoutTls {
+        namespace: ingress
+            .metadata
+            .namespace
+            .as_ref()
+            .expect("failed to get namespace")
+            .clone(),
+
+        ingress_name: ingress
+            .metadata
+            .name
+            .as_ref()
+            .expect("failed to get name")
+            .clone(),
+
+        hosts,
+        has_tls,
+        tls_secret_name: tls.iter().find_map(|tls| tls.secret_name.clone()),
+        severity: if has_tls {
+            Severity::Low
+        } else {
+            Severity::Medium
+        },
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    async fn ingress_tls_check() {
+        let ingress = |name: &str, tls: serde_json::Value| {
+            json!({
+                "metadata": { "namespace": "web", "name": name },
+                "spec": {
+                    "rules": [{ "host": format!("{name}.example.com") }],
+                    "tls": tls,
+                },
+            })
+        };
+
+        let ingresses = json!({
+            "items": [
+                ingress(
+                    "secure",
+                    json!([{ "hosts": ["secure.example.com"], "secretName": "secure-tls" }]),
+                ),
+                ingress("plain", json!([])),
+                ingress(
+                    "default-cert",
+                    json!([{ "hosts": ["default-cert.example.com"] }]),
+                ),
+                { "metadata": { "namespace": "web", "name": "no-spec" } },
+            ],
+        });
+
+        let source = FileSource::from_readers(r#"{ "items": [] }"#.as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_ingresses(ingresses.to_string().as_bytes())
+            .unwrap();
+
+        let ingresses = super::ingress_tls_check(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let ingresses = ingresses
+            .iter()
+            .map(|ingress| {
+                (
+                    ingress.ingress_name.as_str(),
+                    ingress.hosts.iter().map(String::as_str).collect::<Vec<_>>(),
+                    ingress.has_tls,
+                    ingress.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("plain", vec!["plain.example.com"], false, Severity::Medium),
+                (
+                    "default-cert",
+                    vec!["default-cert.example.com"],
+                    true,
+                    Severity::Low
+                ),
+                ("no-spec", vec![], false, Severity::Medium),
+            ],
+            ingresses
+        );
+    }
+}
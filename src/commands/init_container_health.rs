@@ -0,0 +1,278 @@
+//! Find init containers that are stuck or take too long.
+
+use std::time::Duration;
+
+use eyre::{Context, Result};
+use k8s_openapi::{
+    api::core::v1::{ContainerStatus, Pod},
+    chrono::{self, DateTime, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    humanize,
+    source::Source,
+};
+
+/// Init container that runs for too long or keeps crashing.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct StuckInitContainer {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    init_container_name: String,
+
+    /// `Running` or the reason the init container is waiting, for example
+    /// `CrashLoopBackOff`.
+    state: String,
+
+    started_at: Option<String>,
+    duration: Option<String>,
+    restart_count: i32,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for StuckInitContainer {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get init containers that have been running for longer than `max_duration`
+/// and init containers in `CrashLoopBackOff`. Init containers do not support
+/// probes so nothing else notices when they hang.
+pub async fn init_container_health(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    max_duration: Duration,
+    details: DetailOptions,
+) -> Result<Vec<StuckInitContainer>> {
+    let max_duration = chrono::Duration::from_std(max_duration).context("invalid duration")?;
+    let now = Utc::now();
+
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let stuck = pods
+        .iter()
+        .flat_map(|pod| stuck_init_containers(source, pod, now, max_duration, &details))
+        .collect();
+
+    Ok(stuck)
+}
+
+fn stuck_init_containers(
+    source: &dyn Source,
+    pod: &Pod,
+    now: DateTime<Utc>,
+    max_duration: chrono::Duration,
+    details: &DetailOptions,
+) -> Vec<StuckInitContainer> {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.init_container_statuses.as_ref())
+        .into_iter()
+        .flatten()
+        .filter(|status| source.checks_container(&status.name))
+        .filter_map(|status| {
+            let state = status.state.as_ref()?;
+
+            let (state, started_at, severity) = if let Some(running) = &state.running {
+                let started_at = running.started_at.as_ref()?.0;
+
+                if now - started_at <= max_duration {
+                    return None;
+                }
+
+                ("Running".to_string(), Some(started_at), Severity::Medium)
+            } else {
+                let reason = state.waiting.as_ref()?.reason.as_deref()?;
+
+                if reason != "CrashLoopBackOff" {
+                    return None;
+                }
+
+                (reason.to_string(), last_started_at(status), Severity::High)
+            };
+
+            // Clock skew between the cluster and the machine running the check
+            // can make containers appear to start in the future.
+            let duration = started_at.map(|started_at| {
+                humanize::duration((now - started_at).to_std().unwrap_or_default())
+            });
+
+            Some(StuckInitContainer {
+                namespace: pod
+                    .metadata
+                    .namespace
+                    .as_ref()
+                    .expect("failed to get namespace")
+                    .clone(),
+
+                owner: source.pod_owner(pod),
+
+                pod_name: pod
+                    .metadata
+                    .name
+                    .as_ref()
+                    .expect("failed to get name")
+                    .clone(),
+
+                init_container_name: status.name.clone(),
+                state,
+                started_at: started_at.map(|started_at| started_at.to_rfc3339()),
+                duration,
+                restart_count: status.restart_count,
+                severity,
+                details: PodDetails::new(source, pod, details),
+            })
+        })
+        .collect()
+}
+
+/// Start of the last attempt of a crashing container.
+fn last_started_at(status: &ContainerStatus) -> Option<DateTime<Utc>> {
+    status
+        .last_state
+        .as_ref()
+        .and_then(|state| state.terminated.as_ref())
+        .and_then(|terminated| terminated.started_at.as_ref())
+        .map(|time| time.0)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::chrono::{Duration, TimeZone, Utc};
+    use serde_json::json;
+
+    use crate::{
+        commands::{DetailOptions, Severity},
+        source::{FileSource, Source},
+    };
+
+    #[tokio::test]
+    async fn stuck_init_containers() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 1, 0, 0).unwrap();
+
+        let pods = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api-0" },
+                "spec": {
+                    "initContainers": [
+                        { "name": "migrate" },
+                        { "name": "wait-for-db" },
+                        { "name": "fetch-config" },
+                        { "name": "pull-secrets" },
+                    ],
+                    "containers": [{ "name": "api" }],
+                },
+                "status": {
+                    "phase": "Pending",
+                    "initContainerStatuses": [
+                        {
+                            "name": "migrate",
+                            "image": "migrate",
+                            "imageID": "",
+                            "ready": false,
+                            "restartCount": 0,
+                            "state": { "running": { "startedAt": "2024-01-01T00:15:00Z" } },
+                        },
+                        {
+                            "name": "wait-for-db",
+                            "image": "busybox",
+                            "imageID": "",
+                            "ready": false,
+                            "restartCount": 0,
+                            "state": { "running": { "startedAt": "2024-01-01T00:55:00Z" } },
+                        },
+                        {
+                            "name": "fetch-config",
+                            "image": "curl",
+                            "imageID": "",
+                            "ready": false,
+                            "restartCount": 7,
+                            "state": { "waiting": { "reason": "CrashLoopBackOff" } },
+                            "lastState": {
+                                "terminated": {
+                                    "exitCode": 1,
+                                    "startedAt": "2024-01-01T00:59:30Z",
+                                },
+                            },
+                        },
+                        {
+                            "name": "pull-secrets",
+                            "image": "vault",
+                            "imageID": "",
+                            "ready": false,
+                            "restartCount": 0,
+                            "state": { "waiting": { "reason": "PodInitializing" } },
+                        },
+                    ],
+                },
+            }],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+        let pods = source.pods(Vec::new(), true).await.unwrap();
+
+        let stuck = super::stuck_init_containers(
+            &source,
+            &pods[0],
+            now,
+            Duration::minutes(10),
+            &DetailOptions::default(),
+        );
+
+        let stuck = stuck
+            .iter()
+            .map(|stuck| {
+                (
+                    stuck.init_container_name.as_str(),
+                    stuck.state.as_str(),
+                    stuck.duration.as_deref(),
+                    stuck.restart_count,
+                    stuck.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("migrate", "Running", Some("45m"), 0, Severity::Medium),
+                (
+                    "fetch-config",
+                    "CrashLoopBackOff",
+                    Some("30s"),
+                    7,
+                    Severity::High
+                ),
+            ],
+            stuck
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Find jobs whose parallelism does not fit their number of completions.
+
+use eyre::{bail, Result};
+use k8s_openapi::api::batch::v1::Job;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Which jobs are reported. Without any flag both kinds are reported.
+#[derive(Debug, Clone, Copy)]
+pub struct ParallelismFlags {
+    /// Report jobs running many completions one at a time.
+    pub single_thread: bool,
+
+    /// Report jobs with a higher parallelism than completions.
+    pub over_parallel: bool,
+
+    /// Number of completions from which a job running one pod at a time is
+    /// reported.
+    pub serial_completions: i32,
+}
+
+/// Job with a parallelism that does not fit its completions.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct JobParallelism {
+    namespace: String,
+    job_name: String,
+    parallelism: i32,
+    completions: Option<i32>,
+    active: i32,
+    succeeded: i32,
+    failed: i32,
+    efficiency_note: String,
+    severity: Severity,
+}
+
+impl Finding for JobParallelism {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get jobs that start more pods than they have completions and jobs that
+/// run many completions one pod at a time.
+pub async fn job_parallelism_check(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    flags: ParallelismFlags,
+) -> Result<Vec<JobParallelism>> {
+    let jobs = source.jobs(namespaces, all_namespaces).await?;
+
+    let findings = jobs
+        .iter()
+        .map(|job| job_parallelism(job, flags))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    Ok(findings)
+}
+
+fn job_parallelism(job: &Job, flags: ParallelismFlags) -> Result<Option<JobParallelism>> {
+    let Some(spec) = &job.spec else {
+        bail!("Job has no spec");
+    };
+
+    // Kubernetes defaults both to one, jobs without completions are work
+    // queues that finish once any pod succeeds.
+    let parallelism = spec.parallelism.unwrap_or(1);
+    let completions = spec.completions;
+
+    let report_all = !flags.single_thread && !flags.over_parallel;
+
+    let (efficiency_note, severity) = match completions {
+        Some(completions) if parallelism > completions && (report_all || flags.over_parallel) => (
+            format!(
+                "parallelism {parallelism} is higher than the {completions} completions, \
+                 at most {completions} pods are useful"
+            ),
+            Severity::Medium,
+        ),
+
+        Some(completions)
+            if parallelism == 1
+                && completions >= flags.serial_completions
+                && (report_all || flags.single_thread) =>
+        {
+            (
+                format!("{completions} completions run one pod at a time"),
+                Severity::Low,
+            )
+        }
+
+        _ => return Ok(None),
+    };
+
+    let status = job.status.as_ref();
+
+    Ok(Some(JobParallelism {
+        namespace: job
+            .metadata
+            .namespace
+            .as_ref()
+            .expect("failed to get namespace")
+            .clone(),
+
+        job_name: job
+            .metadata
+            .name
+            .as_ref()
+            .expect("failed to get name")
+            .clone(),
+
+        parallelism,
+        completions,
+        active: status.and_then(|status| status.active).unwrap_or_default(),
+        succeeded: status
+            .and_then(|status| status.succeeded)
+            .unwrap_or_default(),
+        failed: status.and_then(|status| status.failed).unwrap_or_default(),
+        efficiency_note,
+        severity,
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::api::batch::v1::{Job, JobSpec};
+
+    use super::ParallelismFlags;
+    use crate::commands::Severity;
+
+    fn job(parallelism: Option<i32>, completions: Option<i32>) -> Job {
+        Job {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("job".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(JobSpec {
+                completions,
+                parallelism,
+                ..Default::default()
+            }),
+
+            ..Default::default()
+        }
+    }
+
+    fn flags(single_thread: bool, over_parallel: bool) -> ParallelismFlags {
+        ParallelismFlags {
+            single_thread,
+            over_parallel,
+            serial_completions: 10,
+        }
+    }
+
+    #[test]
+    fn job_parallelism() {
+        let testcases = [
+            (job(None, None), flags(false, false), None),
+            (job(Some(5), None), flags(false, false), None),
+            (job(Some(4), Some(4)), flags(false, false), None),
+            (job(None, Some(9)), flags(false, false), None),
+            (
+                job(Some(8), Some(2)),
+                flags(false, false),
+                Some(Severity::Medium),
+            ),
+            (job(Some(8), Some(2)), flags(true, false), None),
+            (
+                job(None, Some(100)),
+                flags(false, false),
+                Some(Severity::Low),
+            ),
+            (
+                job(Some(1), Some(100)),
+                flags(true, false),
+                Some(Severity::Low),
+            ),
+            (job(Some(1), Some(100)), flags(false, true), None),
+        ];
+
+        for (job, flags, expected) in testcases {
+            let output = super::job_parallelism(&job, flags).unwrap();
+
+            assert_eq!(expected, output.map(|finding| finding.severity));
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Find cronjobs that keep more jobs around than their history limits allow.
+
+use std::collections::BTreeMap;
+
+use eyre::Result;
+use k8s_openapi::api::batch::v1::Job;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Default of `spec.successfulJobsHistoryLimit`.
+const DEFAULT_SUCCESSFUL_HISTORY_LIMIT: i32 = 3;
+
+/// Default of `spec.failedJobsHistoryLimit`.
+const DEFAULT_FAILED_HISTORY_LIMIT: i32 = 1;
+
+/// Cronjob with more child jobs than its history limits.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct LeakingCronJob {
+    namespace: String,
+    cronjob_name: String,
+    active_jobs: usize,
+    succeeded_jobs: usize,
+    failed_jobs: usize,
+    successful_jobs_history_limit: i32,
+    failed_jobs_history_limit: i32,
+    severity: Severity,
+}
+
+impl Finding for LeakingCronJob {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Number of jobs of a cronjob by their state.
+#[derive(Debug, Default)]
+struct JobCounts {
+    active: usize,
+    succeeded: usize,
+    failed: usize,
+}
+
+/// Get cronjobs whose active and finished jobs exceed the sum of their history
+/// limits by more than `threshold`. Jobs are matched to their cronjob through
+/// their owner references.
+pub async fn leaking_cronjobs(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    threshold: usize,
+) -> Result<Vec<LeakingCronJob>> {
+    let cronjobs = source.cronjobs(namespaces.clone(), all_namespaces).await?;
+    let jobs = source.jobs(namespaces, all_namespaces).await?;
+
+    let mut counts: BTreeMap<(&str, &str), JobCounts> = BTreeMap::new();
+
+    for job in &jobs {
+        let Some(cronjob) = job
+            .metadata
+            .owner_references
+            .iter()
+            .flatten()
+            .find(|owner| owner.kind == "CronJob")
+        else {
+            continue;
+        };
+
+        let namespace = job
+            .metadata
+            .namespace
+            .as_deref()
+            .expect("failed to get namespace");
+
+        let count = counts.entry((namespace, &cronjob.name)).or_default();
+
+        match job_state(job) {
+            JobState::Succeeded => count.succeeded += 1,
+            JobState::Failed => count.failed += 1,
+            JobState::Active => count.active += 1,
+        }
+    }
+
+    let leaking = cronjobs
+        .iter()
+        .filter_map(|cronjob| {
+            let namespace = cronjob
+                .metadata
+                .namespace
+                .as_deref()
+                .expect("failed to get namespace");
+
+            let name = cronjob
+                .metadata
+                .name
+                .as_deref()
+                .expect("failed to get name");
+
+            let count = counts.remove(&(namespace, name))?;
+            let spec = cronjob.spec.as_ref();
+
+            let successful_jobs_history_limit = spec
+                .and_then(|spec| spec.successful_jobs_history_limit)
+                .unwrap_or(DEFAULT_SUCCESSFUL_HISTORY_LIMIT);
+
+            let failed_jobs_history_limit = spec
+                .and_then(|spec| spec.failed_jobs_history_limit)
+                .unwrap_or(DEFAULT_FAILED_HISTORY_LIMIT);
+
+            let allowed = usize::try_from(successful_jobs_history_limit).unwrap_or_default()
+                + usize::try_from(failed_jobs_history_limit).unwrap_or_default()
+                + threshold;
+
+            if count.active + count.succeeded + count.failed <= allowed {
+                return None;
+            }
+
+            Some(LeakingCronJob {
+                namespace: namespace.to_string(),
+                cronjob_name: name.to_string(),
+                active_jobs: count.active,
+                succeeded_jobs: count.succeeded,
+                failed_jobs: count.failed,
+                successful_jobs_history_limit,
+                failed_jobs_history_limit,
+                severity: Severity::Medium,
+            })
+        })
+        .collect();
+
+    Ok(leaking)
+}
+
+enum JobState {
+    Active,
+    Succeeded,
+    Failed,
+}
+
+/// State of the job from its `Complete` and `Failed` conditions, jobs without
+/// either are still active.
+fn job_state(job: &Job) -> JobState {
+    let condition = |kind: &str| {
+        job.status
+            .as_ref()
+            .and_then(|status| status.conditions.as_ref())
+            .into_iter()
+            .flatten()
+            .any(|condition| condition.type_ == kind && condition.status == "True")
+    };
+
+    if condition("Complete") {
+        JobState::Succeeded
+    } else if condition("Failed") {
+        JobState::Failed
+    } else {
+        JobState::Active
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::source::FileSource;
+
+    #[tokio::test]
+    async fn leaking_cronjobs() {
+        let cronjobs = json!({
+            "items": [
+                {
+                    "metadata": { "namespace": "batch", "name": "report" },
+                    "spec": { "schedule": "* * * * *", "jobTemplate": {} },
+                },
+                {
+                    "metadata": { "namespace": "batch", "name": "cleanup" },
+                    "spec": {
+                        "schedule": "* * * * *",
+                        "jobTemplate": {},
+                        "successfulJobsHistoryLimit": 10,
+                    },
+                },
+            ],
+        });
+
+        let job = |name: &str, cronjob: &str, condition: Option<&str>| {
+            json!({
+                "metadata": {
+                    "namespace": "batch",
+                    "name": name,
+                    "ownerReferences": [{
+                        "apiVersion": "batch/v1",
+                        "kind": "CronJob",
+                        "name": cronjob,
+                        "uid": cronjob,
+                    }],
+                },
+                "status": {
+                    "conditions": condition
+                        .map(|kind| json!([{ "type": kind, "status": "True" }])),
+                },
+            })
+        };
+
+        let jobs = json!({
+            "items": [
+                job("report-1", "report", Some("Complete")),
+                job("report-2", "report", Some("Complete")),
+                job("report-3", "report", Some("Complete")),
+                job("report-4", "report", Some("Failed")),
+                job("report-5", "report", Some("Failed")),
+                job("report-6", "report", None),
+                job("cleanup-1", "cleanup", Some("Complete")),
+                job("cleanup-2", "cleanup", Some("Complete")),
+                job("cleanup-3", "cleanup", Some("Complete")),
+                job("cleanup-4", "cleanup", Some("Complete")),
+                job("cleanup-5", "cleanup", Some("Complete")),
+                { "metadata": { "namespace": "batch", "name": "manual" } },
+            ],
+        });
+
+        let source = FileSource::from_readers(r#"{ "items": [] }"#.as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_cronjobs(cronjobs.to_string().as_bytes())
+            .unwrap()
+            .with_jobs(jobs.to_string().as_bytes())
+            .unwrap();
+
+        let leaking = super::leaking_cronjobs(&source, Vec::new(), true, 1)
+            .await
+            .unwrap();
+
+        let leaking = leaking
+            .iter()
+            .map(|cronjob| {
+                (
+                    cronjob.cronjob_name.as_str(),
+                    cronjob.active_jobs,
+                    cronjob.succeeded_jobs,
+                    cronjob.failed_jobs,
+                    cronjob.successful_jobs_history_limit,
+                    cronjob.failed_jobs_history_limit,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![("report", 1, 3, 2, 3, 1)], leaking);
+
+        let leaking = super::leaking_cronjobs(&source, Vec::new(), true, 2)
+            .await
+            .unwrap();
+
+        assert!(leaking.is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+//! Find containers whose liveness probe gives up before the readiness probe.
+
+use std::collections::BTreeSet;
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::{Pod, Probe};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Failure threshold the kubelet uses when a probe does not set one.
+const DEFAULT_FAILURE_THRESHOLD: i32 = 3;
+
+/// Period the kubelet uses when a probe does not set one.
+const DEFAULT_PERIOD_SECONDS: i32 = 10;
+
+/// Container whose liveness probe fails faster than its readiness probe.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct InconsistentProbes {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    liveness_window_seconds: i32,
+    readiness_window_seconds: i32,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for InconsistentProbes {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get containers where the liveness probe window (`failureThreshold *
+/// periodSeconds`) is shorter than the readiness probe window. Such containers
+/// get restarted before they are taken out of the service endpoints and have
+/// no chance to recover.
+pub async fn liveness_readiness_consistency(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    details: DetailOptions,
+) -> Result<Vec<InconsistentProbes>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let inconsistent = pods
+        .iter()
+        .flat_map(|pod| {
+            pod_inconsistent_probes(source, pod, &details)
+                .expect("failed to get inconsistent probes")
+        })
+        .collect::<Vec<_>>();
+
+    Ok(inconsistent)
+}
+
+fn pod_inconsistent_probes(
+    source: &dyn Source,
+    pod: &Pod,
+    details: &DetailOptions,
+) -> Result<BTreeSet<InconsistentProbes>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let inconsistent = spec
+        .containers
+        .iter()
+        .filter(|container| source.checks_container(&container.name))
+        .filter_map(|container| {
+            let liveness = probe_window(container.liveness_probe.as_ref()?);
+            let readiness = probe_window(container.readiness_probe.as_ref()?);
+
+            (liveness < readiness).then_some((container, liveness, readiness))
+        })
+        .map(
+            |(container, liveness_window_seconds, readiness_window_seconds)| InconsistentProbes {
+                namespace: pod
+                    .metadata
+                    .namespace
+                    .as_ref()
+                    .expect("failed to get namespace")
+                    .clone(),
+
+                owner: source.pod_owner(pod),
+
+                pod_name: pod
+                    .metadata
+                    .name
+                    .as_ref()
+                    .expect("failed to get name")
+                    .clone(),
+
+                container_name: container.name.clone(),
+                liveness_window_seconds,
+                readiness_window_seconds,
+
+                severity: Severity::Medium,
+                details: PodDetails::new(source, pod, details),
+            },
+        )
+        .collect();
+
+    Ok(inconsistent)
+}
+
+/// Time in seconds a probe has to fail before it is considered failed.
+fn probe_window(probe: &Probe) -> i32 {
+    probe.failure_threshold.unwrap_or(DEFAULT_FAILURE_THRESHOLD)
+        * probe.period_seconds.unwrap_or(DEFAULT_PERIOD_SECONDS)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::api::core::v1::{Container, Pod, PodSpec, Probe};
+
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    #[allow(clippy::unnecessary_wraps)]
+    fn probe(failure_threshold: Option<i32>, period_seconds: Option<i32>) -> Option<Probe> {
+        Some(Probe {
+            failure_threshold,
+            period_seconds,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn pod_inconsistent_probes() {
+        let pod = Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("pod".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(PodSpec {
+                containers: vec![
+                    Container {
+                        name: "inconsistent".to_string(),
+                        liveness_probe: probe(Some(1), Some(5)),
+                        readiness_probe: probe(None, None),
+                        ..Default::default()
+                    },
+                    Container {
+                        name: "consistent".to_string(),
+                        liveness_probe: probe(Some(6), None),
+                        readiness_probe: probe(Some(3), Some(10)),
+                        ..Default::default()
+                    },
+                    Container {
+                        name: "only-liveness".to_string(),
+                        liveness_probe: probe(Some(1), Some(1)),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+
+            ..Default::default()
+        };
+
+        let output =
+            super::pod_inconsistent_probes(&FileSource::default(), &pod, &DetailOptions::default())
+                .unwrap();
+
+        let output = output
+            .iter()
+            .map(|inconsistent| {
+                (
+                    inconsistent.container_name.as_str(),
+                    inconsistent.liveness_window_seconds,
+                    inconsistent.readiness_window_seconds,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![("inconsistent", 5, 30)], output);
+    }
+}
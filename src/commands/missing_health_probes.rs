@@ -1,81 +1,547 @@
+//! Find containers without health probes.
+
+use std::{cmp::Ordering, collections::BTreeMap, fmt, str::FromStr};
+
 use eyre::Result;
+use k8s_openapi::{api::core::v1::Pod, chrono::Utc};
+use schemars::JsonSchema;
 use serde::Serialize;
 
-use crate::api::{get_pod_owner, get_pods, Owner};
+use crate::{
+    api::Owner,
+    commands::{k8s_version, DetailOptions, Finding, PodDetails, PodPhase, Report, Severity},
+    source::Source,
+};
 
-pub(crate) async fn missing_health_probes(
-    namespaces: Vec<String>,
-    all_namespaces: bool,
-) -> Result<()> {
-    #[derive(Debug, Serialize)]
-    struct Output {
-        pod_name: String,
-        owner: Option<Owner>,
-        container_name: String,
-        liveness_probe: Option<String>,
-        readiness_probe: Option<String>,
+/// Containers with missing health probes and their number per namespace and
+/// owner.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Output {
+    /// Time the report was generated in RFC 3339 format.
+    generated_at: String,
+
+    /// Version of the api server the pods were read from.
+    k8s_version: String,
+
+    containers: Vec<ContainerOutput>,
+    summary: Summary,
+}
+
+/// Container without a liveness or readiness probe.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ContainerOutput {
+    namespace: String,
+    pod_name: String,
+    owner: Option<Owner>,
+    container_name: String,
+    liveness_probe: Option<String>,
+    readiness_probe: Option<String>,
+
+    /// Which of the probes are missing.
+    missing: MissingProbes,
+
+    /// Status of the `Ready` condition of the pod. Running pods can still be
+    /// out of service, for example when a readiness gate is not passed.
+    ready: Option<bool>,
+
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+/// Which health probes a container is missing. A missing readiness probe
+/// affects traffic as the pod gets requests before it can serve them, a
+/// missing liveness probe affects recovery as hanging containers are not
+/// restarted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingProbes {
+    /// Only the liveness probe is missing.
+    LivenessOnly,
+
+    /// Only the readiness probe is missing.
+    ReadinessOnly,
+
+    /// Both probes are missing.
+    Both,
+}
+
+impl MissingProbes {
+    /// All buckets.
+    pub const ALL: [MissingProbes; 3] = [
+        MissingProbes::LivenessOnly,
+        MissingProbes::ReadinessOnly,
+        MissingProbes::Both,
+    ];
+
+    /// Name of the bucket as used on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MissingProbes::LivenessOnly => "liveness-only",
+            MissingProbes::ReadinessOnly => "readiness-only",
+            MissingProbes::Both => "both",
+        }
+    }
+
+    /// Bucket of a container with or without the probes, `None` if it has
+    /// both.
+    fn of(liveness: bool, readiness: bool) -> Option<Self> {
+        match (liveness, readiness) {
+            (true, true) => None,
+            (false, true) => Some(MissingProbes::LivenessOnly),
+            (true, false) => Some(MissingProbes::ReadinessOnly),
+            (false, false) => Some(MissingProbes::Both),
+        }
+    }
+}
+
+impl fmt::Display for MissingProbes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MissingProbes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MissingProbes::ALL
+            .into_iter()
+            .find(|missing| missing.as_str() == s)
+            .ok_or_else(|| {
+                format!("invalid missing probes {s}, expected one of liveness-only, readiness-only, both")
+            })
+    }
+}
+
+/// Number of containers per bucket of missing probes.
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct BucketCounts {
+    liveness_only: usize,
+    readiness_only: usize,
+    both: usize,
+}
+
+/// Containers with missing probes per namespace and per owner.
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct Summary {
+    namespaces: Vec<NamespaceCounts>,
+    owners: Vec<OwnerCounts>,
+}
+
+/// Containers with missing probes of a namespace.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NamespaceCounts {
+    namespace: String,
+
+    #[serde(flatten)]
+    counts: BucketCounts,
+}
+
+/// Containers with missing probes of an owner.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct OwnerCounts {
+    owner: Owner,
+
+    #[serde(flatten)]
+    counts: BucketCounts,
+}
+
+impl BucketCounts {
+    fn add(&mut self, missing: MissingProbes) {
+        match missing {
+            MissingProbes::LivenessOnly => self.liveness_only += 1,
+            MissingProbes::ReadinessOnly => self.readiness_only += 1,
+            MissingProbes::Both => self.both += 1,
+        }
+    }
+}
+
+impl Summary {
+    fn new(containers: &[ContainerOutput]) -> Self {
+        let mut namespaces: BTreeMap<&str, BucketCounts> = BTreeMap::new();
+        let mut owners: BTreeMap<&Owner, BucketCounts> = BTreeMap::new();
+
+        for container in containers {
+            namespaces
+                .entry(&container.namespace)
+                .or_default()
+                .add(container.missing);
+
+            if let Some(owner) = &container.owner {
+                owners.entry(owner).or_default().add(container.missing);
+            }
+        }
+
+        Self {
+            namespaces: namespaces
+                .into_iter()
+                .map(|(namespace, counts)| NamespaceCounts {
+                    namespace: namespace.to_string(),
+                    counts,
+                })
+                .collect(),
+
+            owners: owners
+                .into_iter()
+                .map(|(owner, counts)| OwnerCounts {
+                    owner: owner.clone(),
+                    counts,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Report for Output {
+    type Finding = ContainerOutput;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.containers.iter().collect()
+    }
+
+    /// The summary only counts the containers that are kept.
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        self.containers.retain_mut(|container| keep(container));
+        self.summary = Summary::new(&self.containers);
     }
 
-    let pods = get_pods(namespaces, all_namespaces).await?;
+    fn sort_findings(
+        &mut self,
+        compare: &mut dyn FnMut(&Self::Finding, &Self::Finding) -> Ordering,
+    ) {
+        self.containers.sort_by(|a, b| compare(a, b));
+    }
+}
+
+impl Finding for ContainerOutput {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
 
-    let pods: Vec<_> = pods
+/// Get containers of running pods that miss one of the given buckets of
+/// health probes, by default only those with neither a liveness nor a
+/// readiness probe. Running pods that are not ready are included with a higher
+/// severity as they are out of service.
+pub async fn missing_health_probes(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    missing: Vec<MissingProbes>,
+    details: DetailOptions,
+) -> Result<Output> {
+    let generated_at = Utc::now().to_rfc3339();
+    let k8s_version = k8s_version(source).await;
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let containers: Vec<_> = pods
         .iter()
-        .filter(|pod| pod.status.is_some())
-        .filter(|pod| {
-            pod.status.as_ref().expect("failed to get status").phase == Some("Running".to_string())
-        })
-        .filter(|pod| pod.spec.is_some())
-        .filter(|pod| {
-            !pod.spec
-                .as_ref()
-                .expect("failed to get spec")
-                .containers
+        .filter(|pod| PodPhase::matches(pod, &[PodPhase::Running]))
+        .filter_map(|pod| Some((pod, pod.spec.as_ref()?)))
+        .flat_map(|(pod, spec)| {
+            spec.containers
                 .iter()
-                .all(|container| {
-                    container.liveness_probe.is_some() || container.readiness_probe.is_some()
+                .filter(|container| source.checks_container(&container.name))
+                .filter_map(|container| {
+                    let bucket = MissingProbes::of(
+                        container.liveness_probe.is_some(),
+                        container.readiness_probe.is_some(),
+                    )?;
+                    missing.contains(&bucket).then_some((container, bucket))
                 })
-        })
-        .flat_map(|pod| {
-            pod.spec
-                .as_ref()
-                .expect("failed to get spec")
-                .containers
-                .iter()
-                .map(|container| {
-                    (
-                        container.name.clone(),
-                        container
+                .map(|(container, missing)| {
+                    let ready = pod_ready(pod);
+
+                    ContainerOutput {
+                        namespace: pod
+                            .metadata
+                            .namespace
+                            .as_ref()
+                            .expect("failed to get namespace")
+                            .clone(),
+
+                        pod_name: pod
+                            .metadata
+                            .name
+                            .as_ref()
+                            .expect("failed to get name")
+                            .clone(),
+
+                        owner: source.pod_owner(pod),
+                        container_name: container.name.clone(),
+                        liveness_probe: container
                             .liveness_probe
                             .as_ref()
                             .map(|probe| format!("{probe:?}")),
-                        container
+                        readiness_probe: container
                             .readiness_probe
                             .as_ref()
                             .map(|probe| format!("{probe:?}")),
-                    )
-                })
-                .map(|(container_name, liveness_probe, readiness_probe)| Output {
-                    pod_name: pod
-                        .metadata
-                        .name
-                        .as_ref()
-                        .expect("failed to get name")
-                        .clone(),
-
-                    owner: get_pod_owner(pod),
-                    container_name,
-                    liveness_probe,
-                    readiness_probe,
-                })
-                .filter(|output| {
-                    output.liveness_probe.is_none() && output.readiness_probe.is_none()
+                        missing,
+                        ready,
+                        severity: if ready == Some(false) {
+                            Severity::High
+                        } else {
+                            Severity::Medium
+                        },
+                        details: PodDetails::new(source, pod, &details),
+                    }
                 })
         })
         .collect();
 
-    let out = serde_json::to_string_pretty(&pods)?;
+    Ok(Output {
+        generated_at,
+        k8s_version,
+        summary: Summary::new(&containers),
+        containers,
+    })
+}
+
+fn pod_ready(pod: &Pod) -> Option<bool> {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .and_then(|conditions| {
+            conditions
+                .iter()
+                .find(|condition| condition.type_ == "Ready")
+        })
+        .map(|condition| condition.status == "True")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        commands::{DetailOptions, Severity},
+        source::FileSource,
+    };
+
+    use super::MissingProbes;
+
+    #[test]
+    fn missing_probes() {
+        assert_eq!(None, MissingProbes::of(true, true));
+        assert_eq!(
+            Some(MissingProbes::LivenessOnly),
+            MissingProbes::of(false, true)
+        );
+        assert_eq!(
+            Some(MissingProbes::ReadinessOnly),
+            MissingProbes::of(true, false)
+        );
+        assert_eq!(Some(MissingProbes::Both), MissingProbes::of(false, false));
+
+        for missing in MissingProbes::ALL {
+            assert_eq!(Ok(missing), missing.as_str().parse());
+        }
+    }
+
+    #[tokio::test]
+    async fn offline() {
+        let source = FileSource::from_readers(
+            include_str!("../../resources/fixtures/pods.json").as_bytes(),
+            None::<&[u8]>,
+        )
+        .unwrap();
+
+        let output = super::missing_health_probes(
+            &source,
+            Vec::new(),
+            true,
+            vec![MissingProbes::Both],
+            DetailOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(1, output.containers.len());
+
+        let output = &output.containers[0];
+        assert_eq!("web", output.namespace);
+        assert_eq!("frontend-7d9c8b7f5-abcde", output.pod_name);
+        assert_eq!("frontend", output.container_name);
+        assert_eq!(
+            Some("frontend-7d9c8b7f5"),
+            output.owner.as_ref().map(|owner| owner.name.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn running_not_ready() {
+        let pod = |name: &str, ready: &str| {
+            json!({
+                "metadata": { "namespace": "test", "name": name },
+                "spec": {
+                    "containers": [{ "name": "app" }],
+                    "readinessGates": [{ "conditionType": "example.com/gate" }],
+                },
+                "status": {
+                    "phase": "Running",
+                    "conditions": [{ "type": "Ready", "status": ready }],
+                },
+            })
+        };
+
+        let pods = json!({ "items": [pod("ready", "True"), pod("not-ready", "False")] });
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let output = super::missing_health_probes(
+            &source,
+            Vec::new(),
+            true,
+            vec![MissingProbes::Both],
+            DetailOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let output = output
+            .containers
+            .iter()
+            .map(|output| (output.pod_name.as_str(), output.ready, output.severity))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("ready", Some(true), Severity::Medium),
+                ("not-ready", Some(false), Severity::High),
+            ],
+            output
+        );
+    }
 
-    println!("{out}");
+    #[tokio::test]
+    async fn summary() {
+        let probe = json!({ "tcpSocket": { "port": 8080 } });
+        let container = |name: &str, liveness: bool, readiness: bool| {
+            json!({
+                "name": name,
+                "livenessProbe": liveness.then_some(&probe),
+                "readinessProbe": readiness.then_some(&probe),
+            })
+        };
 
-    Ok(())
+        let pod = |namespace: &str, name: &str, owner: &str| {
+            json!({
+                "metadata": {
+                    "namespace": namespace,
+                    "name": name,
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "StatefulSet",
+                        "name": owner,
+                        "uid": owner,
+                        "controller": true,
+                    }],
+                },
+                "spec": {
+                    "containers": [
+                        container("app", true, false),
+                        container("sidecar", false, true),
+                        container("exporter", false, false),
+                        container("proxy", true, true),
+                    ],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("web", "api-0", "api"),
+                pod("web", "api-1", "api"),
+                pod("db", "postgres-0", "postgres"),
+            ],
+        });
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let output = super::missing_health_probes(
+            &source,
+            Vec::new(),
+            true,
+            MissingProbes::ALL.to_vec(),
+            DetailOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(9, output.containers.len());
+        assert_eq!(
+            json!({
+                "namespaces": [
+                    { "namespace": "db", "liveness_only": 1, "readiness_only": 1, "both": 1 },
+                    { "namespace": "web", "liveness_only": 2, "readiness_only": 2, "both": 2 },
+                ],
+                "owners": [
+                    {
+                        "owner": { "namespace": "web", "kind": "StatefulSet", "name": "api", "uid": "api" },
+                        "liveness_only": 2,
+                        "readiness_only": 2,
+                        "both": 2,
+                    },
+                    {
+                        "owner": { "namespace": "db", "kind": "StatefulSet", "name": "postgres", "uid": "postgres" },
+                        "liveness_only": 1,
+                        "readiness_only": 1,
+                        "both": 1,
+                    },
+                ],
+            }),
+            serde_json::to_value(&output.summary).unwrap()
+        );
+
+        let output = super::missing_health_probes(
+            &source,
+            Vec::new(),
+            true,
+            vec![MissingProbes::LivenessOnly],
+            DetailOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let containers = output
+            .containers
+            .iter()
+            .map(|container| {
+                (
+                    container.pod_name.as_str(),
+                    container.container_name.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("api-0", "sidecar"),
+                ("api-1", "sidecar"),
+                ("postgres-0", "sidecar")
+            ],
+            containers
+        );
+    }
 }
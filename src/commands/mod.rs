@@ -1,3 +1,1051 @@
-pub(crate) mod missing_health_probes;
-pub(crate) mod readonly_root_filesystem;
-pub(crate) mod resource_requests;
+//! The checks that can be run against the pods of a cluster.
+
+use std::{cmp::Ordering, collections::BTreeMap, fmt, str::FromStr};
+
+use k8s_openapi::{
+    api::core::v1::{Pod, PodCondition, Taint, Toleration},
+    apimachinery::pkg::apis::meta::v1::LabelSelector,
+    chrono::Utc,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use log::warn;
+
+use crate::{
+    api::Owner,
+    humanize,
+    source::{Source, UNKNOWN_SERVER_VERSION},
+};
+
+pub mod affinity_check;
+pub mod container_name_conventions;
+pub mod container_port_name_check;
+pub mod container_port_policy;
+pub mod container_probe_port_mismatch;
+pub mod container_scratch_image;
+pub mod container_working_dir;
+pub mod control_plane_pods;
+pub mod daemonset_node_coverage;
+pub mod deployment_image_consistency;
+pub mod deprecated_annotations;
+pub mod endpoint_readiness;
+pub mod fit;
+pub mod ingress_tls_check;
+pub mod init_container_health;
+pub mod job_parallelism_check;
+pub mod leaking_cronjobs;
+pub mod liveness_readiness_consistency;
+pub mod missing_health_probes;
+pub mod namespace_resource_balance;
+pub mod node_allocatable_summary;
+pub mod object_sizes;
+pub mod orphan_replicasets;
+pub mod pod_anti_colocate;
+pub mod pod_completion_rate;
+pub mod pod_cpu_throttling_indicator;
+pub mod pod_dns_policy;
+pub mod pod_dns_search_domains;
+pub mod pod_graceful_shutdown_test;
+pub mod pod_ip_family;
+pub mod readiness_gates;
+pub mod readonly_root_filesystem;
+pub mod requests_drift;
+pub mod resource_pressure_events;
+pub mod resource_request_rightsizing;
+pub mod resource_requests;
+pub mod runtime_socket;
+pub mod secret_type_check;
+pub mod service_account_privilege;
+pub mod service_exposure;
+pub mod service_links;
+pub mod sidecar_injection_check;
+pub mod statefulset_pod_management_policy;
+pub mod stuck_finalizers;
+pub mod termination_messages;
+pub mod tiny_requests;
+pub mod token_expiry_check;
+pub mod token_lifetimes;
+pub mod top_owners;
+pub mod update_strategies;
+pub mod version_skew;
+pub mod volume_mount_read_write;
+
+/// How urgently a finding should be looked at.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Informational, nothing needs to be done.
+    #[default]
+    Info,
+    /// Should be cleaned up eventually.
+    Low,
+    /// Likely causes problems under some conditions.
+    Medium,
+    /// Likely causes outages or weakens security.
+    High,
+    /// Has to be fixed right away.
+    Critical,
+}
+
+impl Severity {
+    /// All severities from the lowest to the highest.
+    pub const ALL: [Severity; 5] = [
+        Severity::Info,
+        Severity::Low,
+        Severity::Medium,
+        Severity::High,
+        Severity::Critical,
+    ];
+
+    /// Name of the severity as used in the output and the config file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Severity::ALL
+            .into_iter()
+            .find(|severity| severity.as_str() == s)
+            .ok_or_else(|| {
+                format!("invalid severity {s}, expected one of critical, high, medium, low, info")
+            })
+    }
+}
+
+/// Phase of a pod from its status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PodPhase {
+    /// Accepted but not all containers are running yet, includes pods that
+    /// are not scheduled yet.
+    Pending,
+
+    /// Bound to a node and at least one container is running.
+    Running,
+
+    /// All containers terminated successfully.
+    Succeeded,
+
+    /// All containers terminated and at least one failed.
+    Failed,
+
+    /// The state of the pod can not be determined, usually because the node
+    /// is lost.
+    Unknown,
+}
+
+impl PodPhase {
+    /// All phases.
+    pub const ALL: [PodPhase; 5] = [
+        PodPhase::Pending,
+        PodPhase::Running,
+        PodPhase::Succeeded,
+        PodPhase::Failed,
+        PodPhase::Unknown,
+    ];
+
+    /// Name of the phase as used in the pod status.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PodPhase::Pending => "Pending",
+            PodPhase::Running => "Running",
+            PodPhase::Succeeded => "Succeeded",
+            PodPhase::Failed => "Failed",
+            PodPhase::Unknown => "Unknown",
+        }
+    }
+
+    /// Phase of the pod if its status has a known one.
+    pub fn of(pod: &Pod) -> Option<Self> {
+        let phase = pod.status.as_ref()?.phase.as_deref()?;
+
+        Self::ALL.into_iter().find(|known| known.as_str() == phase)
+    }
+
+    /// Whether the pod is in one of the phases.
+    pub fn matches(pod: &Pod, phases: &[PodPhase]) -> bool {
+        Self::of(pod).is_some_and(|phase| phases.contains(&phase))
+    }
+}
+
+impl fmt::Display for PodPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Phases are parsed case insensitive so `pending` and `Pending` both work.
+impl FromStr for PodPhase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PodPhase::ALL
+            .into_iter()
+            .find(|phase| phase.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "invalid pod phase {s}, expected one of pending, running, succeeded, failed, \
+                     unknown"
+                )
+            })
+    }
+}
+
+/// A single finding reported by a command.
+pub trait Finding: Serialize {
+    /// Namespace of the object the finding belongs to.
+    fn namespace(&self) -> &str;
+
+    /// Top level owner of the pod the finding belongs to if there is one.
+    fn owner(&self) -> Option<&Owner> {
+        None
+    }
+
+    /// Name of the pod the finding belongs to if it belongs to one.
+    fn pod_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Highest severity the check assigns to its findings unless it is
+    /// overridden in the config.
+    const DEFAULT_SEVERITY: Severity;
+
+    /// Severity of the finding.
+    fn severity(&self) -> Severity;
+
+    /// Replace the severity of the finding, used to apply overrides from the
+    /// config file.
+    fn set_severity(&mut self, severity: Severity);
+}
+
+/// The output document of a command.
+pub trait Report: Serialize {
+    /// Type of the findings in the report.
+    type Finding: Finding;
+
+    /// All findings contained in the report.
+    fn findings(&self) -> Vec<&Self::Finding>;
+
+    /// Remove the findings for which `keep` returns false. `keep` may modify
+    /// the findings it is called with.
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool);
+
+    /// Sort the findings with `compare` keeping the order of equal findings.
+    /// Reports that keep their findings in a fixed order ignore this.
+    fn sort_findings(
+        &mut self,
+        _compare: &mut dyn FnMut(&Self::Finding, &Self::Finding) -> Ordering,
+    ) {
+    }
+
+    /// Notes about how the report was created that are added to the metadata
+    /// of the run.
+    fn notes(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl<T: Finding> Report for Vec<T> {
+    type Finding = T;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.iter().collect()
+    }
+
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        self.retain_mut(|finding| keep(finding));
+    }
+
+    fn sort_findings(
+        &mut self,
+        compare: &mut dyn FnMut(&Self::Finding, &Self::Finding) -> Ordering,
+    ) {
+        self.sort_by(|a, b| compare(a, b));
+    }
+}
+
+/// Order the findings of a report are printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    /// Alphabetically by namespace and pod.
+    Namespace,
+
+    /// Alphabetically by owner, namespace and pod. Findings without an owner
+    /// come last.
+    Owner,
+
+    /// Alphabetically by pod and namespace.
+    Pod,
+
+    /// Namespaces with the most findings first and within them the owners
+    /// with the most findings first.
+    Count,
+}
+
+impl SortBy {
+    /// All orders.
+    pub const ALL: [SortBy; 4] = [SortBy::Namespace, SortBy::Owner, SortBy::Pod, SortBy::Count];
+
+    /// Name of the order as used on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortBy::Namespace => "namespace",
+            SortBy::Owner => "owner",
+            SortBy::Pod => "pod",
+            SortBy::Count => "count",
+        }
+    }
+
+    /// Sort the findings of the report. Findings that are equal in the order
+    /// keep the order the check returned them in.
+    pub fn apply<R: Report>(self, report: &mut R) {
+        let summary = Summary::new(&report.findings());
+
+        let owner = |finding: &R::Finding| finding.owner().map(owner_key);
+
+        let count = |counts: &BTreeMap<String, usize>, key: Option<&String>| {
+            key.and_then(|key| counts.get(key)).copied().unwrap_or(0)
+        };
+
+        report.sort_findings(&mut |a, b| {
+            let (owner_a, owner_b) = (owner(a), owner(b));
+
+            let namespace = a.namespace().cmp(b.namespace());
+            let owner = owner_a.cmp(&owner_b);
+            let pod = a.pod_name().cmp(&b.pod_name());
+
+            match self {
+                SortBy::Namespace => namespace.then(pod),
+
+                SortBy::Owner => owner_a
+                    .is_none()
+                    .cmp(&owner_b.is_none())
+                    .then(owner)
+                    .then(namespace)
+                    .then(pod),
+
+                SortBy::Pod => pod.then(namespace),
+
+                SortBy::Count => {
+                    let namespace_a = a.namespace().to_string();
+                    let namespace_b = b.namespace().to_string();
+
+                    count(&summary.namespaces, Some(&namespace_b))
+                        .cmp(&count(&summary.namespaces, Some(&namespace_a)))
+                        .then(namespace)
+                        .then(
+                            count(&summary.owners, owner_b.as_ref())
+                                .cmp(&count(&summary.owners, owner_a.as_ref())),
+                        )
+                        .then(owner)
+                        .then(pod)
+                }
+            }
+        });
+    }
+}
+
+/// Key of the owner in aggregations, owners with the same name in different
+/// namespaces are counted separately.
+fn owner_key(owner: &Owner) -> String {
+    format!("{}/{}/{}", owner.namespace, owner.kind, owner.name)
+}
+
+impl fmt::Display for SortBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SortBy::ALL
+            .into_iter()
+            .find(|sort_by| sort_by.as_str() == s)
+            .ok_or_else(|| {
+                format!("invalid order {s}, expected one of namespace, owner, pod, count")
+            })
+    }
+}
+
+/// Number of findings of a report in total and grouped by namespace, owner and
+/// severity.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Summary {
+    /// Number of all findings.
+    pub total: usize,
+
+    /// Number of findings per namespace.
+    pub namespaces: BTreeMap<String, usize>,
+
+    /// Number of findings per top level owner as `namespace/kind/name`,
+    /// findings without an owner are not counted.
+    pub owners: BTreeMap<String, usize>,
+
+    /// Number of findings per severity.
+    pub severities: BTreeMap<Severity, usize>,
+}
+
+impl Summary {
+    /// Count the findings of the report.
+    pub fn new<F: Finding>(findings: &[&F]) -> Self {
+        let mut summary = Self {
+            total: findings.len(),
+            ..Self::default()
+        };
+
+        for finding in findings {
+            *summary
+                .namespaces
+                .entry(finding.namespace().to_string())
+                .or_insert(0) += 1;
+
+            if let Some(owner) = finding.owner() {
+                *summary.owners.entry(owner_key(owner)).or_insert(0) += 1;
+            }
+
+            *summary.severities.entry(finding.severity()).or_insert(0) += 1;
+        }
+
+        summary
+    }
+}
+
+/// Severity overrides and the minimum severity of findings that are reported.
+#[derive(Debug, Clone, Default)]
+pub struct SeverityOptions {
+    /// Findings below this severity are removed from the report.
+    pub min: Severity,
+
+    /// Severity of all findings of a check keyed by the name of the check.
+    /// Replaces the default severities the check assigns.
+    pub overrides: BTreeMap<String, Severity>,
+}
+
+impl SeverityOptions {
+    /// Apply the override for the check to the findings of the report and
+    /// remove all findings below the minimum severity.
+    pub fn apply<R: Report>(&self, check: &str, report: &mut R) {
+        let severity = self.overrides.get(check).copied();
+
+        report.retain_findings(&mut |finding| {
+            if let Some(severity) = severity {
+                finding.set_severity(severity);
+            }
+
+            finding.severity() >= self.min
+        });
+    }
+}
+
+/// Reports of a command run against multiple clusters keyed by the name of the
+/// kubeconfig context.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct ClusterReports<R>(pub BTreeMap<String, ClusterReport<R>>);
+
+/// Outcome of running a command against a single cluster.
+#[derive(Debug, Serialize)]
+pub struct ClusterReport<R> {
+    /// Report of the command if it succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<R>,
+
+    /// Version of the api server of the cluster, the document metadata only
+    /// holds one version for runs against a single cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_version: Option<String>,
+
+    /// Errors that prevented the command from finishing on the cluster.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl<R: Report> Report for ClusterReports<R> {
+    type Finding = R::Finding;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.0
+            .values()
+            .filter_map(|cluster| cluster.report.as_ref())
+            .flat_map(Report::findings)
+            .collect()
+    }
+
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        for report in self
+            .0
+            .values_mut()
+            .filter_map(|cluster| cluster.report.as_mut())
+        {
+            report.retain_findings(keep);
+        }
+    }
+
+    fn sort_findings(
+        &mut self,
+        compare: &mut dyn FnMut(&Self::Finding, &Self::Finding) -> Ordering,
+    ) {
+        for report in self
+            .0
+            .values_mut()
+            .filter_map(|cluster| cluster.report.as_mut())
+        {
+            report.sort_findings(compare);
+        }
+    }
+}
+
+/// Controls which optional pod details are added to the output of the
+/// commands.
+#[derive(Debug, Clone, Default)]
+pub struct DetailOptions {
+    /// Include all labels of the pod.
+    pub labels: bool,
+
+    /// Include the given labels of the pod, labels the pod does not have are
+    /// included with an empty value so every entry has the same keys.
+    pub label_columns: Vec<String>,
+
+    /// Include all owners of the pod up to the top level owner.
+    pub owner_chain: bool,
+}
+
+/// Pod details that get flattened into the output structs of all commands.
+/// Everything except the node and the age is only included when enabled in
+/// the [`DetailOptions`].
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, Clone, Default, JsonSchema)]
+pub struct PodDetails {
+    /// Node the pod is scheduled on, pending pods do not have one yet.
+    node: Option<String>,
+
+    /// Creation time of the pod in RFC 3339 format.
+    created: Option<String>,
+
+    /// Time since the pod was created, for example `13d4h`.
+    age: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    labels: Option<BTreeMap<String, Option<String>>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner_chain: Option<Vec<Owner>>,
+}
+
+impl PodDetails {
+    /// Collect the details enabled in the options from the pod.
+    pub fn new(source: &dyn Source, pod: &Pod, options: &DetailOptions) -> Self {
+        let labels = (options.labels || !options.label_columns.is_empty()).then(|| {
+            let pod_labels = pod.metadata.labels.clone().unwrap_or_default();
+
+            let mut labels = if options.labels {
+                pod_labels
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Some(value.clone())))
+                    .collect()
+            } else {
+                BTreeMap::new()
+            };
+
+            for column in &options.label_columns {
+                labels.insert(column.clone(), pod_labels.get(column).cloned());
+            }
+
+            labels
+        });
+
+        let owner_chain = options.owner_chain.then(|| source.owner_chain(pod));
+
+        let created = pod.metadata.creation_timestamp.as_ref().map(|time| time.0);
+
+        // Clock skew between the cluster and the machine running the check can
+        // make fresh pods appear to be created in the future.
+        let age = created
+            .map(|created| humanize::duration((Utc::now() - created).to_std().unwrap_or_default()));
+
+        Self {
+            node: pod.spec.as_ref().and_then(|spec| spec.node_name.clone()),
+            created: created.map(|created| created.to_rfc3339()),
+            age,
+            labels,
+            owner_chain,
+        }
+    }
+}
+
+/// Version of the api server a report is generated from. A version that can
+/// not be read does not fail the check, it is reported as unknown instead.
+pub(crate) async fn k8s_version(source: &dyn Source) -> String {
+    source.server_version().await.unwrap_or_else(|err| {
+        warn!("Failed to get the api server version, skipping it: {err:?}");
+        UNKNOWN_SERVER_VERSION.to_string()
+    })
+}
+
+/// Condition of the pod with the type, for example `Ready`.
+pub(crate) fn pod_condition<'a>(pod: &'a Pod, condition_type: &str) -> Option<&'a PodCondition> {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .into_iter()
+        .flatten()
+        .find(|condition| condition.type_ == condition_type)
+}
+
+/// Whether the toleration lets pods be scheduled on or keep running on a node
+/// with the taint.
+pub(crate) fn tolerates(toleration: &Toleration, taint: &Taint) -> bool {
+    // An empty effect tolerates the taint with every effect.
+    let effect = toleration.effect.as_deref().unwrap_or_default();
+    let effect = effect.is_empty() || effect == taint.effect;
+
+    let key = toleration.key.as_deref().unwrap_or_default();
+
+    match toleration.operator.as_deref() {
+        // An empty key with `Exists` tolerates every taint.
+        Some("Exists") => effect && (key.is_empty() || key == taint.key),
+        _ => {
+            effect
+                && key == taint.key
+                && toleration.value.as_deref().unwrap_or_default()
+                    == taint.value.as_deref().unwrap_or_default()
+        }
+    }
+}
+
+/// Whether the labels match the selector. An empty selector matches
+/// everything, unknown operators match nothing.
+pub(crate) fn matches_selector(
+    selector: &LabelSelector,
+    labels: Option<&BTreeMap<String, String>>,
+) -> bool {
+    let label = |key: &str| labels.and_then(|labels| labels.get(key));
+
+    let labels_match = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .all(|(key, value)| label(key) == Some(value));
+
+    let expressions_match = selector
+        .match_expressions
+        .iter()
+        .flatten()
+        .all(|expression| {
+            let values = expression.values.as_deref().unwrap_or_default();
+            let value = label(&expression.key);
+
+            match expression.operator.as_str() {
+                "In" => value.is_some_and(|value| values.contains(value)),
+                "NotIn" => !value.is_some_and(|value| values.contains(value)),
+                "Exists" => value.is_some(),
+                "DoesNotExist" => value.is_none(),
+                _ => false,
+            }
+        });
+
+    labels_match && expressions_match
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::{
+        api::core::v1::{Taint, Toleration},
+        apimachinery::pkg::apis::meta::v1::{OwnerReference, Time},
+        chrono::{self, Utc},
+    };
+
+    use super::{
+        matches_selector, tolerates, ClusterReport, ClusterReports, DetailOptions, Finding,
+        PodDetails, PodPhase, Report, Severity, SortBy, Summary,
+    };
+    use crate::{
+        api::Owner, commands::readonly_root_filesystem::readonly_root_filesystem,
+        source::FileSource,
+    };
+
+    #[test]
+    fn pod_details() {
+        let pod = k8s_openapi::api::core::v1::Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                labels: Some(BTreeMap::from([("app".to_string(), "test".to_string())])),
+                owner_references: Some(vec![OwnerReference {
+                    kind: "ReplicaSet".to_string(),
+                    name: "test-1234".to_string(),
+                    uid: "1234".to_string(),
+                    controller: Some(true),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let source = FileSource::default();
+
+        let details = PodDetails::new(&source, &pod, &DetailOptions::default());
+        assert_eq!(PodDetails::default(), details);
+        assert_eq!(
+            serde_json::json!({ "node": null, "created": null, "age": null }),
+            serde_json::to_value(&details).unwrap()
+        );
+
+        let details = PodDetails::new(
+            &source,
+            &pod,
+            &DetailOptions {
+                labels: true,
+                owner_chain: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            Some(BTreeMap::from([(
+                "app".to_string(),
+                Some("test".to_string())
+            )])),
+            details.labels
+        );
+
+        assert_eq!(
+            Some(vec![Owner {
+                name: "test-1234".to_string(),
+                kind: "ReplicaSet".to_string(),
+                uid: "1234".to_string(),
+                namespace: "test".to_string(),
+            }]),
+            details.owner_chain
+        );
+    }
+
+    #[test]
+    fn pod_details_age() {
+        let created = Utc::now() - chrono::Duration::hours(3) - chrono::Duration::seconds(30);
+
+        let pod = k8s_openapi::api::core::v1::Pod {
+            metadata: kube::api::ObjectMeta {
+                creation_timestamp: Some(Time(created)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let details = PodDetails::new(&FileSource::default(), &pod, &DetailOptions::default());
+
+        assert_eq!(Some(created.to_rfc3339()), details.created);
+        assert_eq!(Some("3h".to_string()), details.age);
+    }
+
+    #[test]
+    fn pod_details_label_columns() {
+        let pod = k8s_openapi::api::core::v1::Pod {
+            metadata: kube::api::ObjectMeta {
+                labels: Some(BTreeMap::from([
+                    ("app".to_string(), "test".to_string()),
+                    ("team".to_string(), "platform".to_string()),
+                ])),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let options = DetailOptions {
+            label_columns: vec!["team".to_string(), "tier".to_string()],
+            ..Default::default()
+        };
+
+        let details = PodDetails::new(&FileSource::default(), &pod, &options);
+
+        // Missing labels are kept as null so every entry has the same keys.
+        assert_eq!(
+            serde_json::json!({ "node": null, "created": null, "age": null, "labels": { "team": "platform", "tier": null } }),
+            serde_json::to_value(details).unwrap()
+        );
+
+        let pod = k8s_openapi::api::core::v1::Pod::default();
+        let details = PodDetails::new(&FileSource::default(), &pod, &options);
+
+        assert_eq!(
+            serde_json::json!({ "node": null, "created": null, "age": null, "labels": { "team": null, "tier": null } }),
+            serde_json::to_value(details).unwrap()
+        );
+
+        let options = DetailOptions {
+            labels: true,
+            label_columns: vec!["tier".to_string()],
+            ..Default::default()
+        };
+
+        let pod = k8s_openapi::api::core::v1::Pod {
+            metadata: kube::api::ObjectMeta {
+                labels: Some(BTreeMap::from([("app".to_string(), "test".to_string())])),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let details = PodDetails::new(&FileSource::default(), &pod, &options);
+
+        assert_eq!(
+            serde_json::json!({ "node": null, "created": null, "age": null, "labels": { "app": "test", "tier": null } }),
+            serde_json::to_value(details).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn summary() {
+        let pods = include_str!("../../resources/fixtures/pods.json");
+        let source = FileSource::from_readers(pods.as_bytes(), None::<&[u8]>).unwrap();
+
+        let report = readonly_root_filesystem(&source, Vec::new(), true, DetailOptions::default())
+            .await
+            .unwrap();
+
+        let summary = Summary::new(&report.findings());
+
+        assert_eq!(
+            Summary {
+                total: 4,
+                namespaces: BTreeMap::from([("batch".to_string(), 1), ("web".to_string(), 3)]),
+                owners: BTreeMap::from([("web/ReplicaSet/frontend-7d9c8b7f5".to_string(), 2)]),
+                severities: BTreeMap::from([(Severity::Medium, 4)]),
+            },
+            summary
+        );
+    }
+
+    #[test]
+    fn pod_phase() {
+        let pod = |phase: Option<&str>| k8s_openapi::api::core::v1::Pod {
+            status: Some(k8s_openapi::api::core::v1::PodStatus {
+                phase: phase.map(ToString::to_string),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let running = [PodPhase::Running];
+        let demand = [PodPhase::Pending, PodPhase::Running, PodPhase::Unknown];
+
+        assert!(PodPhase::matches(&pod(Some("Running")), &running));
+        assert!(!PodPhase::matches(&pod(Some("Pending")), &running));
+        assert!(PodPhase::matches(&pod(Some("Pending")), &demand));
+        assert!(PodPhase::matches(&pod(Some("Unknown")), &demand));
+        assert!(!PodPhase::matches(&pod(Some("Succeeded")), &demand));
+        assert!(!PodPhase::matches(&pod(Some("Evicted")), &demand));
+        assert!(!PodPhase::matches(&pod(None), &demand));
+
+        assert_eq!(Ok(PodPhase::Pending), "pending".parse());
+        assert_eq!(Ok(PodPhase::Unknown), "Unknown".parse());
+        assert!("lost".parse::<PodPhase>().is_err());
+    }
+
+    #[tokio::test]
+    async fn sort_by() {
+        let pods = include_str!("../../resources/fixtures/pods.json");
+        let source = FileSource::from_readers(pods.as_bytes(), None::<&[u8]>).unwrap();
+
+        let frontend = ("web", "frontend-7d9c8b7f5-abcde");
+
+        let testcases = [
+            (
+                SortBy::Namespace,
+                [("batch", "migrate"), ("web", "api-0"), frontend, frontend],
+            ),
+            (
+                SortBy::Owner,
+                [frontend, frontend, ("batch", "migrate"), ("web", "api-0")],
+            ),
+            (
+                SortBy::Pod,
+                [("web", "api-0"), frontend, frontend, ("batch", "migrate")],
+            ),
+            (
+                SortBy::Count,
+                [frontend, frontend, ("web", "api-0"), ("batch", "migrate")],
+            ),
+        ];
+
+        for (sort_by, expected) in testcases {
+            let mut report =
+                readonly_root_filesystem(&source, Vec::new(), true, DetailOptions::default())
+                    .await
+                    .unwrap();
+
+            sort_by.apply(&mut report);
+
+            let order = report
+                .findings()
+                .into_iter()
+                .map(|finding| (finding.namespace(), finding.pod_name().unwrap()))
+                .collect::<Vec<_>>();
+
+            assert_eq!(expected.to_vec(), order, "{sort_by}");
+        }
+    }
+
+    #[tokio::test]
+    async fn cluster_reports() {
+        let pods = include_str!("../../resources/fixtures/pods.json");
+        let source = FileSource::from_readers(pods.as_bytes(), None::<&[u8]>).unwrap();
+
+        let report = readonly_root_filesystem(&source, Vec::new(), true, DetailOptions::default())
+            .await
+            .unwrap();
+
+        let findings = report.findings().len();
+
+        let reports = ClusterReports(BTreeMap::from([
+            (
+                "ok".to_string(),
+                ClusterReport {
+                    report: Some(report),
+                    server_version: Some("v1.29.2".to_string()),
+                    errors: Vec::new(),
+                },
+            ),
+            (
+                "failed".to_string(),
+                ClusterReport {
+                    report: None,
+                    server_version: None,
+                    errors: vec!["connection refused".to_string()],
+                },
+            ),
+        ]));
+
+        assert_eq!(findings, reports.findings().len());
+
+        let value = serde_json::to_value(&reports).unwrap();
+        assert_eq!(
+            serde_json::json!({ "errors": ["connection refused"] }),
+            value["failed"]
+        );
+        assert_eq!(
+            findings,
+            value["ok"]["report"]["containers"]
+                .as_array()
+                .unwrap()
+                .len()
+        );
+        assert_eq!("v1.29.2", value["ok"]["server_version"]);
+    }
+
+    #[test]
+    fn tolerations() {
+        const KEY: &str = "node-role.kubernetes.io/control-plane";
+
+        let taint: Taint =
+            serde_json::from_value(serde_json::json!({ "key": KEY, "effect": "NoSchedule" }))
+                .unwrap();
+
+        let tolerates = |value: serde_json::Value| {
+            tolerates(
+                &serde_json::from_value::<Toleration>(value).unwrap(),
+                &taint,
+            )
+        };
+
+        assert!(tolerates(serde_json::json!({ "operator": "Exists" })));
+        assert!(tolerates(
+            serde_json::json!({ "key": KEY, "operator": "Exists" })
+        ));
+        assert!(tolerates(
+            serde_json::json!({ "key": KEY, "operator": "Exists", "effect": "NoSchedule" })
+        ));
+        assert!(tolerates(
+            serde_json::json!({ "key": KEY, "operator": "Equal" })
+        ));
+
+        assert!(!tolerates(
+            serde_json::json!({ "key": "node-role.kubernetes.io/master", "operator": "Exists" })
+        ));
+        assert!(!tolerates(
+            serde_json::json!({ "key": KEY, "operator": "Exists", "effect": "NoExecute" })
+        ));
+        assert!(!tolerates(
+            serde_json::json!({ "key": KEY, "operator": "Equal", "value": "true" })
+        ));
+        assert!(!tolerates(serde_json::json!({ "operator": "Equal" })));
+    }
+
+    #[test]
+    fn selector() {
+        let labels = BTreeMap::from([
+            ("app".to_string(), "api".to_string()),
+            ("tier".to_string(), "backend".to_string()),
+        ]);
+
+        let matches = |selector: serde_json::Value, labels| {
+            matches_selector(&serde_json::from_value(selector).unwrap(), labels)
+        };
+
+        assert!(matches(serde_json::json!({}), None));
+        assert!(matches(
+            serde_json::json!({ "matchLabels": { "app": "api" } }),
+            Some(&labels)
+        ));
+        assert!(matches(
+            serde_json::json!({
+                "matchExpressions": [
+                    { "key": "tier", "operator": "In", "values": ["backend", "cache"] },
+                    { "key": "env", "operator": "NotIn", "values": ["test"] },
+                    { "key": "app", "operator": "Exists" },
+                    { "key": "canary", "operator": "DoesNotExist" },
+                ],
+            }),
+            Some(&labels)
+        ));
+
+        assert!(!matches(
+            serde_json::json!({ "matchLabels": { "app": "api" } }),
+            None
+        ));
+        assert!(!matches(
+            serde_json::json!({
+                "matchLabels": { "app": "api" },
+                "matchExpressions": [{ "key": "tier", "operator": "NotIn", "values": ["backend"] }],
+            }),
+            Some(&labels)
+        ));
+        assert!(!matches(
+            serde_json::json!({ "matchExpressions": [{ "key": "app", "operator": "Gt" }] }),
+            Some(&labels)
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+//! Compare the resource requests of namespaces with the capacity of the
+//! cluster.
+
+use std::collections::BTreeMap;
+
+use eyre::{Context, Result};
+use k8s_openapi::api::core::v1::{Node, Pod};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{Cpu, Memory},
+    commands::{Finding, Report, Severity},
+    source::Source,
+};
+
+/// Resource requests of every namespace compared to the allocatable resources
+/// of the cluster.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Output {
+    cluster: ClusterTotal,
+    namespaces: Vec<NamespaceBalance>,
+}
+
+/// Allocatable resources of all nodes and the requests of all checked
+/// namespaces.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClusterTotal {
+    allocatable_cpu: Cpu,
+    allocatable_memory: Memory,
+    requests_cpu: Cpu,
+    requests_memory: Memory,
+    cpu_pct_of_cluster: f64,
+    memory_pct_of_cluster: f64,
+}
+
+/// Resource requests of a single namespace.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NamespaceBalance {
+    namespace: String,
+    requests_cpu: Cpu,
+    requests_memory: Memory,
+    namespace_cpu_pct_of_cluster: f64,
+    namespace_memory_pct_of_cluster: f64,
+
+    /// Whether the cpu or memory share is above the maximum percentage.
+    flagged: bool,
+    severity: Severity,
+}
+
+impl Finding for NamespaceBalance {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+impl Report for Output {
+    type Finding = NamespaceBalance;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.namespaces
+            .iter()
+            .filter(|namespace| namespace.flagged)
+            .collect()
+    }
+
+    /// Only flagged namespaces are findings, the others are always kept.
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        self.namespaces
+            .retain_mut(|namespace| !namespace.flagged || keep(namespace));
+    }
+}
+
+/// Sum the resource requests of the containers of all running and pending pods
+/// per namespace and compare them with the allocatable resources of all nodes.
+/// Namespaces requesting more than `max_namespace_pct` percent of the cpu or
+/// memory of the cluster are flagged.
+pub async fn namespace_resource_balance(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    max_namespace_pct: f64,
+) -> Result<Output> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+    let nodes = source.nodes().await?;
+
+    let (allocatable_cpu, allocatable_memory) = allocatable(&nodes)?;
+    let requests = namespace_requests(source, &pods)?;
+
+    let namespaces = requests
+        .into_iter()
+        .map(|(namespace, (requests_cpu, requests_memory))| {
+            let namespace_cpu_pct_of_cluster = percentage(
+                requests_cpu.to_milliseconds(),
+                allocatable_cpu.to_milliseconds(),
+            );
+
+            let namespace_memory_pct_of_cluster =
+                percentage(requests_memory.to_bytes(), allocatable_memory.to_bytes());
+
+            NamespaceBalance {
+                namespace: namespace.to_string(),
+                requests_cpu,
+                requests_memory,
+                namespace_cpu_pct_of_cluster,
+                namespace_memory_pct_of_cluster,
+                flagged: namespace_cpu_pct_of_cluster > max_namespace_pct
+                    || namespace_memory_pct_of_cluster > max_namespace_pct,
+                severity: Severity::Medium,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let requests_cpu = namespaces.iter().fold(Cpu::default(), |sum, namespace| {
+        sum + namespace.requests_cpu
+    });
+
+    let requests_memory = namespaces.iter().fold(Memory::default(), |sum, namespace| {
+        sum + namespace.requests_memory
+    });
+
+    let cluster = ClusterTotal {
+        allocatable_cpu,
+        allocatable_memory,
+        requests_cpu,
+        requests_memory,
+        cpu_pct_of_cluster: percentage(
+            requests_cpu.to_milliseconds(),
+            allocatable_cpu.to_milliseconds(),
+        ),
+        memory_pct_of_cluster: percentage(
+            requests_memory.to_bytes(),
+            allocatable_memory.to_bytes(),
+        ),
+    };
+
+    Ok(Output {
+        cluster,
+        namespaces,
+    })
+}
+
+/// Sum of the allocatable cpu and memory of the nodes.
+fn allocatable(nodes: &[Node]) -> Result<(Cpu, Memory)> {
+    nodes
+        .iter()
+        .filter_map(|node| node.status.as_ref()?.allocatable.as_ref())
+        .try_fold(
+            (Cpu::default(), Memory::default()),
+            |(cpu, memory), allocatable| {
+                let node_cpu = allocatable
+                    .get("cpu")
+                    .map(Cpu::try_from)
+                    .transpose()
+                    .context("failed to convert allocatable cpu")?
+                    .unwrap_or_default();
+
+                let node_memory = allocatable
+                    .get("memory")
+                    .map(Memory::try_from)
+                    .transpose()
+                    .context("failed to convert allocatable memory")?
+                    .unwrap_or_default();
+
+                Ok((cpu + node_cpu, memory + node_memory))
+            },
+        )
+}
+
+/// Sum of the cpu and memory requests of the containers of all pods that are
+/// not finished, keyed by namespace.
+fn namespace_requests<'a>(
+    source: &dyn Source,
+    pods: &'a [Pod],
+) -> Result<BTreeMap<&'a str, (Cpu, Memory)>> {
+    let mut requests = BTreeMap::new();
+
+    let pods = pods.iter().filter(|pod| {
+        !matches!(
+            pod.status
+                .as_ref()
+                .and_then(|status| status.phase.as_deref()),
+            Some("Succeeded" | "Failed")
+        )
+    });
+
+    for pod in pods {
+        let namespace = pod
+            .metadata
+            .namespace
+            .as_deref()
+            .expect("failed to get namespace");
+
+        let containers = pod
+            .spec
+            .iter()
+            .flat_map(|spec| spec.containers.iter())
+            .filter(|container| source.checks_container(&container.name));
+
+        for container in containers {
+            let Some(container_requests) = container
+                .resources
+                .as_ref()
+                .and_then(|resources| resources.requests.as_ref())
+            else {
+                continue;
+            };
+
+            let cpu = container_requests
+                .get("cpu")
+                .map(Cpu::try_from)
+                .transpose()
+                .context("failed to convert cpu requests")?
+                .unwrap_or_default();
+
+            let memory = container_requests
+                .get("memory")
+                .map(Memory::try_from)
+                .transpose()
+                .context("failed to convert memory requests")?
+                .unwrap_or_default();
+
+            let (sum_cpu, sum_memory) = requests
+                .entry(namespace)
+                .or_insert((Cpu::default(), Memory::default()));
+
+            *sum_cpu = *sum_cpu + cpu;
+            *sum_memory = *sum_memory + memory;
+        }
+    }
+
+    Ok(requests)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn percentage(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    part as f64 / total as f64 * 100.0
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use crate::source::FileSource;
+
+    const PODS: &str = include_str!("../../resources/fixtures/pods.json");
+    const NODES: &str = include_str!("../../resources/fixtures/nodes.json");
+
+    #[tokio::test]
+    async fn namespace_resource_balance() {
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(NODES.as_bytes())
+            .unwrap();
+
+        let output = super::namespace_resource_balance(&source, Vec::new(), true, 10.0)
+            .await
+            .unwrap();
+
+        assert_eq!(2000, output.cluster.allocatable_cpu.to_milliseconds());
+        assert_eq!(1, output.namespaces.len());
+
+        let web = &output.namespaces[0];
+        assert_eq!("web", web.namespace);
+        assert!((web.namespace_cpu_pct_of_cluster - 5.0).abs() < f64::EPSILON);
+        assert!((web.namespace_memory_pct_of_cluster - 12.5).abs() < f64::EPSILON);
+        assert!(web.flagged);
+    }
+
+    #[test]
+    fn percentage() {
+        assert!((super::percentage(1, 4) - 25.0).abs() < f64::EPSILON);
+        assert!(super::percentage(1, 0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,268 @@
+//! Compare the allocatable resources of every node with the requests of the
+//! pods running on it.
+
+use std::collections::BTreeMap;
+
+use eyre::{Context, Result};
+use k8s_openapi::api::core::v1::{Node, Pod};
+use num::traits::SaturatingSub;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{Cpu, Memory},
+    commands::{Finding, PodPhase, Severity},
+    source::Source,
+};
+
+/// Allocatable and requested resources of a node.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct NodeAllocatable {
+    node_name: String,
+    allocatable_cpu: Cpu,
+    allocatable_memory: Memory,
+    requested_cpu: Cpu,
+    requested_memory: Memory,
+
+    /// Allocatable cpu that is not requested yet.
+    available_cpu: Cpu,
+
+    /// Allocatable memory that is not requested yet.
+    available_memory: Memory,
+
+    /// Requested share of the allocatable cpu or memory in percent, whichever
+    /// is higher, rounded down.
+    utilization_pct: u64,
+
+    severity: Severity,
+}
+
+impl Finding for NodeAllocatable {
+    const DEFAULT_SEVERITY: Severity = Severity::Info;
+
+    /// Nodes are not namespaced.
+    fn namespace(&self) -> &'static str {
+        ""
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Sum the cpu and memory requests of the containers of the running pods per
+/// node and compare them with the allocatable resources of the node. Only pods
+/// of the checked namespaces are counted, use `--all-namespaces` to see the
+/// real headroom of the nodes.
+pub async fn node_allocatable_summary(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<NodeAllocatable>> {
+    let nodes = source.nodes().await?;
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let requests = node_requests(&pods, |name| source.checks_container(name))?;
+
+    let mut summary = Vec::new();
+    for node in &nodes {
+        let Some(node_name) = node.metadata.name.as_deref() else {
+            continue;
+        };
+
+        let (allocatable_cpu, allocatable_memory) = node_allocatable(node)?;
+
+        let (requested_cpu, requested_memory) =
+            requests.get(node_name).copied().unwrap_or_default();
+
+        let utilization_pct = percentage(
+            requested_cpu.to_milliseconds(),
+            allocatable_cpu.to_milliseconds(),
+        )
+        .max(percentage(
+            requested_memory.to_bytes(),
+            allocatable_memory.to_bytes(),
+        ));
+
+        summary.push(NodeAllocatable {
+            node_name: node_name.to_string(),
+            allocatable_cpu,
+            allocatable_memory,
+            requested_cpu,
+            requested_memory,
+            available_cpu: allocatable_cpu.saturating_sub(&requested_cpu),
+            available_memory: allocatable_memory.saturating_sub(&requested_memory),
+            utilization_pct,
+            severity: Severity::Info,
+        });
+    }
+
+    summary.sort();
+
+    Ok(summary)
+}
+
+/// Allocatable cpu and memory of the node, zero if the node does not report
+/// them.
+pub(crate) fn node_allocatable(node: &Node) -> Result<(Cpu, Memory)> {
+    let allocatable = node
+        .status
+        .as_ref()
+        .and_then(|status| status.allocatable.as_ref());
+
+    let cpu = allocatable
+        .and_then(|allocatable| allocatable.get("cpu"))
+        .map(Cpu::try_from)
+        .transpose()
+        .context("failed to convert allocatable cpu")?
+        .unwrap_or_default();
+
+    let memory = allocatable
+        .and_then(|allocatable| allocatable.get("memory"))
+        .map(Memory::try_from)
+        .transpose()
+        .context("failed to convert allocatable memory")?
+        .unwrap_or_default();
+
+    Ok((cpu, memory))
+}
+
+/// Sum of the cpu and memory requests of the containers of the running pods,
+/// keyed by the name of their node. Only containers for which
+/// `checks_container` returns true are summed.
+pub(crate) fn node_requests(
+    pods: &[Pod],
+    checks_container: impl Fn(&str) -> bool,
+) -> Result<BTreeMap<&str, (Cpu, Memory)>> {
+    let mut requests: BTreeMap<&str, (Cpu, Memory)> = BTreeMap::new();
+
+    for pod in pods
+        .iter()
+        .filter(|pod| PodPhase::matches(pod, &[PodPhase::Running]))
+    {
+        let Some(spec) = &pod.spec else {
+            continue;
+        };
+
+        let Some(node_name) = spec.node_name.as_deref() else {
+            continue;
+        };
+
+        for container_requests in spec
+            .containers
+            .iter()
+            .filter(|container| checks_container(&container.name))
+            .filter_map(|container| {
+                container
+                    .resources
+                    .as_ref()
+                    .and_then(|resources| resources.requests.as_ref())
+            })
+        {
+            let cpu = container_requests
+                .get("cpu")
+                .map(Cpu::try_from)
+                .transpose()
+                .context("failed to convert cpu requests")?
+                .unwrap_or_default();
+
+            let memory = container_requests
+                .get("memory")
+                .map(Memory::try_from)
+                .transpose()
+                .context("failed to convert memory requests")?
+                .unwrap_or_default();
+
+            let (sum_cpu, sum_memory) = requests.entry(node_name).or_default();
+
+            *sum_cpu = *sum_cpu + cpu;
+            *sum_memory = *sum_memory + memory;
+        }
+    }
+
+    Ok(requests)
+}
+
+fn percentage(part: u64, total: u64) -> u64 {
+    if total == 0 {
+        return 0;
+    }
+
+    part * 100 / total
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::source::FileSource;
+
+    #[tokio::test]
+    async fn node_allocatable_summary() {
+        let node = |name: &str, cpu: &str, memory: &str| {
+            json!({
+                "metadata": { "name": name },
+                "status": { "allocatable": { "cpu": cpu, "memory": memory } },
+            })
+        };
+
+        let nodes = json!({
+            "items": [node("node-a", "2", "4Gi"), node("node-b", "4", "8Gi")],
+        });
+
+        let pod = |name: &str, node: &str, phase: &str, cpu: &str, memory: &str| {
+            json!({
+                "metadata": { "namespace": "web", "name": name },
+                "spec": {
+                    "nodeName": node,
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": cpu, "memory": memory } },
+                    }],
+                },
+                "status": { "phase": phase },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("api-0", "node-a", "Running", "500m", "1Gi"),
+                pod("api-1", "node-a", "Running", "1", "1Gi"),
+                pod("job-0", "node-a", "Succeeded", "1", "1Gi"),
+                pod("worker-0", "node-b", "Running", "1", "6Gi"),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(nodes.to_string().as_bytes())
+            .unwrap();
+
+        let summary = super::node_allocatable_summary(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let summary = summary
+            .iter()
+            .map(|node| {
+                (
+                    node.node_name.as_str(),
+                    node.requested_cpu.to_milliseconds(),
+                    node.available_cpu.to_milliseconds(),
+                    node.available_memory.to_bytes() / 1024 / 1024 / 1024,
+                    node.utilization_pct,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![("node-a", 1500, 500, 2, 75), ("node-b", 1000, 3000, 2, 75)],
+            summary
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Find config maps and secrets that get close to the size limit of objects.
+
+use eyre::Result;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Size limit of a single object stored by the api server.
+const OBJECT_SIZE_LIMIT: u64 = 1024 * 1024;
+
+/// Number of the largest keys listed for an object.
+const LARGEST_KEYS: usize = 3;
+
+/// Config map or secret with large data or annotations.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct LargeObject {
+    namespace: String,
+    kind: String,
+    name: String,
+
+    /// Size of the keys and values of the data in bytes, values of secrets
+    /// and binary data of config maps are counted base64 encoded like they
+    /// are stored.
+    data_bytes: u64,
+
+    /// Size of the keys and values of the annotations in bytes.
+    annotations_bytes: u64,
+
+    /// Largest keys of the data, their values are not included.
+    largest_keys: Vec<KeySize>,
+
+    /// Largest annotations, their values are not included.
+    largest_annotations: Vec<KeySize>,
+
+    severity: Severity,
+}
+
+/// Name and size of a data key or annotation.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct KeySize {
+    key: String,
+    bytes: u64,
+}
+
+impl Finding for LargeObject {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the config maps and secrets whose data is larger than `warn_bytes` or
+/// whose annotations are larger than `warn_annotation_bytes`. Large
+/// annotations are usually a `kubectl.kubernetes.io/last-applied-configuration`
+/// that repeats the whole data. Objects within a tenth of the size limit of
+/// 1MiB are reported as high severity as the next update can fail.
+pub async fn object_sizes(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    warn_bytes: u64,
+    warn_annotation_bytes: u64,
+) -> Result<Vec<LargeObject>> {
+    let config_maps = source
+        .config_maps(namespaces.clone(), all_namespaces)
+        .await?;
+    let secrets = source.secrets(namespaces, all_namespaces).await?;
+
+    let config_maps = config_maps.iter().map(|config_map| {
+        let data = config_map
+            .data
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), len(value.len())));
+
+        let binary_data = config_map
+            .binary_data
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), base64_len(value.0.len())));
+
+        (
+            "ConfigMap",
+            &config_map.metadata,
+            data.chain(binary_data).collect::<Vec<_>>(),
+        )
+    });
+
+    let secrets = secrets.iter().map(|secret| {
+        let data = secret
+            .data
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), base64_len(value.0.len())));
+
+        let string_data = secret
+            .string_data
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), base64_len(value.len())));
+
+        (
+            "Secret",
+            &secret.metadata,
+            data.chain(string_data).collect::<Vec<_>>(),
+        )
+    });
+
+    let mut findings = config_maps
+        .chain(secrets)
+        .filter_map(|(kind, metadata, data)| {
+            large_object(kind, metadata, &data, warn_bytes, warn_annotation_bytes)
+        })
+        .collect::<Vec<_>>();
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn large_object(
+    kind: &str,
+    metadata: &ObjectMeta,
+    data: &[(&str, u64)],
+    warn_bytes: u64,
+    warn_annotation_bytes: u64,
+) -> Option<LargeObject> {
+    let annotations = metadata
+        .annotations
+        .iter()
+        .flatten()
+        .map(|(key, value)| (key.as_str(), len(value.len())))
+        .collect::<Vec<_>>();
+
+    let data_bytes = data
+        .iter()
+        .map(|(key, bytes)| len(key.len()) + bytes)
+        .sum::<u64>();
+
+    let annotations_bytes = annotations
+        .iter()
+        .map(|(key, bytes)| len(key.len()) + bytes)
+        .sum::<u64>();
+
+    if data_bytes <= warn_bytes && annotations_bytes <= warn_annotation_bytes {
+        return None;
+    }
+
+    let severity = if (data_bytes + annotations_bytes) * 10 >= OBJECT_SIZE_LIMIT * 9 {
+        Severity::High
+    } else {
+        Severity::Medium
+    };
+
+    Some(LargeObject {
+        namespace: metadata.namespace.clone().unwrap_or_default(),
+        kind: kind.to_string(),
+        name: metadata.name.clone().unwrap_or_default(),
+        data_bytes,
+        annotations_bytes,
+        largest_keys: largest(data),
+        largest_annotations: largest(&annotations),
+        severity,
+    })
+}
+
+/// The largest keys by the size of their values, largest first.
+fn largest(sizes: &[(&str, u64)]) -> Vec<KeySize> {
+    let mut sizes = sizes
+        .iter()
+        .map(|(key, bytes)| KeySize {
+            key: (*key).to_string(),
+            bytes: *bytes,
+        })
+        .collect::<Vec<_>>();
+
+    sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.key.cmp(&b.key)));
+    sizes.truncate(LARGEST_KEYS);
+
+    sizes
+}
+
+fn len(bytes: usize) -> u64 {
+    u64::try_from(bytes).unwrap_or(u64::MAX)
+}
+
+/// Length of the padded base64 encoding of `bytes` bytes.
+fn base64_len(bytes: usize) -> u64 {
+    (len(bytes) + 2) / 3 * 4
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[test]
+    fn base64_len() {
+        let testcases = [(0, 0), (1, 4), (2, 4), (3, 4), (4, 8), (768, 1024)];
+
+        for (bytes, expected) in testcases {
+            assert_eq!(expected, super::base64_len(bytes), "{bytes} bytes");
+        }
+    }
+
+    #[tokio::test]
+    async fn object_sizes() {
+        let config_maps = json!({
+            "items": [
+                {
+                    "metadata": { "namespace": "web", "name": "small" },
+                    "data": { "a": "x".repeat(100) },
+                },
+                {
+                    "metadata": { "namespace": "web", "name": "large" },
+                    "data": { "big": "x".repeat(2000), "small": "x".repeat(10) },
+                },
+                {
+                    "metadata": {
+                        "namespace": "web",
+                        "name": "applied",
+                        "annotations": {
+                            "kubectl.kubernetes.io/last-applied-configuration": "x".repeat(600),
+                        },
+                    },
+                    "data": { "a": "x".repeat(100) },
+                },
+            ],
+        });
+
+        // 768 bytes are 1024 bytes base64 encoded, enough to cross the limit
+        // only with the expansion.
+        let secrets = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "certs" },
+                "data": { "ca": base64(768) },
+            }],
+        });
+
+        let source =
+            FileSource::from_readers(json!({ "items": [] }).to_string().as_bytes(), None::<&[u8]>)
+                .unwrap()
+                .with_config_maps(config_maps.to_string().as_bytes())
+                .unwrap()
+                .with_secrets(secrets.to_string().as_bytes())
+                .unwrap();
+
+        let findings = super::object_sizes(&source, Vec::new(), true, 1000, 500)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.kind.as_str(),
+                    finding.name.as_str(),
+                    finding.data_bytes,
+                    finding.annotations_bytes,
+                    finding
+                        .largest_keys
+                        .iter()
+                        .map(|key| key.key.as_str())
+                        .collect::<Vec<_>>(),
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "ConfigMap",
+                    "applied",
+                    101,
+                    648,
+                    vec!["a"],
+                    Severity::Medium
+                ),
+                (
+                    "ConfigMap",
+                    "large",
+                    2018,
+                    0,
+                    vec!["big", "small"],
+                    Severity::Medium
+                ),
+                ("Secret", "certs", 1026, 0, vec!["ca"], Severity::Medium),
+            ],
+            findings
+        );
+    }
+
+    fn base64(bytes: usize) -> String {
+        "AAAA".repeat(bytes / 3)
+    }
+}
@@ -0,0 +1,202 @@
+//! Find replica sets whose deployment does not exist anymore.
+
+use std::collections::BTreeMap;
+
+use eyre::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::extract_owner,
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Replica set controlled by a deployment that does not exist.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct OrphanReplicaSet {
+    namespace: String,
+    replicaset_name: String,
+
+    /// Name of the deployment the owner reference of the replica set points
+    /// to.
+    claimed_owner_name: String,
+
+    replicas: i32,
+    ready_replicas: i32,
+    severity: Severity,
+}
+
+impl Finding for OrphanReplicaSet {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the replica sets whose controlling owner is a deployment that does not
+/// exist, either because there is no deployment with its name or because the
+/// deployment was recreated and has a different uid. The garbage collector
+/// normally removes them, so they are left over from orphaning deletes or
+/// were created by hand. Replica sets that still run pods are reported with a
+/// higher severity.
+pub async fn orphan_replicasets(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<OrphanReplicaSet>> {
+    let replica_sets = source
+        .replica_sets(namespaces.clone(), all_namespaces)
+        .await?;
+    let deployments = source.deployments(namespaces, all_namespaces).await?;
+
+    let deployments = deployments
+        .iter()
+        .filter_map(|deployment| {
+            Some((
+                (
+                    deployment.metadata.namespace.as_deref()?,
+                    deployment.metadata.name.as_deref()?,
+                ),
+                deployment.metadata.uid.as_deref(),
+            ))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let mut orphans = replica_sets
+        .iter()
+        .filter_map(|replica_set| {
+            let owner = extract_owner(replica_set).filter(|owner| owner.kind == "Deployment")?;
+            let namespace = replica_set.metadata.namespace.as_deref()?;
+
+            let exists = deployments
+                .get(&(namespace, owner.name.as_str()))
+                .is_some_and(|uid| uid.map_or(true, |uid| uid == owner.uid));
+
+            if exists {
+                return None;
+            }
+
+            let replicas = replica_set
+                .status
+                .as_ref()
+                .map_or(0, |status| status.replicas);
+
+            let ready_replicas = replica_set
+                .status
+                .as_ref()
+                .and_then(|status| status.ready_replicas)
+                .unwrap_or_default();
+
+            Some(OrphanReplicaSet {
+                namespace: namespace.to_string(),
+                replicaset_name: replica_set.metadata.name.clone().unwrap_or_default(),
+                claimed_owner_name: owner.name.clone(),
+                replicas,
+                ready_replicas,
+                severity: if replicas > 0 {
+                    Severity::Medium
+                } else {
+                    Severity::Low
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    orphans.sort();
+
+    Ok(orphans)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    async fn orphan_replicasets() {
+        let replica_set = |name: &str, owner: Option<(&str, &str, &str)>, replicas: i32| {
+            let owner_references = owner.map_or_else(
+                || json!([]),
+                |(kind, name, uid)| {
+                    json!([{
+                        "apiVersion": "apps/v1",
+                        "kind": kind,
+                        "name": name,
+                        "uid": uid,
+                        "controller": true,
+                    }])
+                },
+            );
+
+            json!({
+                "metadata": {
+                    "namespace": "web",
+                    "name": name,
+                    "ownerReferences": owner_references,
+                },
+                "status": { "replicas": replicas, "readyReplicas": replicas },
+            })
+        };
+
+        let replica_sets = json!({
+            "items": [
+                replica_set("api-1", Some(("Deployment", "api", "1")), 2),
+                replica_set("old-1", Some(("Deployment", "old", "2")), 0),
+                replica_set("recreated-1", Some(("Deployment", "recreated", "3")), 1),
+                replica_set("manual", None, 1),
+                replica_set("custom-1", Some(("Rollout", "custom", "4")), 1),
+            ],
+        });
+
+        let deployments = json!({
+            "items": [
+                { "metadata": { "namespace": "web", "name": "api", "uid": "1" } },
+                { "metadata": { "namespace": "web", "name": "recreated", "uid": "5" } },
+            ],
+        });
+
+        let source =
+            FileSource::from_readers(json!({ "items": [] }).to_string().as_bytes(), None::<&[u8]>)
+                .unwrap()
+                .with_replica_sets(replica_sets.to_string().as_bytes())
+                .unwrap()
+                .with_deployments(deployments.to_string().as_bytes())
+                .unwrap();
+
+        let orphans = super::orphan_replicasets(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let orphans = orphans
+            .iter()
+            .map(|orphan| {
+                (
+                    orphan.replicaset_name.as_str(),
+                    orphan.claimed_owner_name.as_str(),
+                    orphan.replicas,
+                    orphan.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("old-1", "old", 0, Severity::Low),
+                ("recreated-1", "recreated", 1, Severity::Medium),
+            ],
+            orphans
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Find pods of the same app that are scheduled onto the same node.
+
+use std::collections::BTreeMap;
+
+use eyre::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, PodPhase, Severity},
+    source::Source,
+};
+
+/// Pods of an app sharing a node.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ColocatedPods {
+    namespace: String,
+
+    /// Value of the label the pods are grouped by.
+    app_label: String,
+
+    node_name: String,
+    pod_names: Vec<String>,
+    pod_count: usize,
+    severity: Severity,
+}
+
+impl Finding for ColocatedPods {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Group the running pods by namespace, the value of `label` and their node
+/// and get the groups with more than `max_per_node` pods. A node failure takes
+/// down all pods of such a group at once. Pods without the label are skipped.
+pub async fn pod_anti_colocate(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    label: &str,
+    max_per_node: usize,
+) -> Result<Vec<ColocatedPods>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut groups: BTreeMap<(&str, &str, &str), Vec<String>> = BTreeMap::new();
+
+    for pod in pods
+        .iter()
+        .filter(|pod| PodPhase::matches(pod, &[PodPhase::Running]))
+    {
+        let Some(app) = pod
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(label))
+        else {
+            continue;
+        };
+
+        let Some(node) = pod.spec.as_ref().and_then(|spec| spec.node_name.as_deref()) else {
+            continue;
+        };
+
+        let namespace = pod
+            .metadata
+            .namespace
+            .as_deref()
+            .expect("failed to get namespace");
+
+        let name = pod.metadata.name.clone().expect("failed to get name");
+
+        groups
+            .entry((namespace, app.as_str(), node))
+            .or_default()
+            .push(name);
+    }
+
+    let colocated = groups
+        .into_iter()
+        .filter(|(_, pod_names)| pod_names.len() > max_per_node)
+        .map(|((namespace, app, node), pod_names)| ColocatedPods {
+            namespace: namespace.to_string(),
+            app_label: app.to_string(),
+            node_name: node.to_string(),
+            pod_count: pod_names.len(),
+            pod_names,
+            severity: Severity::Medium,
+        })
+        .collect();
+
+    Ok(colocated)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::source::FileSource;
+
+    #[tokio::test]
+    async fn pod_anti_colocate() {
+        let pod = |namespace: &str, name: &str, app: Option<&str>, node: &str, phase: &str| {
+            json!({
+                "metadata": {
+                    "namespace": namespace,
+                    "name": name,
+                    "labels": app.map(|app| json!({ "app": app })),
+                },
+                "spec": { "nodeName": node, "containers": [] },
+                "status": { "phase": phase },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("web", "api-0", Some("api"), "node-a", "Running"),
+                pod("web", "api-1", Some("api"), "node-a", "Running"),
+                pod("web", "api-2", Some("api"), "node-b", "Running"),
+                pod("web", "api-3", Some("api"), "node-b", "Succeeded"),
+                pod("web", "cron-0", None, "node-a", "Running"),
+                pod("shop", "api-0", Some("api"), "node-b", "Running"),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let colocated = super::pod_anti_colocate(&source, Vec::new(), true, "app", 1)
+            .await
+            .unwrap();
+
+        let colocated = colocated
+            .iter()
+            .map(|group| {
+                (
+                    group.namespace.as_str(),
+                    group.app_label.as_str(),
+                    group.node_name.as_str(),
+                    group.pod_names.clone(),
+                    group.pod_count,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![(
+                "web",
+                "api",
+                "node-a",
+                vec!["api-0".to_string(), "api-1".to_string()],
+                2
+            )],
+            colocated
+        );
+
+        let colocated = super::pod_anti_colocate(&source, Vec::new(), true, "app", 2)
+            .await
+            .unwrap();
+
+        assert!(colocated.is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+//! Find jobs whose pods fail too often.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use eyre::{Context, Result};
+use k8s_openapi::{
+    api::batch::v1::Job,
+    chrono::{self, DateTime, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Job whose share of succeeded pods is below the minimum.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct PodCompletionRate {
+    namespace: String,
+    job_name: String,
+
+    /// Name of the cronjob that created the job.
+    cronjob_owner: Option<String>,
+
+    /// Share of the finished pods that succeeded, between 0 and 1.
+    success_rate: f64,
+
+    succeeded: i32,
+    failed: i32,
+
+    /// Average run time of the finished jobs of the same cronjob started in
+    /// the window, or of the job itself if it has no cronjob.
+    duration_avg_seconds: Option<u64>,
+
+    severity: Severity,
+}
+
+impl Finding for PodCompletionRate {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the jobs started within `since` whose succeeded pods make up less than
+/// `min_success_rate` of their finished pods. Jobs without finished pods are
+/// skipped. Jobs where no pod succeeded are reported with a higher severity.
+pub async fn pod_completion_rate(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    since: Duration,
+    min_success_rate: f64,
+) -> Result<Vec<PodCompletionRate>> {
+    let since = chrono::Duration::from_std(since).context("invalid duration")?;
+    let jobs = source.jobs(namespaces, all_namespaces).await?;
+
+    Ok(completion_rates(
+        &jobs,
+        Utc::now() - since,
+        min_success_rate,
+    ))
+}
+
+fn completion_rates(
+    jobs: &[Job],
+    window_start: DateTime<Utc>,
+    min_success_rate: f64,
+) -> Vec<PodCompletionRate> {
+    let jobs = jobs
+        .iter()
+        .filter(|job| started(job).is_some_and(|started| started >= window_start))
+        .collect::<Vec<_>>();
+
+    let mut durations: BTreeMap<(&str, &str), Vec<i64>> = BTreeMap::new();
+    for job in &jobs {
+        if let (Some(namespace), Some(cronjob), Some(duration)) = (
+            job.metadata.namespace.as_deref(),
+            cronjob_owner(job),
+            duration(job),
+        ) {
+            durations
+                .entry((namespace, cronjob))
+                .or_default()
+                .push(duration);
+        }
+    }
+
+    let mut findings = jobs
+        .iter()
+        .filter_map(|job| {
+            let status = job.status.as_ref()?;
+            let succeeded = status.succeeded.unwrap_or_default();
+            let failed = status.failed.unwrap_or_default();
+
+            if succeeded + failed == 0 {
+                return None;
+            }
+
+            let success_rate = f64::from(succeeded) / f64::from(succeeded + failed);
+            if success_rate >= min_success_rate {
+                return None;
+            }
+
+            let namespace = job.metadata.namespace.clone().unwrap_or_default();
+            let cronjob = cronjob_owner(job);
+
+            let duration_avg_seconds = match cronjob {
+                Some(cronjob) => durations
+                    .get(&(namespace.as_str(), cronjob))
+                    .and_then(|durations| average(durations)),
+                None => duration(job).and_then(|duration| u64::try_from(duration).ok()),
+            };
+
+            Some(PodCompletionRate {
+                namespace,
+                job_name: job.metadata.name.clone().unwrap_or_default(),
+                cronjob_owner: cronjob.map(ToString::to_string),
+                success_rate,
+                succeeded,
+                failed,
+                duration_avg_seconds,
+                severity: if succeeded == 0 {
+                    Severity::High
+                } else {
+                    Severity::Medium
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    findings.sort_by(|a, b| (&a.namespace, &a.job_name).cmp(&(&b.namespace, &b.job_name)));
+
+    findings
+}
+
+fn cronjob_owner(job: &Job) -> Option<&str> {
+    job.metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .find(|owner| owner.kind == "CronJob")
+        .map(|owner| owner.name.as_str())
+}
+
+/// Time the job started, its creation time if it has not started yet.
+fn started(job: &Job) -> Option<DateTime<Utc>> {
+    job.status
+        .as_ref()
+        .and_then(|status| status.start_time.as_ref())
+        .or(job.metadata.creation_timestamp.as_ref())
+        .map(|time| time.0)
+}
+
+/// Run time of a completed job in seconds.
+fn duration(job: &Job) -> Option<i64> {
+    let status = job.status.as_ref()?;
+    let start = status.start_time.as_ref()?.0;
+    let completion = status.completion_time.as_ref()?.0;
+
+    Some((completion - start).num_seconds())
+}
+
+fn average(durations: &[i64]) -> Option<u64> {
+    let count = i64::try_from(durations.len())
+        .ok()
+        .filter(|count| *count > 0)?;
+
+    u64::try_from(durations.iter().sum::<i64>() / count).ok()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use k8s_openapi::chrono::{self, SecondsFormat, Utc};
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    async fn pod_completion_rate() {
+        let ago = |minutes: i64| {
+            (Utc::now() - chrono::Duration::minutes(minutes))
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+
+        let job = |name: &str, cronjob: Option<&str>, succeeded: i32, failed: i32, minutes: i64| {
+            let owner_references = cronjob.map_or_else(
+                || json!([]),
+                |cronjob| {
+                    json!([{
+                        "apiVersion": "batch/v1",
+                        "kind": "CronJob",
+                        "name": cronjob,
+                        "uid": cronjob,
+                        "controller": true,
+                    }])
+                },
+            );
+
+            json!({
+                "metadata": {
+                    "namespace": "batch",
+                    "name": name,
+                    "ownerReferences": owner_references,
+                },
+                "status": {
+                    "startTime": ago(minutes),
+                    "completionTime": ago(minutes - 10),
+                    "succeeded": succeeded,
+                    "failed": failed,
+                },
+            })
+        };
+
+        let jobs = json!({
+            "items": [
+                job("backup-1", Some("backup"), 1, 0, 120),
+                job("backup-2", Some("backup"), 1, 1, 60),
+                job("backup-old", Some("backup"), 0, 6, 3 * 24 * 60),
+                job("import", None, 0, 3, 30),
+                job("report", None, 20, 1, 30),
+            ],
+        });
+
+        let source =
+            FileSource::from_readers(json!({ "items": [] }).to_string().as_bytes(), None::<&[u8]>)
+                .unwrap()
+                .with_jobs(jobs.to_string().as_bytes())
+                .unwrap();
+
+        let findings = super::pod_completion_rate(
+            &source,
+            Vec::new(),
+            true,
+            Duration::from_secs(24 * 3600),
+            0.95,
+        )
+        .await
+        .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.job_name.as_str(),
+                    finding.cronjob_owner.as_deref(),
+                    finding.succeeded,
+                    finding.failed,
+                    finding.duration_avg_seconds,
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "backup-2",
+                    Some("backup"),
+                    1,
+                    1,
+                    Some(600),
+                    Severity::Medium
+                ),
+                ("import", None, 0, 3, Some(600), Severity::High),
+            ],
+            findings
+        );
+    }
+
+    #[test]
+    fn average() {
+        assert_eq!(Some(15), super::average(&[10, 20]));
+        assert_eq!(None, super::average(&[]));
+    }
+}
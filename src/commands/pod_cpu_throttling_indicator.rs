@@ -0,0 +1,269 @@
+//! Find containers that are likely throttled because they use most of their
+//! cpu limit.
+
+use eyre::{Context, Result};
+use k8s_openapi::api::core::v1::Pod;
+use log::warn;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{Cpu, Owner, PodMetrics},
+    commands::{DetailOptions, Finding, PodDetails, PodPhase, Severity},
+    source::Source,
+};
+
+/// Percentage of the cpu limit above which a container is likely throttled.
+const LIKELY_THROTTLED_PCT: u64 = 90;
+
+/// Cpu usage of a container compared to its cpu limit.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ContainerCpuThrottling {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    cpu_usage: Cpu,
+    cpu_limit: Cpu,
+    cpu_usage_pct_of_limit: u64,
+
+    /// Whether the usage is above 90% of the limit. The metrics api only
+    /// reports the average usage, the throttled periods from cAdvisor are
+    /// needed to know for sure.
+    likely_throttled: bool,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ContainerCpuThrottling {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Compare the current cpu usage of the containers of running pods with their
+/// cpu limit. Containers without a cpu limit can not be throttled and are
+/// skipped.
+pub async fn pod_cpu_throttling_indicator(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    details: DetailOptions,
+) -> Result<Vec<ContainerCpuThrottling>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut containers = Vec::new();
+    for pod in pods
+        .iter()
+        .filter(|pod| PodPhase::matches(pod, &[PodPhase::Running]))
+    {
+        let namespace = pod
+            .metadata
+            .namespace
+            .as_deref()
+            .expect("failed to get namespace");
+
+        let name = pod.metadata.name.as_deref().expect("failed to get name");
+
+        let Some(metrics) = source
+            .pod_resource_usage(namespace, name)
+            .await
+            .context("failed to get pod resource usage")?
+        else {
+            warn!("Failed to get usage for pod: {name}");
+            continue;
+        };
+
+        containers.extend(container_throttling(source, pod, &metrics, &details)?);
+    }
+
+    Ok(containers)
+}
+
+fn container_throttling(
+    source: &dyn Source,
+    pod: &Pod,
+    metrics: &PodMetrics,
+    details: &DetailOptions,
+) -> Result<Vec<ContainerCpuThrottling>> {
+    let mut containers = Vec::new();
+
+    for container in pod.spec.iter().flat_map(|spec| spec.containers.iter()) {
+        if !source.checks_container(&container.name) {
+            continue;
+        }
+
+        let Some(cpu_limit) = container
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.limits.as_ref())
+            .and_then(|limits| limits.get("cpu"))
+            .map(Cpu::try_from)
+            .transpose()
+            .context("failed to convert cpu limits")?
+            .filter(|limit| limit.to_milliseconds() > 0)
+        else {
+            continue;
+        };
+
+        let Some(cpu_usage) = metrics
+            .containers
+            .iter()
+            .find(|metrics| metrics.name == container.name)
+            .map(|metrics| Cpu::try_from(&metrics.usage.cpu))
+            .transpose()
+            .context("failed to convert cpu usage")?
+        else {
+            continue;
+        };
+
+        let cpu_usage_pct_of_limit =
+            cpu_usage.to_milliseconds() * 100 / cpu_limit.to_milliseconds();
+        let likely_throttled = cpu_usage_pct_of_limit > LIKELY_THROTTLED_PCT;
+
+        containers.push(ContainerCpuThrottling {
+            namespace: pod
+                .metadata
+                .namespace
+                .as_ref()
+                .expect("failed to get namespace")
+                .clone(),
+
+            owner: source.pod_owner(pod),
+
+            pod_name: pod
+                .metadata
+                .name
+                .as_ref()
+                .expect("failed to get name")
+                .clone(),
+
+            container_name: container.name.clone(),
+            cpu_usage,
+            cpu_limit,
+            cpu_usage_pct_of_limit,
+            likely_throttled,
+            severity: if likely_throttled {
+                Severity::Medium
+            } else {
+                Severity::Info
+            },
+            details: PodDetails::new(source, pod, details),
+        });
+    }
+
+    Ok(containers)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        commands::{DetailOptions, Severity},
+        source::FileSource,
+    };
+
+    #[tokio::test]
+    async fn pod_cpu_throttling_indicator() {
+        let container = |name: &str, limit: Option<&str>| match limit {
+            Some(limit) => {
+                json!({ "name": name, "resources": { "limits": { "cpu": limit } } })
+            }
+            None => json!({ "name": name }),
+        };
+
+        let pods = json!({
+            "items": [
+                {
+                    "metadata": { "namespace": "web", "name": "api-0" },
+                    "spec": {
+                        "nodeName": "node-a",
+                        "containers": [
+                            container("api", Some("500m")),
+                            container("proxy", Some("100m")),
+                            container("logger", None),
+                        ],
+                    },
+                    "status": { "phase": "Running" },
+                },
+                {
+                    "metadata": { "namespace": "web", "name": "api-1" },
+                    "spec": { "containers": [container("api", Some("500m"))] },
+                    "status": { "phase": "Pending" },
+                },
+            ],
+        });
+
+        let usage = |name: &str, cpu: &str| json!({ "name": name, "usage": { "cpu": cpu, "memory": "1Mi" } });
+
+        let metrics = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api-0" },
+                "timestamp": "2024-01-01T00:00:00Z",
+                "window": "15s",
+                "containers": [
+                    usage("api", "200m"),
+                    usage("proxy", "95m"),
+                    usage("logger", "1"),
+                ],
+            }],
+        });
+
+        let source = FileSource::from_readers(
+            pods.to_string().as_bytes(),
+            Some(metrics.to_string().as_bytes()),
+        )
+        .unwrap();
+
+        let containers = super::pod_cpu_throttling_indicator(
+            &source,
+            Vec::new(),
+            true,
+            DetailOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let containers = containers
+            .iter()
+            .map(|container| {
+                (
+                    container.container_name.as_str(),
+                    container.cpu_usage_pct_of_limit,
+                    container.likely_throttled,
+                    container.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("api", 40, false, Severity::Info),
+                ("proxy", 95, true, Severity::Medium),
+            ],
+            containers
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Find pods with a dns configuration that does not resolve cluster names.
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Pod with a dns policy that is likely not what was intended.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct DnsPolicyFinding {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    dns_policy: String,
+    nameservers: Vec<String>,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for DnsPolicyFinding {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get pods with `dnsPolicy: None` that do not define nameservers in their
+/// `dnsConfig` and pods with `dnsPolicy: Default` which inherit the dns
+/// configuration of the node instead of using the cluster dns.
+pub async fn pod_dns_policy(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    details: DetailOptions,
+) -> Result<Vec<DnsPolicyFinding>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let findings = pods
+        .iter()
+        .filter_map(|pod| {
+            pod_dns_policy_finding(source, pod, &details).expect("failed to get dns policy")
+        })
+        .collect::<Vec<_>>();
+
+    Ok(findings)
+}
+
+fn pod_dns_policy_finding(
+    source: &dyn Source,
+    pod: &Pod,
+    details: &DetailOptions,
+) -> Result<Option<DnsPolicyFinding>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let nameservers = spec
+        .dns_config
+        .as_ref()
+        .and_then(|dns_config| dns_config.nameservers.clone())
+        .unwrap_or_default();
+
+    // Without nameservers the pod can not resolve anything while the node dns
+    // still works for external names.
+    let (dns_policy, severity) = match spec.dns_policy.as_deref() {
+        Some("None") if nameservers.is_empty() => ("None", Severity::High),
+        Some("Default") => ("Default", Severity::Low),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(DnsPolicyFinding {
+        namespace: pod
+            .metadata
+            .namespace
+            .as_ref()
+            .expect("failed to get namespace")
+            .clone(),
+
+        owner: source.pod_owner(pod),
+
+        pod_name: pod
+            .metadata
+            .name
+            .as_ref()
+            .expect("failed to get name")
+            .clone(),
+
+        dns_policy: dns_policy.to_string(),
+        nameservers,
+        severity,
+
+        details: PodDetails::new(source, pod, details),
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::api::core::v1::{Pod, PodDNSConfig, PodSpec};
+
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    fn pod(dns_policy: Option<&str>, nameservers: Option<Vec<&str>>) -> Pod {
+        Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("pod".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(PodSpec {
+                dns_policy: dns_policy.map(ToString::to_string),
+                dns_config: nameservers.map(|nameservers| PodDNSConfig {
+                    nameservers: Some(nameservers.into_iter().map(ToString::to_string).collect()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pod_dns_policy_finding() {
+        let testcases = [
+            (pod(None, None), None),
+            (pod(Some("ClusterFirst"), None), None),
+            (pod(Some("None"), None), Some("None")),
+            (pod(Some("None"), Some(vec![])), Some("None")),
+            (pod(Some("None"), Some(vec!["1.1.1.1"])), None),
+            (pod(Some("Default"), None), Some("Default")),
+        ];
+
+        for (pod, expected) in testcases {
+            let output = super::pod_dns_policy_finding(
+                &FileSource::default(),
+                &pod,
+                &DetailOptions::default(),
+            )
+            .unwrap();
+
+            assert_eq!(
+                expected,
+                output.as_ref().map(|finding| finding.dns_policy.as_str())
+            );
+        }
+    }
+}
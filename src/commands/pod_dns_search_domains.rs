@@ -0,0 +1,200 @@
+//! Find pods with more dns search domains than the resolver handles.
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Maximum total length of the search domains of older glibc resolvers.
+const MAX_SEARCH_DOMAINS_LENGTH: usize = 256;
+
+/// Pod with too many or too long dns search domains.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ExcessiveSearchDomains {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    search_domains: Vec<String>,
+    total_length: usize,
+    warnings: Vec<String>,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ExcessiveSearchDomains {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get pods that define more than `max_search_domains` search domains in
+/// their `dnsConfig` or whose search domains are longer than 256 characters
+/// in total. Every extra search domain adds lookups to the resolution of
+/// names that are not fully qualified.
+pub async fn pod_dns_search_domains(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    max_search_domains: usize,
+    details: DetailOptions,
+) -> Result<Vec<ExcessiveSearchDomains>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let findings = pods
+        .iter()
+        .filter_map(|pod| {
+            pod_excessive_search_domains(source, pod, max_search_domains, &details)
+                .expect("failed to get search domains")
+        })
+        .collect::<Vec<_>>();
+
+    Ok(findings)
+}
+
+fn pod_excessive_search_domains(
+    source: &dyn Source,
+    pod: &Pod,
+    max_search_domains: usize,
+    details: &DetailOptions,
+) -> Result<Option<ExcessiveSearchDomains>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let search_domains = spec
+        .dns_config
+        .as_ref()
+        .and_then(|dns_config| dns_config.searches.clone())
+        .unwrap_or_default();
+
+    // The domains are separated by a space in resolv.conf.
+    let total_length = search_domains.iter().map(String::len).sum::<usize>()
+        + search_domains.len().saturating_sub(1);
+
+    let mut warnings = Vec::new();
+
+    if search_domains.len() > max_search_domains {
+        warnings.push(format!(
+            "{} search domains are more than the maximum of {max_search_domains}",
+            search_domains.len()
+        ));
+    }
+
+    if total_length > MAX_SEARCH_DOMAINS_LENGTH {
+        warnings.push(format!(
+            "search domains are {total_length} characters long, more than the maximum of \
+             {MAX_SEARCH_DOMAINS_LENGTH}"
+        ));
+    }
+
+    if warnings.is_empty() {
+        return Ok(None);
+    }
+
+    let severity = if search_domains.len() > max_search_domains {
+        Severity::Medium
+    } else {
+        Severity::Low
+    };
+
+    Ok(Some(ExcessiveSearchDomains {
+        namespace: pod
+            .metadata
+            .namespace
+            .as_ref()
+            .expect("failed to get namespace")
+            .clone(),
+
+        owner: source.pod_owner(pod),
+
+        pod_name: pod
+            .metadata
+            .name
+            .as_ref()
+            .expect("failed to get name")
+            .clone(),
+
+        search_domains,
+        total_length,
+        warnings,
+        severity,
+
+        details: PodDetails::new(source, pod, details),
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::api::core::v1::{Pod, PodDNSConfig, PodSpec};
+
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    fn pod(searches: Vec<String>) -> Pod {
+        Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("pod".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(PodSpec {
+                dns_config: Some(PodDNSConfig {
+                    searches: Some(searches),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+
+            ..Default::default()
+        }
+    }
+
+    fn warnings(searches: Vec<String>) -> usize {
+        super::pod_excessive_search_domains(
+            &FileSource::default(),
+            &pod(searches),
+            6,
+            &DetailOptions::default(),
+        )
+        .unwrap()
+        .map_or(0, |finding| finding.warnings.len())
+    }
+
+    #[test]
+    fn pod_excessive_search_domains() {
+        let short = |count: usize| (0..count).map(|i| format!("d{i}.local")).collect();
+
+        assert_eq!(0, warnings(Vec::new()));
+        assert_eq!(0, warnings(short(6)));
+        assert_eq!(1, warnings(short(7)));
+        assert_eq!(1, warnings(vec!["a".repeat(200), "b".repeat(100)]));
+        assert_eq!(2, warnings((0..7).map(|_| "c".repeat(50)).collect()));
+    }
+}
@@ -0,0 +1,309 @@
+//! Find containers that are not set up to shut down gracefully.
+
+use eyre::Result;
+use k8s_openapi::api::core::v1::{Container, Pod, Service};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, PodPhase, Severity},
+    source::Source,
+};
+
+/// Grace period kubernetes uses when the pod does not set one.
+const DEFAULT_GRACE_PERIOD_SECONDS: i64 = 30;
+
+/// Grace periods below this leave too little time to drain connections.
+const MIN_GRACE_PERIOD_SECONDS: i64 = 10;
+
+/// Container without a `preStop` hook or with a too short grace period.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct GracefulShutdown {
+    namespace: String,
+    pod_name: String,
+    owner: Option<Owner>,
+    container_name: String,
+    has_prestop: bool,
+
+    /// Seconds the `preStop` hook sleeps, only known for the `sleep` action
+    /// and `sleep` commands.
+    prestop_sleep_seconds: Option<i64>,
+
+    /// `terminationGracePeriodSeconds` of the pod, 30 if it is not set.
+    termination_grace_period: i64,
+
+    /// Whether the selector of a service in the namespace matches the pod so
+    /// it receives traffic that is cut off by an abrupt shutdown.
+    is_behind_service: bool,
+
+    problems: Vec<String>,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for GracefulShutdown {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the containers of running pods that have no `preStop` hook, whose pod
+/// has a grace period below 10 seconds or whose `preStop` hook sleeps for at
+/// least the grace period. Containers behind a service are reported with a
+/// higher severity as they drop requests when they stop.
+pub async fn pod_graceful_shutdown_test(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    details: DetailOptions,
+) -> Result<Vec<GracefulShutdown>> {
+    let services = source.services(namespaces.clone(), all_namespaces).await?;
+    let pods = source.pods(namespaces, all_namespaces).await?;
+    let details = &details;
+
+    let findings = pods
+        .iter()
+        .filter(|pod| PodPhase::matches(pod, &[PodPhase::Running]))
+        .flat_map(|pod| {
+            let is_behind_service = services.iter().any(|service| selects(service, pod));
+
+            pod.spec
+                .iter()
+                .flat_map(|spec| &spec.containers)
+                .filter(|container| source.checks_container(&container.name))
+                .filter_map(move |container| {
+                    graceful_shutdown(source, pod, container, is_behind_service, details)
+                })
+        })
+        .collect();
+
+    Ok(findings)
+}
+
+fn graceful_shutdown(
+    source: &dyn Source,
+    pod: &Pod,
+    container: &Container,
+    is_behind_service: bool,
+    details: &DetailOptions,
+) -> Option<GracefulShutdown> {
+    let termination_grace_period = pod
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.termination_grace_period_seconds)
+        .unwrap_or(DEFAULT_GRACE_PERIOD_SECONDS);
+
+    let prestop = container
+        .lifecycle
+        .as_ref()
+        .and_then(|lifecycle| lifecycle.pre_stop.as_ref());
+
+    let prestop_sleep_seconds = prestop.and_then(|handler| {
+        handler
+            .sleep
+            .as_ref()
+            .map(|sleep| sleep.seconds)
+            .or_else(|| sleep_command(handler.exec.as_ref()?.command.as_ref()?))
+    });
+
+    let mut problems = Vec::new();
+
+    if prestop.is_none() {
+        problems.push("container has no preStop hook".to_string());
+    }
+
+    if termination_grace_period < MIN_GRACE_PERIOD_SECONDS {
+        problems.push(format!(
+            "termination grace period of {termination_grace_period}s is below \
+             {MIN_GRACE_PERIOD_SECONDS}s"
+        ));
+    }
+
+    if let Some(sleep) = prestop_sleep_seconds.filter(|sleep| *sleep >= termination_grace_period) {
+        problems.push(format!(
+            "preStop hook sleeps {sleep}s which is not shorter than the termination grace \
+             period of {termination_grace_period}s"
+        ));
+    }
+
+    if problems.is_empty() {
+        return None;
+    }
+
+    Some(GracefulShutdown {
+        namespace: pod
+            .metadata
+            .namespace
+            .clone()
+            .expect("failed to get namespace"),
+        pod_name: pod.metadata.name.clone().expect("failed to get name"),
+        owner: source.pod_owner(pod),
+        container_name: container.name.clone(),
+        has_prestop: prestop.is_some(),
+        prestop_sleep_seconds,
+        termination_grace_period,
+        is_behind_service,
+        problems,
+        severity: if is_behind_service {
+            Severity::Medium
+        } else {
+            Severity::Low
+        },
+        details: PodDetails::new(source, pod, details),
+    })
+}
+
+/// Seconds of a `sleep` command, either called directly or through a shell
+/// like `sh -c "sleep 5"`.
+fn sleep_command(command: &[String]) -> Option<i64> {
+    let command = match command {
+        [shell, flag, script] if shell.ends_with("sh") && flag == "-c" => {
+            script.split_whitespace().collect::<Vec<_>>()
+        }
+        command => command.iter().map(String::as_str).collect(),
+    };
+
+    match command.as_slice() {
+        [sleep, seconds] if sleep.ends_with("sleep") => seconds.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whether the service sends traffic to the pod. Services without a selector
+/// have their endpoints managed elsewhere and never match.
+fn selects(service: &Service, pod: &Pod) -> bool {
+    if service.metadata.namespace != pod.metadata.namespace {
+        return false;
+    }
+
+    let Some(selector) = service
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.selector.as_ref())
+        .filter(|selector| !selector.is_empty())
+    else {
+        return false;
+    };
+
+    let labels = pod.metadata.labels.as_ref();
+
+    selector.iter().all(|(key, value)| {
+        labels
+            .and_then(|labels| labels.get(key))
+            .is_some_and(|label| label == value)
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        commands::{DetailOptions, Severity},
+        source::FileSource,
+    };
+
+    #[test]
+    fn sleep_command() {
+        let command = |command: &[&str]| {
+            super::sleep_command(&command.iter().map(ToString::to_string).collect::<Vec<_>>())
+        };
+
+        assert_eq!(Some(5), command(&["sleep", "5"]));
+        assert_eq!(Some(15), command(&["/bin/sleep", "15"]));
+        assert_eq!(Some(20), command(&["/bin/sh", "-c", "sleep 20"]));
+        assert_eq!(None, command(&["/bin/sh", "-c", "nginx -s quit"]));
+        assert_eq!(None, command(&["sleep", "infinity"]));
+    }
+
+    #[tokio::test]
+    async fn pod_graceful_shutdown_test() {
+        let pod = |name: &str, app: &str, grace: Option<i64>, lifecycle: serde_json::Value| {
+            json!({
+                "metadata": { "namespace": "web", "name": name, "labels": { "app": app } },
+                "spec": {
+                    "terminationGracePeriodSeconds": grace,
+                    "containers": [{ "name": "app", "lifecycle": lifecycle }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let prestop = |command: &[&str]| json!({ "preStop": { "exec": { "command": command } } });
+
+        let pods = json!({
+            "items": [
+                pod("api-0", "api", None, json!(null)),
+                pod("api-1", "api", Some(60), prestop(&["sleep", "10"])),
+                pod("worker-0", "worker", Some(5), prestop(&["sleep", "10"])),
+                pod(
+                    "worker-1",
+                    "worker",
+                    Some(30),
+                    json!({ "preStop": { "sleep": { "seconds": 5 } } }),
+                ),
+            ],
+        });
+
+        let services = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api" },
+                "spec": { "selector": { "app": "api" } },
+            }],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_services(services.to_string().as_bytes())
+            .unwrap();
+
+        let findings =
+            super::pod_graceful_shutdown_test(&source, Vec::new(), true, DetailOptions::default())
+                .await
+                .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.pod_name.as_str(),
+                    finding.has_prestop,
+                    finding.termination_grace_period,
+                    finding.is_behind_service,
+                    finding.problems.len(),
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("api-0", false, 30, true, 1, Severity::Medium),
+                ("worker-0", true, 5, false, 2, Severity::Low),
+            ],
+            findings
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Find pods that only got an address of one ip family in a dual-stack
+//! cluster.
+
+use std::net::IpAddr;
+
+use eyre::Result;
+use k8s_openapi::api::core::v1::{Node, Pod};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Pod with addresses of only one ip family.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct PodIpFamily {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    pod_ips: Vec<String>,
+    ipv4_only: bool,
+    ipv6_only: bool,
+    dual_stack: bool,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for PodIpFamily {
+    const DEFAULT_SEVERITY: Severity = Severity::Low;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Ip families of a list of addresses.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Families {
+    ipv4: bool,
+    ipv6: bool,
+}
+
+/// Get the pods that only have an ipv4 or only an ipv6 address if the cluster
+/// is dual-stack. The cluster is dual-stack if any node has pod cidrs or
+/// internal addresses of both families. Pods without addresses, for example
+/// pending ones, are skipped. Nothing is reported in single-stack clusters.
+pub async fn pod_ip_family(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    details: DetailOptions,
+) -> Result<Vec<PodIpFamily>> {
+    let nodes = source.nodes().await?;
+
+    if !nodes.iter().any(|node| node_families(node).is_dual_stack()) {
+        return Ok(Vec::new());
+    }
+
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut findings = pods
+        .iter()
+        .filter_map(|pod| single_family(source, pod, &details))
+        .collect::<Vec<_>>();
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn single_family(source: &dyn Source, pod: &Pod, details: &DetailOptions) -> Option<PodIpFamily> {
+    let status = pod.status.as_ref()?;
+
+    let mut pod_ips = status
+        .pod_ips
+        .iter()
+        .flatten()
+        .filter_map(|pod_ip| pod_ip.ip.clone())
+        .chain(status.pod_ip.clone())
+        .collect::<Vec<_>>();
+
+    pod_ips.sort();
+    pod_ips.dedup();
+
+    let families = Families::of(pod_ips.iter().map(String::as_str));
+    if families == Families::default() || families.is_dual_stack() {
+        return None;
+    }
+
+    Some(PodIpFamily {
+        namespace: pod.metadata.namespace.clone().unwrap_or_default(),
+        owner: source.pod_owner(pod),
+        pod_name: pod.metadata.name.clone().unwrap_or_default(),
+        pod_ips,
+        ipv4_only: families.ipv4,
+        ipv6_only: families.ipv6,
+        dual_stack: false,
+        severity: Severity::Low,
+        details: PodDetails::new(source, pod, details),
+    })
+}
+
+fn node_families(node: &Node) -> Families {
+    let cidrs = node
+        .spec
+        .iter()
+        .flat_map(|spec| spec.pod_cidrs.iter().flatten())
+        .filter_map(|cidr| cidr.split('/').next());
+
+    let addresses = node
+        .status
+        .iter()
+        .flat_map(|status| status.addresses.iter().flatten())
+        .filter(|address| address.type_ == "InternalIP")
+        .map(|address| address.address.as_str());
+
+    Families::of(cidrs.chain(addresses))
+}
+
+impl Families {
+    /// Families of the addresses, invalid addresses are ignored.
+    fn of<'a>(addresses: impl Iterator<Item = &'a str>) -> Self {
+        addresses
+            .filter_map(|address| address.parse::<IpAddr>().ok())
+            .fold(Self::default(), |families, address| Self {
+                ipv4: families.ipv4 || address.is_ipv4(),
+                ipv6: families.ipv6 || address.is_ipv6(),
+            })
+    }
+
+    fn is_dual_stack(self) -> bool {
+        self.ipv4 && self.ipv6
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    fn pods() -> serde_json::Value {
+        let pod = |name: &str, ips: &[&str]| {
+            json!({
+                "metadata": { "namespace": "web", "name": name },
+                "spec": { "containers": [] },
+                "status": {
+                    "podIP": ips.first(),
+                    "podIPs": ips.iter().map(|ip| json!({ "ip": ip })).collect::<Vec<_>>(),
+                },
+            })
+        };
+
+        json!({
+            "items": [
+                pod("dual", &["10.0.0.1", "fd00::1"]),
+                pod("legacy", &["10.0.0.2"]),
+                pod("modern", &["fd00::3"]),
+                pod("pending", &[]),
+            ],
+        })
+    }
+
+    fn node(pod_cidrs: &[&str]) -> serde_json::Value {
+        json!({
+            "items": [{
+                "metadata": { "name": "node-a" },
+                "spec": { "podCIDRs": pod_cidrs },
+                "status": {
+                    "addresses": [{ "type": "InternalIP", "address": "192.168.0.10" }],
+                },
+            }],
+        })
+    }
+
+    #[tokio::test]
+    async fn pod_ip_family() {
+        let source = FileSource::from_readers(pods().to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(node(&["10.0.0.0/24", "fd00::/64"]).to_string().as_bytes())
+            .unwrap();
+
+        let findings = super::pod_ip_family(&source, Vec::new(), true, DetailOptions::default())
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.pod_name.as_str(),
+                    finding.ipv4_only,
+                    finding.ipv6_only,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![("legacy", true, false), ("modern", false, true)],
+            findings
+        );
+    }
+
+    #[tokio::test]
+    async fn single_stack() {
+        let source = FileSource::from_readers(pods().to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_nodes(node(&["10.0.0.0/24"]).to_string().as_bytes())
+            .unwrap();
+
+        let findings = super::pod_ip_family(&source, Vec::new(), true, DetailOptions::default())
+            .await
+            .unwrap();
+
+        assert!(findings.is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Find pods whose readiness gates have not been satisfied for too long.
+
+use std::time::Duration;
+
+use eyre::{Context, Result};
+use k8s_openapi::{
+    api::core::v1::Pod,
+    chrono::{self, DateTime, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{pod_condition, Finding, Severity},
+    humanize,
+    source::Source,
+};
+
+/// Readiness gate of a pod whose condition is missing or not true.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct UnsatisfiedReadinessGate {
+    namespace: String,
+    pod_name: String,
+    owner: Option<Owner>,
+
+    /// `conditionType` of the readiness gate, for example
+    /// `target-health.elbv2.k8s.aws/my-ingress`.
+    condition_type: String,
+
+    /// Status of the condition, empty if the pod does not have the condition
+    /// yet.
+    status: Option<String>,
+
+    /// How long the condition has had its status, or how long the pod exists
+    /// if the condition is missing, for example `3h12m`.
+    age: Option<String>,
+
+    /// Whether the containers of the pod are ready, so only the gate keeps the
+    /// pod out of its services.
+    containers_ready: bool,
+
+    severity: Severity,
+}
+
+impl Finding for UnsatisfiedReadinessGate {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the readiness gates of pods whose condition has been missing or not
+/// true for longer than `unsatisfied_for`. Such pods never receive traffic,
+/// for example when a load balancer controller does not register them, and
+/// block rollouts.
+pub async fn readiness_gates(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    unsatisfied_for: Duration,
+) -> Result<Vec<UnsatisfiedReadinessGate>> {
+    let unsatisfied_for =
+        chrono::Duration::from_std(unsatisfied_for).context("invalid duration")?;
+    let now = Utc::now();
+
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut gates = pods
+        .iter()
+        .flat_map(|pod| unsatisfied_gates(source, pod, now, unsatisfied_for))
+        .collect::<Vec<_>>();
+
+    gates.sort();
+
+    Ok(gates)
+}
+
+fn unsatisfied_gates(
+    source: &dyn Source,
+    pod: &Pod,
+    now: DateTime<Utc>,
+    unsatisfied_for: chrono::Duration,
+) -> Vec<UnsatisfiedReadinessGate> {
+    let created = pod.metadata.creation_timestamp.as_ref().map(|time| time.0);
+
+    let containers_ready =
+        pod_condition(pod, "ContainersReady").is_some_and(|condition| condition.status == "True");
+
+    pod.spec
+        .iter()
+        .flat_map(|spec| spec.readiness_gates.iter().flatten())
+        .filter_map(|gate| {
+            let condition = pod_condition(pod, &gate.condition_type);
+
+            if condition.is_some_and(|condition| condition.status == "True") {
+                return None;
+            }
+
+            let since = match condition {
+                Some(condition) => condition
+                    .last_transition_time
+                    .as_ref()
+                    .map(|time| time.0)
+                    .or(created),
+                None => created,
+            };
+
+            let age = since.map(|since| now - since);
+
+            // Pods without any timestamp are reported, they can not be told
+            // apart from pods that are stuck for long.
+            if age.is_some_and(|age| age <= unsatisfied_for) {
+                return None;
+            }
+
+            Some(UnsatisfiedReadinessGate {
+                namespace: pod
+                    .metadata
+                    .namespace
+                    .clone()
+                    .expect("failed to get namespace"),
+                pod_name: pod.metadata.name.clone().expect("failed to get name"),
+                owner: source.pod_owner(pod),
+                condition_type: gate.condition_type.clone(),
+                status: condition.map(|condition| condition.status.clone()),
+                age: age.map(|age| humanize::duration(age.to_std().unwrap_or_default())),
+                containers_ready,
+                severity: Severity::Medium,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use k8s_openapi::chrono::{self, DateTime};
+
+    use crate::source::{FileSource, Source};
+
+    const PODS: &str = include_str!("../../resources/fixtures/readiness-gates.json");
+
+    #[tokio::test]
+    async fn readiness_gates() {
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+
+        let gates = super::readiness_gates(&source, Vec::new(), true, Duration::from_secs(300))
+            .await
+            .unwrap();
+
+        let gates = gates
+            .iter()
+            .map(|gate| {
+                (
+                    gate.pod_name.as_str(),
+                    gate.condition_type.as_str(),
+                    gate.status.as_deref(),
+                    gate.containers_ready,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "api-7c9b-a",
+                    "target-health.elbv2.k8s.aws/api",
+                    Some("False"),
+                    true
+                ),
+                ("api-7c9b-b", "target-health.elbv2.k8s.aws/api", None, false),
+            ],
+            gates
+        );
+    }
+
+    #[tokio::test]
+    async fn unsatisfied_for() {
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+        let pods = source.pods(Vec::new(), true).await.unwrap();
+
+        // The condition of the first pod turned false at 10:00.
+        let now = DateTime::parse_from_rfc3339("2024-01-01T10:03:00Z")
+            .unwrap()
+            .into();
+
+        let gates = |minutes| {
+            super::unsatisfied_gates(&source, &pods[0], now, chrono::Duration::minutes(minutes))
+        };
+
+        assert_eq!(Some("3m"), gates(1)[0].age.as_deref());
+        assert!(gates(5).is_empty());
+    }
+}
@@ -1,40 +1,120 @@
-use std::collections::BTreeSet;
+//! Find containers without a read-only root filesystem.
+
+use std::{cmp::Ordering, collections::BTreeSet};
 
 use eyre::{bail, Result};
-use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::{
+    api::core::v1::{Container, Pod},
+    chrono::Utc,
+};
+use schemars::JsonSchema;
 use serde::Serialize;
 
-use crate::api::{get_pod_owner, get_pods, Owner};
+use crate::{
+    api::Owner,
+    commands::{k8s_version, DetailOptions, Finding, PodDetails, Report, Severity},
+    source::Source,
+};
+
+/// Containers without a read-only root filesystem.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Output {
+    /// Time the report was generated in RFC 3339 format.
+    generated_at: String,
+
+    /// Version of the api server the pods were read from.
+    k8s_version: String,
+
+    containers: Vec<NoReadOnlyRootFilesystem>,
+}
+
+impl Report for Output {
+    type Finding = NoReadOnlyRootFilesystem;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.containers.iter().collect()
+    }
+
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        self.containers.retain_mut(|container| keep(container));
+    }
+
+    fn sort_findings(
+        &mut self,
+        compare: &mut dyn FnMut(&Self::Finding, &Self::Finding) -> Ordering,
+    ) {
+        self.containers.sort_by(|a, b| compare(a, b));
+    }
+}
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize)]
-pub(crate) struct NoReadOnlyRootFilesystem {
+/// Container that can write to its root filesystem.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct NoReadOnlyRootFilesystem {
     namespace: String,
     owner: Option<Owner>,
     pod_name: String,
     container_name: String,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for NoReadOnlyRootFilesystem {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
 }
 
-pub(crate) async fn readonly_root_filesystem(
+/// Get containers that are not running with a read-only root filesystem.
+pub async fn readonly_root_filesystem(
+    source: &dyn Source,
     namespaces: Vec<String>,
     all_namespaces: bool,
-) -> Result<()> {
-    let pods = get_pods(namespaces, all_namespaces).await?;
+    details: DetailOptions,
+) -> Result<Output> {
+    let generated_at = Utc::now().to_rfc3339();
+    let k8s_version = k8s_version(source).await;
+    let pods = source.pods(namespaces, all_namespaces).await?;
 
-    let pods = pods
+    let containers = pods
         .iter()
         .flat_map(|pod| {
-            all_pod_containers_read_only(pod).expect("failed to get all pod containers")
+            all_pod_containers_read_only(source, pod, &details)
+                .expect("failed to get all pod containers")
         })
         .collect::<Vec<_>>();
 
-    let output = serde_json::to_string_pretty(&pods)?;
-
-    println!("{output}");
-
-    Ok(())
+    Ok(Output {
+        generated_at,
+        k8s_version,
+        containers,
+    })
 }
 
-fn all_pod_containers_read_only(pod: &Pod) -> Result<BTreeSet<NoReadOnlyRootFilesystem>> {
+fn all_pod_containers_read_only(
+    source: &dyn Source,
+    pod: &Pod,
+    details: &DetailOptions,
+) -> Result<BTreeSet<NoReadOnlyRootFilesystem>> {
     if pod.spec.is_none() {
         bail!("Pod has no spec");
     }
@@ -44,13 +124,8 @@ fn all_pod_containers_read_only(pod: &Pod) -> Result<BTreeSet<NoReadOnlyRootFile
     let containers_not_read_only = spec
         .containers
         .iter()
-        .filter(|container| {
-            if let Some(security_context) = &container.security_context {
-                !security_context.read_only_root_filesystem.unwrap_or(false)
-            } else {
-                true
-            }
-        })
+        .filter(|container| source.checks_container(&container.name))
+        .filter(|container| !has_read_only_root_filesystem(container))
         .map(|container| NoReadOnlyRootFilesystem {
             namespace: pod
                 .metadata
@@ -68,19 +143,37 @@ fn all_pod_containers_read_only(pod: &Pod) -> Result<BTreeSet<NoReadOnlyRootFile
 
             container_name: container.name.clone(),
 
-            owner: get_pod_owner(pod),
+            owner: source.pod_owner(pod),
+
+            severity: Severity::Medium,
+            details: PodDetails::new(source, pod, details),
         })
         .collect();
 
     Ok(containers_not_read_only)
 }
 
+/// Whether the security context of the container makes its root filesystem
+/// read-only.
+pub(crate) fn has_read_only_root_filesystem(container: &Container) -> bool {
+    container
+        .security_context
+        .as_ref()
+        .and_then(|security_context| security_context.read_only_root_filesystem)
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod test {
     use std::collections::BTreeSet;
 
-    use crate::commands::readonly_root_filesystem::NoReadOnlyRootFilesystem;
+    use crate::{
+        commands::{
+            readonly_root_filesystem::NoReadOnlyRootFilesystem, DetailOptions, PodDetails, Severity,
+        },
+        source::FileSource,
+    };
 
     #[test]
     fn readonly_pod() {
@@ -125,18 +218,27 @@ mod test {
                 owner: None,
                 pod_name: "pod".to_string(),
                 container_name: "readwrite-explicit".to_string(),
+                severity: Severity::Medium,
+                details: PodDetails::default(),
             },
             NoReadOnlyRootFilesystem {
                 namespace: "test".to_string(),
                 owner: None,
                 pod_name: "pod".to_string(),
                 container_name: "readwrite".to_string(),
+                severity: Severity::Medium,
+                details: PodDetails::default(),
             },
         ]
         .into_iter()
         .collect::<BTreeSet<_>>();
 
-        let output = super::all_pod_containers_read_only(&pod).unwrap();
+        let output = super::all_pod_containers_read_only(
+            &FileSource::default(),
+            &pod,
+            &DetailOptions::default(),
+        )
+        .unwrap();
 
         assert_eq!(expected, output);
     }
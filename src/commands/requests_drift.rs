@@ -0,0 +1,229 @@
+//! Find owners whose pods do not all have the same resource requests and
+//! limits.
+
+use std::collections::BTreeMap;
+
+use eyre::{Context, Result};
+use k8s_openapi::api::core::v1::Container;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{Cpu, Memory, Owner},
+    commands::{Finding, PodPhase, Severity},
+    source::Source,
+};
+
+/// Container of an owner with different requests or limits across its pods.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct RequestsDrift {
+    namespace: String,
+    owner: Owner,
+    container_name: String,
+
+    /// Distinct requests and limits of the container, the most common first.
+    variants: Vec<ResourcesVariant>,
+
+    severity: Severity,
+}
+
+/// Requests and limits seen on some of the pods of an owner.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ResourcesVariant {
+    #[serde(flatten)]
+    resources: ContainerResources,
+
+    /// Number of pods whose container has these requests and limits.
+    pods: usize,
+
+    pod_names: Vec<String>,
+}
+
+/// Parsed requests and limits of a container, so `1000m` and `1` are the
+/// same.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Default, Serialize, JsonSchema)]
+pub struct ContainerResources {
+    requests_cpu: Option<Cpu>,
+    requests_memory: Option<Memory>,
+    limits_cpu: Option<Cpu>,
+    limits_memory: Option<Memory>,
+}
+
+impl Finding for RequestsDrift {
+    const DEFAULT_SEVERITY: Severity = Severity::Low;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        Some(&self.owner)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the containers of owners whose running pods do not all have the same
+/// cpu and memory requests and limits. This happens during rollouts or when a
+/// pod was edited directly. Pods without an owner are skipped.
+pub async fn requests_drift(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<RequestsDrift>> {
+    type Variants<'a> = BTreeMap<ContainerResources, Vec<&'a str>>;
+
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut containers: BTreeMap<(Owner, &str), Variants<'_>> = BTreeMap::new();
+
+    for pod in &pods {
+        if !PodPhase::matches(pod, &[PodPhase::Running]) {
+            continue;
+        }
+
+        let (Some(owner), Some(pod_name), Some(spec)) =
+            (source.pod_owner(pod), &pod.metadata.name, &pod.spec)
+        else {
+            continue;
+        };
+
+        for container in &spec.containers {
+            if !source.checks_container(&container.name) {
+                continue;
+            }
+
+            let resources = ContainerResources::of(container)
+                .with_context(|| format!("invalid resources of pod {pod_name}"))?;
+
+            containers
+                .entry((owner.clone(), container.name.as_str()))
+                .or_default()
+                .entry(resources)
+                .or_default()
+                .push(pod_name.as_str());
+        }
+    }
+
+    let mut drifts = containers
+        .into_iter()
+        .filter(|(_, variants)| variants.len() > 1)
+        .map(|((owner, container_name), variants)| {
+            let mut variants = variants
+                .into_iter()
+                .map(|(resources, mut pod_names)| {
+                    pod_names.sort_unstable();
+
+                    ResourcesVariant {
+                        resources,
+                        pods: pod_names.len(),
+                        pod_names: pod_names.into_iter().map(ToString::to_string).collect(),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            variants.sort_by(|a, b| b.pods.cmp(&a.pods).then_with(|| a.cmp(b)));
+
+            RequestsDrift {
+                namespace: owner.namespace.clone(),
+                owner,
+                container_name: container_name.to_string(),
+                variants,
+                severity: Severity::Low,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    drifts.sort();
+
+    Ok(drifts)
+}
+
+impl ContainerResources {
+    fn of(container: &Container) -> Result<Self> {
+        let resources = container.resources.as_ref();
+        let requests = resources.and_then(|resources| resources.requests.as_ref());
+        let limits = resources.and_then(|resources| resources.limits.as_ref());
+
+        let cpu = |amounts: Option<&BTreeMap<_, _>>| {
+            amounts
+                .and_then(|amounts| amounts.get("cpu"))
+                .map(Cpu::try_from)
+                .transpose()
+                .context("failed to convert cpu")
+        };
+
+        let memory = |amounts: Option<&BTreeMap<_, _>>| {
+            amounts
+                .and_then(|amounts| amounts.get("memory"))
+                .map(Memory::try_from)
+                .transpose()
+                .context("failed to convert memory")
+        };
+
+        Ok(Self {
+            requests_cpu: cpu(requests)?,
+            requests_memory: memory(requests)?,
+            limits_cpu: cpu(limits)?,
+            limits_memory: memory(limits)?,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use crate::{api::Cpu, source::FileSource};
+
+    const PODS: &str = include_str!("../../resources/fixtures/requests-drift.json");
+
+    #[tokio::test]
+    async fn requests_drift() {
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+
+        let drifts = super::requests_drift(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let drifts = drifts
+            .iter()
+            .map(|drift| {
+                (
+                    drift.owner.name.as_str(),
+                    drift.container_name.as_str(),
+                    drift
+                        .variants
+                        .iter()
+                        .map(|variant| {
+                            (
+                                variant.resources.requests_cpu.map(Cpu::to_milliseconds),
+                                variant.pod_names.clone(),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Mid rollout of the statefulset, `1000m` and `1` as well as `2Gi`
+        // and `2048Mi` are the same. The pending pod is not counted and the
+        // exporter and web containers only differ in their notation.
+        assert_eq!(
+            vec![(
+                "db",
+                "postgres",
+                vec![
+                    (Some(1000), vec!["db-1".to_string(), "db-2".to_string()]),
+                    (Some(2000), vec!["db-0".to_string()]),
+                ],
+            )],
+            drifts
+        );
+    }
+}
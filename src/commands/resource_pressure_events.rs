@@ -0,0 +1,240 @@
+//! Summarize the recent warning events of nodes that are caused by resource
+//! pressure.
+
+use std::collections::BTreeMap;
+
+use eyre::Result;
+use k8s_openapi::{
+    api::core::v1::Event,
+    chrono::{self, DateTime, SecondsFormat, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{event_node, event_timestamp},
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Resource pressure events of a node during the last day.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct NodePressureEvents {
+    node_name: String,
+
+    /// Occurrences of the events during the last hour.
+    count_last_hour: u64,
+
+    /// Occurrences of the events during the last day.
+    count_last_day: u64,
+
+    /// Occurrences during the last day per reason of the events.
+    reasons: BTreeMap<String, u64>,
+
+    /// Time the latest event happened.
+    last_seen: String,
+
+    /// Message of the latest event.
+    last_message: String,
+
+    severity: Severity,
+}
+
+impl Finding for NodePressureEvents {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    /// Nodes are not namespaced.
+    fn namespace(&self) -> &'static str {
+        ""
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Reasons of the events the kubelet and the node problem detector emit when
+/// a node runs out of memory or disk space.
+const PRESSURE_REASONS: &[&str] = &["Evicted", "OOMKilling", "SystemOOM", "FreeDiskSpaceFailed"];
+
+/// Group the warning events of the last day with a resource pressure reason
+/// by their node. The kubelet folds repeated events into one with a count, all
+/// occurrences of such an event are counted if it last happened in the
+/// window.
+pub async fn resource_pressure_events(source: &dyn Source) -> Result<Vec<NodePressureEvents>> {
+    let events = source.node_events().await?;
+
+    Ok(summarize(&events, Utc::now()))
+}
+
+fn summarize(events: &[Event], now: DateTime<Utc>) -> Vec<NodePressureEvents> {
+    let hour_ago = now - chrono::Duration::hours(1);
+    let day_ago = now - chrono::Duration::days(1);
+
+    let mut by_node: BTreeMap<&str, Vec<(&Event, DateTime<Utc>)>> = BTreeMap::new();
+    for event in events {
+        let is_pressure = event
+            .reason
+            .as_deref()
+            .is_some_and(|reason| PRESSURE_REASONS.contains(&reason));
+
+        let (Some(node), Some(timestamp)) = (event_node(event), event_timestamp(event)) else {
+            continue;
+        };
+
+        if is_pressure && timestamp >= day_ago {
+            by_node.entry(node).or_default().push((event, timestamp));
+        }
+    }
+
+    let mut summary = by_node
+        .into_iter()
+        .filter_map(|(node, events)| {
+            let occurrences = |event: &Event| u64::try_from(event.count.unwrap_or(1)).unwrap_or(1);
+
+            let count_last_hour = events
+                .iter()
+                .filter(|(_, timestamp)| *timestamp >= hour_ago)
+                .map(|(event, _)| occurrences(event))
+                .sum::<u64>();
+
+            let mut reasons: BTreeMap<String, u64> = BTreeMap::new();
+            for (event, _) in &events {
+                *reasons
+                    .entry(event.reason.clone().unwrap_or_default())
+                    .or_default() += occurrences(event);
+            }
+
+            let (latest, last_seen) = events.iter().max_by_key(|(_, timestamp)| *timestamp)?;
+
+            Some(NodePressureEvents {
+                node_name: node.to_string(),
+                count_last_hour,
+                count_last_day: reasons.values().sum(),
+                reasons,
+                last_seen: last_seen.to_rfc3339_opts(SecondsFormat::Secs, true),
+                last_message: latest.message.clone().unwrap_or_default(),
+                severity: if count_last_hour > 0 {
+                    Severity::High
+                } else {
+                    Severity::Medium
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    summary.sort();
+
+    summary
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::chrono::{self, SecondsFormat, Utc};
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    async fn resource_pressure_events() {
+        let ago = |minutes: i64| {
+            (Utc::now() - chrono::Duration::minutes(minutes))
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+
+        let node_event = |node: &str, reason: &str, count: i32, minutes: i64| {
+            json!({
+                "metadata": { "namespace": "default", "name": format!("{node}.{reason}") },
+                "involvedObject": { "kind": "Node", "name": node },
+                "type": "Warning",
+                "reason": reason,
+                "message": format!("{reason} on {node}"),
+                "count": count,
+                "lastTimestamp": ago(minutes),
+            })
+        };
+
+        let events = json!({
+            "items": [
+                node_event("node-a", "SystemOOM", 2, 10),
+                node_event("node-a", "FreeDiskSpaceFailed", 1, 300),
+                node_event("node-b", "OOMKilling", 1, 120),
+                node_event("node-b", "SystemOOM", 5, 2 * 24 * 60),
+                node_event("node-c", "Rebooted", 1, 10),
+                {
+                    "metadata": { "namespace": "web", "name": "api-0.evicted" },
+                    "involvedObject": { "kind": "Pod", "namespace": "web", "name": "api-0" },
+                    "source": { "component": "kubelet", "host": "node-c" },
+                    "type": "Warning",
+                    "reason": "Evicted",
+                    "message": "The node was low on resource: memory.",
+                    "lastTimestamp": ago(30),
+                },
+                {
+                    "metadata": { "namespace": "default", "name": "node-d.oom" },
+                    "involvedObject": { "kind": "Node", "name": "node-d" },
+                    "type": "Normal",
+                    "reason": "SystemOOM",
+                    "lastTimestamp": ago(10),
+                },
+            ],
+        });
+
+        let source =
+            FileSource::from_readers(json!({ "items": [] }).to_string().as_bytes(), None::<&[u8]>)
+                .unwrap()
+                .with_events(events.to_string().as_bytes())
+                .unwrap();
+
+        let summary = super::resource_pressure_events(&source).await.unwrap();
+
+        let summary = summary
+            .iter()
+            .map(|node| {
+                (
+                    node.node_name.as_str(),
+                    node.count_last_hour,
+                    node.count_last_day,
+                    node.reasons.keys().map(String::as_str).collect::<Vec<_>>(),
+                    node.last_message.as_str(),
+                    node.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "node-a",
+                    2,
+                    3,
+                    vec!["FreeDiskSpaceFailed", "SystemOOM"],
+                    "SystemOOM on node-a",
+                    Severity::High
+                ),
+                (
+                    "node-b",
+                    0,
+                    1,
+                    vec!["OOMKilling"],
+                    "OOMKilling on node-b",
+                    Severity::Medium
+                ),
+                (
+                    "node-c",
+                    1,
+                    1,
+                    vec!["Evicted"],
+                    "The node was low on resource: memory.",
+                    Severity::High
+                ),
+            ],
+            summary
+        );
+    }
+}
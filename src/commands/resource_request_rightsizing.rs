@@ -0,0 +1,545 @@
+//! Compare the resource requests of workloads with the recommendations of
+//! their vertical pod autoscalers.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use eyre::Result;
+use k8s_openapi::{
+    api::core::v1::{Container, PodTemplateSpec},
+    apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::ObjectMeta},
+};
+use log::warn;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{Cpu, Memory, VerticalPodAutoscaler},
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Container whose requests differ from the recommendation of the vertical pod
+/// autoscaler.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ResourceRequestRightsizing {
+    namespace: String,
+    vpa_name: String,
+    target_kind: String,
+    target_name: String,
+    container_name: String,
+
+    /// Requests of the container in the pod template of the target, missing
+    /// if the container has none or the target could not be found.
+    current_cpu: Option<Cpu>,
+    current_memory: Option<Memory>,
+
+    /// Requests recommended by the vertical pod autoscaler.
+    target_cpu: Option<Cpu>,
+    target_memory: Option<Memory>,
+
+    /// Recommended minus current cpu request, positive if the container
+    /// should request more.
+    delta_cpu_ms: Option<i64>,
+
+    /// Recommended minus current memory request, positive if the container
+    /// should request more.
+    delta_memory_bytes: Option<i64>,
+
+    severity: Severity,
+}
+
+impl Finding for ResourceRequestRightsizing {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Pod templates of the targets keyed by the kind, namespace and name of the
+/// workload.
+type Templates = BTreeMap<(String, String, String), PodTemplateSpec>;
+
+/// Get the containers whose requests differ from the target recommended by
+/// their vertical pod autoscaler. The current requests are taken from the pod
+/// template of deployments, statefulsets and daemonsets, other targets only
+/// show the recommendation. Containers requesting less than recommended are
+/// reported with a higher severity as they are likely throttled or killed.
+/// Nothing is reported if the vertical pod autoscaler is not installed.
+pub async fn resource_request_rightsizing(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<ResourceRequestRightsizing>> {
+    let vpas = source
+        .vertical_pod_autoscalers(namespaces.clone(), all_namespaces)
+        .await?;
+
+    let kinds = vpas
+        .iter()
+        .filter_map(|vpa| vpa.spec.target_ref.as_ref())
+        .map(|target| target.kind.as_str())
+        .collect::<BTreeSet<_>>();
+
+    let templates = templates(source, &kinds, namespaces, all_namespaces).await;
+
+    let mut findings = Vec::new();
+    for vpa in &vpas {
+        findings.extend(rightsizing(source, vpa, &templates));
+    }
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+/// Fetch the pod templates of the given workload kinds. Lists that can not be
+/// fetched are skipped with a warning, for example when reading from files
+/// without them.
+async fn templates(
+    source: &dyn Source,
+    kinds: &BTreeSet<&str>,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Templates {
+    let mut templates = Templates::new();
+
+    let mut insert = |kind: &str, metadata: ObjectMeta, template| {
+        if let (Some(namespace), Some(name)) = (metadata.namespace, metadata.name) {
+            templates.insert((kind.to_string(), namespace, name), template);
+        }
+    };
+
+    if kinds.contains("Deployment") {
+        match source.deployments(namespaces.clone(), all_namespaces).await {
+            Ok(deployments) => {
+                for deployment in deployments {
+                    if let Some(spec) = deployment.spec {
+                        insert("Deployment", deployment.metadata, spec.template);
+                    }
+                }
+            }
+
+            Err(err) => warn!("Failed to get deployments, skipping their requests: {err:?}"),
+        }
+    }
+
+    if kinds.contains("StatefulSet") {
+        match source
+            .statefulsets(namespaces.clone(), all_namespaces)
+            .await
+        {
+            Ok(statefulsets) => {
+                for statefulset in statefulsets {
+                    if let Some(spec) = statefulset.spec {
+                        insert("StatefulSet", statefulset.metadata, spec.template);
+                    }
+                }
+            }
+
+            Err(err) => warn!("Failed to get statefulsets, skipping their requests: {err:?}"),
+        }
+    }
+
+    if kinds.contains("DaemonSet") {
+        match source.daemonsets(namespaces, all_namespaces).await {
+            Ok(daemonsets) => {
+                for daemonset in daemonsets {
+                    if let Some(spec) = daemonset.spec {
+                        insert("DaemonSet", daemonset.metadata, spec.template);
+                    }
+                }
+            }
+
+            Err(err) => warn!("Failed to get daemonsets, skipping their requests: {err:?}"),
+        }
+    }
+
+    templates
+}
+
+/// Compare the recommendations of the vertical pod autoscaler with the
+/// requests of the containers of its target. Quantities that can not be parsed
+/// are skipped with a warning as if they were missing.
+fn rightsizing(
+    source: &dyn Source,
+    vpa: &VerticalPodAutoscaler,
+    templates: &Templates,
+) -> Vec<ResourceRequestRightsizing> {
+    let Some(target) = &vpa.spec.target_ref else {
+        return Vec::new();
+    };
+
+    let namespace = vpa.metadata.namespace.clone().unwrap_or_default();
+    let vpa_name = vpa.metadata.name.clone().unwrap_or_default();
+
+    let containers = templates
+        .get(&(target.kind.clone(), namespace.clone(), target.name.clone()))
+        .and_then(|template| template.spec.as_ref())
+        .map(|spec| spec.containers.as_slice())
+        .unwrap_or_default();
+
+    let recommendations = vpa
+        .status
+        .iter()
+        .filter_map(|status| status.recommendation.as_ref())
+        .flat_map(|recommendation| &recommendation.container_recommendations)
+        .filter(|recommendation| source.checks_container(&recommendation.container_name));
+
+    let mut findings = Vec::new();
+    for recommendation in recommendations {
+        let context = || {
+            format!(
+                "recommendation for container {} of vertical pod autoscaler {namespace}/{vpa_name}",
+                recommendation.container_name
+            )
+        };
+
+        let target_cpu = quantity(recommendation.target.get("cpu"), context);
+        let target_memory = quantity(recommendation.target.get("memory"), context);
+
+        let container = containers
+            .iter()
+            .find(|container| container.name == recommendation.container_name);
+
+        let (current_cpu, current_memory) = match container {
+            Some(container) => requests(container, || {
+                format!(
+                    "requests of container {} of {} {namespace}/{}",
+                    container.name, target.kind, target.name
+                )
+            }),
+            None => (None, None),
+        };
+
+        let delta_cpu_ms = delta(
+            target_cpu.map(Cpu::to_milliseconds),
+            current_cpu.map(Cpu::to_milliseconds),
+        );
+
+        let delta_memory_bytes = delta(
+            target_memory.map(Memory::to_bytes),
+            current_memory.map(Memory::to_bytes),
+        );
+
+        if delta_cpu_ms == Some(0) && delta_memory_bytes == Some(0) {
+            continue;
+        }
+
+        let under_requested = [delta_cpu_ms, delta_memory_bytes]
+            .into_iter()
+            .flatten()
+            .any(|delta| delta > 0);
+
+        findings.push(ResourceRequestRightsizing {
+            namespace: namespace.clone(),
+            vpa_name: vpa_name.clone(),
+            target_kind: target.kind.clone(),
+            target_name: target.name.clone(),
+            container_name: recommendation.container_name.clone(),
+            current_cpu,
+            current_memory,
+            target_cpu,
+            target_memory,
+            delta_cpu_ms,
+            delta_memory_bytes,
+            severity: if under_requested {
+                Severity::Medium
+            } else {
+                Severity::Low
+            },
+        });
+    }
+
+    findings
+}
+
+fn requests(container: &Container, context: impl Fn() -> String) -> (Option<Cpu>, Option<Memory>) {
+    let requests = container
+        .resources
+        .as_ref()
+        .and_then(|resources| resources.requests.as_ref());
+
+    let cpu = quantity(requests.and_then(|requests| requests.get("cpu")), &context);
+    let memory = quantity(
+        requests.and_then(|requests| requests.get("memory")),
+        &context,
+    );
+
+    (cpu, memory)
+}
+
+/// Convert the quantity, an invalid one is skipped with a warning.
+fn quantity<'a, T>(quantity: Option<&'a Quantity>, context: impl Fn() -> String) -> Option<T>
+where
+    T: TryFrom<&'a Quantity, Error = eyre::Error>,
+{
+    let quantity = quantity?;
+
+    match T::try_from(quantity) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!(
+                "Skipping invalid quantity {} in {}: {err:?}",
+                quantity.0,
+                context()
+            );
+            None
+        }
+    }
+}
+
+/// Recommended minus current value, missing if either is unknown.
+fn delta(target: Option<u64>, current: Option<u64>) -> Option<i64> {
+    let target = i64::try_from(target?).ok()?;
+    let current = i64::try_from(current?).ok()?;
+
+    target.checked_sub(current)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use regex::Regex;
+    use serde_json::json;
+
+    use crate::{
+        commands::Severity,
+        source::{FileSource, FilteredContainers},
+    };
+
+    #[tokio::test]
+    async fn resource_request_rightsizing() {
+        let vpa = |name: &str, kind: &str, cpu: &str, memory: &str| {
+            json!({
+                "metadata": { "namespace": "web", "name": name },
+                "spec": {
+                    "targetRef": { "apiVersion": "apps/v1", "kind": kind, "name": name },
+                },
+                "status": {
+                    "recommendation": {
+                        "containerRecommendations": [{
+                            "containerName": "app",
+                            "target": { "cpu": cpu, "memory": memory },
+                        }],
+                    },
+                },
+            })
+        };
+
+        let vpas = json!({
+            "items": [
+                vpa("api", "Deployment", "250m", "256Mi"),
+                vpa("frontend", "Deployment", "100m", "128Mi"),
+                vpa("worker", "Deployment", "50m", "64Mi"),
+                vpa("nightly", "CronJob", "1", "1Gi"),
+            ],
+        });
+
+        let deployment = |name: &str, cpu: &str, memory: &str| {
+            json!({
+                "metadata": { "namespace": "web", "name": name },
+                "spec": {
+                    "selector": {},
+                    "template": {
+                        "spec": {
+                            "containers": [{
+                                "name": "app",
+                                "resources": { "requests": { "cpu": cpu, "memory": memory } },
+                            }],
+                        },
+                    },
+                },
+            })
+        };
+
+        let deployments = json!({
+            "items": [
+                deployment("api", "100m", "512Mi"),
+                deployment("frontend", "100m", "128Mi"),
+                deployment("worker", "200m", "128Mi"),
+            ],
+        });
+
+        let source =
+            FileSource::from_readers(json!({ "items": [] }).to_string().as_bytes(), None::<&[u8]>)
+                .unwrap()
+                .with_vertical_pod_autoscalers(vpas.to_string().as_bytes())
+                .unwrap()
+                .with_deployments(deployments.to_string().as_bytes())
+                .unwrap();
+
+        let findings = super::resource_request_rightsizing(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.target_name.as_str(),
+                    finding.delta_cpu_ms,
+                    finding.delta_memory_bytes,
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // The frontend requests match the recommendation and the cronjob has
+        // no known requests.
+        assert_eq!(
+            vec![
+                ("api", Some(150), Some(-256 * 1024 * 1024), Severity::Medium),
+                ("nightly", None, None, Severity::Low),
+                ("worker", Some(-150), Some(-64 * 1024 * 1024), Severity::Low),
+            ],
+            findings
+        );
+    }
+
+    /// Status as written by the recommender, memory is given in k or plain
+    /// bytes.
+    fn vpa_status_source() -> FileSource {
+        let vpas = json!({
+            "items": [{
+                "apiVersion": "autoscaling.k8s.io/v1",
+                "kind": "VerticalPodAutoscaler",
+                "metadata": { "namespace": "web", "name": "api" },
+                "spec": {
+                    "targetRef": { "apiVersion": "apps/v1", "kind": "Deployment", "name": "api" },
+                    "updatePolicy": { "updateMode": "Off" },
+                },
+                "status": {
+                    "conditions": [{
+                        "type": "RecommendationProvided",
+                        "status": "True",
+                        "lastTransitionTime": "2024-01-01T00:00:00Z",
+                    }],
+                    "recommendation": {
+                        "containerRecommendations": [
+                            {
+                                "containerName": "app",
+                                "lowerBound": { "cpu": "25m", "memory": "262144k" },
+                                "target": { "cpu": "63m", "memory": "262144k" },
+                                "uncappedTarget": { "cpu": "63m", "memory": "262144k" },
+                                "upperBound": { "cpu": "1", "memory": "1102569341" },
+                            },
+                            {
+                                "containerName": "proxy",
+                                "lowerBound": { "cpu": "10m", "memory": "52428800" },
+                                "target": { "cpu": "11m", "memory": "52428800" },
+                                "uncappedTarget": { "cpu": "11m", "memory": "52428800" },
+                                "upperBound": { "cpu": "120m", "memory": "209715200" },
+                            },
+                            {
+                                "containerName": "metrics",
+                                "target": { "cpu": "15m", "memory": "lots" },
+                            },
+                        ],
+                    },
+                },
+            }],
+        });
+
+        let container = |name: &str, cpu: &str, memory: &str| {
+            json!({
+                "name": name,
+                "resources": { "requests": { "cpu": cpu, "memory": memory } },
+            })
+        };
+
+        let deployments = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api" },
+                "spec": {
+                    "selector": {},
+                    "template": {
+                        "spec": {
+                            "containers": [
+                                container("app", "0.1", "268435456"),
+                                container("proxy", "11m", "50Mi"),
+                                container("metrics", "10m", "32M"),
+                            ],
+                        },
+                    },
+                },
+            }],
+        });
+
+        FileSource::from_readers(json!({ "items": [] }).to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_vertical_pod_autoscalers(vpas.to_string().as_bytes())
+            .unwrap()
+            .with_deployments(deployments.to_string().as_bytes())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn vpa_status() {
+        let source = vpa_status_source();
+
+        let findings = super::resource_request_rightsizing(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.container_name.as_str(),
+                    finding.delta_cpu_ms,
+                    finding.delta_memory_bytes,
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // The proxy matches its recommendation, the invalid memory of the
+        // metrics container is skipped.
+        assert_eq!(
+            vec![
+                (
+                    "app",
+                    Some(-37),
+                    Some(262_144_000 - 268_435_456),
+                    Severity::Low
+                ),
+                ("metrics", Some(5), None, Severity::Medium),
+            ],
+            findings
+        );
+    }
+
+    #[tokio::test]
+    async fn filtered_containers() {
+        let source = vpa_status_source();
+        let regex = Regex::new("^app$").unwrap();
+        let filtered = FilteredContainers::new(&source, &regex);
+
+        let findings = super::resource_request_rightsizing(&filtered, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| finding.container_name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["app"], findings);
+    }
+
+    #[test]
+    fn delta() {
+        assert_eq!(Some(-50), super::delta(Some(50), Some(100)));
+        assert_eq!(None, super::delta(Some(50), None));
+        assert_eq!(None, super::delta(None, Some(100)));
+    }
+}
@@ -1,39 +1,99 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap};
-
-use eyre::{Context, Result};
-use k8s_openapi::api::core::v1::{Container, Pod};
+//! Compare the resource requests and limits of containers with their usage.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use eyre::{eyre, Context, Result};
+use k8s_openapi::{
+    api::core::v1::{Container, Pod},
+    apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    chrono::Utc,
+};
 use log::{info, warn};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-use crate::api::{self, get_pod_owner, get_pod_resource_usage, get_pods, Cpu, Memory, Owner};
+use crate::{
+    api::{self, Cpu, Memory, Owner},
+    commands::{k8s_version, DetailOptions, Finding, PodDetails, PodPhase, Report, Severity},
+    source::Source,
+};
 
-#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default)]
+#[derive(Debug, Serialize, PartialEq, Default, JsonSchema)]
 struct Total {
     namespaces: Vec<TotalNamespace>,
     owners: Vec<TotalOwner>,
+
+    /// Totals per value of the label given with `--aggregate-by-label`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<TotalLabel>,
 }
 
-#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default)]
-struct Output {
+/// Resource requests, limits and usage of containers with totals per
+/// namespace and owner.
+#[derive(Debug, Serialize, PartialEq, Default, JsonSchema)]
+pub struct Output {
+    /// Time the report was generated in RFC 3339 format.
+    generated_at: String,
+
+    /// Version of the api server the pods were read from.
+    k8s_version: String,
+
     total: Total,
+    waste_summary: WasteSummary,
     pods: BTreeSet<PodOutput>,
+
+    /// Difference to the report given with `--baseline-file`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline_diff: Option<BaselineDiff>,
+
+    /// Whether the usage was not requested from the metrics api.
+    #[serde(skip)]
+    usage_skipped: bool,
 }
 
-#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone)]
+/// Hours in a month of 30 days.
+const HOURS_PER_MONTH: f64 = 24.0 * 30.0;
+
+/// Requested capacity the containers do not use, extrapolated from the current
+/// usage to a month. Containers without usage are not counted.
+#[derive(Debug, Serialize, PartialEq, Default, JsonSchema)]
+struct WasteSummary {
+    wasted_cpu_core_hours_per_month: f64,
+    wasted_memory_gib_hours_per_month: f64,
+}
+
+/// Containers using more than this percentage of their cpu limit are likely
+/// throttled.
+const NEAR_LIMIT_PCT: u64 = 90;
+
+#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone, JsonSchema)]
 struct Resources {
     usage: ResourcePair,
     requests: ResourcePair,
     limits: ResourcePair,
     difference: UsageDifference,
+    limits_utilization: Utilization,
 }
 
-#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone)]
+/// Usage in percent of another resource amount, rounded down.
+#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone, JsonSchema)]
+struct Utilization {
+    cpu_pct: Option<u64>,
+    memory_pct: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone, JsonSchema)]
 struct UsageDifference {
     requests: ResourcePair,
     limits: ResourcePair,
 }
 
-#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone)]
+#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone, JsonSchema)]
 struct ResourcePair {
     cpu: Option<Cpu>,
     cpu_milliseconds: Option<u64>,
@@ -41,70 +101,366 @@ struct ResourcePair {
     memory_bytes: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone)]
+#[derive(Debug, Serialize, PartialEq, Default, Clone, JsonSchema)]
 struct TotalNamespace {
     namespace: String,
     resources: Resources,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<ResourcesStats>,
+
+    /// Estimated monthly cost with `--cost`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<Cost>,
 }
 
-#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone)]
+#[derive(Debug, Serialize, PartialEq, Default, Clone, JsonSchema)]
 struct TotalOwner {
     owner: Owner,
     resources: Resources,
     count: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<ResourcesStats>,
+
+    /// Desired replicas from the spec of the owner, the current size for
+    /// replica sets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replicas: Option<i32>,
+
+    /// Average requests of a single pod of the owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    per_replica_requests: Option<ResourcePair>,
+
+    /// Maximum replicas of the horizontal pod autoscaler scaling the owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hpa_max_replicas: Option<i32>,
+
+    /// Requests of the owner when scaled to the maximum replicas of its
+    /// horizontal pod autoscaler.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    projected_requests: Option<ResourcePair>,
+
+    /// Estimated monthly cost with `--cost`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<Cost>,
+}
+
+/// Replica counts of the owners of pods and the maximum replicas of the
+/// horizontal pod autoscalers scaling them, keyed by kind, namespace and name.
+#[derive(Debug, Default)]
+struct Workloads {
+    replicas: BTreeMap<(String, String, String), i32>,
+
+    /// Deployment controlling a replica set keyed by the namespace and name of
+    /// the replica set. Autoscalers scale the deployment and not its replica
+    /// sets.
+    deployments: BTreeMap<(String, String), String>,
+
+    hpa_max_replicas: BTreeMap<(String, String, String), i32>,
+}
+
+/// Resources of the containers of pods that have the same value for a label,
+/// for example all pods of a team across namespaces.
+#[derive(Debug, Serialize, PartialEq, Default, Clone, JsonSchema)]
+struct TotalLabel {
+    label: String,
+
+    /// Value of the label, empty for pods without the label.
+    value: Option<String>,
+
+    resources: Resources,
+    count: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<ResourcesStats>,
+
+    /// Estimated monthly cost with `--cost`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<Cost>,
+}
+
+/// Unit prices the cost of the requests and usage is estimated with. Read
+/// from the `prices` section of the config file, the command line flags take
+/// precedence.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Prices {
+    /// Price of one cpu core for an hour.
+    #[serde(default)]
+    pub cpu_per_core_hour: f64,
+
+    /// Price of one GiB of memory for an hour.
+    #[serde(default)]
+    pub memory_per_gib_hour: f64,
+
+    /// Label of the currency the prices are in, for example `EUR`.
+    #[serde(default)]
+    pub currency: Option<String>,
+}
+
+/// Estimated cost of the containers of a namespace, owner or label value for a
+/// month of 30 days, rounded to two decimals.
+#[derive(Debug, Serialize, PartialEq, Default, Clone, JsonSchema)]
+struct Cost {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
+
+    /// Cost of the requested cpu and memory.
+    requests_per_month: f64,
+
+    /// Cost of the used cpu and memory, containers without usage are not
+    /// counted.
+    usage_per_month: f64,
+
+    /// Cost of the requested cpu and memory the containers do not use.
+    /// Containers without usage are not counted, containers using more than
+    /// they request do not lower it.
+    slack_per_month: f64,
+}
+
+/// Statistics over the resources of multiple containers. Shows how much the
+/// resources vary between the replicas of an owner or the pods of a
+/// namespace.
+#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone, JsonSchema)]
+struct ResourcesStats {
+    usage: ResourceStats,
+    requests: ResourceStats,
+    limits: ResourceStats,
+}
+
+#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, Default, Clone, JsonSchema)]
+struct ResourceStats {
+    sum: ResourcePair,
+    min: ResourcePair,
+    max: ResourcePair,
+    p50: ResourcePair,
+    count: usize,
+}
+
+/// Requests and usage of the containers of a previously saved report.
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    containers: BTreeMap<(String, String, String), BaselineResources>,
+}
+
+/// Container of a saved report, only the fields needed for the difference are
+/// read.
+#[derive(Debug, Deserialize)]
+struct BaselineContainer {
+    namespace: String,
+    pod_name: String,
+    container_name: String,
+    resources: BaselineResources,
 }
 
-#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq)]
-struct PodOutput {
+#[derive(Debug, Clone, Deserialize)]
+struct BaselineResources {
+    #[serde(default)]
+    requests: BaselinePair,
+
+    #[serde(default)]
+    usage: BaselinePair,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct BaselinePair {
+    cpu_milliseconds: Option<u64>,
+    memory_bytes: Option<u64>,
+}
+
+/// Growth of the requests and usage since the baseline. Cpu is in
+/// milliseconds and memory in bytes.
+#[derive(Debug, Serialize, PartialEq, Default, JsonSchema)]
+struct BaselineDiff {
+    delta_cpu_request: i64,
+    delta_memory_request: i64,
+    delta_cpu_usage: i64,
+
+    /// Containers that are new, removed or whose requests or usage changed.
+    containers: Vec<ContainerDelta>,
+}
+
+/// Difference of a single container to the baseline. New containers count
+/// with their full amounts, removed ones with their negated amounts.
+#[derive(Debug, Serialize, PartialEq, Eq, JsonSchema)]
+struct ContainerDelta {
+    namespace: String,
+    pod_name: String,
+    container_name: String,
+    status: BaselineStatus,
+    delta_cpu_request: Option<i64>,
+    delta_memory_request: Option<i64>,
+    delta_cpu_usage: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum BaselineStatus {
+    /// The container was not part of the baseline.
+    New,
+    /// The container is gone since the baseline.
+    Removed,
+    /// The container requests or uses a different amount than in the
+    /// baseline.
+    Changed,
+}
+
+/// Resources of a single container.
+#[derive(Debug, Serialize, Ord, PartialOrd, Eq, PartialEq, JsonSchema)]
+pub struct PodOutput {
     pod_name: String,
     container_name: String,
     namespace: String,
     owner: Option<Owner>,
+    phase: String,
+
+    /// Labels of the pod. Named `pod_labels` so they do not collide with the
+    /// labels selected with `--output-labels` and `--label-columns`.
+    pod_labels: Option<BTreeMap<String, String>>,
 
     resources: Resources,
+
+    /// Whether the container has no cpu limit.
+    missing_cpu_limit: bool,
+
+    /// Whether the container has no memory limit.
+    missing_memory_limit: bool,
+
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
 }
 
-#[allow(clippy::too_many_lines)]
-pub(crate) async fn resource_requests(
+/// Get the resource requests, limits and usage of the containers of pods in
+/// the given phases. Only running pods have usage.
+///
+/// With a threshold only containers where the difference between the cpu
+/// request and usage is bigger than the threshold are returned. Containers
+/// using more than 90% of their cpu limit are reported with a medium severity,
+/// with `near_limits` only those are returned. With `no_limits_only` only
+/// containers without a cpu or memory limit are returned. With `no_usage` the
+/// metrics api is not queried and the usage is left empty. With a `baseline`
+/// the difference of the returned containers to it is added. With
+/// `aggregate_by_label` the totals are also grouped by the value of that pod
+/// label. With `aggregate_by_owner` the owner totals also get the replicas of
+/// the owner and the average requests per replica, with `project_hpa_max` also
+/// the requests when scaled to the maximum of its horizontal pod autoscaler.
+/// Both are computed from all containers of the owner, also the ones removed
+/// by the filters.
+/// With `prices` the totals also get the estimated monthly cost of the
+/// requests, the usage and the unused requests.
+#[allow(
+    clippy::too_many_lines,
+    clippy::too_many_arguments,
+    clippy::fn_params_excessive_bools
+)]
+pub async fn resource_requests(
+    source: &dyn Source,
     namespaces: Vec<String>,
     all_namespaces: bool,
     threshold: Option<u64>,
     no_check_higher: bool,
-) -> Result<()> {
-    let pods = get_pods(namespaces, all_namespaces).await?;
+    details: DetailOptions,
+    aggregate_by_owner: bool,
+    near_limits: bool,
+    no_limits_only: bool,
+    phases: &[PodPhase],
+    no_usage: bool,
+    baseline: Option<&Baseline>,
+    aggregate_by_label: Option<&str>,
+    project_hpa_max: bool,
+    prices: Option<&Prices>,
+) -> Result<Output> {
+    let generated_at = Utc::now().to_rfc3339();
+    let k8s_version = k8s_version(source).await;
+    let pods = source.pods(namespaces.clone(), all_namespaces).await?;
+
+    // Pods can briefly be running before the node name is set, they can not
+    // have metrics yet.
+    let unscheduled = pods
+        .iter()
+        .filter(|pod| {
+            pod.spec
+                .as_ref()
+                .and_then(|spec| spec.node_name.as_ref())
+                .is_none()
+        })
+        .filter_map(|pod| Some((pod.metadata.namespace.clone()?, pod.metadata.name.clone()?)))
+        .collect::<BTreeSet<_>>();
 
     let output = pods
         .into_iter()
-        .filter(|pod| pod.status.is_some())
         .filter(|pod| {
-            let is_running =
-                pod.status.as_ref().map(|status| status.phase.as_deref()) == Some(Some("Running"));
+            let included = PodPhase::matches(pod, phases);
 
-            if !is_running {
+            if !included {
                 if let Some(name) = &pod.metadata.name {
-                    info!("Ignoring not running pod: {name}");
+                    info!("Ignoring pod in excluded phase: {name}");
                 }
             }
 
-            is_running
+            included
         })
-        .flat_map(pod_to_output)
+        .flat_map(|pod| pod_to_output(source, pod, &details))
         .flatten()
         .collect::<BTreeSet<PodOutput>>();
 
+    if no_usage {
+        info!("Skipping the resource usage of pods");
+    }
+
+    // Fetch the metrics of all pods at once instead of one request per pod.
+    let all_usage = if all_namespaces && !no_usage {
+        Some(
+            source
+                .all_pod_resource_usage()
+                .await
+                .context("failed to get resource usage of all pods")?,
+        )
+    } else {
+        None
+    };
+
+    // Keyed by namespace and name as pods with the same name can run in
+    // different namespaces.
     let mut tops = BTreeMap::new();
     for pod in &output {
-        let top = get_pod_resource_usage(&pod.namespace, &pod.pod_name)
-            .await
-            .with_context(|| "failed to get pod resource usage")?;
+        let key = (pod.namespace.clone(), pod.pod_name.clone());
+
+        if no_usage || pod.phase != PodPhase::Running.as_str() {
+            tops.insert(key, None);
+            continue;
+        }
 
-        tops.insert(pod.pod_name.clone(), top);
+        if unscheduled.contains(&key) {
+            warn!(
+                "Pod {}/{} has no node name yet, skipping metrics",
+                pod.namespace, pod.pod_name
+            );
+
+            tops.insert(key, None);
+            continue;
+        }
+
+        let top = match &all_usage {
+            Some(all_usage) => all_usage.get(&key).cloned(),
+            None => source
+                .pod_resource_usage(&pod.namespace, &pod.pod_name)
+                .await
+                .with_context(|| "failed to get pod resource usage")?,
+        };
+
+        tops.insert(key, top);
     }
 
     let pods = output
         .into_iter()
         .map(|pod| {
-            let usage = tops.get(&pod.pod_name).expect("failed to get usage");
+            let usage = tops
+                .get(&(pod.namespace.clone(), pod.pod_name.clone()))
+                .expect("failed to get usage");
 
             if let Some(usage) = usage {
                 let container_usage = usage
@@ -131,10 +487,19 @@ pub(crate) async fn resource_requests(
 
                 PodOutput { resources, ..pod }
             } else {
-                warn!("Failed to get usage for pod: {}", pod.pod_name);
+                if !no_usage && pod.phase == PodPhase::Running.as_str() {
+                    warn!("Failed to get usage for pod: {}", pod.pod_name);
+                }
+
                 pod
             }
         })
+        .collect::<Vec<_>>();
+
+    let owner_requests = owner_requests(&pods);
+
+    let pods = pods
+        .into_iter()
         .filter(|pod| {
             // Check if cpu_usage is higher than requests_cpu
             if let Some(cpu_usage) = pod.resources.usage.cpu {
@@ -160,10 +525,22 @@ pub(crate) async fn resource_requests(
 
             true
         })
+        .filter(|pod| !near_limits || pod.resources.near_cpu_limit())
+        .filter(|pod| !no_limits_only || pod.missing_cpu_limit || pod.missing_memory_limit)
+        .map(|pod| {
+            if pod.resources.near_cpu_limit() {
+                PodOutput {
+                    severity: Severity::Medium,
+                    ..pod
+                }
+            } else {
+                pod
+            }
+        })
         .collect::<BTreeSet<_>>();
 
-    let total_namespaces: HashMap<&str, TotalNamespace> =
-        pods.iter().fold(HashMap::default(), |mut total, pod| {
+    let mut total_namespaces: BTreeMap<&str, TotalNamespace> =
+        pods.iter().fold(BTreeMap::default(), |mut total, pod| {
             let entry = total
                 .entry(&pod.namespace)
                 .or_insert_with(|| TotalNamespace {
@@ -175,10 +552,20 @@ pub(crate) async fn resource_requests(
             total
         });
 
-    let total_owners: HashMap<&str, TotalOwner> =
-        pods.iter().fold(HashMap::default(), |mut total, pod| {
+    // Keyed by kind, name, namespace and uid so owners with the same name in
+    // different namespaces are kept apart and sorted the same way in every
+    // run.
+    let mut total_owners: BTreeMap<(&str, &str, &str, &str), TotalOwner> =
+        pods.iter().fold(BTreeMap::default(), |mut total, pod| {
             if let Some(owner) = &pod.owner {
-                let entry = total.entry(&pod.namespace).or_insert_with(|| TotalOwner {
+                let key = (
+                    owner.kind.as_str(),
+                    owner.name.as_str(),
+                    owner.namespace.as_str(),
+                    owner.uid.as_str(),
+                );
+
+                let entry = total.entry(key).or_insert_with(|| TotalOwner {
                     owner: owner.clone(),
                     ..Default::default()
                 });
@@ -189,20 +576,575 @@ pub(crate) async fn resource_requests(
             total
         });
 
+    let mut total_labels: BTreeMap<Option<&str>, TotalLabel> = BTreeMap::new();
+    if let Some(label) = aggregate_by_label {
+        for pod in &pods {
+            let value = pod.label_value(label);
+
+            let entry = total_labels.entry(value).or_insert_with(|| TotalLabel {
+                label: label.to_string(),
+                value: value.map(ToString::to_string),
+                ..Default::default()
+            });
+
+            *entry += pod;
+        }
+    }
+
+    if aggregate_by_owner {
+        for (namespace, total) in &mut total_namespaces {
+            let resources = pods
+                .iter()
+                .filter(|pod| pod.namespace == *namespace)
+                .map(|pod| &pod.resources)
+                .collect::<Vec<_>>();
+
+            total.stats = Some(ResourcesStats::new(&resources));
+        }
+
+        let kinds = pods
+            .iter()
+            .filter_map(|pod| pod.owner.as_ref())
+            .map(|owner| owner.kind.as_str())
+            .collect::<BTreeSet<_>>();
+
+        let workloads =
+            Workloads::fetch(source, &kinds, namespaces, all_namespaces, project_hpa_max).await;
+
+        for total in total_owners.values_mut() {
+            let owned = pods
+                .iter()
+                .filter(|pod| pod.owner.as_ref() == Some(&total.owner))
+                .collect::<Vec<_>>();
+
+            let resources = owned.iter().map(|pod| &pod.resources).collect::<Vec<_>>();
+            total.stats = Some(ResourcesStats::new(&resources));
+
+            total.replicas = workloads.replicas(&total.owner);
+
+            let Some((requests, pod_names)) = owner_requests.get(&total.owner) else {
+                continue;
+            };
+
+            // Count pods and not containers like `count` does.
+            let replicas = u64::try_from(pod_names.len()).unwrap_or_default();
+            total.per_replica_requests = requests.scaled(1, replicas);
+
+            if project_hpa_max {
+                total.hpa_max_replicas = workloads.hpa_max_replicas(&total.owner);
+                total.projected_requests = total
+                    .hpa_max_replicas
+                    .and_then(|max| u64::try_from(max).ok())
+                    .and_then(|max| requests.scaled(max, replicas));
+            }
+        }
+
+        for total in total_labels.values_mut() {
+            let resources = pods
+                .iter()
+                .filter(|pod| pod.label_value(&total.label) == total.value.as_deref())
+                .map(|pod| &pod.resources)
+                .collect::<Vec<_>>();
+
+            total.stats = Some(ResourcesStats::new(&resources));
+        }
+    }
+
+    if let Some(prices) = prices {
+        for total in total_namespaces.values_mut() {
+            let pods = pods.iter().filter(|pod| pod.namespace == total.namespace);
+            total.cost = Some(prices.cost(pods));
+        }
+
+        for total in total_owners.values_mut() {
+            let pods = pods
+                .iter()
+                .filter(|pod| pod.owner.as_ref() == Some(&total.owner));
+            total.cost = Some(prices.cost(pods));
+        }
+
+        for total in total_labels.values_mut() {
+            let pods = pods
+                .iter()
+                .filter(|pod| pod.label_value(&total.label) == total.value.as_deref());
+            total.cost = Some(prices.cost(pods));
+        }
+    }
+
     let output = Output {
+        generated_at,
+        k8s_version,
         total: Total {
-            namespaces: total_namespaces.values().cloned().collect(),
-            owners: total_owners.values().cloned().collect(),
+            namespaces: total_namespaces.into_values().collect(),
+            owners: total_owners.into_values().collect(),
+            labels: total_labels.into_values().collect(),
         },
 
+        waste_summary: WasteSummary::new(&pods),
+        baseline_diff: baseline.map(|baseline| baseline.diff(&pods)),
         pods,
+        usage_skipped: no_usage,
     };
 
-    let out = serde_json::to_string_pretty(&output)?;
+    Ok(output)
+}
+
+impl Workloads {
+    /// Fetch the workloads of the given owner kinds and with `hpa` the
+    /// horizontal pod autoscalers. Lists that can not be fetched are skipped
+    /// with a warning, for example when reading from files without them.
+    async fn fetch(
+        source: &dyn Source,
+        kinds: &BTreeSet<&str>,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+        hpa: bool,
+    ) -> Self {
+        let mut workloads = Self::default();
+
+        if kinds.contains("Deployment") {
+            match source.deployments(namespaces.clone(), all_namespaces).await {
+                Ok(deployments) => {
+                    for deployment in &deployments {
+                        let replicas = deployment.spec.as_ref().and_then(|spec| spec.replicas);
+                        workloads.insert("Deployment", &deployment.metadata, replicas);
+                    }
+                }
+
+                Err(err) => warn!("Failed to get deployments, skipping their replicas: {err:?}"),
+            }
+        }
+
+        if kinds.contains("StatefulSet") {
+            match source
+                .statefulsets(namespaces.clone(), all_namespaces)
+                .await
+            {
+                Ok(statefulsets) => {
+                    for statefulset in &statefulsets {
+                        let replicas = statefulset.spec.as_ref().and_then(|spec| spec.replicas);
+                        workloads.insert("StatefulSet", &statefulset.metadata, replicas);
+                    }
+                }
+
+                Err(err) => warn!("Failed to get statefulsets, skipping their replicas: {err:?}"),
+            }
+        }
+
+        if kinds.contains("ReplicaSet") {
+            match source
+                .replica_sets(namespaces.clone(), all_namespaces)
+                .await
+            {
+                Ok(replica_sets) => {
+                    for replica_set in &replica_sets {
+                        let replicas = replica_set.status.as_ref().map(|status| status.replicas);
+                        workloads.insert("ReplicaSet", &replica_set.metadata, replicas);
+
+                        if let (Some(namespace), Some(name), Some(owner)) = (
+                            &replica_set.metadata.namespace,
+                            &replica_set.metadata.name,
+                            api::extract_owner(replica_set)
+                                .filter(|owner| owner.kind == "Deployment"),
+                        ) {
+                            workloads
+                                .deployments
+                                .insert((namespace.clone(), name.clone()), owner.name.clone());
+                        }
+                    }
+                }
+
+                Err(err) => warn!("Failed to get replica sets, skipping their replicas: {err:?}"),
+            }
+        }
+
+        if hpa {
+            match source
+                .horizontal_pod_autoscalers(namespaces, all_namespaces)
+                .await
+            {
+                Ok(autoscalers) => {
+                    for autoscaler in &autoscalers {
+                        let (Some(namespace), Some(spec)) =
+                            (&autoscaler.metadata.namespace, &autoscaler.spec)
+                        else {
+                            continue;
+                        };
+
+                        let target = &spec.scale_target_ref;
+                        workloads.hpa_max_replicas.insert(
+                            (target.kind.clone(), namespace.clone(), target.name.clone()),
+                            spec.max_replicas,
+                        );
+                    }
+                }
+
+                Err(err) => warn!(
+                    "Failed to get horizontal pod autoscalers, skipping the projection: {err:?}"
+                ),
+            }
+        }
+
+        workloads
+    }
+
+    fn insert(&mut self, kind: &str, metadata: &ObjectMeta, replicas: Option<i32>) {
+        if let (Some(namespace), Some(name)) = (&metadata.namespace, &metadata.name) {
+            // Kubernetes defaults the replicas of workloads to one.
+            self.replicas.insert(
+                (kind.to_string(), namespace.clone(), name.clone()),
+                replicas.unwrap_or(1),
+            );
+        }
+    }
+
+    fn replicas(&self, owner: &Owner) -> Option<i32> {
+        self.replicas
+            .get(&(
+                owner.kind.clone(),
+                owner.namespace.clone(),
+                owner.name.clone(),
+            ))
+            .copied()
+    }
+
+    /// Maximum replicas of the autoscaler scaling the owner or, for replica
+    /// sets, the deployment controlling it.
+    fn hpa_max_replicas(&self, owner: &Owner) -> Option<i32> {
+        let key = (
+            owner.kind.clone(),
+            owner.namespace.clone(),
+            owner.name.clone(),
+        );
+
+        if let Some(max) = self.hpa_max_replicas.get(&key) {
+            return Some(*max);
+        }
+
+        if owner.kind != "ReplicaSet" {
+            return None;
+        }
+
+        let deployment = self
+            .deployments
+            .get(&(owner.namespace.clone(), owner.name.clone()))?;
+
+        self.hpa_max_replicas
+            .get(&(
+                "Deployment".to_string(),
+                owner.namespace.clone(),
+                deployment.clone(),
+            ))
+            .copied()
+    }
+}
+
+impl Baseline {
+    /// Read the baseline from a report saved from `resource-requests`, either
+    /// plain or wrapped with `--report-title`.
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("failed to open baseline {}", path.display()))?;
+
+        Self::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to read baseline {}", path.display()))
+    }
+
+    fn from_reader(reader: impl Read) -> Result<Self> {
+        let document: serde_json::Value =
+            serde_json::from_reader(reader).context("failed to parse json")?;
+
+        let document = document.get("data").unwrap_or(&document);
+        let report = document.get("report").unwrap_or(document);
+
+        let pods = report
+            .get("pods")
+            .ok_or_else(|| eyre!("report has no pods, it is not a resource-requests report"))?;
+
+        let containers = serde_json::from_value::<Vec<BaselineContainer>>(pods.clone())
+            .context("failed to parse pods")?
+            .into_iter()
+            .map(|container| {
+                (
+                    (
+                        container.namespace,
+                        container.pod_name,
+                        container.container_name,
+                    ),
+                    container.resources,
+                )
+            })
+            .collect();
+
+        Ok(Self { containers })
+    }
+
+    fn diff(&self, pods: &BTreeSet<PodOutput>) -> BaselineDiff {
+        let mut containers = Vec::new();
+        let mut seen = BTreeSet::new();
+
+        for pod in pods {
+            let key = (
+                pod.namespace.clone(),
+                pod.pod_name.clone(),
+                pod.container_name.clone(),
+            );
+
+            let current = &pod.resources;
+
+            let (status, baseline) = match self.containers.get(&key) {
+                Some(baseline) => (BaselineStatus::Changed, baseline.clone()),
+                None => (BaselineStatus::New, BaselineResources::zero()),
+            };
+
+            let container = ContainerDelta {
+                namespace: key.0.clone(),
+                pod_name: key.1.clone(),
+                container_name: key.2.clone(),
+                status,
+                delta_cpu_request: delta(
+                    current.requests.cpu_milliseconds,
+                    baseline.requests.cpu_milliseconds,
+                ),
+                delta_memory_request: delta(
+                    current.requests.memory_bytes,
+                    baseline.requests.memory_bytes,
+                ),
+                delta_cpu_usage: delta(
+                    current.usage.cpu_milliseconds,
+                    baseline.usage.cpu_milliseconds,
+                ),
+            };
+
+            seen.insert(key);
+            containers.push(container);
+        }
+
+        for (key, baseline) in &self.containers {
+            if seen.contains(key) {
+                continue;
+            }
+
+            let negated = |amount: Option<u64>| delta(Some(0), amount);
+
+            containers.push(ContainerDelta {
+                namespace: key.0.clone(),
+                pod_name: key.1.clone(),
+                container_name: key.2.clone(),
+                status: BaselineStatus::Removed,
+                delta_cpu_request: negated(baseline.requests.cpu_milliseconds),
+                delta_memory_request: negated(baseline.requests.memory_bytes),
+                delta_cpu_usage: negated(baseline.usage.cpu_milliseconds),
+            });
+        }
+
+        containers.retain(|container| {
+            container.status != BaselineStatus::Changed
+                || [
+                    container.delta_cpu_request,
+                    container.delta_memory_request,
+                    container.delta_cpu_usage,
+                ]
+                .into_iter()
+                .any(|delta| delta.is_some_and(|delta| delta != 0))
+        });
+
+        let sum = |field: fn(&ContainerDelta) -> Option<i64>| {
+            containers.iter().filter_map(field).sum::<i64>()
+        };
+
+        BaselineDiff {
+            delta_cpu_request: sum(|container| container.delta_cpu_request),
+            delta_memory_request: sum(|container| container.delta_memory_request),
+            delta_cpu_usage: sum(|container| container.delta_cpu_usage),
+            containers,
+        }
+    }
+}
+
+impl BaselineResources {
+    /// Amounts of a container that did not exist yet.
+    fn zero() -> Self {
+        let zero = BaselinePair {
+            cpu_milliseconds: Some(0),
+            memory_bytes: Some(0),
+        };
+
+        Self {
+            requests: zero.clone(),
+            usage: zero,
+        }
+    }
+}
+
+/// Difference between two amounts, `None` if one of them is missing or does
+/// not fit.
+fn delta(current: Option<u64>, baseline: Option<u64>) -> Option<i64> {
+    let current = i64::try_from(current?).ok()?;
+    let baseline = i64::try_from(baseline?).ok()?;
+
+    current.checked_sub(baseline)
+}
+
+impl WasteSummary {
+    #[allow(clippy::cast_precision_loss)]
+    fn new(pods: &BTreeSet<PodOutput>) -> Self {
+        let (cpu_milliseconds, memory_bytes) =
+            pods.iter()
+                .fold((0, 0), |(cpu_milliseconds, memory_bytes), pod| {
+                    let resources = &pod.resources;
+
+                    (
+                        cpu_milliseconds
+                            + unused(
+                                resources.requests.cpu_milliseconds,
+                                resources.usage.cpu_milliseconds,
+                            ),
+                        memory_bytes
+                            + unused(
+                                resources.requests.memory_bytes,
+                                resources.usage.memory_bytes,
+                            ),
+                    )
+                });
+
+        Self {
+            wasted_cpu_core_hours_per_month: round(
+                cpu_milliseconds as f64 / 1000.0 * HOURS_PER_MONTH,
+            ),
+            wasted_memory_gib_hours_per_month: round(
+                memory_bytes as f64 / f64::from(1 << 30) * HOURS_PER_MONTH,
+            ),
+        }
+    }
+}
+
+impl Prices {
+    /// Prices from the config file with the ones given on the command line
+    /// taking precedence.
+    pub fn merge(
+        config: Option<&Prices>,
+        cpu_per_core_hour: Option<f64>,
+        memory_per_gib_hour: Option<f64>,
+        currency: Option<String>,
+    ) -> Self {
+        let config = config.cloned().unwrap_or_default();
+
+        Self {
+            cpu_per_core_hour: cpu_per_core_hour.unwrap_or(config.cpu_per_core_hour),
+            memory_per_gib_hour: memory_per_gib_hour.unwrap_or(config.memory_per_gib_hour),
+            currency: currency.or(config.currency),
+        }
+    }
+
+    /// Whether no price is set, the cost would always be zero.
+    pub fn is_empty(&self) -> bool {
+        self.cpu_per_core_hour == 0.0 && self.memory_per_gib_hour == 0.0
+    }
+
+    /// Cost of the containers for a month.
+    fn cost<'a>(&self, pods: impl Iterator<Item = &'a PodOutput>) -> Cost {
+        let mut requests = (0, 0);
+        let mut usage = (0, 0);
+        let mut slack = (0, 0);
+
+        for pod in pods {
+            let resources = &pod.resources;
+
+            requests.0 += resources.requests.cpu_milliseconds.unwrap_or_default();
+            requests.1 += resources.requests.memory_bytes.unwrap_or_default();
+
+            usage.0 += resources.usage.cpu_milliseconds.unwrap_or_default();
+            usage.1 += resources.usage.memory_bytes.unwrap_or_default();
+
+            slack.0 += unused(
+                resources.requests.cpu_milliseconds,
+                resources.usage.cpu_milliseconds,
+            );
+            slack.1 += unused(
+                resources.requests.memory_bytes,
+                resources.usage.memory_bytes,
+            );
+        }
+
+        Cost {
+            currency: self.currency.clone(),
+            requests_per_month: self.per_month(requests.0, requests.1),
+            usage_per_month: self.per_month(usage.0, usage.1),
+            slack_per_month: self.per_month(slack.0, slack.1),
+        }
+    }
+
+    /// Cost of the cpu in milliseconds and memory in bytes for a month,
+    /// rounded to two decimals.
+    #[allow(clippy::cast_precision_loss)]
+    fn per_month(&self, cpu_milliseconds: u64, memory_bytes: u64) -> f64 {
+        let cores = cpu_milliseconds as f64 / 1000.0;
+        let gib = memory_bytes as f64 / f64::from(1 << 30);
+
+        round((cores * self.cpu_per_core_hour + gib * self.memory_per_gib_hour) * HOURS_PER_MONTH)
+    }
+}
+
+/// Requested amount that is not used, zero if either is unknown or the usage
+/// is higher.
+fn unused(requests: Option<u64>, usage: Option<u64>) -> u64 {
+    match (requests, usage) {
+        (Some(requests), Some(usage)) => requests.saturating_sub(usage),
+        _ => 0,
+    }
+}
+
+/// Round to two decimals.
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+impl Finding for PodOutput {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
 
-    println!("{out}");
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
 
-    Ok(())
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+impl Report for Output {
+    type Finding = PodOutput;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.pods.iter().collect()
+    }
+
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        self.pods = std::mem::take(&mut self.pods)
+            .into_iter()
+            .filter_map(|mut pod| keep(&mut pod).then_some(pod))
+            .collect();
+    }
+
+    fn notes(&self) -> Vec<String> {
+        if self.usage_skipped {
+            vec!["usage was skipped with --no-usage, usage values are missing".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
 }
 
 impl std::ops::AddAssign<&PodOutput> for TotalNamespace {
@@ -221,10 +1163,48 @@ impl std::ops::AddAssign<&PodOutput> for TotalOwner {
     }
 }
 
-fn pod_to_output(pod: Pod) -> Result<Vec<PodOutput>> {
-    let owner = get_pod_owner(&pod);
+impl std::ops::AddAssign<&PodOutput> for TotalLabel {
+    fn add_assign(&mut self, rhs: &PodOutput) {
+        let new = &self.resources + &rhs.resources;
+
+        self.resources = new;
+        self.count += 1;
+    }
+}
+
+/// Summed requests and names of the pods of every owner. The projections of
+/// an owner describe all of its pods, so they are computed before the filters
+/// drop containers of some of them.
+fn owner_requests(pods: &[PodOutput]) -> BTreeMap<Owner, (ResourcePair, BTreeSet<String>)> {
+    pods.iter().fold(BTreeMap::new(), |mut owners, pod| {
+        if let Some(owner) = &pod.owner {
+            let (requests, pod_names) = owners.entry(owner.clone()).or_default();
+
+            *requests = &*requests + &pod.resources.requests;
+            pod_names.insert(pod.pod_name.clone());
+        }
+
+        owners
+    })
+}
+
+impl PodOutput {
+    /// Value of the label of the pod, `None` if the pod does not have it.
+    fn label_value(&self, label: &str) -> Option<&str> {
+        self.pod_labels
+            .as_ref()
+            .and_then(|labels| labels.get(label))
+            .map(String::as_str)
+    }
+}
+
+fn pod_to_output(source: &dyn Source, pod: Pod, details: &DetailOptions) -> Result<Vec<PodOutput>> {
+    let owner = source.pod_owner(&pod);
+    let details = PodDetails::new(source, &pod, details);
+    let phase = PodPhase::of(&pod).map_or_else(String::new, |phase| phase.as_str().to_string());
 
     let metadata = pod.metadata;
+    let labels = metadata.labels;
     let name = metadata.name.expect("missing pod name");
     let namespace = metadata.namespace.expect("missing pod name");
     let spec = pod.spec.expect("missing pod spec");
@@ -233,8 +1213,17 @@ fn pod_to_output(pod: Pod) -> Result<Vec<PodOutput>> {
     containers
         .into_iter()
         .filter(|container| container.resources.is_some())
+        .filter(|container| source.checks_container(&container.name))
         .map(move |container| {
-            generate_pod_output(name.clone(), namespace.clone(), owner.clone(), container)
+            generate_pod_output(
+                name.clone(),
+                namespace.clone(),
+                owner.clone(),
+                phase.clone(),
+                labels.clone(),
+                details.clone(),
+                container,
+            )
         })
         .collect()
 }
@@ -243,6 +1232,9 @@ fn generate_pod_output(
     pod_name: String,
     namespace: String,
     owner: Option<Owner>,
+    phase: String,
+    pod_labels: Option<BTreeMap<String, String>>,
+    details: PodDetails,
     container: Container,
 ) -> Result<PodOutput> {
     let requests_cpu = container
@@ -301,6 +1293,12 @@ fn generate_pod_output(
         pod_name,
         container_name: container.name,
         owner,
+        phase,
+        pod_labels,
+        missing_cpu_limit: limits_cpu.is_none(),
+        missing_memory_limit: limits_memory.is_none(),
+        severity: Severity::Info,
+        details,
 
         resources: Resources {
             limits: ResourcePair {
@@ -339,6 +1337,8 @@ fn generate_pod_output(
                     memory_bytes: None,
                 },
             },
+
+            limits_utilization: Utilization::default(),
         },
     })
 }
@@ -352,6 +1352,10 @@ impl std::ops::Add<&Resources> for &Resources {
             requests: &self.requests + &rhs.requests,
             limits: &self.limits + &rhs.limits,
             difference: &self.difference + &rhs.difference,
+            limits_utilization: Utilization::new(
+                &(&self.usage + &rhs.usage),
+                &(&self.limits + &rhs.limits),
+            ),
         }
     }
 }
@@ -363,6 +1367,7 @@ impl Resources {
 
         self.difference.requests = &self.requests - &self.usage;
         self.difference.limits = &self.limits - &self.usage;
+        self.limits_utilization = Utilization::new(&self.usage, &self.limits);
 
         self
     }
@@ -373,9 +1378,123 @@ impl Resources {
 
         self.difference.requests = &self.requests - &self.usage;
         self.difference.limits = &self.limits - &self.usage;
+        self.limits_utilization = Utilization::new(&self.usage, &self.limits);
 
         self
     }
+
+    fn near_cpu_limit(&self) -> bool {
+        self.limits_utilization
+            .cpu_pct
+            .is_some_and(|pct| pct > NEAR_LIMIT_PCT)
+    }
+}
+
+impl Utilization {
+    fn new(usage: &ResourcePair, total: &ResourcePair) -> Self {
+        Self {
+            cpu_pct: percent(usage.cpu_milliseconds, total.cpu_milliseconds),
+            memory_pct: percent(usage.memory_bytes, total.memory_bytes),
+        }
+    }
+}
+
+fn percent(part: Option<u64>, total: Option<u64>) -> Option<u64> {
+    match (part, total) {
+        (Some(part), Some(total)) if total > 0 => Some(part * 100 / total),
+        _ => None,
+    }
+}
+
+impl ResourcesStats {
+    fn new(resources: &[&Resources]) -> Self {
+        let usage = resources.iter().map(|resources| &resources.usage);
+        let requests = resources.iter().map(|resources| &resources.requests);
+        let limits = resources.iter().map(|resources| &resources.limits);
+
+        Self {
+            usage: ResourceStats::new(&usage.collect::<Vec<_>>()),
+            requests: ResourceStats::new(&requests.collect::<Vec<_>>()),
+            limits: ResourceStats::new(&limits.collect::<Vec<_>>()),
+        }
+    }
+}
+
+impl ResourceStats {
+    fn new(pairs: &[&ResourcePair]) -> Self {
+        let sum = pairs
+            .iter()
+            .fold(ResourcePair::default(), |sum, pair| &sum + pair);
+
+        let [cpu_min, cpu_max, cpu_p50] = field_stats(pairs.iter().map(|pair| pair.cpu));
+        let [cpu_milliseconds_min, cpu_milliseconds_max, cpu_milliseconds_p50] =
+            field_stats(pairs.iter().map(|pair| pair.cpu_milliseconds));
+        let [memory_min, memory_max, memory_p50] =
+            field_stats(pairs.iter().map(|pair| pair.memory));
+        let [memory_bytes_min, memory_bytes_max, memory_bytes_p50] =
+            field_stats(pairs.iter().map(|pair| pair.memory_bytes));
+
+        Self {
+            sum,
+
+            min: ResourcePair {
+                cpu: cpu_min,
+                cpu_milliseconds: cpu_milliseconds_min,
+                memory: memory_min,
+                memory_bytes: memory_bytes_min,
+            },
+
+            max: ResourcePair {
+                cpu: cpu_max,
+                cpu_milliseconds: cpu_milliseconds_max,
+                memory: memory_max,
+                memory_bytes: memory_bytes_max,
+            },
+
+            p50: ResourcePair {
+                cpu: cpu_p50,
+                cpu_milliseconds: cpu_milliseconds_p50,
+                memory: memory_p50,
+                memory_bytes: memory_bytes_p50,
+            },
+
+            count: pairs.len(),
+        }
+    }
+}
+
+/// Returns the minimum, maximum and median of the given values ignoring
+/// missing ones. For an even number of values the upper median is used.
+fn field_stats<T: Ord + Copy>(values: impl Iterator<Item = Option<T>>) -> [Option<T>; 3] {
+    let mut values = values.flatten().collect::<Vec<_>>();
+    values.sort_unstable();
+
+    [
+        values.first().copied(),
+        values.last().copied(),
+        values.get(values.len() / 2).copied(),
+    ]
+}
+
+impl ResourcePair {
+    /// Amounts multiplied by `factor` and divided by `divisor`, `None` if the
+    /// divisor is zero.
+    fn scaled(&self, factor: u64, divisor: u64) -> Option<Self> {
+        if divisor == 0 {
+            return None;
+        }
+
+        let scale = |amount: u64| amount.saturating_mul(factor) / divisor;
+        let cpu_milliseconds = self.cpu_milliseconds.map(scale);
+        let memory_bytes = self.memory_bytes.map(scale);
+
+        Some(Self {
+            cpu: cpu_milliseconds.map(Cpu::from),
+            cpu_milliseconds,
+            memory: memory_bytes.map(Memory::from),
+            memory_bytes,
+        })
+    }
 }
 
 impl std::ops::Add<&ResourcePair> for &ResourcePair {
@@ -435,3 +1554,1179 @@ fn add_option<T: std::ops::Add<Output = T>>(lhs: Option<T>, rhs: Option<T>) -> O
         (Some(r), Some(l)) => Some(l + r),
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use super::{ResourcePair, ResourceStats};
+    use crate::{
+        commands::{DetailOptions, PodPhase, Report, Severity},
+        source::FileSource,
+    };
+
+    fn pair(cpu: u64, memory: Option<u64>) -> ResourcePair {
+        ResourcePair {
+            cpu: Some(cpu.into()),
+            cpu_milliseconds: Some(cpu),
+            memory: None,
+            memory_bytes: memory,
+        }
+    }
+
+    #[test]
+    fn resource_stats() {
+        let pairs = [pair(300, Some(10)), pair(100, None), pair(200, Some(30))];
+        let pairs = pairs.iter().collect::<Vec<_>>();
+
+        let stats = ResourceStats::new(&pairs);
+
+        assert_eq!(3, stats.count);
+        assert_eq!(Some(600), stats.sum.cpu_milliseconds);
+        assert_eq!(Some(100), stats.min.cpu_milliseconds);
+        assert_eq!(Some(300), stats.max.cpu_milliseconds);
+        assert_eq!(Some(200), stats.p50.cpu_milliseconds);
+
+        assert_eq!(Some(40), stats.sum.memory_bytes);
+        assert_eq!(Some(10), stats.min.memory_bytes);
+        assert_eq!(Some(30), stats.max.memory_bytes);
+        assert_eq!(Some(30), stats.p50.memory_bytes);
+        assert_eq!(None, stats.p50.memory);
+    }
+
+    #[test]
+    fn resource_stats_empty() {
+        let stats = ResourceStats::new(&[]);
+
+        assert_eq!(0, stats.count);
+        assert_eq!(ResourcePair::default(), stats.min);
+        assert_eq!(ResourcePair::default(), stats.p50);
+    }
+
+    fn source() -> FileSource {
+        let pods = (0..16)
+            .map(|i| {
+                json!({
+                    "metadata": {
+                        "name": format!("app-{i}-abcde"),
+                        "namespace": format!("namespace-{}", i % 8),
+                        "ownerReferences": [{
+                            "apiVersion": "apps/v1",
+                            "kind": if i % 2 == 0 { "ReplicaSet" } else { "StatefulSet" },
+                            "name": format!("app-{i}"),
+                            "uid": format!("{i}"),
+                            "controller": true,
+                        }],
+                    },
+                    "spec": {
+                        "containers": [{
+                            "name": "app",
+                            "resources": { "requests": { "cpu": "100m", "memory": "64Mi" } },
+                        }],
+                    },
+                    "status": { "phase": "Running" },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let pods = serde_json::to_vec(&json!({ "items": pods })).unwrap();
+
+        FileSource::from_readers(pods.as_slice(), None::<&[u8]>).unwrap()
+    }
+
+    #[tokio::test]
+    async fn totals_deterministic() {
+        let source = source();
+
+        let mut outputs = Vec::new();
+        for _ in 0..2 {
+            let output = super::resource_requests(
+                &source,
+                Vec::new(),
+                true,
+                None,
+                false,
+                DetailOptions::default(),
+                true,
+                false,
+                false,
+                &[PodPhase::Running],
+                false,
+                None,
+                None,
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+
+            let mut output = serde_json::to_value(&output).unwrap();
+            // The generation time is the only field expected to change between runs.
+            output.as_object_mut().unwrap().remove("generated_at");
+            outputs.push(output);
+        }
+
+        assert_eq!(outputs[0], outputs[1]);
+
+        let output = &outputs[0];
+
+        let namespaces = output["total"]["namespaces"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|total| total["namespace"].as_str().unwrap())
+            .collect::<Vec<_>>();
+
+        let mut sorted = namespaces.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, namespaces);
+        assert_eq!(8, namespaces.len());
+
+        let owners = output["total"]["owners"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|total| {
+                (
+                    total["owner"]["kind"].as_str().unwrap(),
+                    total["owner"]["name"].as_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut sorted = owners.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, owners);
+        assert_eq!(16, owners.len());
+    }
+
+    #[tokio::test]
+    async fn unscheduled_pod_usage() {
+        let pod = |namespace: &str, name: &str, node_name: Option<&str>| {
+            json!({
+                "metadata": { "name": name, "namespace": namespace },
+                "spec": {
+                    "nodeName": node_name,
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": "100m" } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let metrics = |namespace: &str, name: &str| {
+            json!({
+                "metadata": { "name": name, "namespace": namespace },
+                "timestamp": "2024-01-01T00:00:00Z",
+                "window": "30s",
+                "containers": [{ "name": "app", "usage": { "cpu": "50m", "memory": "1Mi" } }],
+            })
+        };
+
+        // The unscheduled pod in other has the same name as a scheduled one in
+        // test, its missing node name must not hide the usage of the other.
+        let pods = json!({
+            "items": [
+                pod("test", "scheduled", Some("node-a")),
+                pod("test", "unscheduled", None),
+                pod("other", "scheduled", None),
+            ],
+        });
+        let metrics = json!({
+            "items": [
+                metrics("test", "scheduled"),
+                metrics("test", "unscheduled"),
+                metrics("other", "scheduled"),
+            ],
+        });
+
+        let pods = serde_json::to_vec(&pods).unwrap();
+        let metrics = serde_json::to_vec(&metrics).unwrap();
+
+        let source = FileSource::from_readers(pods.as_slice(), Some(metrics.as_slice())).unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            false,
+            &[PodPhase::Running],
+            false,
+            None,
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let usage = output
+            .pods
+            .iter()
+            .map(|pod| {
+                (
+                    pod.namespace.as_str(),
+                    pod.pod_name.as_str(),
+                    pod.resources.usage.cpu_milliseconds,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("other", "scheduled", None),
+                ("test", "scheduled", Some(50)),
+                ("test", "unscheduled", None),
+            ],
+            usage
+        );
+    }
+
+    #[tokio::test]
+    async fn same_named_pods_in_phases() {
+        let pod = |namespace: &str, phase: &str| {
+            json!({
+                "metadata": { "name": "web-0", "namespace": namespace },
+                "spec": {
+                    "nodeName": "node-a",
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": "100m" } },
+                    }],
+                },
+                "status": { "phase": phase },
+            })
+        };
+
+        let pods = json!({ "items": [pod("ns-a", "Running"), pod("ns-b", "Pending")] });
+        let metrics = json!({
+            "items": [{
+                "metadata": { "name": "web-0", "namespace": "ns-a" },
+                "timestamp": "2024-01-01T00:00:00Z",
+                "window": "30s",
+                "containers": [{ "name": "app", "usage": { "cpu": "50m", "memory": "1Mi" } }],
+            }],
+        });
+
+        let pods = serde_json::to_vec(&pods).unwrap();
+        let metrics = serde_json::to_vec(&metrics).unwrap();
+
+        let source = FileSource::from_readers(pods.as_slice(), Some(metrics.as_slice())).unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            false,
+            &[PodPhase::Running, PodPhase::Pending],
+            false,
+            None,
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let usage = output
+            .pods
+            .iter()
+            .map(|pod| (pod.namespace.as_str(), pod.resources.usage.cpu_milliseconds))
+            .collect::<Vec<_>>();
+
+        // The pending pod must not hide the usage of the running one.
+        assert_eq!(vec![("ns-a", Some(50)), ("ns-b", None)], usage);
+    }
+
+    #[tokio::test]
+    async fn no_usage() {
+        const PODS: &str = include_str!("../../resources/fixtures/pods.json");
+        const METRICS: &str = include_str!("../../resources/fixtures/metrics.json");
+
+        let source = FileSource::from_readers(PODS.as_bytes(), Some(METRICS.as_bytes())).unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            false,
+            &[PodPhase::Running],
+            true,
+            None,
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(!output.pods.is_empty());
+        assert!(output
+            .pods
+            .iter()
+            .all(|pod| pod.resources.usage.cpu.is_none()
+                && pod.resources.difference.requests.cpu.is_none()));
+        assert_eq!(
+            vec!["usage was skipped with --no-usage, usage values are missing".to_string()],
+            output.notes()
+        );
+    }
+
+    #[tokio::test]
+    async fn near_limits() {
+        let pod = |name: &str| {
+            json!({
+                "metadata": { "name": name, "namespace": "test" },
+                "spec": {
+                    "nodeName": "node-a",
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "limits": { "cpu": "100m", "memory": "4Mi" } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let metrics = |name: &str, cpu: &str| {
+            json!({
+                "metadata": { "name": name, "namespace": "test" },
+                "timestamp": "2024-01-01T00:00:00Z",
+                "window": "30s",
+                "containers": [{ "name": "app", "usage": { "cpu": cpu, "memory": "1Mi" } }],
+            })
+        };
+
+        let pods = json!({ "items": [pod("busy"), pod("idle")] });
+        let metrics = json!({ "items": [metrics("busy", "95m"), metrics("idle", "50m")] });
+
+        let pods = serde_json::to_vec(&pods).unwrap();
+        let metrics = serde_json::to_vec(&metrics).unwrap();
+
+        let source = FileSource::from_readers(pods.as_slice(), Some(metrics.as_slice())).unwrap();
+
+        for (near_limits, expected) in [
+            (
+                false,
+                vec![
+                    ("busy", Some(95), Some(25), Severity::Medium),
+                    ("idle", Some(50), Some(25), Severity::Info),
+                ],
+            ),
+            (true, vec![("busy", Some(95), Some(25), Severity::Medium)]),
+        ] {
+            let output = super::resource_requests(
+                &source,
+                Vec::new(),
+                true,
+                None,
+                false,
+                DetailOptions::default(),
+                false,
+                near_limits,
+                false,
+                &[PodPhase::Running],
+                false,
+                None,
+                None,
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+
+            let utilization = output
+                .pods
+                .iter()
+                .map(|pod| {
+                    (
+                        pod.pod_name.as_str(),
+                        pod.resources.limits_utilization.cpu_pct,
+                        pod.resources.limits_utilization.memory_pct,
+                        pod.severity,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            assert_eq!(expected, utilization);
+        }
+    }
+
+    #[tokio::test]
+    async fn no_limits_only() {
+        let pod = |name: &str, limits: serde_json::Value| {
+            json!({
+                "metadata": { "name": name, "namespace": "test" },
+                "spec": {
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": "10m" }, "limits": limits },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("limited", json!({ "cpu": "100m", "memory": "64Mi" })),
+                pod("no-cpu", json!({ "memory": "64Mi" })),
+                pod("no-memory", json!({ "cpu": "100m" })),
+                pod("unlimited", json!({})),
+            ],
+        });
+
+        let pods = serde_json::to_vec(&pods).unwrap();
+        let source = FileSource::from_readers(pods.as_slice(), None::<&[u8]>).unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            true,
+            &[PodPhase::Running],
+            true,
+            None,
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let pods = output
+            .pods
+            .iter()
+            .map(|pod| {
+                (
+                    pod.pod_name.as_str(),
+                    pod.missing_cpu_limit,
+                    pod.missing_memory_limit,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("no-cpu", true, false),
+                ("no-memory", false, true),
+                ("unlimited", true, true),
+            ],
+            pods
+        );
+    }
+
+    #[tokio::test]
+    async fn owners_in_different_namespaces() {
+        // Same owner name and uid in both namespaces, for example after
+        // restoring a backup of a namespace into another one.
+        let pod = |namespace: &str| {
+            json!({
+                "metadata": {
+                    "name": "api-abcde",
+                    "namespace": namespace,
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "ReplicaSet",
+                        "name": "api",
+                        "uid": "1234",
+                        "controller": true,
+                    }],
+                },
+                "spec": {
+                    "containers": [{
+                        "name": "api",
+                        "resources": { "requests": { "cpu": "100m" } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let pods = json!({ "items": [pod("staging"), pod("production")] });
+        let pods = serde_json::to_vec(&pods).unwrap();
+
+        let source = FileSource::from_readers(pods.as_slice(), None::<&[u8]>).unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            false,
+            &[PodPhase::Running],
+            false,
+            None,
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let owners = output
+            .total
+            .owners
+            .iter()
+            .map(|total| {
+                (
+                    total.owner.namespace.as_str(),
+                    total.owner.name.as_str(),
+                    total.count,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![("production", "api", 1), ("staging", "api", 1)],
+            owners
+        );
+    }
+
+    #[tokio::test]
+    async fn same_named_deployments() {
+        // The same chart installed into two tenant namespaces.
+        let pod = |namespace: &str, name: &str| {
+            json!({
+                "metadata": {
+                    "name": name,
+                    "namespace": namespace,
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "Deployment",
+                        "name": "ingress-nginx",
+                        "uid": format!("{namespace}-ingress-nginx"),
+                        "controller": true,
+                    }],
+                },
+                "spec": {
+                    "containers": [{
+                        "name": "controller",
+                        "resources": { "requests": { "cpu": "100m" } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("tenant-a", "ingress-nginx-a"),
+                pod("tenant-a", "ingress-nginx-b"),
+                pod("tenant-b", "ingress-nginx-c"),
+            ],
+        });
+        let pods = serde_json::to_vec(&pods).unwrap();
+
+        let deployment = |namespace: &str, replicas: i32| {
+            json!({
+                "metadata": { "namespace": namespace, "name": "ingress-nginx" },
+                "spec": { "replicas": replicas, "selector": {}, "template": {} },
+            })
+        };
+
+        let deployments = json!({
+            "items": [deployment("tenant-a", 2), deployment("tenant-b", 1)],
+        });
+
+        let source = FileSource::from_readers(pods.as_slice(), None::<&[u8]>)
+            .unwrap()
+            .with_deployments(deployments.to_string().as_bytes())
+            .unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            true,
+            false,
+            false,
+            &[PodPhase::Running],
+            false,
+            None,
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let owners = serde_json::to_value(output.total.owners).unwrap();
+        let owners = owners
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|total| {
+                (
+                    total["owner"]["namespace"].as_str().unwrap(),
+                    total["owner"]["name"].as_str().unwrap(),
+                    total["count"].as_u64().unwrap(),
+                    total["replicas"].as_i64(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("tenant-a", "ingress-nginx", 2, Some(2)),
+                ("tenant-b", "ingress-nginx", 1, Some(1)),
+            ],
+            owners
+        );
+    }
+
+    #[tokio::test]
+    #[allow(clippy::too_many_lines)]
+    async fn owner_replicas() {
+        let pod = |name: &str, kind: &str, owner: &str| {
+            json!({
+                "metadata": {
+                    "name": name,
+                    "namespace": "web",
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": kind,
+                        "name": owner,
+                        "uid": owner,
+                        "controller": true,
+                    }],
+                },
+                "spec": {
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": "500m", "memory": "256Mi" } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("api-1-a", "ReplicaSet", "api-1"),
+                pod("api-1-b", "ReplicaSet", "api-1"),
+                pod("db-0", "StatefulSet", "db"),
+            ],
+        });
+
+        let replica_sets = json!({
+            "items": [{
+                "metadata": {
+                    "namespace": "web",
+                    "name": "api-1",
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "Deployment",
+                        "name": "api",
+                        "uid": "api",
+                        "controller": true,
+                    }],
+                },
+                "status": { "replicas": 3 },
+            }],
+        });
+
+        let autoscalers = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api" },
+                "spec": {
+                    "scaleTargetRef": { "apiVersion": "apps/v1", "kind": "Deployment", "name": "api" },
+                    "minReplicas": 2,
+                    "maxReplicas": 8,
+                },
+            }],
+        });
+
+        // Without a statefulset list the replicas of `db` are skipped.
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_replica_sets(replica_sets.to_string().as_bytes())
+            .unwrap()
+            .with_horizontal_pod_autoscalers(autoscalers.to_string().as_bytes())
+            .unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            true,
+            false,
+            false,
+            &[PodPhase::Running],
+            true,
+            None,
+            None,
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let owners = output
+            .total
+            .owners
+            .iter()
+            .map(|total| {
+                (
+                    total.owner.name.as_str(),
+                    total.replicas,
+                    total
+                        .per_replica_requests
+                        .as_ref()
+                        .and_then(|requests| requests.cpu_milliseconds),
+                    total.hpa_max_replicas,
+                    total
+                        .projected_requests
+                        .as_ref()
+                        .and_then(|requests| requests.memory_bytes),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "api-1",
+                    Some(3),
+                    Some(500),
+                    Some(8),
+                    Some(8 * 256 * 1024 * 1024)
+                ),
+                ("db", None, Some(500), None, None),
+            ],
+            owners
+        );
+    }
+
+    #[tokio::test]
+    async fn owner_projections_unfiltered() {
+        let pod = |name: &str, cpu: &str, limits: serde_json::Value| {
+            json!({
+                "metadata": {
+                    "name": name,
+                    "namespace": "web",
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "ReplicaSet",
+                        "name": "api",
+                        "uid": "api",
+                        "controller": true,
+                    }],
+                },
+                "spec": {
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": cpu }, "limits": limits },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("api-a", "500m", json!({})),
+                pod("api-b", "300m", json!({ "cpu": "1", "memory": "64Mi" })),
+            ],
+        });
+
+        let pods = serde_json::to_vec(&pods).unwrap();
+        let source = FileSource::from_readers(pods.as_slice(), None::<&[u8]>).unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            true,
+            false,
+            true,
+            &[PodPhase::Running],
+            true,
+            None,
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(1, output.pods.len());
+
+        // The filtered out pod still counts as a replica of the owner.
+        let owner = &output.total.owners[0];
+        assert_eq!(Some(500), owner.resources.requests.cpu_milliseconds);
+        assert_eq!(
+            Some(400),
+            owner
+                .per_replica_requests
+                .as_ref()
+                .and_then(|requests| requests.cpu_milliseconds)
+        );
+    }
+
+    #[tokio::test]
+    async fn aggregate_by_label() {
+        let pod = |namespace: &str, name: &str, team: Option<&str>, cpu: &str| {
+            let labels = team.map(|team| json!({ "team": team }));
+
+            json!({
+                "metadata": { "name": name, "namespace": namespace, "labels": labels },
+                "spec": {
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": cpu } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("staging", "api", Some("payments"), "100m"),
+                pod("production", "api", Some("payments"), "300m"),
+                pod("production", "search", Some("discovery"), "200m"),
+                pod("production", "debug", None, "50m"),
+            ],
+        });
+
+        let pods = serde_json::to_vec(&pods).unwrap();
+        let source = FileSource::from_readers(pods.as_slice(), None::<&[u8]>).unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            false,
+            &[PodPhase::Running],
+            true,
+            None,
+            Some("team"),
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let labels = output
+            .total
+            .labels
+            .iter()
+            .map(|total| {
+                (
+                    total.value.as_deref(),
+                    total.count,
+                    total.resources.requests.cpu_milliseconds,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (None, 1, Some(50)),
+                (Some("discovery"), 1, Some(200)),
+                (Some("payments"), 2, Some(400)),
+            ],
+            labels
+        );
+    }
+
+    #[tokio::test]
+    async fn waste_summary() {
+        let pod = |name: &str| {
+            json!({
+                "metadata": { "name": name, "namespace": "test" },
+                "spec": {
+                    "nodeName": "node-a",
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": "500m", "memory": "2Gi" } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let metrics = |name: &str, cpu: &str, memory: &str| {
+            json!({
+                "metadata": { "name": name, "namespace": "test" },
+                "timestamp": "2024-01-01T00:00:00Z",
+                "window": "30s",
+                "containers": [{ "name": "app", "usage": { "cpu": cpu, "memory": memory } }],
+            })
+        };
+
+        // Usage above the request is not counted against the waste of the
+        // other containers and containers without usage are skipped.
+        let pods = json!({ "items": [pod("idle"), pod("busy"), pod("unknown")] });
+        let metrics = json!({
+            "items": [metrics("idle", "250m", "1Gi"), metrics("busy", "900m", "3Gi")],
+        });
+
+        let pods = serde_json::to_vec(&pods).unwrap();
+        let metrics = serde_json::to_vec(&metrics).unwrap();
+
+        let source = FileSource::from_readers(pods.as_slice(), Some(metrics.as_slice())).unwrap();
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            false,
+            &[PodPhase::Running],
+            false,
+            None,
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            super::WasteSummary {
+                wasted_cpu_core_hours_per_month: 180.0,
+                wasted_memory_gib_hours_per_month: 720.0,
+            },
+            output.waste_summary
+        );
+    }
+
+    #[tokio::test]
+    async fn cost() {
+        let pod = |name: &str| {
+            json!({
+                "metadata": {
+                    "name": name,
+                    "namespace": "test",
+                    "labels": { "team": "payments" },
+                },
+                "spec": {
+                    "nodeName": "node-a",
+                    "containers": [{
+                        "name": "app",
+                        "resources": { "requests": { "cpu": "500m", "memory": "2Gi" } },
+                    }],
+                },
+                "status": { "phase": "Running" },
+            })
+        };
+
+        let metrics = |name: &str, cpu: &str, memory: &str| {
+            json!({
+                "metadata": { "name": name, "namespace": "test" },
+                "timestamp": "2024-01-01T00:00:00Z",
+                "window": "30s",
+                "containers": [{ "name": "app", "usage": { "cpu": cpu, "memory": memory } }],
+            })
+        };
+
+        let pods = json!({ "items": [pod("idle"), pod("busy"), pod("unknown")] });
+        let metrics = json!({
+            "items": [metrics("idle", "250m", "1Gi"), metrics("busy", "900m", "3Gi")],
+        });
+
+        let pods = serde_json::to_vec(&pods).unwrap();
+        let metrics = serde_json::to_vec(&metrics).unwrap();
+
+        let source = FileSource::from_readers(pods.as_slice(), Some(metrics.as_slice())).unwrap();
+
+        let prices = super::Prices {
+            cpu_per_core_hour: 0.03,
+            memory_per_gib_hour: 0.004,
+            currency: Some("EUR".to_string()),
+        };
+
+        let output = super::resource_requests(
+            &source,
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            false,
+            &[PodPhase::Running],
+            false,
+            None,
+            Some("team"),
+            false,
+            Some(&prices),
+        )
+        .await
+        .unwrap();
+
+        // 1500m and 6Gi requested, 1150m and 4Gi used. Only the idle pod
+        // leaves 250m and 1Gi of its requests unused.
+        let expected = super::Cost {
+            currency: Some("EUR".to_string()),
+            requests_per_month: 49.68,
+            usage_per_month: 36.36,
+            slack_per_month: 8.28,
+        };
+
+        assert_eq!(Some(&expected), output.total.namespaces[0].cost.as_ref());
+        assert_eq!(Some(&expected), output.total.labels[0].cost.as_ref());
+    }
+
+    #[test]
+    fn prices() {
+        let prices = super::Prices {
+            cpu_per_core_hour: 0.05,
+            memory_per_gib_hour: 0.01,
+            currency: None,
+        };
+
+        // Rounded from 0.036 and 0.0035 per month.
+        assert!((0.04 - prices.per_month(1, 0)).abs() < f64::EPSILON);
+        assert!((0.0 - prices.per_month(0, 1 << 19)).abs() < f64::EPSILON);
+        assert!((7.2 - prices.per_month(0, 1 << 30)).abs() < f64::EPSILON);
+
+        let config = super::Prices {
+            cpu_per_core_hour: 0.03,
+            memory_per_gib_hour: 0.004,
+            currency: Some("EUR".to_string()),
+        };
+
+        assert_eq!(
+            super::Prices {
+                cpu_per_core_hour: 0.05,
+                memory_per_gib_hour: 0.004,
+                currency: Some("EUR".to_string()),
+            },
+            super::Prices::merge(Some(&config), Some(0.05), None, None)
+        );
+
+        assert!(super::Prices::merge(None, None, None, Some("EUR".to_string())).is_empty());
+    }
+
+    #[tokio::test]
+    async fn baseline_diff() {
+        let container = |namespace: &str, pod_name: &str, cpu: u64, memory: u64| {
+            json!({
+                "namespace": namespace,
+                "pod_name": pod_name,
+                "container_name": "app",
+                "resources": {
+                    "requests": { "cpu_milliseconds": cpu, "memory_bytes": memory },
+                    "usage": { "cpu_milliseconds": null, "memory_bytes": null },
+                },
+            })
+        };
+
+        // Saved with `--report-title` so the document is wrapped.
+        let baseline = json!({
+            "title": "Last week",
+            "data": {
+                "meta": {},
+                "report": {
+                    "pods": [
+                        container("namespace-0", "app-0-abcde", 50, 67_108_864),
+                        container("namespace-1", "app-1-abcde", 100, 67_108_864),
+                        container("namespace-0", "gone-abcde", 200, 1024),
+                    ],
+                },
+            },
+        });
+
+        let baseline = super::Baseline::from_reader(baseline.to_string().as_bytes()).unwrap();
+
+        let output = super::resource_requests(
+            &source(),
+            Vec::new(),
+            true,
+            None,
+            false,
+            DetailOptions::default(),
+            false,
+            false,
+            false,
+            &[PodPhase::Running],
+            false,
+            Some(&baseline),
+            None,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let diff = output.baseline_diff.unwrap();
+
+        let containers = diff
+            .containers
+            .iter()
+            .map(|container| {
+                (
+                    container.pod_name.as_str(),
+                    container.status,
+                    container.delta_cpu_request,
+                )
+            })
+            .filter(|(_, status, _)| *status != super::BaselineStatus::New)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("app-0-abcde", super::BaselineStatus::Changed, Some(50)),
+                ("gone-abcde", super::BaselineStatus::Removed, Some(-200)),
+            ],
+            containers
+        );
+
+        assert_eq!(16, diff.containers.len());
+        assert_eq!(50 + 14 * 100 - 200, diff.delta_cpu_request);
+        assert_eq!(14 * 67_108_864 - 1024, diff.delta_memory_request);
+        assert_eq!(0, diff.delta_cpu_usage);
+
+        let summary = json!({ "meta": {}, "report": { "total": 1 } });
+        assert!(super::Baseline::from_reader(summary.to_string().as_bytes()).is_err());
+    }
+}
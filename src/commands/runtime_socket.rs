@@ -0,0 +1,318 @@
+//! Find containers mounting the socket of the container runtime.
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Sockets of the container runtimes, access to any of them is root on the
+/// node.
+const RUNTIME_SOCKETS: &[&str] = &[
+    "/var/run/docker.sock",
+    "/run/containerd/containerd.sock",
+    "/var/run/crio/crio.sock",
+];
+
+/// Container with the socket of the container runtime mounted from the node.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+#[allow(clippy::module_name_repetitions)]
+pub struct RuntimeSocketMount {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    volume_name: String,
+
+    /// Path of the host path volume, the socket or a directory containing it.
+    host_path: String,
+
+    /// Socket of the container runtime that is reachable through the mount.
+    socket: String,
+
+    mount_path: String,
+
+    /// Read-only mounts still allow connecting to the socket.
+    read_only: bool,
+
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for RuntimeSocketMount {
+    const DEFAULT_SEVERITY: Severity = Severity::Critical;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get containers that mount a host path volume of the docker, containerd or
+/// cri-o socket or of a directory containing one. This is kept apart from other
+/// host path checks as it is effectively root on the node.
+pub async fn runtime_socket(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    details: DetailOptions,
+) -> Result<Vec<RuntimeSocketMount>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mounts = pods
+        .iter()
+        .map(|pod| runtime_socket_mounts(source, pod, &details))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    Ok(mounts)
+}
+
+fn runtime_socket_mounts(
+    source: &dyn Source,
+    pod: &Pod,
+    details: &DetailOptions,
+) -> Result<Vec<RuntimeSocketMount>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let sockets = spec
+        .volumes
+        .iter()
+        .flatten()
+        .filter_map(|volume| {
+            let host_path = &volume.host_path.as_ref()?.path;
+            let socket = exposed_socket(host_path)?;
+
+            Some((volume.name.as_str(), host_path, socket))
+        })
+        .collect::<Vec<_>>();
+
+    if sockets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mounts = spec
+        .init_containers
+        .iter()
+        .flatten()
+        .chain(&spec.containers)
+        .filter(|container| source.checks_container(&container.name))
+        .flat_map(|container| {
+            container
+                .volume_mounts
+                .iter()
+                .flatten()
+                .map(move |mount| (container, mount))
+        })
+        .filter_map(|(container, mount)| {
+            let (volume_name, host_path, socket) = sockets
+                .iter()
+                .find(|(volume_name, _, _)| *volume_name == mount.name)?;
+
+            Some(RuntimeSocketMount {
+                namespace: pod
+                    .metadata
+                    .namespace
+                    .as_ref()
+                    .expect("failed to get namespace")
+                    .clone(),
+
+                owner: source.pod_owner(pod),
+
+                pod_name: pod
+                    .metadata
+                    .name
+                    .as_ref()
+                    .expect("failed to get name")
+                    .clone(),
+
+                container_name: container.name.clone(),
+                volume_name: (*volume_name).to_string(),
+                host_path: (*host_path).clone(),
+                socket: (*socket).to_string(),
+                mount_path: mount.mount_path.clone(),
+                read_only: mount.read_only.unwrap_or(false),
+                severity: Severity::Critical,
+                details: PodDetails::new(source, pod, details),
+            })
+        })
+        .collect();
+
+    Ok(mounts)
+}
+
+/// Runtime socket reachable through the host path, either the socket itself or
+/// a directory it is in. `/var/run` is a link to `/run` on current
+/// distributions so both are treated the same.
+fn exposed_socket(host_path: &str) -> Option<&'static str> {
+    let normalize = |path: &str| {
+        let path = path.trim_end_matches('/');
+
+        match path.strip_prefix("/var/run") {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("/run{rest}"),
+            _ => path.to_string(),
+        }
+    };
+
+    let host_path = normalize(host_path);
+
+    RUNTIME_SOCKETS.iter().copied().find(|socket| {
+        let socket = normalize(socket);
+
+        socket == host_path
+            || socket
+                .strip_prefix(&host_path)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        commands::{DetailOptions, Severity},
+        source::FileSource,
+    };
+
+    #[test]
+    fn exposed_socket() {
+        assert_eq!(
+            Some("/var/run/docker.sock"),
+            super::exposed_socket("/var/run/docker.sock")
+        );
+        assert_eq!(
+            Some("/var/run/docker.sock"),
+            super::exposed_socket("/run/docker.sock")
+        );
+        assert_eq!(
+            Some("/run/containerd/containerd.sock"),
+            super::exposed_socket("/run/containerd/")
+        );
+        assert_eq!(
+            Some("/var/run/crio/crio.sock"),
+            super::exposed_socket("/var/run/crio/crio.sock")
+        );
+        assert_eq!(
+            Some("/var/run/docker.sock"),
+            super::exposed_socket("/var/run")
+        );
+        assert_eq!(None, super::exposed_socket("/var/log"));
+        assert_eq!(None, super::exposed_socket("/run/containerd-shim"));
+        assert_eq!(None, super::exposed_socket("/var/runtime"));
+    }
+
+    #[tokio::test]
+    async fn runtime_socket() {
+        let pod = |name: &str, path: &str, read_only: Option<bool>| {
+            json!({
+                "metadata": { "namespace": "ci", "name": name },
+                "spec": {
+                    "volumes": [
+                        { "name": "socket", "hostPath": { "path": path } },
+                        { "name": "cache", "emptyDir": {} },
+                    ],
+                    "containers": [
+                        {
+                            "name": "builder",
+                            "volumeMounts": [
+                                {
+                                    "name": "socket",
+                                    "mountPath": "/socket",
+                                    "readOnly": read_only,
+                                },
+                                { "name": "cache", "mountPath": "/cache" },
+                            ],
+                        },
+                        {
+                            "name": "uploader",
+                            "volumeMounts": [{ "name": "cache", "mountPath": "/cache" }],
+                        },
+                    ],
+                },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("docker", "/var/run/docker.sock", None),
+                pod("containerd", "/run/containerd/containerd.sock", Some(false)),
+                pod("crio", "/var/run/crio/crio.sock", Some(true)),
+                pod("logs", "/var/log", None),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let mounts = super::runtime_socket(&source, Vec::new(), true, DetailOptions::default())
+            .await
+            .unwrap();
+
+        let mounts = mounts
+            .iter()
+            .map(|mount| {
+                (
+                    mount.pod_name.as_str(),
+                    mount.container_name.as_str(),
+                    mount.socket.as_str(),
+                    mount.read_only,
+                    mount.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "docker",
+                    "builder",
+                    "/var/run/docker.sock",
+                    false,
+                    Severity::Critical
+                ),
+                (
+                    "containerd",
+                    "builder",
+                    "/run/containerd/containerd.sock",
+                    false,
+                    Severity::Critical
+                ),
+                (
+                    "crio",
+                    "builder",
+                    "/var/run/crio/crio.sock",
+                    true,
+                    Severity::Critical
+                ),
+            ],
+            mounts
+        );
+    }
+}
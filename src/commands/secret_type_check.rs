@@ -0,0 +1,336 @@
+//! Find secrets whose data does not fit their type.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use eyre::Result;
+use k8s_openapi::{
+    api::core::v1::Secret,
+    chrono::{DateTime, TimeZone, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use x509_parser::pem::Pem;
+
+use crate::{
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Annotation linking a service account token secret to its service account.
+const SERVICE_ACCOUNT_ANNOTATION: &str = "kubernetes.io/service-account.name";
+
+/// Secret that is empty, invalid or dangling for its type.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct SecretProblem {
+    namespace: String,
+    secret_name: String,
+    secret_type: String,
+    problem: String,
+
+    /// End of the validity of the certificate of tls secrets in RFC 3339
+    /// format.
+    not_after: Option<String>,
+
+    severity: Severity,
+}
+
+impl Finding for SecretProblem {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get `Opaque` secrets without data, `kubernetes.io/tls` secrets without a
+/// valid and current certificate or without a key and
+/// `kubernetes.io/service-account-token` secrets whose service account does not
+/// exist. Service accounts are only fetched if there are token secrets.
+pub async fn secret_type_check(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<SecretProblem>> {
+    let secrets = source.secrets(namespaces.clone(), all_namespaces).await?;
+
+    let service_accounts = if secrets
+        .iter()
+        .any(|secret| secret_type(secret) == "kubernetes.io/service-account-token")
+    {
+        source
+            .service_accounts(namespaces, all_namespaces)
+            .await?
+            .into_iter()
+            .filter_map(|account| Some((account.metadata.namespace?, account.metadata.name?)))
+            .collect()
+    } else {
+        BTreeSet::new()
+    };
+
+    let now = Utc::now();
+
+    Ok(secrets
+        .iter()
+        .flat_map(|secret| secret_problems(secret, &service_accounts, now))
+        .collect())
+}
+
+fn secret_type(secret: &Secret) -> &str {
+    secret.type_.as_deref().unwrap_or("Opaque")
+}
+
+fn secret_problems(
+    secret: &Secret,
+    service_accounts: &BTreeSet<(String, String)>,
+    now: DateTime<Utc>,
+) -> Vec<SecretProblem> {
+    let namespace = secret
+        .metadata
+        .namespace
+        .as_ref()
+        .expect("failed to get namespace");
+
+    let name = secret.metadata.name.as_ref().expect("failed to get name");
+
+    let data = |key: &str| {
+        secret
+            .data
+            .as_ref()
+            .and_then(|data| data.get(key))
+            .map(|value| value.0.as_slice())
+            .or_else(|| {
+                secret
+                    .string_data
+                    .as_ref()
+                    .and_then(|data| data.get(key))
+                    .map(String::as_bytes)
+            })
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut problems: Vec<(String, Option<String>, Severity)> = Vec::new();
+
+    match secret_type(secret) {
+        "Opaque" => {
+            let entries = secret.data.as_ref().map_or(0, BTreeMap::len)
+                + secret.string_data.as_ref().map_or(0, BTreeMap::len);
+
+            if entries == 0 {
+                problems.push(("secret has no data".to_string(), None, Severity::Low));
+            }
+        }
+
+        "kubernetes.io/tls" => {
+            match data("tls.crt") {
+                Some(crt) => problems.extend(certificate_problem(crt, now)),
+                None => problems.push(("secret has no tls.crt".to_string(), None, Severity::High)),
+            }
+
+            if data("tls.key").is_none() {
+                problems.push(("secret has no tls.key".to_string(), None, Severity::High));
+            }
+        }
+
+        "kubernetes.io/service-account-token" => {
+            let account = secret
+                .metadata
+                .annotations
+                .as_ref()
+                .and_then(|annotations| annotations.get(SERVICE_ACCOUNT_ANNOTATION));
+
+            match account {
+                Some(account) => {
+                    if !service_accounts.contains(&(namespace.clone(), account.clone())) {
+                        problems.push((
+                            format!("service account {account} does not exist"),
+                            None,
+                            Severity::Medium,
+                        ));
+                    }
+                }
+                None => problems.push((
+                    format!("secret has no {SERVICE_ACCOUNT_ANNOTATION} annotation"),
+                    None,
+                    Severity::Medium,
+                )),
+            }
+        }
+
+        _ => {}
+    }
+
+    problems
+        .into_iter()
+        .map(|(problem, not_after, severity)| SecretProblem {
+            namespace: namespace.clone(),
+            secret_name: name.clone(),
+            secret_type: secret_type(secret).to_string(),
+            problem,
+            not_after,
+            severity,
+        })
+        .collect()
+}
+
+/// Problem with the first certificate of the pem encoded chain together with
+/// the end of its validity, `None` if it is currently valid.
+fn certificate_problem(
+    crt: &[u8],
+    now: DateTime<Utc>,
+) -> Option<(String, Option<String>, Severity)> {
+    let invalid = |err: String| {
+        Some((
+            format!("tls.crt is not a valid certificate: {err}"),
+            None,
+            Severity::High,
+        ))
+    };
+
+    let pem = match Pem::iter_from_buffer(crt).next() {
+        Some(Ok(pem)) => pem,
+        Some(Err(err)) => return invalid(err.to_string()),
+        None => return invalid("no pem block found".to_string()),
+    };
+
+    let certificate = match pem.parse_x509() {
+        Ok(certificate) => certificate,
+        Err(err) => return invalid(err.to_string()),
+    };
+
+    let validity = certificate.validity();
+    let timestamp = |time: i64| Utc.timestamp_opt(time, 0).single();
+
+    let not_before = timestamp(validity.not_before.timestamp());
+    let not_after = timestamp(validity.not_after.timestamp());
+    let not_after_text = not_after.map(|not_after| not_after.to_rfc3339());
+
+    if not_after.is_some_and(|not_after| not_after < now) {
+        return Some((
+            "certificate expired".to_string(),
+            not_after_text,
+            Severity::High,
+        ));
+    }
+
+    if not_before.is_some_and(|not_before| not_before > now) {
+        return Some((
+            "certificate is not valid yet".to_string(),
+            not_after_text,
+            Severity::Medium,
+        ));
+    }
+
+    None
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use k8s_openapi::{
+        api::core::v1::Secret,
+        chrono::{TimeZone, Utc},
+    };
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    const SECRETS: &str = include_str!("../../resources/fixtures/secrets.json");
+
+    #[test]
+    fn secret_problems() {
+        let secrets: Vec<Secret> = serde_json::from_value(
+            serde_json::from_str::<serde_json::Value>(SECRETS).unwrap()["items"].clone(),
+        )
+        .unwrap();
+
+        let service_accounts = BTreeSet::from([("shop".to_string(), "builder".to_string())]);
+
+        let problems = |now| {
+            secrets
+                .iter()
+                .flat_map(|secret| super::secret_problems(secret, &service_accounts, now))
+                .map(|problem| (problem.secret_name, problem.problem, problem.severity))
+                .collect::<Vec<_>>()
+        };
+
+        let valid = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let problems_when_valid = problems(valid);
+
+        assert_eq!(4, problems_when_valid.len());
+        assert!(problems_when_valid[0]
+            .1
+            .starts_with("tls.crt is not a valid certificate"));
+        assert_eq!(
+            vec![
+                ("broken-tls".to_string(), Severity::High),
+                ("empty".to_string(), Severity::Low),
+                ("untyped".to_string(), Severity::Low),
+                ("deleted-token".to_string(), Severity::Medium),
+            ],
+            problems_when_valid
+                .iter()
+                .map(|(name, _, severity)| (name.clone(), *severity))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "service account deleted does not exist",
+            problems_when_valid[3].1
+        );
+
+        let expired = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        assert!(problems(expired).contains(&(
+            "shop-tls".to_string(),
+            "certificate expired".to_string(),
+            Severity::High
+        )));
+
+        let early = Utc.with_ymd_and_hms(2023, 6, 1, 0, 0, 0).unwrap();
+        assert!(problems(early).contains(&(
+            "shop-tls".to_string(),
+            "certificate is not valid yet".to_string(),
+            Severity::Medium
+        )));
+    }
+
+    #[tokio::test]
+    async fn secret_type_check() {
+        let secrets = json!({
+            "items": [
+                { "metadata": { "namespace": "shop", "name": "empty" }, "type": "Opaque" },
+            ],
+        });
+
+        // Without token secrets no service accounts are needed.
+        let source = FileSource::from_readers(r#"{ "items": [] }"#.as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_secrets(secrets.to_string().as_bytes())
+            .unwrap();
+
+        let problems = super::secret_type_check(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        assert_eq!(1, problems.len());
+        assert_eq!("secret has no data", problems[0].problem);
+        assert_eq!(None, problems[0].not_after);
+
+        let source = FileSource::from_readers(r#"{ "items": [] }"#.as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_secrets(SECRETS.as_bytes())
+            .unwrap();
+
+        assert!(super::secret_type_check(&source, Vec::new(), true)
+            .await
+            .is_err());
+    }
+}
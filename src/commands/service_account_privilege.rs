@@ -0,0 +1,361 @@
+//! Find pods whose service account is bound to a privileged cluster role.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use eyre::Result;
+use k8s_openapi::api::rbac::v1::{ClusterRole, RoleRef, Subject};
+use log::warn;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Cluster roles that grant more than most workloads need.
+const PRIVILEGED_CLUSTER_ROLES: [&str; 3] = ["cluster-admin", "admin", "edit"];
+
+/// Pod whose service account is bound to a privileged cluster role.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ServiceAccountPrivilege {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    service_account: String,
+
+    /// `ClusterRoleBinding` for bindings in all namespaces or `RoleBinding`
+    /// for bindings in a single namespace.
+    binding_kind: String,
+
+    /// Name of the binding.
+    binding_name: String,
+
+    /// Namespace of role bindings, the service account can modify this
+    /// namespace which can differ from the namespace of the pod.
+    binding_namespace: Option<String>,
+
+    cluster_role: String,
+
+    /// Why the cluster role is privileged.
+    reason: String,
+
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ServiceAccountPrivilege {
+    const DEFAULT_SEVERITY: Severity = Severity::Critical;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Binding of a service account to a privileged cluster role.
+#[derive(Debug, Clone)]
+struct Binding {
+    kind: &'static str,
+    name: String,
+    namespace: Option<String>,
+    cluster_role: String,
+    reason: String,
+}
+
+/// Get the pods whose service account is bound to `cluster-admin`, `admin`,
+/// `edit` or a cluster role allowing all verbs, either with a cluster role
+/// binding or a role binding. Role bindings grant access to their own
+/// namespace, which can be another one than the namespace of the pod. Only
+/// role bindings of the selected namespaces are checked. Bindings for all
+/// namespaces are reported with a higher severity, `cluster-admin` for all
+/// namespaces with the highest. If the cluster roles can not be read only the
+/// well known roles are checked.
+pub async fn service_account_privilege(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    details: DetailOptions,
+) -> Result<Vec<ServiceAccountPrivilege>> {
+    let pods = source.pods(namespaces.clone(), all_namespaces).await?;
+    let role_bindings = source.role_bindings(namespaces, all_namespaces).await?;
+    let cluster_role_bindings = source.cluster_role_bindings().await?;
+
+    let wildcard_roles = match source.cluster_roles().await {
+        Ok(cluster_roles) => cluster_roles
+            .iter()
+            .filter(|cluster_role| allows_all_verbs(cluster_role))
+            .filter_map(|cluster_role| cluster_role.metadata.name.clone())
+            .collect(),
+
+        Err(err) => {
+            warn!("Failed to get cluster roles, only checking well known roles: {err:?}");
+            BTreeSet::new()
+        }
+    };
+
+    // Keyed by the namespace and name of the service account.
+    let mut privileged_bindings: BTreeMap<(String, String), Vec<Binding>> = BTreeMap::new();
+
+    for binding in &cluster_role_bindings {
+        let Some(reason) = privileged(&binding.role_ref, &wildcard_roles) else {
+            continue;
+        };
+
+        for (namespace, name) in service_accounts(binding.subjects.as_deref(), None) {
+            privileged_bindings
+                .entry((namespace, name))
+                .or_default()
+                .push(Binding {
+                    kind: "ClusterRoleBinding",
+                    name: binding.metadata.name.clone().unwrap_or_default(),
+                    namespace: None,
+                    cluster_role: binding.role_ref.name.clone(),
+                    reason: reason.clone(),
+                });
+        }
+    }
+
+    for binding in &role_bindings {
+        let Some(reason) = privileged(&binding.role_ref, &wildcard_roles) else {
+            continue;
+        };
+
+        let namespace = binding.metadata.namespace.as_deref();
+        for (service_account_namespace, name) in
+            service_accounts(binding.subjects.as_deref(), namespace)
+        {
+            privileged_bindings
+                .entry((service_account_namespace, name))
+                .or_default()
+                .push(Binding {
+                    kind: "RoleBinding",
+                    name: binding.metadata.name.clone().unwrap_or_default(),
+                    namespace: namespace.map(ToString::to_string),
+                    cluster_role: binding.role_ref.name.clone(),
+                    reason: reason.clone(),
+                });
+        }
+    }
+
+    let mut findings = Vec::new();
+    for pod in &pods {
+        let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+        let service_account = pod
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.service_account_name.clone())
+            .unwrap_or_else(|| "default".to_string());
+
+        let Some(account_bindings) = privileged_bindings.get(&(namespace.clone(), service_account.clone())) else {
+            continue;
+        };
+
+        for binding in account_bindings {
+            findings.push(ServiceAccountPrivilege {
+                namespace: namespace.clone(),
+                owner: source.pod_owner(pod),
+                pod_name: pod.metadata.name.clone().unwrap_or_default(),
+                service_account: service_account.clone(),
+                binding_kind: binding.kind.to_string(),
+                binding_name: binding.name.clone(),
+                binding_namespace: binding.namespace.clone(),
+                cluster_role: binding.cluster_role.clone(),
+                reason: binding.reason.clone(),
+                severity: match (binding.kind, binding.cluster_role.as_str()) {
+                    ("ClusterRoleBinding", "cluster-admin") => Severity::Critical,
+                    ("ClusterRoleBinding", _) => Severity::High,
+                    _ => Severity::Medium,
+                },
+                details: PodDetails::new(source, pod, &details),
+            });
+        }
+    }
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+/// Why the cluster role the binding refers to is privileged, `None` if it is
+/// not or the binding refers to a role.
+fn privileged(role_ref: &RoleRef, wildcard_roles: &BTreeSet<String>) -> Option<String> {
+    if role_ref.kind != "ClusterRole" {
+        return None;
+    }
+
+    if PRIVILEGED_CLUSTER_ROLES.contains(&role_ref.name.as_str()) {
+        return Some(format!("bound to {}", role_ref.name));
+    }
+
+    wildcard_roles
+        .contains(&role_ref.name)
+        .then(|| "allows all verbs".to_string())
+}
+
+fn allows_all_verbs(cluster_role: &ClusterRole) -> bool {
+    cluster_role
+        .rules
+        .iter()
+        .flatten()
+        .any(|rule| rule.verbs.iter().any(|verb| verb == "*"))
+}
+
+/// Namespace and name of the service accounts among the subjects. Service
+/// account subjects without a namespace belong to `default_namespace`, the
+/// namespace of the role binding.
+fn service_accounts(
+    subjects: Option<&[Subject]>,
+    default_namespace: Option<&str>,
+) -> Vec<(String, String)> {
+    subjects
+        .into_iter()
+        .flatten()
+        .filter(|subject| subject.kind == "ServiceAccount")
+        .filter_map(|subject| {
+            let namespace = subject.namespace.as_deref().or(default_namespace)?;
+            Some((namespace.to_string(), subject.name.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        commands::{DetailOptions, Severity},
+        source::FileSource,
+    };
+
+    #[tokio::test]
+    async fn service_account_privilege() {
+        let pod = |namespace: &str, name: &str, service_account: Option<&str>| {
+            json!({
+                "metadata": { "namespace": namespace, "name": name },
+                "spec": { "containers": [], "serviceAccountName": service_account },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("ci", "runner", Some("runner")),
+                pod("web", "api", Some("api")),
+                pod("web", "frontend", None),
+                pod("monitoring", "exporter", Some("exporter")),
+            ],
+        });
+
+        let service_account = |namespace: Option<&str>, name: &str| json!({ "kind": "ServiceAccount", "namespace": namespace, "name": name });
+
+        let binding = |namespace: Option<&str>,
+                       name: &str,
+                       role: &str,
+                       subjects: serde_json::Value| {
+            json!({
+                "metadata": { "namespace": namespace, "name": name },
+                "roleRef": { "apiGroup": "rbac.authorization.k8s.io", "kind": "ClusterRole", "name": role },
+                "subjects": subjects,
+            })
+        };
+
+        let cluster_role_bindings = json!({
+            "items": [
+                binding(None, "runner-admin", "cluster-admin", json!([service_account(Some("ci"), "runner")])),
+                binding(None, "exporter-view", "view", json!([service_account(Some("monitoring"), "exporter")])),
+                binding(None, "exporter-all", "everything", json!([service_account(Some("monitoring"), "exporter")])),
+            ],
+        });
+
+        let role_bindings = json!({
+            "items": [
+                binding(Some("web"), "api-edit", "edit", json!([service_account(None, "api")])),
+                // Gives the service account of `web` access to `ci`.
+                binding(Some("ci"), "frontend-edit", "edit", json!([service_account(Some("web"), "default")])),
+            ],
+        });
+
+        let cluster_roles = json!({
+            "items": [
+                {
+                    "metadata": { "name": "view" },
+                    "rules": [{ "apiGroups": [""], "resources": ["pods"], "verbs": ["get", "list"] }],
+                },
+                {
+                    "metadata": { "name": "everything" },
+                    "rules": [{ "apiGroups": ["*"], "resources": ["*"], "verbs": ["*"] }],
+                },
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_cluster_role_bindings(cluster_role_bindings.to_string().as_bytes())
+            .unwrap()
+            .with_role_bindings(role_bindings.to_string().as_bytes())
+            .unwrap()
+            .with_cluster_roles(cluster_roles.to_string().as_bytes())
+            .unwrap();
+
+        let findings =
+            super::service_account_privilege(&source, Vec::new(), true, DetailOptions::default())
+                .await
+                .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.pod_name.as_str(),
+                    finding.binding_name.as_str(),
+                    finding.reason.as_str(),
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "runner",
+                    "runner-admin",
+                    "bound to cluster-admin",
+                    Severity::Critical
+                ),
+                (
+                    "exporter",
+                    "exporter-all",
+                    "allows all verbs",
+                    Severity::High
+                ),
+                ("api", "api-edit", "bound to edit", Severity::Medium),
+                (
+                    "frontend",
+                    "frontend-edit",
+                    "bound to edit",
+                    Severity::Medium
+                ),
+            ],
+            findings
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! Inventory the services that are exposed through load balancers and node
+//! ports.
+
+use std::{collections::BTreeMap, ops::RangeInclusive, time::Duration};
+
+use eyre::{Context, Result};
+use k8s_openapi::{
+    api::core::v1::Service,
+    chrono::{self, DateTime, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Report, Severity},
+    humanize,
+    source::Source,
+};
+
+/// Default ephemeral port range of linux. Node ports in it can collide with
+/// the source ports of outgoing connections of the nodes.
+const EPHEMERAL_PORTS: RangeInclusive<i32> = 32768..=60999;
+
+/// Load balancer and node port services grouped by namespace.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Output {
+    totals: ExposureTotals,
+    namespaces: Vec<NamespaceExposure>,
+}
+
+/// Number of exposed services of all checked namespaces.
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct ExposureTotals {
+    load_balancers: usize,
+    node_ports: usize,
+    pending_load_balancers: usize,
+    ephemeral_node_ports: usize,
+}
+
+/// Exposed services of a single namespace.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NamespaceExposure {
+    namespace: String,
+    load_balancers: usize,
+    node_ports: usize,
+    services: Vec<ExposedService>,
+}
+
+/// Service of type `LoadBalancer` or `NodePort`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ExposedService {
+    namespace: String,
+    service_name: String,
+    service_type: String,
+
+    /// Ips and hostnames of the load balancer.
+    external_addresses: Vec<String>,
+
+    /// Node ports allocated for the service, load balancers get them as well
+    /// unless `allocateLoadBalancerNodePorts` is disabled.
+    node_ports: Vec<i32>,
+
+    /// How long a load balancer has been waiting for an external address.
+    pending_for: Option<String>,
+
+    /// Node ports in the ephemeral port range of the nodes.
+    ephemeral_node_ports: Vec<i32>,
+
+    /// Whether the load balancer is pending for too long or a node port is in
+    /// the ephemeral port range.
+    flagged: bool,
+    severity: Severity,
+}
+
+impl Finding for ExposedService {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+impl Report for Output {
+    type Finding = ExposedService;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.namespaces
+            .iter()
+            .flat_map(|namespace| &namespace.services)
+            .filter(|service| service.flagged)
+            .collect()
+    }
+
+    /// Only flagged services are findings, the others are always kept.
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        for namespace in &mut self.namespaces {
+            namespace
+                .services
+                .retain_mut(|service| !service.flagged || keep(service));
+        }
+    }
+}
+
+/// List all services of type `LoadBalancer` and `NodePort` grouped by
+/// namespace. Load balancers without an external address that were created
+/// more than `pending_after` ago and node ports in the ephemeral port range are
+/// flagged.
+pub async fn service_exposure(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    pending_after: Duration,
+) -> Result<Output> {
+    let pending_after = chrono::Duration::from_std(pending_after).context("invalid duration")?;
+    let services = source.services(namespaces, all_namespaces).await?;
+
+    let mut grouped: BTreeMap<String, Vec<ExposedService>> = BTreeMap::new();
+
+    for service in services
+        .iter()
+        .filter_map(|service| exposed_service(service, Utc::now(), pending_after))
+    {
+        grouped
+            .entry(service.namespace.clone())
+            .or_default()
+            .push(service);
+    }
+
+    let mut totals = ExposureTotals::default();
+
+    let namespaces = grouped
+        .into_iter()
+        .map(|(namespace, services)| {
+            let count = |service_type: &str| {
+                services
+                    .iter()
+                    .filter(|service| service.service_type == service_type)
+                    .count()
+            };
+
+            let load_balancers = count("LoadBalancer");
+            let node_ports = count("NodePort");
+
+            totals.load_balancers += load_balancers;
+            totals.node_ports += node_ports;
+            totals.pending_load_balancers += services
+                .iter()
+                .filter(|service| service.pending_for.is_some())
+                .count();
+            totals.ephemeral_node_ports += services
+                .iter()
+                .map(|service| service.ephemeral_node_ports.len())
+                .sum::<usize>();
+
+            NamespaceExposure {
+                namespace,
+                load_balancers,
+                node_ports,
+                services,
+            }
+        })
+        .collect();
+
+    Ok(Output { totals, namespaces })
+}
+
+fn exposed_service(
+    service: &Service,
+    now: DateTime<Utc>,
+    pending_after: chrono::Duration,
+) -> Option<ExposedService> {
+    let spec = service.spec.as_ref()?;
+    let service_type = spec.type_.as_deref()?;
+
+    if service_type != "LoadBalancer" && service_type != "NodePort" {
+        return None;
+    }
+
+    let external_addresses = service
+        .status
+        .as_ref()
+        .and_then(|status| status.load_balancer.as_ref())
+        .and_then(|load_balancer| load_balancer.ingress.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|ingress| ingress.ip.clone().or_else(|| ingress.hostname.clone()))
+        .collect::<Vec<_>>();
+
+    let node_ports = spec
+        .ports
+        .iter()
+        .flatten()
+        .filter_map(|port| port.node_port)
+        .collect::<Vec<_>>();
+
+    let ephemeral_node_ports = node_ports
+        .iter()
+        .copied()
+        .filter(|port| EPHEMERAL_PORTS.contains(port))
+        .collect::<Vec<_>>();
+
+    let pending_for = if service_type == "LoadBalancer" && external_addresses.is_empty() {
+        service
+            .metadata
+            .creation_timestamp
+            .as_ref()
+            .map(|created| now - created.0)
+            .filter(|pending| *pending > pending_after)
+            .map(|pending| humanize::duration(pending.to_std().unwrap_or_default()))
+    } else {
+        None
+    };
+
+    let severity = if pending_for.is_some() {
+        Severity::Medium
+    } else if !ephemeral_node_ports.is_empty() {
+        Severity::Low
+    } else {
+        Severity::Info
+    };
+
+    Some(ExposedService {
+        namespace: service
+            .metadata
+            .namespace
+            .clone()
+            .expect("failed to get namespace"),
+        service_name: service.metadata.name.clone().expect("failed to get name"),
+        service_type: service_type.to_string(),
+        external_addresses,
+        node_ports,
+        flagged: pending_for.is_some() || !ephemeral_node_ports.is_empty(),
+        pending_for,
+        ephemeral_node_ports,
+        severity,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use k8s_openapi::chrono::{self, SecondsFormat, Utc};
+    use serde_json::json;
+
+    use crate::{
+        commands::{Report, Severity},
+        source::FileSource,
+    };
+
+    #[tokio::test]
+    async fn service_exposure() {
+        let created = |minutes: i64| {
+            (Utc::now() - chrono::Duration::minutes(minutes))
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+
+        let service = |namespace: &str, name: &str, service_type: &str, node_port: i32| {
+            json!({
+                "metadata": {
+                    "namespace": namespace,
+                    "name": name,
+                    "creationTimestamp": created(60),
+                },
+                "spec": {
+                    "type": service_type,
+                    "ports": [{ "port": 80, "nodePort": node_port }],
+                },
+            })
+        };
+
+        let mut ready = service("web", "frontend", "LoadBalancer", 30080);
+        ready["status"] = json!({ "loadBalancer": { "ingress": [{ "ip": "203.0.113.10" }] } });
+
+        let mut fresh = service("web", "api", "LoadBalancer", 30081);
+        fresh["metadata"]["creationTimestamp"] = json!(created(1));
+
+        let services = json!({
+            "items": [
+                ready,
+                fresh,
+                service("web", "forgotten", "LoadBalancer", 30082),
+                service("web", "debug", "NodePort", 40000),
+                service("shop", "admin", "NodePort", 30090),
+                {
+                    "metadata": { "namespace": "shop", "name": "db" },
+                    "spec": { "type": "ClusterIP", "ports": [{ "port": 5432 }] },
+                },
+            ],
+        });
+
+        let source = FileSource::from_readers(r#"{ "items": [] }"#.as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_services(services.to_string().as_bytes())
+            .unwrap();
+
+        let output = super::service_exposure(&source, Vec::new(), true, Duration::from_secs(600))
+            .await
+            .unwrap();
+
+        assert_eq!(3, output.totals.load_balancers);
+        assert_eq!(2, output.totals.node_ports);
+        assert_eq!(1, output.totals.pending_load_balancers);
+        assert_eq!(1, output.totals.ephemeral_node_ports);
+
+        let namespaces = output
+            .namespaces
+            .iter()
+            .map(|namespace| {
+                (
+                    namespace.namespace.as_str(),
+                    namespace.load_balancers,
+                    namespace.node_ports,
+                    namespace.services.len(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![("shop", 0, 1, 1), ("web", 3, 1, 4)], namespaces);
+
+        let frontend = &output.namespaces[1].services[0];
+        assert_eq!(
+            vec!["203.0.113.10".to_string()],
+            frontend.external_addresses
+        );
+        assert_eq!(vec![30080], frontend.node_ports);
+
+        let findings = output
+            .findings()
+            .into_iter()
+            .map(|service| (service.service_name.as_str(), service.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![("forgotten", Severity::Medium), ("debug", Severity::Low)],
+            findings
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Find namespaces whose pods get flooded with service link environment
+//! variables.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use eyre::Result;
+use k8s_openapi::api::core::v1::Service;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Namespace with many services whose pods keep service links enabled.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ServiceLinks {
+    namespace: String,
+    service_count: usize,
+
+    /// Pods that do not set `enableServiceLinks: false`.
+    affected_pods: usize,
+
+    /// Distinct top level owners of the affected pods.
+    affected_owners: usize,
+
+    /// Number of environment variables the kubelet injects into every
+    /// container of an affected pod for the services of the namespace.
+    injected_env_vars: usize,
+
+    /// One of the affected pods.
+    example_pod: String,
+
+    severity: Severity,
+}
+
+impl Finding for ServiceLinks {
+    const DEFAULT_SEVERITY: Severity = Severity::Low;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the namespaces with more than `min_services` services that contain
+/// pods which do not disable service links. The kubelet injects several
+/// `*_SERVICE_HOST` and `*_PORT` environment variables per service into such
+/// pods, which slows down their start and breaks applications that read
+/// variables with the same prefix.
+pub async fn service_links(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    min_services: usize,
+) -> Result<Vec<ServiceLinks>> {
+    let services = source.services(namespaces.clone(), all_namespaces).await?;
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut by_namespace: BTreeMap<&str, Vec<&Service>> = BTreeMap::new();
+    for service in &services {
+        let Some(namespace) = service.metadata.namespace.as_deref() else {
+            continue;
+        };
+
+        by_namespace.entry(namespace).or_default().push(service);
+    }
+
+    let mut findings = Vec::new();
+    for (namespace, services) in by_namespace {
+        if services.len() <= min_services {
+            continue;
+        }
+
+        let affected = pods
+            .iter()
+            .filter(|pod| pod.metadata.namespace.as_deref() == Some(namespace))
+            .filter(|pod| {
+                pod.spec
+                    .as_ref()
+                    .and_then(|spec| spec.enable_service_links)
+                    .unwrap_or(true)
+            })
+            .collect::<Vec<_>>();
+
+        let Some(example) = affected.first() else {
+            continue;
+        };
+
+        let affected_owners = affected
+            .iter()
+            .filter_map(|pod| source.pod_owner(pod))
+            .collect::<BTreeSet<_>>()
+            .len();
+
+        findings.push(ServiceLinks {
+            namespace: namespace.to_string(),
+            service_count: services.len(),
+            affected_pods: affected.len(),
+            affected_owners,
+            injected_env_vars: services.iter().map(|service| env_vars(service)).sum(),
+            example_pod: example.metadata.name.clone().expect("failed to get name"),
+            severity: Severity::Low,
+        });
+    }
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+/// Number of environment variables the kubelet injects for the service.
+/// Services without a cluster ip, like headless and `ExternalName` services,
+/// are skipped by the kubelet.
+fn env_vars(service: &Service) -> usize {
+    let Some(spec) = &service.spec else {
+        return 0;
+    };
+
+    let has_cluster_ip = spec
+        .cluster_ip
+        .as_deref()
+        .is_some_and(|ip| !ip.is_empty() && ip != "None");
+
+    if !has_cluster_ip {
+        return 0;
+    }
+
+    let ports = spec.ports.iter().flatten().collect::<Vec<_>>();
+    let named_ports = ports.iter().filter(|port| port.name.is_some()).count();
+
+    // `{NAME}_SERVICE_HOST`, `{NAME}_SERVICE_PORT` and one
+    // `{NAME}_SERVICE_PORT_{PORT_NAME}` per named port.
+    let service_vars = 2 + named_ports;
+
+    // Docker link variables, `{NAME}_PORT` and `{NAME}_PORT_{PORT}_{PROTOCOL}`
+    // with its `_PROTO`, `_PORT` and `_ADDR` variants per port.
+    let link_vars = if ports.is_empty() {
+        0
+    } else {
+        1 + 4 * ports.len()
+    };
+
+    service_vars + link_vars
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::source::FileSource;
+
+    #[tokio::test]
+    async fn service_links() {
+        let service = |namespace: &str, name: &str, cluster_ip: &str| {
+            json!({
+                "metadata": { "namespace": namespace, "name": name },
+                "spec": {
+                    "clusterIP": cluster_ip,
+                    "ports": [{ "name": "http", "port": 80 }],
+                },
+            })
+        };
+
+        let services = json!({
+            "items": [
+                service("web", "api", "10.0.0.1"),
+                service("web", "cache", "10.0.0.2"),
+                service("web", "db", "None"),
+                service("jobs", "queue", "10.0.0.3"),
+            ],
+        });
+
+        let pod = |namespace: &str, name: &str, enable_service_links: Option<bool>| {
+            json!({
+                "metadata": { "namespace": namespace, "name": name },
+                "spec": {
+                    "containers": [{ "name": "app" }],
+                    "enableServiceLinks": enable_service_links,
+                },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("web", "api-0", None),
+                pod("web", "cache-0", Some(true)),
+                pod("web", "db-0", Some(false)),
+                pod("jobs", "queue-0", None),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_services(services.to_string().as_bytes())
+            .unwrap();
+
+        let findings = super::service_links(&source, Vec::new(), true, 2)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.namespace.as_str(),
+                    finding.service_count,
+                    finding.affected_pods,
+                    finding.injected_env_vars,
+                    finding.example_pod.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![("web", 3, 2, 16, "api-0")], findings);
+    }
+}
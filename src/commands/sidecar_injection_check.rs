@@ -0,0 +1,254 @@
+//! Find pods that asked for a sidecar to be injected but do not have it.
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Pod that requests sidecar injection but runs without the sidecar.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct MissingSidecar {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    expected_sidecar: String,
+    injection_annotation: String,
+    containers: Vec<String>,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for MissingSidecar {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Which sidecar pods are expected to have and how they request it.
+#[derive(Debug, Clone)]
+pub struct SidecarExpectation {
+    /// Name of the injected sidecar container.
+    pub sidecar: String,
+
+    /// Annotation or label that requests the injection when set to `true`.
+    pub annotation: String,
+}
+
+/// Get pods that request sidecar injection with the annotation or label but
+/// have no container with the name of the sidecar. These pods were usually
+/// created before the injection webhook was installed or while it was down.
+pub async fn sidecar_injection_check(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    expected: &SidecarExpectation,
+    details: DetailOptions,
+) -> Result<Vec<MissingSidecar>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let missing = pods
+        .iter()
+        .map(|pod| missing_sidecar(source, pod, expected, &details))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    Ok(missing)
+}
+
+fn missing_sidecar(
+    source: &dyn Source,
+    pod: &Pod,
+    expected: &SidecarExpectation,
+    details: &DetailOptions,
+) -> Result<Option<MissingSidecar>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let requested = [&pod.metadata.annotations, &pod.metadata.labels]
+        .into_iter()
+        .flatten()
+        .any(|values| values.get(&expected.annotation).map(String::as_str) == Some("true"));
+
+    if !requested {
+        return Ok(None);
+    }
+
+    // Native sidecars are init containers that keep running next to the
+    // containers of the pod.
+    let containers = spec
+        .init_containers
+        .iter()
+        .flatten()
+        .chain(&spec.containers)
+        .map(|container| container.name.clone())
+        .collect::<Vec<_>>();
+
+    if containers.contains(&expected.sidecar) {
+        return Ok(None);
+    }
+
+    Ok(Some(MissingSidecar {
+        namespace: pod
+            .metadata
+            .namespace
+            .as_ref()
+            .expect("failed to get namespace")
+            .clone(),
+
+        owner: source.pod_owner(pod),
+
+        pod_name: pod
+            .metadata
+            .name
+            .as_ref()
+            .expect("failed to get name")
+            .clone(),
+
+        expected_sidecar: expected.sidecar.clone(),
+        injection_annotation: expected.annotation.clone(),
+        containers,
+        severity: Severity::High,
+        details: PodDetails::new(source, pod, details),
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use regex::Regex;
+    use serde_json::json;
+
+    use super::SidecarExpectation;
+    use crate::{
+        commands::DetailOptions,
+        source::{FileSource, FilteredContainers},
+    };
+
+    #[tokio::test]
+    async fn sidecar_injection_check() {
+        let pod = |name: &str, metadata: serde_json::Value, init: &[&str], containers: &[&str]| {
+            let names = |names: &[&str]| {
+                names
+                    .iter()
+                    .map(|name| json!({ "name": name }))
+                    .collect::<Vec<_>>()
+            };
+
+            let mut pod = json!({
+                "metadata": { "namespace": "web", "name": name },
+                "spec": { "initContainers": names(init), "containers": names(containers) },
+            });
+
+            pod["metadata"]
+                .as_object_mut()
+                .unwrap()
+                .extend(metadata.as_object().unwrap().clone());
+
+            pod
+        };
+
+        let inject = json!({ "annotations": { "sidecar.istio.io/inject": "true" } });
+
+        let pods = json!({
+            "items": [
+                pod("injected", inject.clone(), &[], &["app", "istio-proxy"]),
+                pod("native", inject.clone(), &["istio-proxy"], &["app"]),
+                pod("missing", inject, &["migrate"], &["app"]),
+                pod(
+                    "label",
+                    json!({ "labels": { "sidecar.istio.io/inject": "true" } }),
+                    &[],
+                    &["app"],
+                ),
+                pod(
+                    "disabled",
+                    json!({ "annotations": { "sidecar.istio.io/inject": "false" } }),
+                    &[],
+                    &["app"],
+                ),
+                pod("unrelated", json!({}), &[], &["app"]),
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>).unwrap();
+
+        let expected = SidecarExpectation {
+            sidecar: "istio-proxy".to_string(),
+            annotation: "sidecar.istio.io/inject".to_string(),
+        };
+
+        let missing = super::sidecar_injection_check(
+            &source,
+            Vec::new(),
+            true,
+            &expected,
+            DetailOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let missing = missing
+            .iter()
+            .map(|missing| (missing.pod_name.as_str(), missing.containers.clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("missing", vec!["migrate".to_string(), "app".to_string()]),
+                ("label", vec!["app".to_string()]),
+            ],
+            missing
+        );
+
+        // Only checking the app containers still sees the sidecars next to
+        // them.
+        let regex = Regex::new("^app$").unwrap();
+        let filtered = FilteredContainers::new(&source, &regex);
+
+        let missing = super::sidecar_injection_check(
+            &filtered,
+            Vec::new(),
+            true,
+            &expected,
+            DetailOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let missing = missing
+            .iter()
+            .map(|missing| missing.pod_name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["missing", "label"], missing);
+    }
+}
@@ -0,0 +1,286 @@
+//! Find statefulsets that do not create and update their pods one at a time.
+
+use eyre::{bail, Result};
+use k8s_openapi::{api::apps::v1::StatefulSet, apimachinery::pkg::util::intstr::IntOrString};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    source::Source,
+};
+
+/// Which statefulsets are reported. Without any flag every statefulset with a
+/// policy other than `OrderedReady` is reported.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PolicyFlags {
+    /// Report statefulsets using the `Parallel` pod management policy.
+    pub parallel: bool,
+
+    /// Report statefulsets whose rolling updates replace more than one pod
+    /// at a time.
+    pub burst: bool,
+}
+
+/// Statefulset that does not keep the ordering guarantees for its pods.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct PodManagementPolicyFinding {
+    namespace: String,
+    statefulset_name: String,
+    policy: String,
+    replicas: i32,
+    update_strategy: String,
+
+    /// Number of pods that can be unavailable during a rolling update.
+    max_unavailable: Option<i32>,
+
+    /// Whether rolling updates replace more than one pod at a time.
+    burst: bool,
+
+    severity: Severity,
+}
+
+impl Finding for PodManagementPolicyFinding {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get statefulsets using the `Parallel` pod management policy, or any other
+/// policy than `OrderedReady`, and statefulsets with burst rolling updates
+/// depending on the given flags.
+pub async fn statefulset_pod_management_policy(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    flags: PolicyFlags,
+) -> Result<Vec<PodManagementPolicyFinding>> {
+    let statefulsets = source.statefulsets(namespaces, all_namespaces).await?;
+
+    let findings = statefulsets
+        .iter()
+        .map(|statefulset| pod_management_policy_finding(statefulset, flags))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    Ok(findings)
+}
+
+fn pod_management_policy_finding(
+    statefulset: &StatefulSet,
+    flags: PolicyFlags,
+) -> Result<Option<PodManagementPolicyFinding>> {
+    let Some(spec) = &statefulset.spec else {
+        bail!("StatefulSet has no spec");
+    };
+
+    let policy = spec
+        .pod_management_policy
+        .clone()
+        .unwrap_or_else(|| "OrderedReady".to_string());
+
+    // Kubernetes defaults to a single replica and rolling updates.
+    let replicas = spec.replicas.unwrap_or(1);
+
+    let update_strategy = spec
+        .update_strategy
+        .as_ref()
+        .and_then(|strategy| strategy.type_.clone())
+        .unwrap_or_else(|| "RollingUpdate".to_string());
+
+    let max_unavailable = if update_strategy == "RollingUpdate" {
+        spec.update_strategy
+            .as_ref()
+            .and_then(|strategy| strategy.rolling_update.as_ref())
+            .and_then(|rolling_update| rolling_update.max_unavailable.as_ref())
+            .map(|max_unavailable| scaled_max_unavailable(max_unavailable, replicas))
+            .transpose()?
+    } else {
+        None
+    };
+
+    let parallel = policy == "Parallel";
+    let burst = max_unavailable.is_some_and(|max_unavailable| max_unavailable > 1);
+
+    let report = if flags.parallel || flags.burst {
+        (flags.parallel && parallel) || (flags.burst && burst)
+    } else {
+        policy != "OrderedReady"
+    };
+
+    if !report {
+        return Ok(None);
+    }
+
+    let severity = match (parallel, burst) {
+        (true, true) => Severity::High,
+        (true, false) | (false, true) => Severity::Medium,
+        (false, false) => Severity::Low,
+    };
+
+    Ok(Some(PodManagementPolicyFinding {
+        namespace: statefulset
+            .metadata
+            .namespace
+            .as_ref()
+            .expect("failed to get namespace")
+            .clone(),
+
+        statefulset_name: statefulset
+            .metadata
+            .name
+            .as_ref()
+            .expect("failed to get name")
+            .clone(),
+
+        policy,
+        replicas,
+        update_strategy,
+        max_unavailable,
+        burst,
+        severity,
+    }))
+}
+
+/// Percentages are rounded up the same way the statefulset controller does.
+fn scaled_max_unavailable(max_unavailable: &IntOrString, replicas: i32) -> Result<i32> {
+    match max_unavailable {
+        IntOrString::Int(value) => Ok(*value),
+        IntOrString::String(value) => {
+            let Some(percent) = value
+                .strip_suffix('%')
+                .and_then(|percent| percent.parse::<i32>().ok())
+            else {
+                bail!("invalid maxUnavailable value {value}");
+            };
+
+            Ok((percent * replicas + 99) / 100)
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::{
+        api::apps::v1::{
+            RollingUpdateStatefulSetStrategy, StatefulSet, StatefulSetSpec,
+            StatefulSetUpdateStrategy,
+        },
+        apimachinery::pkg::util::intstr::IntOrString,
+    };
+
+    use super::PolicyFlags;
+    use crate::commands::Severity;
+
+    fn statefulset(policy: Option<&str>, max_unavailable: Option<IntOrString>) -> StatefulSet {
+        StatefulSet {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("statefulset".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(StatefulSetSpec {
+                pod_management_policy: policy.map(ToString::to_string),
+                replicas: Some(4),
+                update_strategy: Some(StatefulSetUpdateStrategy {
+                    type_: Some("RollingUpdate".to_string()),
+                    rolling_update: Some(RollingUpdateStatefulSetStrategy {
+                        max_unavailable,
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            }),
+
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pod_management_policy_finding() {
+        let parallel = PolicyFlags {
+            parallel: true,
+            burst: false,
+        };
+
+        let burst = PolicyFlags {
+            parallel: false,
+            burst: true,
+        };
+
+        let testcases = [
+            (statefulset(None, None), PolicyFlags::default(), None),
+            (
+                statefulset(Some("OrderedReady"), None),
+                PolicyFlags::default(),
+                None,
+            ),
+            (
+                statefulset(Some("Parallel"), None),
+                PolicyFlags::default(),
+                Some(Severity::Medium),
+            ),
+            (
+                statefulset(Some("Unknown"), None),
+                PolicyFlags::default(),
+                Some(Severity::Low),
+            ),
+            (statefulset(Some("Unknown"), None), parallel, None),
+            (
+                statefulset(Some("Parallel"), Some(IntOrString::Int(1))),
+                burst,
+                None,
+            ),
+            (
+                statefulset(None, Some(IntOrString::String("50%".to_string()))),
+                burst,
+                Some(Severity::Medium),
+            ),
+            (
+                statefulset(Some("Parallel"), Some(IntOrString::Int(2))),
+                parallel,
+                Some(Severity::High),
+            ),
+        ];
+
+        for (statefulset, flags, expected) in testcases {
+            let output = super::pod_management_policy_finding(&statefulset, flags).unwrap();
+
+            assert_eq!(expected, output.map(|finding| finding.severity));
+        }
+    }
+
+    #[test]
+    fn scaled_max_unavailable() {
+        let testcases = [
+            (IntOrString::Int(3), 3),
+            (IntOrString::String("25%".to_string()), 1),
+            (IntOrString::String("30%".to_string()), 2),
+        ];
+
+        for (max_unavailable, expected) in testcases {
+            assert_eq!(
+                expected,
+                super::scaled_max_unavailable(&max_unavailable, 4).unwrap()
+            );
+        }
+
+        assert!(
+            super::scaled_max_unavailable(&IntOrString::String("many".to_string()), 4).is_err()
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Find objects that are being deleted for too long because of finalizers.
+
+use std::time::Duration;
+
+use eyre::{Context, Result};
+use k8s_openapi::{
+    apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    chrono::{self, DateTime, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    humanize,
+    source::Source,
+};
+
+/// Object with a deletion timestamp older than the grace period.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct StuckObject {
+    /// Namespace of the object, the name of the namespace for namespaces.
+    namespace: String,
+
+    kind: String,
+    name: String,
+    deletion_timestamp: String,
+
+    /// How long the object has been deleting.
+    deleting_for: String,
+
+    finalizers: Vec<String>,
+
+    /// Messages of the conditions of namespaces that name the resources
+    /// blocking their deletion.
+    blocking: Vec<String>,
+
+    severity: Severity,
+}
+
+impl Finding for StuckObject {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Namespace conditions that are set while content is left in a terminating
+/// namespace.
+const BLOCKING_CONDITIONS: &[&str] = &[
+    "NamespaceDeletionDiscoveryFailure",
+    "NamespaceDeletionGroupVersionParsingFailure",
+    "NamespaceDeletionContentFailure",
+    "NamespaceContentRemaining",
+    "NamespaceFinalizersRemaining",
+];
+
+/// Get pods, persistent volume claims and namespaces whose deletion started
+/// more than `grace` ago. Namespaces are only checked with `all_namespaces` or
+/// when they are given in `namespaces`.
+pub async fn stuck_finalizers(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    grace: Duration,
+) -> Result<Vec<StuckObject>> {
+    let grace = chrono::Duration::from_std(grace).context("invalid duration")?;
+    let now = Utc::now();
+
+    let pods = source.pods(namespaces.clone(), all_namespaces).await?;
+    let claims = source
+        .persistent_volume_claims(namespaces.clone(), all_namespaces)
+        .await?;
+
+    let namespace_list = if all_namespaces || !namespaces.is_empty() {
+        source
+            .namespace_list()
+            .await?
+            .into_iter()
+            .filter(|namespace| {
+                all_namespaces
+                    || namespace
+                        .metadata
+                        .name
+                        .as_ref()
+                        .is_some_and(|name| namespaces.contains(name))
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let pods = pods
+        .iter()
+        .map(|pod| ("Pod", &pod.metadata, Vec::new(), Vec::new()));
+
+    let claims = claims.iter().map(|claim| {
+        (
+            "PersistentVolumeClaim",
+            &claim.metadata,
+            Vec::new(),
+            Vec::new(),
+        )
+    });
+
+    let namespace_list = namespace_list.iter().map(|namespace| {
+        let blocking = namespace
+            .status
+            .as_ref()
+            .and_then(|status| status.conditions.as_ref())
+            .into_iter()
+            .flatten()
+            .filter(|condition| {
+                condition.status == "True"
+                    && BLOCKING_CONDITIONS.contains(&condition.type_.as_str())
+            })
+            .filter_map(|condition| condition.message.clone())
+            .collect();
+
+        let spec_finalizers = namespace
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.finalizers.clone())
+            .unwrap_or_default();
+
+        ("Namespace", &namespace.metadata, spec_finalizers, blocking)
+    });
+
+    let stuck = pods
+        .chain(claims)
+        .chain(namespace_list)
+        .filter_map(|(kind, metadata, spec_finalizers, blocking)| {
+            stuck_object(kind, metadata, spec_finalizers, blocking, now, grace)
+        })
+        .collect();
+
+    Ok(stuck)
+}
+
+/// Stuck object from its metadata, namespaces have further finalizers in their
+/// spec.
+fn stuck_object(
+    kind: &str,
+    metadata: &ObjectMeta,
+    spec_finalizers: Vec<String>,
+    blocking: Vec<String>,
+    now: DateTime<Utc>,
+    grace: chrono::Duration,
+) -> Option<StuckObject> {
+    let deleted_at = metadata.deletion_timestamp.as_ref()?.0;
+
+    if now - deleted_at <= grace {
+        return None;
+    }
+
+    let name = metadata.name.clone().expect("failed to get name");
+
+    Some(StuckObject {
+        namespace: metadata.namespace.clone().unwrap_or_else(|| name.clone()),
+        kind: kind.to_string(),
+        name,
+        deletion_timestamp: deleted_at.to_rfc3339(),
+        deleting_for: humanize::duration((now - deleted_at).to_std().unwrap_or_default()),
+        finalizers: metadata
+            .finalizers
+            .iter()
+            .flatten()
+            .cloned()
+            .chain(spec_finalizers)
+            .collect(),
+        blocking,
+        severity: Severity::Medium,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use k8s_openapi::chrono::{self, SecondsFormat, Utc};
+    use serde_json::json;
+
+    use crate::source::FileSource;
+
+    const REMAINING: &str = "Some resources are remaining: pods. has 1 resource instances";
+
+    #[tokio::test]
+    #[allow(clippy::too_many_lines)]
+    async fn stuck_finalizers() {
+        let deleted = |minutes: i64| {
+            (Utc::now() - chrono::Duration::minutes(minutes))
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+
+        let pods = json!({
+            "items": [
+                {
+                    "metadata": {
+                        "namespace": "old",
+                        "name": "worker-0",
+                        "deletionTimestamp": deleted(60),
+                        "finalizers": ["example.com/drain"],
+                    },
+                    "spec": { "containers": [] },
+                },
+                {
+                    "metadata": {
+                        "namespace": "web",
+                        "name": "api-0",
+                        "deletionTimestamp": deleted(1),
+                    },
+                    "spec": { "containers": [] },
+                },
+                {
+                    "metadata": { "namespace": "web", "name": "api-1" },
+                    "spec": { "containers": [] },
+                },
+            ],
+        });
+
+        let claims = json!({
+            "items": [{
+                "metadata": {
+                    "namespace": "old",
+                    "name": "data-worker-0",
+                    "deletionTimestamp": deleted(120),
+                    "finalizers": ["kubernetes.io/pvc-protection"],
+                },
+            }],
+        });
+
+        let namespaces = json!({
+            "items": [
+                {
+                    "metadata": {
+                        "name": "old",
+                        "deletionTimestamp": deleted(180),
+                    },
+                    "spec": { "finalizers": ["kubernetes"] },
+                    "status": {
+                        "phase": "Terminating",
+                        "conditions": [
+                            {
+                                "type": "NamespaceContentRemaining",
+                                "status": "True",
+                                "message": REMAINING,
+                            },
+                            {
+                                "type": "NamespaceDeletionDiscoveryFailure",
+                                "status": "False",
+                                "message": "All resources successfully discovered",
+                            },
+                        ],
+                    },
+                },
+                { "metadata": { "name": "web" } },
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_persistent_volume_claims(claims.to_string().as_bytes())
+            .unwrap()
+            .with_namespaces(namespaces.to_string().as_bytes())
+            .unwrap();
+
+        let stuck = super::stuck_finalizers(&source, Vec::new(), true, Duration::from_secs(300))
+            .await
+            .unwrap();
+
+        let stuck = stuck
+            .iter()
+            .map(|object| {
+                (
+                    object.namespace.as_str(),
+                    object.kind.as_str(),
+                    object.name.as_str(),
+                    object.finalizers.clone(),
+                    object.blocking.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "old",
+                    "Pod",
+                    "worker-0",
+                    vec!["example.com/drain".to_string()],
+                    vec![]
+                ),
+                (
+                    "old",
+                    "PersistentVolumeClaim",
+                    "data-worker-0",
+                    vec!["kubernetes.io/pvc-protection".to_string()],
+                    vec![]
+                ),
+                (
+                    "old",
+                    "Namespace",
+                    "old",
+                    vec!["kubernetes".to_string()],
+                    vec![REMAINING.to_string()]
+                ),
+            ],
+            stuck
+        );
+
+        let stuck = super::stuck_finalizers(
+            &source,
+            vec!["web".to_string()],
+            false,
+            Duration::from_secs(30),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(1, stuck.len());
+        assert_eq!("api-0", stuck[0].name);
+    }
+}
@@ -0,0 +1,214 @@
+//! Find containers whose termination message ends up empty when they crash.
+
+use std::path::Path;
+
+use eyre::Result;
+use k8s_openapi::api::core::v1::{Container, Pod};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{readonly_root_filesystem::has_read_only_root_filesystem, Finding, Severity},
+    source::Source,
+};
+
+/// Policy kubernetes uses when the container does not set one.
+const DEFAULT_POLICY: &str = "File";
+
+/// Path kubernetes uses when the container does not set one.
+const DEFAULT_PATH: &str = "/dev/termination-log";
+
+/// Container with a termination message setup that loses the crash reason.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct TerminationMessage {
+    namespace: String,
+    pod_name: String,
+    owner: Option<Owner>,
+    container_name: String,
+
+    /// `terminationMessagePolicy` of the container, `File` if it is not set.
+    policy: String,
+
+    /// `terminationMessagePath` of the container, `/dev/termination-log` if
+    /// it is not set.
+    path: String,
+
+    rule: TerminationMessageRule,
+    severity: Severity,
+}
+
+/// Rule a container violates.
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TerminationMessageRule {
+    /// The policy is not `FallbackToLogsOnError`, so the message is empty
+    /// unless the application writes it to the path itself.
+    NoLogFallback,
+
+    /// The path is moved onto a read-only root filesystem and not onto a
+    /// writable volume, so the application can not write the message.
+    UnwritablePath,
+}
+
+impl Finding for TerminationMessage {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the containers that do not fall back to their logs for the
+/// termination message and the containers that moved the termination message
+/// path somewhere they can not write to.
+pub async fn termination_messages(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<TerminationMessage>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut findings = pods
+        .iter()
+        .flat_map(|pod| {
+            pod.spec
+                .iter()
+                .flat_map(|spec| &spec.containers)
+                .filter(|container| source.checks_container(&container.name))
+                .flat_map(move |container| {
+                    violated_rules(container)
+                        .into_iter()
+                        .map(move |rule| termination_message(source, pod, container, rule))
+                })
+        })
+        .collect::<Vec<_>>();
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn violated_rules(container: &Container) -> Vec<TerminationMessageRule> {
+    let mut rules = Vec::new();
+
+    if container.termination_message_policy.as_deref() != Some("FallbackToLogsOnError") {
+        rules.push(TerminationMessageRule::NoLogFallback);
+    }
+
+    let path = container
+        .termination_message_path
+        .as_deref()
+        .unwrap_or(DEFAULT_PATH);
+
+    if path != DEFAULT_PATH
+        && has_read_only_root_filesystem(container)
+        && !on_writable_volume(container, path)
+    {
+        rules.push(TerminationMessageRule::UnwritablePath);
+    }
+
+    rules
+}
+
+/// Whether the path is at or below a volume mount of the container that is
+/// not read-only.
+fn on_writable_volume(container: &Container, path: &str) -> bool {
+    container
+        .volume_mounts
+        .iter()
+        .flatten()
+        .filter(|mount| !mount.read_only.unwrap_or(false))
+        .any(|mount| Path::new(path).starts_with(&mount.mount_path))
+}
+
+fn termination_message(
+    source: &dyn Source,
+    pod: &Pod,
+    container: &Container,
+    rule: TerminationMessageRule,
+) -> TerminationMessage {
+    TerminationMessage {
+        namespace: pod
+            .metadata
+            .namespace
+            .clone()
+            .expect("failed to get namespace"),
+        pod_name: pod.metadata.name.clone().expect("failed to get name"),
+        owner: source.pod_owner(pod),
+        container_name: container.name.clone(),
+        policy: container
+            .termination_message_policy
+            .clone()
+            .unwrap_or_else(|| DEFAULT_POLICY.to_string()),
+        path: container
+            .termination_message_path
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PATH.to_string()),
+        rule,
+        severity: match rule {
+            TerminationMessageRule::NoLogFallback => Severity::Low,
+            TerminationMessageRule::UnwritablePath => Severity::Medium,
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::TerminationMessageRule::{NoLogFallback, UnwritablePath};
+    use crate::source::FileSource;
+
+    const PODS: &str = include_str!("../../resources/fixtures/termination-messages.json");
+
+    #[tokio::test]
+    async fn termination_messages() {
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+
+        let findings = super::termination_messages(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.pod_name.as_str(),
+                    finding.container_name.as_str(),
+                    finding.path.as_str(),
+                    finding.rule,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("api-0", "api", "/dev/termination-log", NoLogFallback),
+                (
+                    "worker-0",
+                    "readonly",
+                    "/var/log/termination",
+                    UnwritablePath
+                ),
+                ("worker-0", "volume", "/tmp/termination", NoLogFallback),
+            ],
+            findings
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Find containers with resource requests too small to be meant seriously.
+
+use eyre::{Context, Result};
+use k8s_openapi::{api::core::v1::Container, apimachinery::pkg::api::resource::Quantity};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{Cpu, Memory, Owner, PodMetrics},
+    commands::{Finding, Severity},
+    source::{glob_to_regex, Source},
+};
+
+/// Images of pause and proxy sidecar containers that run with tiny requests on
+/// purpose.
+const IGNORED_IMAGES: &[&str] = &[
+    "*/pause",
+    "*/pause:*",
+    "*/istio/proxyv2:*",
+    "*/linkerd/proxy:*",
+    "*/cloudsql-proxy:*",
+];
+
+/// Container that requests less cpu or memory than the minimums.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct TinyRequest {
+    namespace: String,
+    pod_name: String,
+    owner: Option<Owner>,
+    container_name: String,
+    image: String,
+
+    /// Cpu request of the container if it is below the minimum.
+    cpu_request: Option<Cpu>,
+
+    /// Memory request of the container if it is below the minimum.
+    memory_request: Option<Memory>,
+
+    /// Current cpu usage of the container if metrics are available.
+    cpu_usage: Option<Cpu>,
+
+    /// Current memory usage of the container if metrics are available.
+    memory_usage: Option<Memory>,
+
+    severity: Severity,
+}
+
+impl Finding for TinyRequest {
+    const DEFAULT_SEVERITY: Severity = Severity::Low;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the containers that request less than `min_cpu` or `min_memory`. Such
+/// values are usually placeholders and make the scheduler pack far more pods
+/// on a node than it can handle. Containers whose image matches the built in
+/// list of pause and sidecar images or one of the `ignore_images` globs are
+/// skipped.
+pub async fn tiny_requests(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    min_cpu: Cpu,
+    min_memory: Memory,
+    ignore_images: &[String],
+) -> Result<Vec<TinyRequest>> {
+    let ignored = IGNORED_IMAGES
+        .iter()
+        .copied()
+        .chain(ignore_images.iter().map(String::as_str))
+        .map(glob_to_regex)
+        .collect::<Vec<_>>();
+
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut findings = Vec::new();
+    for pod in &pods {
+        let namespace = pod
+            .metadata
+            .namespace
+            .as_deref()
+            .expect("failed to get namespace");
+
+        let name = pod.metadata.name.as_deref().expect("failed to get name");
+
+        let mut tiny = Vec::new();
+        for container in pod.spec.iter().flat_map(|spec| &spec.containers) {
+            if !source.checks_container(&container.name) {
+                continue;
+            }
+
+            let image = container.image.as_deref().unwrap_or_default();
+
+            if ignored.iter().any(|ignored| ignored.is_match(image)) {
+                continue;
+            }
+
+            let cpu_request = request(container, "cpu")
+                .map(Cpu::try_from)
+                .transpose()
+                .context("failed to convert cpu request")?
+                .filter(|cpu| *cpu < min_cpu);
+
+            let memory_request = request(container, "memory")
+                .map(Memory::try_from)
+                .transpose()
+                .context("failed to convert memory request")?
+                .filter(|memory| *memory < min_memory);
+
+            if cpu_request.is_some() || memory_request.is_some() {
+                tiny.push((container, cpu_request, memory_request));
+            }
+        }
+
+        if tiny.is_empty() {
+            continue;
+        }
+
+        let metrics = source
+            .pod_resource_usage(namespace, name)
+            .await
+            .context("failed to get pod resource usage")?;
+
+        for (container, cpu_request, memory_request) in tiny {
+            let (cpu_usage, memory_usage) = usage(metrics.as_ref(), &container.name)?;
+
+            findings.push(TinyRequest {
+                namespace: namespace.to_string(),
+                pod_name: name.to_string(),
+                owner: source.pod_owner(pod),
+                container_name: container.name.clone(),
+                image: container.image.clone().unwrap_or_default(),
+                cpu_request,
+                memory_request,
+                cpu_usage,
+                memory_usage,
+                severity: Severity::Low,
+            });
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Request of the container for the resource, `None` if it is not set.
+fn request<'a>(container: &'a Container, resource: &str) -> Option<&'a Quantity> {
+    container
+        .resources
+        .as_ref()
+        .and_then(|resources| resources.requests.as_ref())
+        .and_then(|requests| requests.get(resource))
+}
+
+/// Current usage of the container, `None` without metrics for the pod.
+fn usage(
+    metrics: Option<&PodMetrics>,
+    container_name: &str,
+) -> Result<(Option<Cpu>, Option<Memory>)> {
+    let Some(usage) = metrics
+        .into_iter()
+        .flat_map(|metrics| &metrics.containers)
+        .find(|container| container.name == container_name)
+        .map(|container| &container.usage)
+    else {
+        return Ok((None, None));
+    };
+
+    let cpu = Cpu::try_from(&usage.cpu).context("failed to convert cpu usage")?;
+    let memory = Memory::try_from(&usage.memory).context("failed to convert memory usage")?;
+
+    Ok((Some(cpu), Some(memory)))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        api::{Cpu, Memory},
+        source::FileSource,
+    };
+
+    #[tokio::test]
+    async fn tiny_requests() {
+        let container = |name: &str, image: &str, cpu: &str, memory: &str| {
+            json!({
+                "name": name,
+                "image": image,
+                "resources": { "requests": { "cpu": cpu, "memory": memory } },
+            })
+        };
+
+        let pods = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api-0" },
+                "spec": {
+                    "containers": [
+                        container("api", "example.com/api:1.0", "1m", "256Mi"),
+                        container("cache", "example.com/cache:1.0", "100m", "1Mi"),
+                        container("worker", "example.com/worker:1.0", "250m", "512Mi"),
+                        container("mesh", "docker.io/istio/proxyv2:1.20.0", "1m", "1Mi"),
+                        container("agent", "example.com/agent:1.0", "5m", "8Mi"),
+                    ],
+                },
+            }],
+        });
+
+        let metrics = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api-0" },
+                "timestamp": "2024-01-01T00:00:00Z",
+                "window": "30s",
+                "containers": [
+                    { "name": "api", "usage": { "cpu": "850m", "memory": "200Mi" } },
+                ],
+            }],
+        });
+
+        let source = FileSource::from_readers(
+            pods.to_string().as_bytes(),
+            Some(metrics.to_string().as_bytes()),
+        )
+        .unwrap();
+
+        let findings = super::tiny_requests(
+            &source,
+            Vec::new(),
+            true,
+            "10m".parse().unwrap(),
+            "16Mi".parse().unwrap(),
+            &["example.com/agent:*".to_string()],
+        )
+        .await
+        .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.container_name.as_str(),
+                    finding.cpu_request.map(Cpu::to_milliseconds),
+                    finding.memory_request.map(Memory::to_bytes),
+                    finding.cpu_usage.map(Cpu::to_milliseconds),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("api", Some(1), None, Some(850)),
+                ("cache", None, Some(1024 * 1024), None),
+            ],
+            findings
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Find projected service account tokens that are about to expire.
+
+use std::{collections::BTreeSet, time::Duration};
+
+use eyre::{bail, Context, Result};
+use k8s_openapi::{
+    api::core::v1::Pod,
+    chrono::{self, DateTime, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Expiration the api server uses when a token projection does not set one.
+pub(crate) const DEFAULT_EXPIRATION_SECONDS: i64 = 3600;
+
+/// Age after which the kubelet refreshes a token regardless of its expiration.
+const MAX_TOKEN_AGE_SECONDS: i64 = 24 * 60 * 60;
+
+/// Projected service account token that expires soon.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ExpiringToken {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    volume_name: String,
+    expiration_seconds: i64,
+    expires_at_estimate: String,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for ExpiringToken {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get projected service account tokens that will expire within the given
+/// duration.
+///
+/// The expiry is estimated from the start time of the pod and the
+/// `expirationSeconds` of the token projection. The kubelet refreshes a token
+/// once it reached 80% of its lifetime or is older than 24 hours, so the
+/// estimate is for the token that is currently mounted, assuming every refresh
+/// happened on time.
+pub async fn token_expiry_check(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    warn_within: Duration,
+    details: DetailOptions,
+) -> Result<Vec<ExpiringToken>> {
+    let warn_within = chrono::Duration::from_std(warn_within).context("invalid duration")?;
+    let now = Utc::now();
+
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let tokens = pods
+        .iter()
+        .flat_map(|pod| {
+            pod_expiring_tokens(source, pod, now, warn_within, &details)
+                .expect("failed to get expiring tokens")
+        })
+        .collect::<Vec<_>>();
+
+    Ok(tokens)
+}
+
+fn pod_expiring_tokens(
+    source: &dyn Source,
+    pod: &Pod,
+    now: DateTime<Utc>,
+    warn_within: chrono::Duration,
+    details: &DetailOptions,
+) -> Result<BTreeSet<ExpiringToken>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let started_at = pod
+        .status
+        .as_ref()
+        .and_then(|status| status.start_time.as_ref())
+        .or(pod.metadata.creation_timestamp.as_ref())
+        .map(|time| time.0);
+
+    let Some(started_at) = started_at else {
+        return Ok(BTreeSet::new());
+    };
+
+    let tokens = spec
+        .volumes
+        .iter()
+        .flatten()
+        .flat_map(|volume| {
+            volume
+                .projected
+                .iter()
+                .flat_map(|projected| projected.sources.iter().flatten())
+                .filter_map(|projection| projection.service_account_token.as_ref())
+                .map(move |token| {
+                    (
+                        volume,
+                        token
+                            .expiration_seconds
+                            .unwrap_or(DEFAULT_EXPIRATION_SECONDS),
+                    )
+                })
+        })
+        .map(|(volume, expiration_seconds)| {
+            let expires_at = current_expiry(started_at, now, expiration_seconds);
+            (volume, expiration_seconds, expires_at)
+        })
+        .filter(|(_, _, expires_at)| *expires_at >= now && *expires_at - now <= warn_within)
+        .map(|(volume, expiration_seconds, expires_at)| ExpiringToken {
+            namespace: pod
+                .metadata
+                .namespace
+                .as_ref()
+                .expect("failed to get namespace")
+                .clone(),
+
+            owner: source.pod_owner(pod),
+
+            pod_name: pod
+                .metadata
+                .name
+                .as_ref()
+                .expect("failed to get name")
+                .clone(),
+
+            volume_name: volume.name.clone(),
+            expiration_seconds,
+            expires_at_estimate: expires_at.to_rfc3339(),
+
+            severity: Severity::Medium,
+            details: PodDetails::new(source, pod, details),
+        })
+        .collect();
+
+    Ok(tokens)
+}
+
+/// Estimate when the token that is mounted at `now` expires. The kubelet
+/// requests the first token when the pod starts and a new one every time the
+/// current token reached 80% of its lifetime or is older than 24 hours.
+fn current_expiry(
+    started_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    expiration_seconds: i64,
+) -> DateTime<Utc> {
+    let refresh_seconds = (expiration_seconds * 8 / 10).min(MAX_TOKEN_AGE_SECONDS);
+    let age_seconds = (now - started_at).num_seconds().max(0);
+
+    let refreshes = age_seconds.checked_div(refresh_seconds).unwrap_or_default();
+    let issued_at = started_at + chrono::Duration::seconds(refreshes * refresh_seconds);
+
+    issued_at + chrono::Duration::seconds(expiration_seconds)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::{
+        api::core::v1::{
+            Pod, PodSpec, PodStatus, ProjectedVolumeSource, ServiceAccountTokenProjection, Volume,
+            VolumeProjection,
+        },
+        apimachinery::pkg::apis::meta::v1::Time,
+        chrono::{DateTime, Duration, TimeZone, Utc},
+    };
+
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    fn token_volume(name: &str, expiration_seconds: Option<i64>) -> Volume {
+        Volume {
+            name: name.to_string(),
+            projected: Some(ProjectedVolumeSource {
+                sources: Some(vec![VolumeProjection {
+                    service_account_token: Some(ServiceAccountTokenProjection {
+                        expiration_seconds,
+                        path: "token".to_string(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn pod(started_at: DateTime<Utc>) -> Pod {
+        Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("pod".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(PodSpec {
+                volumes: Some(vec![
+                    token_volume("default-expiration", None),
+                    token_volume("short", Some(600)),
+                    token_volume("long", Some(86400)),
+                ]),
+                ..Default::default()
+            }),
+
+            status: Some(PodStatus {
+                start_time: Some(Time(started_at)),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn expiring_tokens(started_at: DateTime<Utc>, now: DateTime<Utc>) -> Vec<(String, String)> {
+        super::pod_expiring_tokens(
+            &FileSource::default(),
+            &pod(started_at),
+            now,
+            Duration::minutes(10),
+            &DetailOptions::default(),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|token| (token.volume_name, token.expires_at_estimate))
+        .collect()
+    }
+
+    #[test]
+    fn pod_expiring_tokens() {
+        let started_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let now = started_at + Duration::minutes(5);
+
+        let output = super::pod_expiring_tokens(
+            &FileSource::default(),
+            &pod(started_at),
+            now,
+            Duration::minutes(10),
+            &DetailOptions::default(),
+        )
+        .unwrap();
+
+        let output = output
+            .iter()
+            .map(|token| {
+                (
+                    token.volume_name.as_str(),
+                    token.expiration_seconds,
+                    token.expires_at_estimate.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![("short", 600, "2024-01-01T00:10:00+00:00")], output);
+    }
+
+    #[test]
+    fn refreshed_tokens() {
+        let started_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        // The short token was refreshed every 8 minutes, the last time 7
+        // minutes ago. The default and the long token were refreshed recently
+        // enough to not expire soon.
+        let now = started_at + Duration::days(3) + Duration::minutes(7);
+        assert_eq!(
+            vec![("short".to_string(), "2024-01-04T00:10:00+00:00".to_string())],
+            expiring_tokens(started_at, now)
+        );
+    }
+
+    #[test]
+    fn current_expiry() {
+        let started_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let expiry = |now: DateTime<Utc>, expiration_seconds: i64| {
+            super::current_expiry(started_at, now, expiration_seconds).to_rfc3339()
+        };
+
+        assert_eq!("2024-01-01T01:00:00+00:00", expiry(started_at, 3600));
+        assert_eq!(
+            "2024-01-01T01:48:00+00:00",
+            expiry(started_at + Duration::minutes(50), 3600)
+        );
+
+        // Tokens are refreshed after 24 hours even if they live longer.
+        assert_eq!(
+            "2024-01-04T00:00:00+00:00",
+            expiry(started_at + Duration::hours(25), 2 * 86400)
+        );
+
+        // A pod that starts in the future has its first token.
+        assert_eq!(
+            "2024-01-01T01:00:00+00:00",
+            expiry(started_at - Duration::minutes(1), 3600)
+        );
+    }
+}
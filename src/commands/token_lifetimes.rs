@@ -0,0 +1,262 @@
+//! Find service account tokens that stay valid for too long.
+
+use std::collections::BTreeSet;
+
+use eyre::Result;
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{token_expiry_check::DEFAULT_EXPIRATION_SECONDS, Finding, Severity},
+    source::Source,
+};
+
+/// Type of the secrets the token controller fills with legacy tokens.
+const SERVICE_ACCOUNT_TOKEN_TYPE: &str = "kubernetes.io/service-account-token";
+
+/// Service account token of a pod with a long or unlimited lifetime.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct TokenLifetime {
+    namespace: String,
+    pod_name: String,
+    owner: Option<Owner>,
+    volume_name: String,
+    kind: TokenKind,
+
+    /// `expirationSeconds` of the token projection, empty for legacy tokens
+    /// which never expire.
+    expiration_seconds: Option<i64>,
+
+    /// Audience of the token projection, empty if it uses the api server
+    /// audience.
+    audience: Option<String>,
+
+    /// Name of the mounted secret for legacy tokens.
+    secret_name: Option<String>,
+
+    severity: Severity,
+}
+
+/// How the token gets into the pod.
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenKind {
+    /// Projected `serviceAccountToken` source whose expiration is above the
+    /// maximum.
+    Projected,
+
+    /// Secret of type `kubernetes.io/service-account-token` mounted as a
+    /// volume, the token in it never expires.
+    LegacySecret,
+}
+
+impl Finding for TokenLifetime {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the projected service account tokens with an `expirationSeconds` above
+/// `max_expiration_seconds` and the mounted legacy service account token
+/// secrets. A leaked token stays usable for as long as it is valid, legacy
+/// tokens until their secret is deleted.
+pub async fn token_lifetimes(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    max_expiration_seconds: i64,
+) -> Result<Vec<TokenLifetime>> {
+    let pods = source.pods(namespaces.clone(), all_namespaces).await?;
+    let secrets = source.secrets(namespaces, all_namespaces).await?;
+
+    let legacy_secrets = secrets
+        .iter()
+        .filter(|secret| secret.type_.as_deref() == Some(SERVICE_ACCOUNT_TOKEN_TYPE))
+        .filter_map(|secret| {
+            Some((
+                secret.metadata.namespace.as_deref()?,
+                secret.metadata.name.as_deref()?,
+            ))
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut findings = pods
+        .iter()
+        .flat_map(|pod| pod_token_lifetimes(source, pod, max_expiration_seconds, &legacy_secrets))
+        .collect::<Vec<_>>();
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn pod_token_lifetimes(
+    source: &dyn Source,
+    pod: &Pod,
+    max_expiration_seconds: i64,
+    legacy_secrets: &BTreeSet<(&str, &str)>,
+) -> Vec<TokenLifetime> {
+    let namespace = pod
+        .metadata
+        .namespace
+        .as_deref()
+        .expect("failed to get namespace");
+
+    let finding = |volume_name: &str, kind, severity| TokenLifetime {
+        namespace: namespace.to_string(),
+        pod_name: pod.metadata.name.clone().expect("failed to get name"),
+        owner: source.pod_owner(pod),
+        volume_name: volume_name.to_string(),
+        kind,
+        expiration_seconds: None,
+        audience: None,
+        secret_name: None,
+        severity,
+    };
+
+    let mut findings = Vec::new();
+    for volume in pod
+        .spec
+        .iter()
+        .flat_map(|spec| spec.volumes.iter().flatten())
+    {
+        for token in volume
+            .projected
+            .iter()
+            .flat_map(|projected| projected.sources.iter().flatten())
+            .filter_map(|projection| projection.service_account_token.as_ref())
+        {
+            let expiration_seconds = token
+                .expiration_seconds
+                .unwrap_or(DEFAULT_EXPIRATION_SECONDS);
+
+            if expiration_seconds <= max_expiration_seconds {
+                continue;
+            }
+
+            findings.push(TokenLifetime {
+                expiration_seconds: Some(expiration_seconds),
+                audience: token.audience.clone(),
+                ..finding(&volume.name, TokenKind::Projected, Severity::Medium)
+            });
+        }
+
+        let Some(secret_name) = volume
+            .secret
+            .as_ref()
+            .and_then(|secret| secret.secret_name.as_deref())
+        else {
+            continue;
+        };
+
+        if legacy_secrets.contains(&(namespace, secret_name)) {
+            findings.push(TokenLifetime {
+                secret_name: Some(secret_name.to_string()),
+                ..finding(&volume.name, TokenKind::LegacySecret, Severity::High)
+            });
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use super::TokenKind::{LegacySecret, Projected};
+    use crate::source::FileSource;
+
+    #[tokio::test]
+    async fn token_lifetimes() {
+        let token = |expiration_seconds: Option<i64>| {
+            json!({
+                "serviceAccountToken": {
+                    "path": "token",
+                    "audience": "vault",
+                    "expirationSeconds": expiration_seconds,
+                },
+            })
+        };
+
+        let pods = json!({
+            "items": [{
+                "metadata": { "namespace": "web", "name": "api-0" },
+                "spec": {
+                    "containers": [{ "name": "api" }],
+                    "volumes": [
+                        { "name": "default", "projected": { "sources": [token(None)] } },
+                        { "name": "short", "projected": { "sources": [token(Some(7200))] } },
+                        { "name": "long", "projected": { "sources": [token(Some(604_800))] } },
+                        { "name": "legacy", "secret": { "secretName": "api-token" } },
+                        { "name": "tls", "secret": { "secretName": "api-tls" } },
+                    ],
+                },
+            }],
+        });
+
+        let secrets = json!({
+            "items": [
+                {
+                    "metadata": { "namespace": "web", "name": "api-token" },
+                    "type": "kubernetes.io/service-account-token",
+                },
+                {
+                    "metadata": { "namespace": "web", "name": "api-tls" },
+                    "type": "kubernetes.io/tls",
+                },
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_secrets(secrets.to_string().as_bytes())
+            .unwrap();
+
+        let findings = super::token_lifetimes(&source, Vec::new(), true, 86400)
+            .await
+            .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.volume_name.as_str(),
+                    finding.kind,
+                    finding.expiration_seconds,
+                    finding.audience.as_deref(),
+                    finding.secret_name.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                ("legacy", LegacySecret, None, None, Some("api-token")),
+                ("long", Projected, Some(604_800), Some("vault"), None),
+            ],
+            findings
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Rank the owners of pods by their current cpu or memory usage.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BTreeMap,
+    fmt,
+    str::FromStr,
+};
+
+use eyre::{Context, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::{Cpu, Memory, Owner, PodMetrics},
+    commands::{Finding, PodPhase, Report, Severity},
+    source::Source,
+};
+
+/// Owners ranked by their usage, the highest first.
+#[derive(Debug, Serialize, PartialEq, Default, JsonSchema)]
+pub struct Output {
+    owners: Vec<TopOwner>,
+}
+
+/// Owner with the summed usage of its running pods.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct TopOwner {
+    namespace: String,
+    owner: Owner,
+
+    /// Number of running pods of the owner that usage is known for.
+    pods: usize,
+
+    /// Summed usage of all containers of the pods.
+    cpu: Cpu,
+    memory: Memory,
+
+    /// Average usage of a single pod.
+    average_cpu: Cpu,
+    average_memory: Memory,
+
+    severity: Severity,
+}
+
+/// Resource the owners are ranked by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankBy {
+    /// Highest cpu usage first.
+    Cpu,
+
+    /// Highest memory usage first.
+    Memory,
+}
+
+impl RankBy {
+    /// All resources.
+    pub const ALL: [RankBy; 2] = [RankBy::Cpu, RankBy::Memory];
+
+    /// Name of the resource as used on the command line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RankBy::Cpu => "cpu",
+            RankBy::Memory => "memory",
+        }
+    }
+
+    /// Order of the owners, the one using the most of the resource first.
+    /// Ties are broken by the other resource and then by the owner.
+    fn compare(self, a: &TopOwner, b: &TopOwner) -> Ordering {
+        let cpu = |owner: &TopOwner| Reverse(owner.cpu);
+        let memory = |owner: &TopOwner| Reverse(owner.memory);
+
+        let usage = match self {
+            RankBy::Cpu => cpu(a).cmp(&cpu(b)).then(memory(a).cmp(&memory(b))),
+            RankBy::Memory => memory(a).cmp(&memory(b)).then(cpu(a).cmp(&cpu(b))),
+        };
+
+        usage.then_with(|| a.cmp(b))
+    }
+}
+
+impl fmt::Display for RankBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RankBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RankBy::ALL
+            .into_iter()
+            .find(|rank_by| rank_by.as_str() == s)
+            .ok_or_else(|| format!("invalid resource {s}, expected one of cpu, memory"))
+    }
+}
+
+impl Report for Output {
+    type Finding = TopOwner;
+
+    fn findings(&self) -> Vec<&Self::Finding> {
+        self.owners.iter().collect()
+    }
+
+    fn retain_findings(&mut self, keep: &mut dyn FnMut(&mut Self::Finding) -> bool) {
+        self.owners.retain_mut(|owner| keep(owner));
+    }
+}
+
+impl Finding for TopOwner {
+    const DEFAULT_SEVERITY: Severity = Severity::Info;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        Some(&self.owner)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the owners of the running pods ranked by the summed current usage of
+/// their pods, like `kubectl top pods` aggregated by deployment, statefulset
+/// or daemonset. Only the metrics api is read, use `resource-requests` to
+/// compare the usage with the requests and limits. Pods without an owner or
+/// without usage are skipped. With `top` only that many owners are returned.
+pub async fn top_owners(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    rank_by: RankBy,
+    top: Option<usize>,
+) -> Result<Output> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    // Fetch the metrics of all pods at once instead of one request per pod.
+    let all_usage = if all_namespaces {
+        Some(
+            source
+                .all_pod_resource_usage()
+                .await
+                .context("failed to get resource usage of all pods")?,
+        )
+    } else {
+        None
+    };
+
+    let mut owners: BTreeMap<Owner, (usize, Cpu, Memory)> = BTreeMap::new();
+
+    for pod in &pods {
+        if !PodPhase::matches(pod, &[PodPhase::Running]) {
+            continue;
+        }
+
+        let (Some(namespace), Some(name)) = (&pod.metadata.namespace, &pod.metadata.name) else {
+            continue;
+        };
+
+        let Some(owner) = source.pod_owner(pod) else {
+            continue;
+        };
+
+        let usage = match &all_usage {
+            Some(all_usage) => all_usage.get(&(namespace.clone(), name.clone())).cloned(),
+            None => source
+                .pod_resource_usage(namespace, name)
+                .await
+                .context("failed to get pod resource usage")?,
+        };
+
+        let Some(usage) = usage else {
+            continue;
+        };
+
+        let (cpu, memory) =
+            pod_usage(source, &usage).with_context(|| format!("invalid usage of pod {name}"))?;
+
+        let entry = owners.entry(owner).or_default();
+        entry.0 += 1;
+        entry.1 = entry.1 + cpu;
+        entry.2 = entry.2 + memory;
+    }
+
+    let mut owners = owners
+        .into_iter()
+        .map(|(owner, (pods, cpu, memory))| {
+            let count = pods as u64;
+
+            TopOwner {
+                namespace: owner.namespace.clone(),
+                owner,
+                pods,
+                cpu,
+                memory,
+                average_cpu: Cpu::from(cpu.to_milliseconds() / count),
+                average_memory: Memory::from(memory.to_bytes() / count),
+                severity: Severity::Info,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    owners.sort_by(|a, b| rank_by.compare(a, b));
+
+    if let Some(top) = top {
+        owners.truncate(top);
+    }
+
+    Ok(Output { owners })
+}
+
+/// Summed usage of the checked containers of the pod.
+fn pod_usage(source: &dyn Source, usage: &PodMetrics) -> Result<(Cpu, Memory)> {
+    let mut cpu = Cpu::default();
+    let mut memory = Memory::default();
+
+    for container in usage
+        .containers
+        .iter()
+        .filter(|container| source.checks_container(&container.name))
+    {
+        cpu = cpu + Cpu::try_from(&container.usage.cpu).context("failed to convert cpu")?;
+        memory = memory
+            + Memory::try_from(&container.usage.memory).context("failed to convert memory")?;
+    }
+
+    Ok((cpu, memory))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::json;
+
+    use super::RankBy;
+    use crate::source::FileSource;
+
+    fn source() -> FileSource {
+        let pod = |namespace: &str, name: &str, owner: Option<(&str, &str)>, phase: &str| {
+            json!({
+                "metadata": {
+                    "namespace": namespace,
+                    "name": name,
+                    "ownerReferences": owner.map(|(kind, owner)| vec![json!({
+                        "apiVersion": "apps/v1",
+                        "kind": kind,
+                        "name": owner,
+                        "uid": owner,
+                        "controller": true,
+                    })]),
+                },
+                "spec": { "containers": [] },
+                "status": { "phase": phase },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("web", "api-0", Some(("StatefulSet", "api")), "Running"),
+                pod("web", "api-1", Some(("StatefulSet", "api")), "Running"),
+                pod("web", "cache-0", Some(("StatefulSet", "cache")), "Running"),
+                pod("web", "cache-1", Some(("StatefulSet", "cache")), "Pending"),
+                pod("web", "debug", None, "Running"),
+                pod("logs", "agent-a", Some(("DaemonSet", "agent")), "Running"),
+            ],
+        });
+
+        let metrics = |namespace: &str, name: &str, containers: &[(&str, &str)]| {
+            json!({
+                "metadata": { "namespace": namespace, "name": name },
+                "timestamp": "2024-01-01T00:00:00Z",
+                "window": "15s",
+                "containers": containers
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (cpu, memory))| json!({
+                        "name": format!("container-{index}"),
+                        "usage": { "cpu": cpu, "memory": memory },
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        };
+
+        let metrics = json!({
+            "items": [
+                metrics("web", "api-0", &[("100m", "64Mi"), ("50m", "16Mi")]),
+                metrics("web", "api-1", &[("250m", "80Mi")]),
+                metrics("web", "cache-0", &[("20m", "1Gi")]),
+                metrics("web", "debug", &[("900m", "2Gi")]),
+                metrics("logs", "agent-a", &[("300m", "32Mi")]),
+            ],
+        });
+
+        FileSource::from_readers(
+            pods.to_string().as_bytes(),
+            Some(metrics.to_string().as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn top_owners() {
+        let source = source();
+
+        let output = super::top_owners(&source, Vec::new(), true, RankBy::Cpu, None)
+            .await
+            .unwrap();
+
+        let owners = output
+            .owners
+            .iter()
+            .map(|owner| {
+                (
+                    owner.owner.name.as_str(),
+                    owner.pods,
+                    owner.cpu.to_milliseconds(),
+                    owner.average_cpu.to_milliseconds(),
+                    owner.average_memory.to_bytes(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // The pending cache pod and the pod without an owner are not counted.
+        assert_eq!(
+            vec![
+                ("api", 2, 400, 200, 80 * 1024 * 1024),
+                ("agent", 1, 300, 300, 32 * 1024 * 1024),
+                ("cache", 1, 20, 20, 1024 * 1024 * 1024),
+            ],
+            owners
+        );
+
+        let output = super::top_owners(&source, Vec::new(), true, RankBy::Memory, Some(2))
+            .await
+            .unwrap();
+
+        let owners = output
+            .owners
+            .iter()
+            .map(|owner| owner.owner.name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["cache", "api"], owners);
+    }
+
+    #[tokio::test]
+    async fn per_pod_usage() {
+        let source = source();
+
+        let output = super::top_owners(&source, vec!["logs".to_string()], false, RankBy::Cpu, None)
+            .await
+            .unwrap();
+
+        let owners = output
+            .owners
+            .iter()
+            .map(|owner| (owner.namespace.as_str(), owner.owner.name.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![("logs", "agent")], owners);
+    }
+}
@@ -0,0 +1,401 @@
+//! Find statefulsets and daemonsets whose updates are not rolled out to all
+//! pods.
+
+use std::time::Duration;
+
+use eyre::{Context, Result};
+use k8s_openapi::{
+    api::{
+        apps::v1::{DaemonSet, StatefulSet},
+        core::v1::Pod,
+    },
+    chrono::{self, DateTime, SecondsFormat, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{Finding, Severity},
+    humanize,
+    source::Source,
+};
+
+/// Label the controllers set on pods to the revision they were created from.
+const REVISION_LABEL: &str = "controller-revision-hash";
+
+/// Workload whose update strategy keeps pods on an old revision.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct UpdateStrategyFinding {
+    namespace: String,
+    kind: String,
+    name: String,
+    strategy: String,
+
+    /// Ordinal below which statefulset pods are not updated.
+    partition: Option<i32>,
+
+    /// Pods running the latest revision.
+    updated_replicas: i32,
+
+    replicas: i32,
+
+    /// Creation time of the oldest pod of the latest revision, when the
+    /// revisions started to differ. Only known for statefulsets with at least
+    /// one updated pod.
+    mismatch_since: Option<String>,
+
+    /// How long the revisions have been differing.
+    mismatch_for: Option<String>,
+
+    severity: Severity,
+}
+
+impl Finding for UpdateStrategyFinding {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get the statefulsets and daemonsets using the `OnDelete` update strategy,
+/// which only update pods once they are deleted by hand, and the statefulsets
+/// whose rolling update stopped at a partition for longer than `stalled_after`.
+/// A rolling update counts as stopped if the controller observed the latest
+/// generation but not all pods run the latest revision. Workloads with pods
+/// left on an old revision are reported with a higher severity.
+pub async fn update_strategies(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    stalled_after: Duration,
+) -> Result<Vec<UpdateStrategyFinding>> {
+    let stalled_after = chrono::Duration::from_std(stalled_after).context("invalid duration")?;
+    let now = Utc::now();
+
+    let statefulsets = source
+        .statefulsets(namespaces.clone(), all_namespaces)
+        .await?;
+    let daemonsets = source
+        .daemonsets(namespaces.clone(), all_namespaces)
+        .await?;
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut findings = statefulsets
+        .iter()
+        .filter_map(|statefulset| statefulset_finding(statefulset, &pods, now, stalled_after))
+        .chain(daemonsets.iter().filter_map(daemonset_finding))
+        .collect::<Vec<_>>();
+
+    findings.sort();
+
+    Ok(findings)
+}
+
+fn statefulset_finding(
+    statefulset: &StatefulSet,
+    pods: &[Pod],
+    now: DateTime<Utc>,
+    stalled_after: chrono::Duration,
+) -> Option<UpdateStrategyFinding> {
+    let spec = statefulset.spec.as_ref()?;
+
+    let strategy = spec
+        .update_strategy
+        .as_ref()
+        .and_then(|strategy| strategy.type_.clone())
+        .unwrap_or_else(|| "RollingUpdate".to_string());
+
+    let partition = spec
+        .update_strategy
+        .as_ref()
+        .and_then(|strategy| strategy.rolling_update.as_ref())
+        .and_then(|rolling_update| rolling_update.partition)
+        .filter(|partition| *partition > 0);
+
+    let status = statefulset.status.as_ref();
+    let replicas = spec.replicas.unwrap_or(1);
+    let updated_replicas = status
+        .and_then(|status| status.updated_replicas)
+        .unwrap_or_default();
+
+    let observed =
+        status.and_then(|status| status.observed_generation) == statefulset.metadata.generation;
+
+    let mismatch = observed
+        && updated_replicas < replicas
+        && status.and_then(|status| status.current_revision.as_ref())
+            != status.and_then(|status| status.update_revision.as_ref());
+
+    let mismatch_since = if mismatch {
+        mismatch_since(statefulset, pods)
+    } else {
+        None
+    };
+
+    let report = match strategy.as_str() {
+        "OnDelete" => true,
+        _ => {
+            partition.is_some()
+                && mismatch
+                && mismatch_since.map_or(true, |since| now - since >= stalled_after)
+        }
+    };
+
+    if !report {
+        return None;
+    }
+
+    Some(UpdateStrategyFinding {
+        namespace: statefulset.metadata.namespace.clone().unwrap_or_default(),
+        kind: "StatefulSet".to_string(),
+        name: statefulset.metadata.name.clone().unwrap_or_default(),
+        strategy,
+        partition,
+        updated_replicas,
+        replicas,
+        mismatch_since: mismatch_since
+            .map(|since| since.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        mismatch_for: mismatch_since.map(|since| age(now, since)),
+        severity: if mismatch {
+            Severity::Medium
+        } else {
+            Severity::Low
+        },
+    })
+}
+
+fn daemonset_finding(daemonset: &DaemonSet) -> Option<UpdateStrategyFinding> {
+    let strategy = daemonset
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.update_strategy.as_ref())
+        .and_then(|strategy| strategy.type_.clone())
+        .unwrap_or_else(|| "RollingUpdate".to_string());
+
+    if strategy != "OnDelete" {
+        return None;
+    }
+
+    let status = daemonset.status.as_ref();
+    let replicas = status.map_or(0, |status| status.desired_number_scheduled);
+    let updated_replicas = status
+        .and_then(|status| status.updated_number_scheduled)
+        .unwrap_or_default();
+
+    Some(UpdateStrategyFinding {
+        namespace: daemonset.metadata.namespace.clone().unwrap_or_default(),
+        kind: "DaemonSet".to_string(),
+        name: daemonset.metadata.name.clone().unwrap_or_default(),
+        strategy,
+        partition: None,
+        updated_replicas,
+        replicas,
+        mismatch_since: None,
+        mismatch_for: None,
+        severity: if updated_replicas < replicas {
+            Severity::Medium
+        } else {
+            Severity::Low
+        },
+    })
+}
+
+/// Creation time of the oldest pod of the statefulset that runs its update
+/// revision.
+fn mismatch_since(statefulset: &StatefulSet, pods: &[Pod]) -> Option<DateTime<Utc>> {
+    let update_revision = statefulset.status.as_ref()?.update_revision.as_deref()?;
+    let uid = statefulset.metadata.uid.as_deref()?;
+
+    pods.iter()
+        .filter(|pod| {
+            pod.metadata
+                .owner_references
+                .iter()
+                .flatten()
+                .any(|owner| owner.uid == uid)
+        })
+        .filter(|pod| {
+            pod.metadata
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get(REVISION_LABEL))
+                .is_some_and(|revision| revision == update_revision)
+        })
+        .filter_map(|pod| pod.metadata.creation_timestamp.as_ref())
+        .map(|time| time.0)
+        .min()
+}
+
+fn age(now: DateTime<Utc>, since: DateTime<Utc>) -> String {
+    humanize::duration((now - since).to_std().unwrap_or_default())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use k8s_openapi::chrono::{self, SecondsFormat, Utc};
+    use serde_json::json;
+
+    use crate::{commands::Severity, source::FileSource};
+
+    #[tokio::test]
+    #[allow(clippy::too_many_lines)]
+    async fn update_strategies() {
+        let ago = |hours: i64| {
+            (Utc::now() - chrono::Duration::hours(hours)).to_rfc3339_opts(SecondsFormat::Secs, true)
+        };
+
+        let statefulset = |name: &str, strategy: serde_json::Value, updated: i32| {
+            json!({
+                "metadata": { "namespace": "db", "name": name, "uid": name, "generation": 2 },
+                "spec": {
+                    "replicas": 3,
+                    "selector": {},
+                    "serviceName": name,
+                    "template": {},
+                    "updateStrategy": strategy,
+                },
+                "status": {
+                    "replicas": 3,
+                    "updatedReplicas": updated,
+                    "observedGeneration": 2,
+                    "currentRevision": format!("{name}-1"),
+                    "updateRevision": if updated < 3 { format!("{name}-2") } else { format!("{name}-1") },
+                },
+            })
+        };
+
+        let partitioned = |partition: i32| json!({ "type": "RollingUpdate", "rollingUpdate": { "partition": partition } });
+
+        let statefulsets = json!({
+            "items": [
+                statefulset("canary", partitioned(2), 1),
+                statefulset("fresh-canary", partitioned(2), 1),
+                statefulset("done", partitioned(0), 3),
+                statefulset("manual", json!({ "type": "OnDelete" }), 0),
+                statefulset("rolling", json!({ "type": "RollingUpdate" }), 1),
+            ],
+        });
+
+        let pod = |name: &str, owner: &str, revision: &str, hours: i64| {
+            json!({
+                "metadata": {
+                    "namespace": "db",
+                    "name": name,
+                    "creationTimestamp": ago(hours),
+                    "labels": { "controller-revision-hash": revision },
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "StatefulSet",
+                        "name": owner,
+                        "uid": owner,
+                        "controller": true,
+                    }],
+                },
+            })
+        };
+
+        let pods = json!({
+            "items": [
+                pod("canary-0", "canary", "canary-1", 100),
+                pod("canary-2", "canary", "canary-2", 50),
+                pod("fresh-canary-2", "fresh-canary", "fresh-canary-2", 1),
+            ],
+        });
+
+        let daemonsets = json!({
+            "items": [
+                {
+                    "metadata": { "namespace": "db", "name": "agent" },
+                    "spec": {
+                        "selector": {},
+                        "template": {},
+                        "updateStrategy": { "type": "OnDelete" },
+                    },
+                    "status": {
+                        "currentNumberScheduled": 4,
+                        "desiredNumberScheduled": 4,
+                        "numberMisscheduled": 0,
+                        "numberReady": 4,
+                        "updatedNumberScheduled": 4,
+                    },
+                },
+                {
+                    "metadata": { "namespace": "db", "name": "exporter" },
+                    "spec": { "selector": {}, "template": {} },
+                },
+            ],
+        });
+
+        let source = FileSource::from_readers(pods.to_string().as_bytes(), None::<&[u8]>)
+            .unwrap()
+            .with_statefulsets(statefulsets.to_string().as_bytes())
+            .unwrap()
+            .with_daemonsets(daemonsets.to_string().as_bytes())
+            .unwrap();
+
+        let findings =
+            super::update_strategies(&source, Vec::new(), true, Duration::from_secs(24 * 3600))
+                .await
+                .unwrap();
+
+        let findings = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.kind.as_str(),
+                    finding.name.as_str(),
+                    finding.strategy.as_str(),
+                    finding.partition,
+                    finding.updated_replicas,
+                    finding.mismatch_for.as_deref(),
+                    finding.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "DaemonSet",
+                    "agent",
+                    "OnDelete",
+                    None,
+                    4,
+                    None,
+                    Severity::Low
+                ),
+                (
+                    "StatefulSet",
+                    "canary",
+                    "RollingUpdate",
+                    Some(2),
+                    1,
+                    Some("2d2h"),
+                    Severity::Medium
+                ),
+                (
+                    "StatefulSet",
+                    "manual",
+                    "OnDelete",
+                    None,
+                    0,
+                    None,
+                    Severity::Medium
+                ),
+            ],
+            findings
+        );
+    }
+}
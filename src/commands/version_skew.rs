@@ -0,0 +1,229 @@
+//! Find workloads whose running pods run different images for the same
+//! container.
+
+use std::collections::BTreeMap;
+
+use eyre::Result;
+use k8s_openapi::{
+    api::core::v1::Pod,
+    chrono::{DateTime, Utc},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{Finding, PodPhase, Severity},
+    humanize,
+    source::Source,
+};
+
+/// Container of an owner that runs with more than one image.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct VersionSkew {
+    namespace: String,
+    owner: Owner,
+    container_name: String,
+    versions: Vec<ImageVersion>,
+    severity: Severity,
+}
+
+/// Image of a container together with the pods running it.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct ImageVersion {
+    image: String,
+    pod_count: usize,
+
+    /// Start time of the oldest pod running the image in RFC 3339 format.
+    oldest_pod_started: Option<String>,
+
+    /// How long the oldest pod running the image has been running, for
+    /// example `3d4h`.
+    oldest_pod_running_for: Option<String>,
+}
+
+impl Finding for VersionSkew {
+    const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        Some(&self.owner)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Start times of the pods running an image.
+type PodStarts = Vec<Option<DateTime<Utc>>>;
+
+/// Group the running pods by their owner and get the containers that run
+/// different images in different pods, for example because a rollout got
+/// stuck or a statefulset is only partially updated.
+pub async fn version_skew(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+) -> Result<Vec<VersionSkew>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mut images: BTreeMap<(String, Owner, String), BTreeMap<String, PodStarts>> =
+        BTreeMap::new();
+
+    for pod in pods
+        .iter()
+        .filter(|pod| PodPhase::matches(pod, &[PodPhase::Running]))
+    {
+        let Some(owner) = source.pod_owner(pod) else {
+            continue;
+        };
+
+        let started = started(pod);
+
+        for container in pod.spec.iter().flat_map(|spec| &spec.containers) {
+            if !source.checks_container(&container.name) {
+                continue;
+            }
+
+            images
+                .entry((
+                    owner.namespace.clone(),
+                    owner.clone(),
+                    container.name.clone(),
+                ))
+                .or_default()
+                .entry(container.image.clone().unwrap_or_default())
+                .or_default()
+                .push(started);
+        }
+    }
+
+    let now = Utc::now();
+
+    let skews = images
+        .into_iter()
+        .filter(|(_, starts_per_image)| starts_per_image.len() > 1)
+        .map(
+            |((namespace, owner, container_name), starts_per_image)| VersionSkew {
+                namespace,
+                owner,
+                container_name,
+                versions: starts_per_image
+                    .into_iter()
+                    .map(|(image, starts)| image_version(image, &starts, now))
+                    .collect(),
+                severity: Severity::Medium,
+            },
+        )
+        .collect();
+
+    Ok(skews)
+}
+
+/// When the pod was started by the kubelet, the creation time if it has not
+/// reported it.
+fn started(pod: &Pod) -> Option<DateTime<Utc>> {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.start_time.as_ref())
+        .or(pod.metadata.creation_timestamp.as_ref())
+        .map(|time| time.0)
+}
+
+fn image_version(image: String, starts: &PodStarts, now: DateTime<Utc>) -> ImageVersion {
+    let oldest = starts.iter().flatten().min();
+
+    ImageVersion {
+        image,
+        pod_count: starts.len(),
+        oldest_pod_started: oldest.map(DateTime::to_rfc3339),
+        oldest_pod_running_for: oldest
+            .map(|oldest| humanize::duration((now - *oldest).to_std().unwrap_or_default())),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use crate::{commands::Severity, source::FileSource};
+
+    const PODS: &str = include_str!("../../resources/fixtures/mixed-image-pods.json");
+
+    #[tokio::test]
+    async fn version_skew() {
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+
+        let skews = super::version_skew(&source, Vec::new(), true)
+            .await
+            .unwrap();
+
+        let skews = skews
+            .iter()
+            .map(|skew| {
+                (
+                    skew.owner.kind.as_str(),
+                    skew.owner.name.as_str(),
+                    skew.container_name.as_str(),
+                    skew.versions
+                        .iter()
+                        .map(|version| {
+                            (
+                                version.image.as_str(),
+                                version.pod_count,
+                                version.oldest_pod_started.as_deref(),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "StatefulSet",
+                    "db",
+                    "postgres",
+                    vec![
+                        ("postgres:15.4", 1, Some("2024-01-03T00:00:00+00:00")),
+                        ("postgres:15.5", 1, Some("2024-01-04T00:00:00+00:00")),
+                    ]
+                ),
+                (
+                    "ReplicaSet",
+                    "api-7c9b",
+                    "api",
+                    vec![
+                        (
+                            "registry.example.com/api:v1.4.2",
+                            2,
+                            Some("2024-01-01T08:00:00+00:00"),
+                        ),
+                        (
+                            "registry.example.com/api:v1.4.3",
+                            1,
+                            Some("2024-01-02T09:30:00+00:00"),
+                        ),
+                    ]
+                ),
+            ],
+            skews
+        );
+
+        let skews = super::version_skew(&source, vec!["shop".to_string()], false)
+            .await
+            .unwrap();
+
+        assert_eq!(1, skews.len());
+        assert_eq!(Severity::Medium, skews[0].severity);
+        assert!(skews[0].versions[0].oldest_pod_running_for.is_some());
+    }
+}
@@ -0,0 +1,216 @@
+//! Find writable volume mounts at sensitive paths.
+
+use std::collections::BTreeSet;
+
+use eyre::{bail, Result};
+use k8s_openapi::api::core::v1::Pod;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    api::Owner,
+    commands::{DetailOptions, Finding, PodDetails, Severity},
+    source::Source,
+};
+
+/// Writable volume mount at a sensitive path.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, JsonSchema)]
+pub struct WritableVolumeMount {
+    namespace: String,
+    owner: Option<Owner>,
+    pod_name: String,
+    container_name: String,
+    volume_name: String,
+    mount_path: String,
+    severity: Severity,
+
+    #[serde(flatten)]
+    details: PodDetails,
+}
+
+impl Finding for WritableVolumeMount {
+    const DEFAULT_SEVERITY: Severity = Severity::High;
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn owner(&self) -> Option<&Owner> {
+        self.owner.as_ref()
+    }
+
+    fn pod_name(&self) -> Option<&str> {
+        Some(&self.pod_name)
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+}
+
+/// Get volume mounts that are writable and mounted at or below one of the
+/// sensitive paths.
+pub async fn volume_mount_read_write(
+    source: &dyn Source,
+    namespaces: Vec<String>,
+    all_namespaces: bool,
+    sensitive_paths: Vec<String>,
+    details: DetailOptions,
+) -> Result<Vec<WritableVolumeMount>> {
+    let pods = source.pods(namespaces, all_namespaces).await?;
+
+    let mounts = pods
+        .iter()
+        .flat_map(|pod| {
+            writable_sensitive_mounts(source, pod, &sensitive_paths, &details)
+                .expect("failed to get writable volume mounts")
+        })
+        .collect::<Vec<_>>();
+
+    Ok(mounts)
+}
+
+fn writable_sensitive_mounts(
+    source: &dyn Source,
+    pod: &Pod,
+    sensitive_paths: &[String],
+    details: &DetailOptions,
+) -> Result<BTreeSet<WritableVolumeMount>> {
+    let Some(spec) = &pod.spec else {
+        bail!("Pod has no spec");
+    };
+
+    let mounts = spec
+        .containers
+        .iter()
+        .filter(|container| source.checks_container(&container.name))
+        .flat_map(|container| {
+            container
+                .volume_mounts
+                .iter()
+                .flatten()
+                .filter(|mount| !mount.read_only.unwrap_or(false))
+                .filter(|mount| is_sensitive_path(&mount.mount_path, sensitive_paths))
+                .map(|mount| WritableVolumeMount {
+                    namespace: pod
+                        .metadata
+                        .namespace
+                        .as_ref()
+                        .expect("failed to get namespace")
+                        .clone(),
+
+                    owner: source.pod_owner(pod),
+
+                    pod_name: pod
+                        .metadata
+                        .name
+                        .as_ref()
+                        .expect("failed to get name")
+                        .clone(),
+
+                    container_name: container.name.clone(),
+                    volume_name: mount.name.clone(),
+                    mount_path: mount.mount_path.clone(),
+
+                    severity: Severity::High,
+                    details: PodDetails::new(source, pod, details),
+                })
+        })
+        .collect();
+
+    Ok(mounts)
+}
+
+/// Check if the path is one of the sensitive paths or below one of them.
+fn is_sensitive_path(path: &str, sensitive_paths: &[String]) -> bool {
+    let path = path.trim_end_matches('/');
+
+    sensitive_paths.iter().any(|sensitive| {
+        let sensitive = sensitive.trim_end_matches('/');
+
+        path == sensitive
+            || path
+                .strip_prefix(sensitive)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use k8s_openapi::api::core::v1::{Container, Pod, PodSpec, VolumeMount};
+
+    use crate::{commands::DetailOptions, source::FileSource};
+
+    fn sensitive_paths() -> Vec<String> {
+        ["/etc", "/usr", "/lib", "/bin", "/sbin"]
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn is_sensitive_path() {
+        let sensitive_paths = sensitive_paths();
+
+        assert!(super::is_sensitive_path("/etc", &sensitive_paths));
+        assert!(super::is_sensitive_path("/etc/", &sensitive_paths));
+        assert!(super::is_sensitive_path("/etc/nginx", &sensitive_paths));
+        assert!(super::is_sensitive_path("/usr/local/bin", &sensitive_paths));
+        assert!(!super::is_sensitive_path("/etcd", &sensitive_paths));
+        assert!(!super::is_sensitive_path("/var/lib", &sensitive_paths));
+        assert!(!super::is_sensitive_path("/", &sensitive_paths));
+    }
+
+    #[test]
+    fn writable_sensitive_mounts() {
+        let mount = |name: &str, path: &str, read_only: Option<bool>| VolumeMount {
+            name: name.to_string(),
+            mount_path: path.to_string(),
+            read_only,
+            ..Default::default()
+        };
+
+        let pod = Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some("test".to_string()),
+                name: Some("pod".to_string()),
+                ..Default::default()
+            },
+
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "container".to_string(),
+                    volume_mounts: Some(vec![
+                        mount("config", "/etc/app", None),
+                        mount("certs", "/etc/ssl", Some(true)),
+                        mount("data", "/data", None),
+                        mount("tools", "/usr/local", Some(false)),
+                    ]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let output = super::writable_sensitive_mounts(
+            &FileSource::default(),
+            &pod,
+            &sensitive_paths(),
+            &DetailOptions::default(),
+        )
+        .unwrap();
+
+        let volumes = output
+            .iter()
+            .map(|mount| mount.volume_name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["config", "tools"], volumes);
+    }
+}
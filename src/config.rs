@@ -0,0 +1,192 @@
+//! Settings read from the `k8s-tools.toml` config file.
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use eyre::{Context, Result};
+use serde::Deserialize;
+
+use crate::{
+    commands::{resource_requests::Prices, Severity},
+    schema,
+};
+
+/// Contents of the config file. Every setting is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Severity of all findings of a check keyed by the name of the check,
+    /// for example `read-only-root-filesystem = "high"`.
+    #[serde(default)]
+    pub severity: BTreeMap<String, Severity>,
+
+    /// Unit prices for the cost estimates of `resource-requests --cost`.
+    #[serde(default)]
+    pub prices: Option<Prices>,
+}
+
+impl Config {
+    /// Read the config file at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+
+        Self::parse(&content)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+
+    /// Parse the content of a config file.
+    pub fn parse(content: &str) -> Result<Self> {
+        let config = toml::from_str(content)?;
+
+        Ok(config)
+    }
+
+    /// Starter config file with every setting commented out. It parses to
+    /// the default config, so it changes nothing until lines are uncommented.
+    pub fn generate_default() -> String {
+        let severities = Severity::ALL
+            .iter()
+            .map(|severity| severity.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut config = format!(
+            "# Settings of k8s-tools, pass the file with --config or set \
+             K8S_TOOLS_CONFIG.\n\
+             # Every setting is optional, remove the leading `#` to change one.\n\
+             \n\
+             # Unit prices for the cost estimates of `resource-requests --cost`.\n\
+             # [prices]\n\
+             # cpu-per-core-hour = 0.03\n\
+             # memory-per-gib-hour = 0.004\n\
+             # currency = \"USD\"\n\
+             \n\
+             # Severity of all findings of a check. Checks assign their own\n\
+             # severities unless they are overridden here, the values below are\n\
+             # the highest severity each check assigns. One of {severities}.\n\
+             [severity]\n"
+        );
+
+        for (name, check) in schema::checks() {
+            let severity = check.default_severity;
+
+            writeln!(config, "# {name} = \"{severity}\"")
+                .expect("writing to a string can not fail");
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::Config;
+    use crate::commands::{
+        readonly_root_filesystem::readonly_root_filesystem, DetailOptions, Finding, Report,
+        Severity, SeverityOptions,
+    };
+    use crate::source::FileSource;
+
+    const PODS: &str = include_str!("../resources/fixtures/pods.json");
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            r#"
+            [severity]
+            read-only-root-filesystem = "high"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(&Severity::High),
+            config.severity.get("read-only-root-filesystem")
+        );
+
+        assert!(config.prices.is_none());
+
+        let config = Config::parse(
+            r#"
+            [prices]
+            cpu-per-core-hour = 0.03
+            currency = "EUR"
+            "#,
+        )
+        .unwrap();
+
+        let prices = config.prices.unwrap();
+        assert!((0.03 - prices.cpu_per_core_hour).abs() < f64::EPSILON);
+        assert!(prices.memory_per_gib_hour.abs() < f64::EPSILON);
+        assert_eq!(Some("EUR"), prices.currency.as_deref());
+
+        assert!(Config::parse("[severity]\nresource-requests = \"urgent\"").is_err());
+        assert!(Config::parse("unknown = true").is_err());
+    }
+
+    #[test]
+    fn generate_default() {
+        let content = Config::generate_default();
+
+        assert!(content.contains("# read-only-root-filesystem = \"medium\"\n"));
+        assert!(content.contains("# runtime-socket = \"critical\"\n"));
+        assert!(content.contains("# volume-mount-read-write = \"high\"\n"));
+        assert!(content.contains("# tiny-requests = \"low\"\n"));
+
+        assert!(Config::parse(&content).unwrap().severity.is_empty());
+
+        let uncommented =
+            content.replace("# read-only-root-filesystem", "read-only-root-filesystem");
+        assert_eq!(
+            Some(&Severity::Medium),
+            Config::parse(&uncommented)
+                .unwrap()
+                .severity
+                .get("read-only-root-filesystem")
+        );
+    }
+
+    #[tokio::test]
+    async fn severity_override_precedence() {
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+
+        let report = || async {
+            readonly_root_filesystem(&source, Vec::new(), true, DetailOptions::default())
+                .await
+                .unwrap()
+        };
+
+        let total = report().await.findings().len();
+        assert!(total > 0);
+
+        // Without an override the default severity of the check is used.
+        let mut findings = report().await;
+        SeverityOptions::default().apply("read-only-root-filesystem", &mut findings);
+        assert_eq!(total, findings.findings().len());
+        assert!(findings
+            .findings()
+            .iter()
+            .all(|finding| finding.severity() == Severity::Medium));
+
+        // The override from the config replaces the default of the check.
+        let config = Config::parse("[severity]\nread-only-root-filesystem = \"critical\"").unwrap();
+        let options = SeverityOptions {
+            min: Severity::High,
+            overrides: config.severity,
+        };
+
+        let mut findings = report().await;
+        options.apply("read-only-root-filesystem", &mut findings);
+        assert_eq!(total, findings.findings().len());
+        assert!(findings
+            .findings()
+            .iter()
+            .all(|finding| finding.severity() == Severity::Critical));
+
+        // Overrides of other checks do not apply.
+        let mut findings = report().await;
+        options.apply("volume-mount-read-write", &mut findings);
+        assert!(findings.findings().is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+//! The document the report of a command is printed in.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::api::Impersonation;
+
+/// Report of a command together with information about the run that created
+/// it.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Document<R> {
+    /// Information about the run that created the report.
+    pub meta: Meta,
+
+    /// The report of the command.
+    pub report: R,
+}
+
+/// Output document wrapped with a title and description from the command line
+/// for reports that are stored or sent to dashboards.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Envelope<D> {
+    /// Title given with `--report-title`.
+    pub title: Option<String>,
+
+    /// Description given with `--report-description`.
+    pub description: Option<String>,
+
+    /// Time the output was printed in RFC 3339 format.
+    pub generated_at: String,
+
+    /// The output document.
+    pub data: D,
+}
+
+/// Where and when a report was created so saved reports can be traced back to
+/// the cluster they came from.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Meta {
+    /// Version of the tool that created the report.
+    pub tool_version: String,
+
+    /// Name of the command that created the report.
+    pub command: String,
+
+    /// Command line arguments the tool was called with.
+    pub args: Vec<String>,
+
+    /// Kubeconfig context the command ran against, multiple contexts are
+    /// separated by commas.
+    pub context: Option<String>,
+
+    /// Url of the api server the command ran against.
+    pub server: Option<String>,
+
+    /// Version of the api server the command ran against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_version: Option<String>,
+
+    /// User and groups impersonated with `--as` and `--as-group`, the report
+    /// shows what they can see.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impersonation: Option<Impersonation>,
+
+    /// Time the oldest api response read from the cache with `--cache` was
+    /// fetched in RFC 3339 format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_at: Option<String>,
+
+    /// Time the command started in RFC 3339 format.
+    pub timestamp: String,
+
+    /// How long the command took in seconds.
+    pub duration_seconds: f64,
+
+    /// Number of namespaces the examined pods belong to.
+    pub namespaces_examined: usize,
+
+    /// Number of pods the command examined.
+    pub pods_examined: usize,
+
+    /// Namespaces selected by `--namespace-regex` and `--exclude-namespaces`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected_namespaces: Option<Vec<String>>,
+
+    /// Notes about the data the report is based on, for example that no
+    /// metrics were available.
+    pub notes: Vec<String>,
+
+    /// Namespaces that could not be read, the report does not contain their
+    /// pods.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<NamespaceError>,
+}
+
+/// Namespace the command could not read, for example because the token has
+/// no access to it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+pub struct NamespaceError {
+    /// Kubeconfig context of the cluster the namespace belongs to when
+    /// multiple contexts are checked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+
+    /// Name of the namespace.
+    pub namespace: String,
+
+    /// Why the namespace could not be read.
+    pub error: String,
+}
+
+impl Meta {
+    /// Version of the tool as it is put into [`Meta::tool_version`].
+    pub const TOOL_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+}
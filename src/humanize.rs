@@ -0,0 +1,54 @@
+//! Formatting of values for humans reading the reports.
+
+use std::time::Duration;
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+/// Format the duration like `kubectl` formats ages by using the two largest
+/// units, for example `13d4h`, `2h5m` or `45s`. Units that are zero are left
+/// out after the largest one.
+pub fn duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    let (large, large_unit, small, small_unit) = if seconds >= DAY {
+        (seconds / DAY, "d", seconds % DAY / HOUR, "h")
+    } else if seconds >= HOUR {
+        (seconds / HOUR, "h", seconds % HOUR / MINUTE, "m")
+    } else if seconds >= MINUTE {
+        (seconds / MINUTE, "m", seconds % MINUTE, "s")
+    } else {
+        return format!("{seconds}s");
+    };
+
+    if small == 0 {
+        format!("{large}{large_unit}")
+    } else {
+        format!("{large}{large_unit}{small}{small_unit}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    #[test]
+    fn duration() {
+        let testcases = [
+            (0, "0s"),
+            (45, "45s"),
+            (60, "1m"),
+            (5 * 60 + 30, "5m30s"),
+            (2 * 3600, "2h"),
+            (2 * 3600 + 5 * 60 + 59, "2h5m"),
+            (24 * 3600, "1d"),
+            (13 * 24 * 3600 + 4 * 3600 + 59, "13d4h"),
+            (400 * 24 * 3600, "400d"),
+        ];
+
+        for (seconds, expected) in testcases {
+            assert_eq!(expected, super::duration(Duration::from_secs(seconds)));
+        }
+    }
+}
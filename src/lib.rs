@@ -0,0 +1,30 @@
+//! Checks for common misconfigurations of workloads running in kubernetes
+//! clusters.
+//!
+//! The [`commands`] return typed reports that can be serialized or inspected
+//! directly. Pods, owners and metrics are read through a [`source::Source`]
+//! which can either be a live cluster or json dumps of one.
+
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+#![warn(rust_2018_idioms, unused_lifetimes, missing_debug_implementations)]
+#![warn(clippy::dbg_macro)]
+#![warn(clippy::panic)]
+#![warn(clippy::pedantic)]
+#![warn(clippy::unwrap_used)]
+// The checks are meant to be run to completion by the binary so documenting
+// every error and panic path of them would mostly repeat the kubernetes api.
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::must_use_candidate
+)]
+
+pub mod api;
+pub mod cache;
+pub mod commands;
+pub mod config;
+pub mod document;
+pub mod humanize;
+pub mod schema;
+pub mod source;
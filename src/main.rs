@@ -6,132 +6,2595 @@
 #![warn(clippy::pedantic)]
 #![warn(clippy::unwrap_used)]
 
-use clap::{Parser, Subcommand};
-use commands::{
-    missing_health_probes::missing_health_probes,
-    readonly_root_filesystem::readonly_root_filesystem, resource_requests::resource_requests,
+use std::{num::NonZeroUsize, path::PathBuf, time::Duration};
+
+use clap::{error::ErrorKind, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use eyre::{eyre, Context, Result};
+use k8s_tools::{
+    api::{
+        context_list, contexts, set_concurrency, set_cpu_units, set_memory_units, Cpu, CpuUnits,
+        Impersonation, Memory, MemoryUnits, DEFAULT_CONCURRENCY,
+    },
+    cache::{self, CacheOptions},
+    commands::{
+        affinity_check::affinity_check,
+        container_name_conventions::{container_name_conventions, NameConventions},
+        container_port_name_check::container_port_name_check,
+        container_port_policy::{container_port_policy, PortRange},
+        container_probe_port_mismatch::container_probe_port_mismatch,
+        container_scratch_image::container_scratch_image,
+        container_working_dir::container_working_dir,
+        control_plane_pods::control_plane_pods,
+        daemonset_node_coverage::daemonset_node_coverage,
+        deployment_image_consistency::deployment_image_consistency,
+        deprecated_annotations::deprecated_annotations,
+        endpoint_readiness::endpoint_readiness,
+        fit::fit,
+        ingress_tls_check::ingress_tls_check,
+        init_container_health::init_container_health,
+        job_parallelism_check::{job_parallelism_check, ParallelismFlags},
+        leaking_cronjobs::leaking_cronjobs,
+        liveness_readiness_consistency::liveness_readiness_consistency,
+        missing_health_probes::{missing_health_probes, MissingProbes},
+        namespace_resource_balance::namespace_resource_balance,
+        node_allocatable_summary::node_allocatable_summary,
+        object_sizes::object_sizes,
+        orphan_replicasets::orphan_replicasets,
+        pod_anti_colocate::pod_anti_colocate,
+        pod_completion_rate::pod_completion_rate,
+        pod_cpu_throttling_indicator::pod_cpu_throttling_indicator,
+        pod_dns_policy::pod_dns_policy,
+        pod_dns_search_domains::pod_dns_search_domains,
+        pod_graceful_shutdown_test::pod_graceful_shutdown_test,
+        pod_ip_family::pod_ip_family,
+        readiness_gates::readiness_gates,
+        readonly_root_filesystem::readonly_root_filesystem,
+        requests_drift::requests_drift,
+        resource_pressure_events::resource_pressure_events,
+        resource_request_rightsizing::resource_request_rightsizing,
+        resource_requests::{resource_requests, Baseline, Prices},
+        runtime_socket::runtime_socket,
+        secret_type_check::secret_type_check,
+        service_account_privilege::service_account_privilege,
+        service_exposure::service_exposure,
+        service_links::service_links,
+        sidecar_injection_check::{sidecar_injection_check, SidecarExpectation},
+        statefulset_pod_management_policy::{statefulset_pod_management_policy, PolicyFlags},
+        stuck_finalizers::stuck_finalizers,
+        termination_messages::termination_messages,
+        tiny_requests::tiny_requests,
+        token_expiry_check::token_expiry_check,
+        token_lifetimes::token_lifetimes,
+        top_owners::{top_owners, RankBy},
+        update_strategies::update_strategies,
+        version_skew::version_skew,
+        volume_mount_read_write::volume_mount_read_write,
+        DetailOptions, PodPhase, Severity, SeverityOptions, SortBy,
+    },
+    config::Config,
+    document::Meta,
+    schema,
+    source::{FileSource, NamespaceSelector, OptionalFiles},
 };
-use eyre::{Context, Result};
-use log::LevelFilter;
+use log::{info, warn, LevelFilter};
+use notify::NotifyOptions;
+use output::{EnvelopeOptions, OutputMode, OutputOptions};
+use regex::Regex;
+use serde::Serialize;
+use target::{Target, TargetKind};
+use watch::Watch;
 
-mod api;
-mod commands;
+mod notify;
+mod output;
+mod target;
+mod watch;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
+#[allow(clippy::struct_excessive_bools)]
 struct Args {
     /// The log level to run under.
     #[arg(long, env, default_value = "info")]
     pub log_level: LevelFilter,
 
+    /// Include all labels of the pods in the output.
+    #[arg(long, visible_alias = "show-labels", global = true)]
+    output_labels: bool,
+
+    /// Include the given labels of the pods in the output, labels a pod does
+    /// not have are included as null. Multiple labels are separated by commas.
+    #[arg(long, global = true, value_delimiter = ',')]
+    label_columns: Vec<String>,
+
+    /// Include all owners of the pods up to the top level owner in the
+    /// output.
+    #[arg(long, global = true)]
+    output_owner_chain: bool,
+
+    /// Read the pods from a json pod list (for example from `kubectl get pods
+    /// --all-namespaces -o json`) instead of the cluster. Use `-` to read from
+    /// stdin.
+    #[arg(long, global = true)]
+    from_file: Option<PathBuf>,
+
+    /// Read the pod metrics from a json pod metrics list (for example from
+    /// `kubectl get --raw /apis/metrics.k8s.io/v1beta1/pods`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    metrics_file: Option<PathBuf>,
+
+    /// Read the nodes from a json node list (for example from `kubectl get
+    /// nodes -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    nodes_file: Option<PathBuf>,
+
+    /// Read the namespaces from a json namespace list (for example from
+    /// `kubectl get namespaces -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    namespaces_file: Option<PathBuf>,
+
+    /// Read the statefulsets from a json statefulset list (for example from
+    /// `kubectl get statefulsets --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    statefulsets_file: Option<PathBuf>,
+
+    /// Read the daemonsets from a json daemonset list (for example from
+    /// `kubectl get daemonsets --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    daemonsets_file: Option<PathBuf>,
+
+    /// Read the jobs from a json job list (for example from `kubectl get jobs
+    /// --all-namespaces -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    jobs_file: Option<PathBuf>,
+
+    /// Read the ingresses from a json ingress list (for example from `kubectl
+    /// get ingresses --all-namespaces -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    ingresses_file: Option<PathBuf>,
+
+    /// Read the cronjobs from a json cronjob list (for example from `kubectl
+    /// get cronjobs --all-namespaces -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    cronjobs_file: Option<PathBuf>,
+
+    /// Read the services from a json service list (for example from `kubectl
+    /// get services --all-namespaces -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    services_file: Option<PathBuf>,
+
+    /// Read the secrets from a json secret list (for example from
+    /// `kubectl get secrets --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    secrets_file: Option<PathBuf>,
+
+    /// Read the service accounts from a json service account list (for example
+    /// from `kubectl get serviceaccounts --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    service_accounts_file: Option<PathBuf>,
+
+    /// Read the persistent volume claims from a json persistent volume claim list (for example from
+    /// `kubectl get persistentvolumeclaims --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    persistent_volume_claims_file: Option<PathBuf>,
+
+    /// Read the endpoint slices from a json endpoint slice list (for example
+    /// from `kubectl get endpointslices --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    endpoint_slices_file: Option<PathBuf>,
+
+    /// Read the events from a json event list (for example from `kubectl get
+    /// events --all-namespaces -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    events_file: Option<PathBuf>,
+
+    /// Read the config maps from a json config map list (for example from
+    /// `kubectl get configmaps --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    config_maps_file: Option<PathBuf>,
+
+    /// Read the role bindings from a json role binding list (for example from
+    /// `kubectl get rolebindings --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    role_bindings_file: Option<PathBuf>,
+
+    /// Read the cluster roles from a json cluster role list (for example from
+    /// `kubectl get clusterroles -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    cluster_roles_file: Option<PathBuf>,
+
+    /// Read the cluster role bindings from a json cluster role binding list
+    /// (for example from `kubectl get clusterrolebindings -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    cluster_role_bindings_file: Option<PathBuf>,
+
+    /// Read the vertical pod autoscalers from a json vertical pod autoscaler
+    /// list (for example from `kubectl get verticalpodautoscalers
+    /// --all-namespaces -o json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    vertical_pod_autoscalers_file: Option<PathBuf>,
+
+    /// Read the horizontal pod autoscalers from a json horizontal pod
+    /// autoscaler list (for example from `kubectl get hpa --all-namespaces -o
+    /// json`) when using `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    horizontal_pod_autoscalers_file: Option<PathBuf>,
+
+    /// Read the deployments from a json deployment list (for example from
+    /// `kubectl get deployments --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    deployments_file: Option<PathBuf>,
+
+    /// Read the replica sets from a json replica set list (for example from
+    /// `kubectl get replicasets --all-namespaces -o json`) when using
+    /// `--from-file`.
+    #[arg(long, global = true, requires = "from_file")]
+    replica_sets_file: Option<PathBuf>,
+
+    /// Do not resolve the owners of pods. Speeds up the commands when the
+    /// owners are not needed, the owner fields are left empty.
+    #[arg(long, global = true)]
+    no_owner: bool,
+
+    /// Only check namespaces matching the regex. Without `--namespaces` the
+    /// regex is matched against all namespaces of the cluster.
+    #[arg(long, global = true)]
+    namespace_regex: Option<Regex>,
+
+    /// Only check containers whose name matches the regex. Pods without a
+    /// matching container are skipped. Sums of requests and usage only count
+    /// the matching containers. Checks of the pod as a whole still see all of
+    /// its containers: readiness-gates reads the readiness of the pod,
+    /// sidecar-injection-check looks for the sidecar next to the matching
+    /// containers and fit counts every container as taking up room on the
+    /// node.
+    #[arg(long, global = true)]
+    filter_container: Option<Regex>,
+
+    /// Title to wrap the json output in together with the description and the
+    /// time it was generated.
+    #[arg(long, global = true)]
+    report_title: Option<String>,
+
+    /// Description to wrap the json output in together with the title and the
+    /// time it was generated.
+    #[arg(long, global = true)]
+    report_description: Option<String>,
+
+    /// Cache the pods, owners and metrics read from clusters on disk and reuse
+    /// them in the following runs, for example while iterating on filters.
+    #[arg(long, global = true)]
+    cache: bool,
+
+    /// How long cached responses are reused, for example `120s` or `5m`.
+    #[arg(
+        long,
+        global = true,
+        default_value = "120s",
+        value_parser = humantime::parse_duration
+    )]
+    cache_ttl: Duration,
+
+    /// Fetch everything again and replace the cached responses.
+    #[arg(long, global = true, requires = "cache")]
+    refresh: bool,
+
+    /// Do not use the cache even if `--cache` is given.
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Exit with an error if some namespaces could not be read. Without it
+    /// they are skipped and listed in the errors of the output.
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Skip namespaces matching the glob (`*` and `?` are supported), can be
+    /// given multiple times. Without `--namespaces` all namespaces of the
+    /// cluster except the excluded ones are checked.
+    #[arg(long, global = true)]
+    exclude_namespaces: Vec<String>,
+
+    /// Units memory amounts are printed in: iec (GiB), si (GB) or bytes (plain
+    /// integers). Fields ending in `_bytes` are always plain integers.
+    #[arg(long, global = true, default_value_t = MemoryUnits::Iec)]
+    memory_units: MemoryUnits,
+
+    /// Units cpu amounts are printed in: millicores (1500m) or cores (1.5).
+    /// Fields ending in `_milliseconds` are always plain millicores.
+    #[arg(long, global = true, default_value_t = CpuUnits::Millicores)]
+    cpu_units: CpuUnits,
+
+    /// Maximum number of kubernetes api requests that are in flight at the
+    /// same time.
+    #[arg(long, global = true, default_value_t = DEFAULT_CONCURRENCY)]
+    concurrency: NonZeroUsize,
+
+    /// Use the given kubeconfig context instead of the current one.
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["contexts", "all_contexts", "from_file"]
+    )]
+    context: Option<String>,
+
+    /// Run the command against each of the given kubeconfig contexts and nest
+    /// the results by context name.
+    #[arg(
+        long,
+        global = true,
+        value_delimiter = ',',
+        conflicts_with_all = ["all_contexts", "from_file"]
+    )]
+    contexts: Vec<String>,
+
+    /// Run the command against all contexts of the kubeconfig and nest the
+    /// results by context name.
+    #[arg(long, global = true, conflicts_with = "from_file")]
+    all_contexts: bool,
+
+    /// Number of clusters the command runs against at the same time when using
+    /// `--contexts` or `--all-contexts`.
+    #[arg(long, global = true, default_value = "1")]
+    parallel_clusters: NonZeroUsize,
+
+    /// Send all requests to the cluster as the given user like `kubectl
+    /// --as`, for example `system:serviceaccount:team-a:auditor` to check what
+    /// a tenant can see.
+    #[arg(long = "as", global = true, conflicts_with = "from_file")]
+    as_user: Option<String>,
+
+    /// Group to impersonate together with `--as`, can be given multiple
+    /// times.
+    #[arg(long = "as-group", global = true, requires = "as_user")]
+    as_groups: Vec<String>,
+
+    /// Read settings like severity overrides from the given config file.
+    #[arg(long, env = "K8S_TOOLS_CONFIG", global = true)]
+    config: Option<PathBuf>,
+
+    /// Only report findings with at least this severity (critical, high,
+    /// medium, low or info).
+    #[arg(long, global = true, default_value_t = Severity::Info)]
+    min_severity: Severity,
+
+    /// Sort the findings by namespace, owner, pod or count. `count` puts the
+    /// namespaces and owners with the most findings first.
+    #[arg(long, global = true)]
+    sort_by: Option<SortBy>,
+
+    /// Only print the number of findings per namespace, owner and severity
+    /// instead of the findings themselves.
+    #[arg(long, global = true, conflicts_with_all = ["count", "watch_interval"])]
+    summary_only: bool,
+
+    /// Only print the total number of findings.
+    #[arg(long, global = true, conflicts_with = "watch_interval")]
+    count: bool,
+
+    /// Run the command again after waiting for the given duration, for
+    /// example `30s` or `5m`, and only print the findings that appeared or
+    /// disappeared since the previous run.
+    #[arg(long, global = true, value_parser = humantime::parse_duration)]
+    watch_interval: Option<Duration>,
+
+    #[command(flatten)]
+    notify: NotifyOptions,
+
     #[command(subcommand)]
     command: Command,
 }
 
-#[derive(Debug, Subcommand)]
+/// Output of the `version` subcommand.
+#[derive(Debug, Serialize)]
+struct Version {
+    tool_version: &'static str,
+    git_sha: &'static str,
+
+    /// Kubernetes version the api types were built for.
+    k8s_openapi_version: &'static str,
+
+    server_version: Option<String>,
+}
+
+/// Namespaces the pods and other objects are read from, shared by all
+/// subcommands.
+#[derive(Debug, Clone, clap::Args)]
+struct NamespaceSelection {
+    /// Check the given namespaces if not defined the current one will be
+    /// used. Can be given multiple times or as a comma separated list.
+    #[arg(
+        name = "namespaces",
+        short = 'n',
+        long,
+        visible_alias = "namespace",
+        value_delimiter = ',',
+        required = false,
+        conflicts_with = "all-namespaces"
+    )]
+    namespaces: Vec<String>,
+
+    /// Check all namespaces.
+    #[arg(
+        name = "all-namespaces",
+        long,
+        required = false,
+        conflicts_with = "namespaces"
+    )]
+    all_namespaces: bool,
+}
+
+#[derive(Debug, Clone, Subcommand)]
 enum Command {
     /// Get pods in the current namespace that have missing health (liveness,
     /// readiness) probes.
     MissingHealthProbes {
-        /// Check the given namespaces if not defined the current one will be
-        /// used.
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Only show containers missing these probes, any of
+        /// `liveness-only`, `readiness-only` and `both`.
+        #[arg(long, value_delimiter = ',', default_value = "both")]
+        missing: Vec<MissingProbes>,
+    },
+
+    /// Check for containers without a preStop hook or pods with a too short
+    /// termination grace period to shut down gracefully.
+    PodGracefulShutdownTest {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Get the resource requests for pods in the current namespace.
+    ResourceRequests {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Threshold for displaying containers. Will calculate the difference
+        /// between the request and the current cpu usage if thats
+        /// bigger than the threshold the container will be displayed.
+        /// When not specified will print all pods.
+        #[arg(name = "threshold", long, required = false)]
+        threshold: Option<u64>,
+
+        /// Disable checking for higher cpu usage than the request.
+        #[arg(name = "no-check-higher", long, required = false)]
+        no_check_higher: bool,
+
+        /// Add statistics (sum, min, max, median) over the resources of the
+        /// containers to the namespace and owner totals and the replicas and
+        /// average requests per replica to the owner totals.
+        #[arg(name = "aggregate-by-owner", long, required = false)]
+        aggregate_by_owner: bool,
+
+        /// Only show containers using more than 90% of their cpu limit.
+        #[arg(name = "flag-near-limits", long, required = false)]
+        flag_near_limits: bool,
+
+        /// Only show containers without a cpu or memory limit.
+        #[arg(name = "no-limits-only", long, required = false)]
+        no_limits_only: bool,
+
+        /// Include pods in these phases, for example `pending,running` to see
+        /// requests of pods that are not running yet. Only running pods have
+        /// usage.
+        #[arg(
+            name = "include-phases",
+            long,
+            value_delimiter = ',',
+            default_value = "running"
+        )]
+        include_phases: Vec<PodPhase>,
+
+        /// Only list the requests and limits without querying the metrics api
+        /// for the usage, for example when metrics-server is down.
         #[arg(
-            name = "namespaces",
+            name = "no-usage",
             long,
             required = false,
-            conflicts_with = "all-namespaces"
+            conflicts_with_all = ["threshold", "no-check-higher", "flag-near-limits"]
         )]
-        namespaces: Vec<String>,
+        no_usage: bool,
+
+        /// Compare with a report previously saved from this command and add
+        /// the growth of the requests and usage per container, for example to
+        /// track the requests week over week.
+        #[arg(name = "baseline-file", long, required = false)]
+        baseline_file: Option<PathBuf>,
 
-        /// Check all namespaces.
+        /// Add totals per value of this pod label, for example `team` to see
+        /// the requests of every team across namespaces.
+        #[arg(name = "aggregate-by-label", long, required = false)]
+        aggregate_by_label: Option<String>,
+
+        /// Add the requests of every owner when scaled to the maximum replicas
+        /// of its horizontal pod autoscaler to the owner totals.
         #[arg(
-            name = "all-namespaces",
+            name = "project-hpa-max",
             long,
             required = false,
-            conflicts_with = "namespaces"
+            requires = "aggregate-by-owner"
         )]
-        all_namespaces: bool,
+        project_hpa_max: bool,
+
+        /// Add the estimated monthly cost of the requests, the usage and the
+        /// unused requests to the totals. The prices are taken from the
+        /// `prices` section of the config file or the price flags.
+        #[arg(long, required = false)]
+        cost: bool,
+
+        /// Price of one cpu core for an hour, overrides the config file.
+        #[arg(name = "cpu-price-per-core-hour", long, requires = "cost")]
+        cpu_price_per_core_hour: Option<f64>,
+
+        /// Price of one GiB of memory for an hour, overrides the config file.
+        #[arg(name = "memory-price-per-gib-hour", long, requires = "cost")]
+        memory_price_per_gib_hour: Option<f64>,
+
+        /// Label of the currency the prices are in, for example `EUR`.
+        #[arg(long, requires = "cost")]
+        currency: Option<String>,
     },
 
-    /// Get the resource requests for pods in the current namespace.
-    ResourceRequests {
-        /// Check the given namespaces if not defined the current one will be
-        /// used.
+    /// Check for owners whose running pods do not all have the same resource
+    /// requests and limits.
+    RequestsDrift {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Compare the resource requests of workloads with the targets
+    /// recommended by their vertical pod autoscalers.
+    ResourceRequestRightsizing {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Rank the owners of running pods by their current cpu or memory usage
+    /// summed over their pods, like `kubectl top pods` per deployment,
+    /// statefulset or daemonset. Use `resource-requests` to compare the usage
+    /// with the requests and limits.
+    TopOwners {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Rank the owners by `cpu` or `memory` usage.
+        #[arg(long, default_value_t = RankBy::Cpu)]
+        rank_by: RankBy,
+
+        /// Only show the given number of owners with the highest usage.
+        #[arg(long)]
+        top: Option<usize>,
+    },
+
+    /// Check if pods are running with a read-only root filesystem.
+    ReadOnlyRootFilesystem {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check if containers have writable volume mounts at sensitive paths.
+    VolumeMountReadWrite {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Paths that should not be mounted writable. Mounts below these paths
+        /// are reported as well.
         #[arg(
-            name = "namespaces",
+            name = "sensitive-path",
             long,
-            required = false,
-            conflicts_with = "all-namespaces"
+            default_values = ["/etc", "/usr", "/lib", "/bin", "/sbin"]
         )]
-        namespaces: Vec<String>,
+        sensitive_paths: Vec<String>,
+    },
 
-        /// Check all namespaces.
+    /// Check for containers whose working directory is at a privileged path.
+    ContainerWorkingDir {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Working directories that are reported, directories below them are
+        /// reported as well. `/` only matches the root itself.
         #[arg(
-            name = "all-namespaces",
+            name = "forbidden-path-prefix",
             long,
-            required = false,
-            conflicts_with = "namespaces"
+            default_values = ["/", "/etc", "/proc", "/sys"]
         )]
-        all_namespaces: bool,
+        forbidden_path_prefixes: Vec<String>,
 
-        /// Threshold for displaying containers. Will calculate the difference
-        /// between the request and the current cpu usage if thats
-        /// bigger than the threshold the container will be displayed.
-        /// When not specified will print all pods.
-        #[arg(name = "threshold", long, required = false)]
-        threshold: Option<u64>,
+        /// Only report containers whose working directory is `/`.
+        #[arg(name = "flag-root-workdir", long)]
+        flag_root_workdir: bool,
+    },
 
-        /// Disable checking for higher cpu usage than the request.
-        #[arg(name = "no-check-higher", long, required = false)]
-        no_check_higher: bool,
+    /// Check for container ports without a name.
+    ContainerPortNameCheck {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Only report unnamed ports with these numbers, for example
+        /// `8080,9090`. All unnamed ports are reported if not given.
+        #[arg(name = "require-names-for-ports", long, value_delimiter = ',')]
+        require_names_for_ports: Vec<i32>,
     },
 
-    /// Check if pods are running with a read-only root filesystem.
-    ReadOnlyRootFilesystem {
-        /// Check the given namespaces if not defined the current one will be
-        /// used.
+    /// Check if http probes of containers target ports that the container
+    /// does not define.
+    ContainerProbePortMismatch {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check if container names follow naming conventions.
+    ContainerNameConventions {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Regex the whole container name has to match, for example
+        /// `[a-z0-9]+(-[a-z0-9]+)*` for kebab-case.
+        #[arg(name = "pattern", long, required_unless_present = "max-length")]
+        pattern: Option<String>,
+
+        /// Maximum length of container names.
+        #[arg(name = "max-length", long)]
+        max_length: Option<usize>,
+    },
+
+    /// Check for containers whose liveness probe fails faster than their
+    /// readiness probe.
+    LivenessReadinessConsistency {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Compare the resource requests of namespaces with the allocatable
+    /// resources of the cluster.
+    NamespaceResourceBalance {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Flag namespaces that request more than this percentage of the cpu
+        /// or memory of the cluster.
+        #[arg(name = "max-namespace-pct", long, default_value = "25")]
+        max_namespace_pct: f64,
+    },
+
+    /// Show the allocatable resources of every node compared to the requests
+    /// of the pods running on it.
+    NodeAllocatableSummary {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Estimate how many pods of the given size fit on the nodes next to the
+    /// requests of the running pods of all namespaces.
+    Fit {
+        /// Cpu request of a single pod.
+        #[arg(long, default_value = "0")]
+        cpu: Cpu,
+
+        /// Memory request of a single pod.
+        #[arg(long, default_value = "0")]
+        memory: Memory,
+
+        /// Number of pods that should fit, for example the planned replicas
+        /// of a deployment.
+        #[arg(long)]
+        replicas: Option<u64>,
+
+        /// Only count nodes with these labels, `key=value` pairs separated by
+        /// commas.
+        #[arg(name = "node-selector", long, value_delimiter = ',')]
+        node_selector: Vec<String>,
+    },
+
+    /// Check for config maps and secrets that get close to the size limit of
+    /// objects.
+    ObjectSizes {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report objects whose data is larger than this many bytes, objects
+        /// can be at most 1MiB.
+        #[arg(name = "warn-bytes", long, default_value_t = 512 * 1024)]
+        warn_bytes: u64,
+
+        /// Report objects whose annotations are larger than this many bytes,
+        /// annotations can be at most 256KiB.
+        #[arg(name = "warn-annotation-bytes", long, default_value_t = 128 * 1024)]
+        warn_annotation_bytes: u64,
+    },
+
+    /// Check for replica sets whose deployment does not exist anymore.
+    OrphanReplicasets {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Show the nodes with evictions, out of memory kills or failed disk
+    /// cleanups during the last day.
+    ResourcePressureEvents,
+
+    /// Check for pods with a dns policy that does not resolve cluster names.
+    PodDnsPolicy {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for pods with more dns search domains than the resolver handles.
+    PodDnsSearchDomains {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report pods with more search domains than this.
+        #[arg(name = "max-search-domains", long, default_value = "6")]
+        max_search_domains: usize,
+    },
+
+    /// Check for pods that only got an ipv4 or only an ipv6 address in a
+    /// dual-stack cluster.
+    PodIpFamily {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for required pod affinity and anti affinity terms that match no
+    /// pods or use a topology key no node has.
+    AffinityCheck {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for workload pods scheduled on control plane nodes.
+    ControlPlanePods {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Do not report pods in these namespaces, for example the system
+        /// namespaces whose pods belong on the control plane.
         #[arg(
-            name = "namespaces",
+            name = "ignore-namespaces",
             long,
-            required = false,
-            conflicts_with = "all-namespaces"
+            value_delimiter = ',',
+            default_value = "kube-system"
         )]
-        namespaces: Vec<String>,
+        ignore_namespaces: Vec<String>,
+    },
+
+    /// Check for nodes a daemonset has no running pod on.
+    DaemonsetNodeCoverage {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
 
-        /// Check all namespaces.
+    /// Check for deployments whose running pods use different images for the
+    /// same container.
+    DeploymentImageConsistency {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for services with many endpoints that are not ready or
+    /// terminating.
+    EndpointReadiness {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Only count endpoints that have been not ready or terminating for
+        /// longer than this duration, for example `5m` or `1h`.
         #[arg(
-            name = "all-namespaces",
+            name = "for",
             long,
-            required = false,
-            conflicts_with = "namespaces"
+            default_value = "5m",
+            value_parser = humantime::parse_duration
+        )]
+        not_ready_for: Duration,
+
+        /// Report services where at least this percentage of the endpoints
+        /// is not ready.
+        #[arg(name = "min-not-ready-pct", long, default_value = "25")]
+        min_not_ready_pct: u64,
+    },
+
+    /// Check for jobs whose parallelism does not fit their number of
+    /// completions.
+    JobParallelismCheck {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Only report jobs running many completions one pod at a time.
+        #[arg(name = "flag-single-thread", long)]
+        flag_single_thread: bool,
+
+        /// Only report jobs with a higher parallelism than completions.
+        #[arg(name = "flag-over-parallel", long)]
+        flag_over_parallel: bool,
+
+        /// Number of completions from which a job running one pod at a time
+        /// is reported.
+        #[arg(name = "serial-completions", long, default_value = "10")]
+        serial_completions: i32,
+    },
+
+    /// Check for cronjobs that keep more jobs than their history limits.
+    LeakingCronjobs {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Number of jobs a cronjob may keep above the sum of its
+        /// `successfulJobsHistoryLimit` and `failedJobsHistoryLimit`.
+        #[arg(name = "threshold", long, default_value_t = 5)]
+        threshold: usize,
+    },
+
+    /// Check for jobs whose pods fail too often.
+    PodCompletionRate {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Only check jobs started within this duration, for example `24h` or
+        /// `7d`.
+        #[arg(
+            name = "since",
+            long,
+            default_value = "24h",
+            value_parser = humantime::parse_duration
+        )]
+        since: Duration,
+
+        /// Report jobs where less than this share of the finished pods
+        /// succeeded, between 0 and 1.
+        #[arg(name = "min-success-rate", long, default_value = "0.95")]
+        min_success_rate: f64,
+    },
+
+    /// Check for pods whose readiness gates have not been satisfied for too
+    /// long, so they never receive traffic.
+    ReadinessGates {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Only report gates that have been missing or not true for longer
+        /// than this duration, for example `5m` or `1h`.
+        #[arg(
+            name = "for",
+            long,
+            default_value = "5m",
+            value_parser = humantime::parse_duration
+        )]
+        unsatisfied_for: Duration,
+    },
+
+    /// Check for statefulsets that do not create and update their pods one at
+    /// a time.
+    StatefulsetPodManagementPolicy {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Only report statefulsets using the `Parallel` pod management
+        /// policy.
+        #[arg(name = "flag-parallel", long)]
+        flag_parallel: bool,
+
+        /// Only report statefulsets whose rolling updates replace more than
+        /// one pod at a time.
+        #[arg(name = "flag-burst", long)]
+        flag_burst: bool,
+    },
+
+    /// Check for containers whose termination message is empty when they
+    /// crash.
+    TerminationMessages {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for containers with cpu or memory requests so small they are
+    /// likely placeholders.
+    TinyRequests {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report containers that request less cpu than this.
+        #[arg(name = "min-cpu", long, default_value = "10m")]
+        min_cpu: Cpu,
+
+        /// Report containers that request less memory than this.
+        #[arg(name = "min-memory", long, default_value = "16Mi")]
+        min_memory: Memory,
+
+        /// Skip containers whose image matches the glob, for example
+        /// `registry.example.com/agent:*`. Pause and common proxy sidecar
+        /// images are always skipped.
+        #[arg(name = "ignore-image", long)]
+        ignore_images: Vec<String>,
+    },
+
+    /// Check for projected service account tokens that expire soon.
+    TokenExpiryCheck {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report tokens that are estimated to expire within this duration,
+        /// for example `30m` or `2h`.
+        #[arg(
+            name = "warn-within",
+            long,
+            default_value = "1h",
+            value_parser = humantime::parse_duration
+        )]
+        warn_within: Duration,
+    },
+
+    /// Check for projected service account tokens that are valid for too long
+    /// and for mounted legacy service account token secrets.
+    TokenLifetimes {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report projected tokens whose `expirationSeconds` is above this
+        /// value.
+        #[arg(name = "max-expiration-seconds", long, default_value_t = 86400)]
+        max_expiration_seconds: i64,
+    },
+
+    /// Check for statefulsets and daemonsets whose update strategy leaves pods
+    /// on an old revision.
+    UpdateStrategies {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report statefulsets whose rolling update stopped at a partition for
+        /// longer than this duration, for example `12h` or `7d`.
+        #[arg(
+            name = "stalled-after",
+            long,
+            default_value = "24h",
+            value_parser = humantime::parse_duration
+        )]
+        stalled_after: Duration,
+    },
+
+    /// Check for owners whose running pods use different images for the same
+    /// container.
+    VersionSkew {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for pods, persistent volume claims and namespaces that are stuck
+    /// deleting because of their finalizers.
+    StuckFinalizers {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report objects whose deletion started longer ago than this
+        /// duration, for example `5m` or `1h`.
+        #[arg(
+            name = "grace",
+            long,
+            default_value = "5m",
+            value_parser = humantime::parse_duration
+        )]
+        grace: Duration,
+    },
+
+    /// Check for containers exposing ports outside of the allowed port ranges.
+    ContainerPortPolicy {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Range of ports containers may expose, for example `8000-8999` or
+        /// `443`. Can be given multiple times.
+        #[arg(name = "allowed-port-range", long, required = true)]
+        allowed_port_range: Vec<PortRange>,
+    },
+
+    /// Check for ingresses without tls or without a certificate secret.
+    IngressTlsCheck {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check pods, services and ingresses for annotations that were replaced
+    /// by typed fields.
+    DeprecatedAnnotations {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for init containers that run for too long or are in
+    /// `CrashLoopBackOff`.
+    InitContainerHealth {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report init containers that have been running for longer than this
+        /// duration, for example `10m` or `1h`.
+        #[arg(
+            name = "max-duration",
+            long,
+            default_value = "10m",
+            value_parser = humantime::parse_duration
+        )]
+        max_duration: Duration,
+    },
+
+    /// Check for containers mounting the docker, containerd or cri-o socket
+    /// from the node.
+    RuntimeSocket {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for empty opaque secrets, tls secrets without a valid
+    /// certificate and service account tokens without a service account.
+    SecretTypeCheck {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// Check for pods whose service account is bound to `cluster-admin`,
+    /// `admin`, `edit` or a cluster role allowing all verbs.
+    ServiceAccountPrivilege {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// List the load balancer and node port services per namespace and check
+    /// for pending load balancers and node ports in the ephemeral port range.
+    ServiceExposure {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report load balancers that have been waiting for an external
+        /// address for longer than this duration, for example `10m` or `1h`.
+        #[arg(
+            name = "pending-after",
+            long,
+            default_value = "10m",
+            value_parser = humantime::parse_duration
+        )]
+        pending_after: Duration,
+    },
+
+    /// Check for namespaces with many services whose pods get their service
+    /// links injected as environment variables.
+    ServiceLinks {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Report namespaces with more services than this.
+        #[arg(name = "min-services", long, default_value_t = 50)]
+        min_services: usize,
+    },
+
+    /// Check for pods that request sidecar injection but have no sidecar
+    /// container.
+    SidecarInjectionCheck {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Name of the injected sidecar container.
+        #[arg(name = "expected-sidecar", long, default_value = "istio-proxy")]
+        expected_sidecar: String,
+
+        /// Annotation or label that requests the injection when set to `true`.
+        #[arg(
+            name = "injection-annotation",
+            long,
+            default_value = "sidecar.istio.io/inject"
+        )]
+        injection_annotation: String,
+    },
+
+    /// Check for pods of the same app that run on the same node.
+    PodAntiColocate {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Label whose value identifies the app of a pod.
+        #[arg(name = "group-by-label", long, default_value = "app")]
+        group_by_label: String,
+
+        /// Report apps with more pods than this on a single node.
+        #[arg(name = "max-per-node", long, default_value_t = 1)]
+        max_per_node: usize,
+    },
+
+    /// Check for containers that use more than 90% of their cpu limit and are
+    /// likely throttled.
+    PodCpuThrottlingIndicator {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+    },
+
+    /// List the containers and whether they likely run a scratch or
+    /// distroless image without a shell.
+    ContainerScratchImage {
+        #[command(flatten)]
+        namespaces: NamespaceSelection,
+
+        /// Image names that mark distroless images. Globs that support `*` and
+        /// `?` and have to match the whole image name.
+        #[arg(
+            name = "distroless-pattern",
+            long,
+            default_values = [
+                "scratch",
+                "scratch:*",
+                "*/distroless/*",
+                "cgr.dev/chainguard/*",
+            ]
         )]
-        all_namespaces: bool,
+        distroless_patterns: Vec<String>,
+    },
+
+    /// List the contexts of the kubeconfig with their cluster, user and default
+    /// namespace.
+    Contexts,
+
+    /// Print the version of the tool, the kubernetes version it was built for
+    /// and the version of the api server.
+    Version,
+
+    /// Print a starter config file with every setting commented out.
+    Config {
+        /// Print the settings with their defaults and descriptions, for
+        /// example to redirect them into `k8s-tools.toml`.
+        #[arg(name = "generate-default", long, required = true)]
+        generate_default: bool,
+    },
+
+    /// Print the json schema of the output of the commands.
+    #[command(hide = true)]
+    Schema {
+        /// Only print the schema of the given command.
+        #[arg(name = "command", long)]
+        command: Option<String>,
     },
 }
 
 #[tokio::main]
+#[allow(clippy::too_many_lines)]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    check_namespace_selection(&args, &matches);
 
     std::env::set_var("RUST_LOG", args.log_level.as_str());
     pretty_env_logger::try_init_timed().context("failed to initialize logger")?;
 
-    match args.command {
+    info!(
+        "Limiting kubernetes api requests to {} at a time",
+        args.concurrency
+    );
+    set_concurrency(args.concurrency)?;
+    set_memory_units(args.memory_units)?;
+    set_cpu_units(args.cpu_units)?;
+
+    let details = DetailOptions {
+        labels: args.output_labels,
+        label_columns: args.label_columns.clone(),
+        owner_chain: args.output_owner_chain,
+    };
+
+    let config = args
+        .config
+        .as_deref()
+        .map(Config::load)
+        .transpose()?
+        .unwrap_or_default();
+
+    let output = OutputOptions {
+        notify: &args.notify,
+        severity: SeverityOptions {
+            min: args.min_severity,
+            overrides: config.severity,
+        },
+        sort: args.sort_by,
+        watch: args.watch_interval.map(|_| Watch::default()),
+        mode: if args.count {
+            OutputMode::Count
+        } else if args.summary_only {
+            OutputMode::Summary
+        } else {
+            OutputMode::Report
+        },
+        envelope: EnvelopeOptions {
+            title: args.report_title.clone(),
+            description: args.report_description.clone(),
+        },
+    };
+
+    let kind = if let Some(path) = &args.from_file {
+        TargetKind::File(FileSource::open(
+            path,
+            OptionalFiles {
+                metrics: args.metrics_file.as_deref(),
+                nodes: args.nodes_file.as_deref(),
+                namespaces: args.namespaces_file.as_deref(),
+                statefulsets: args.statefulsets_file.as_deref(),
+                daemonsets: args.daemonsets_file.as_deref(),
+                jobs: args.jobs_file.as_deref(),
+                ingresses: args.ingresses_file.as_deref(),
+                cronjobs: args.cronjobs_file.as_deref(),
+                services: args.services_file.as_deref(),
+                secrets: args.secrets_file.as_deref(),
+                service_accounts: args.service_accounts_file.as_deref(),
+                persistent_volume_claims: args.persistent_volume_claims_file.as_deref(),
+                endpoint_slices: args.endpoint_slices_file.as_deref(),
+                events: args.events_file.as_deref(),
+                config_maps: args.config_maps_file.as_deref(),
+                role_bindings: args.role_bindings_file.as_deref(),
+                cluster_roles: args.cluster_roles_file.as_deref(),
+                cluster_role_bindings: args.cluster_role_bindings_file.as_deref(),
+                vertical_pod_autoscalers: args.vertical_pod_autoscalers_file.as_deref(),
+                horizontal_pod_autoscalers: args.horizontal_pod_autoscalers_file.as_deref(),
+                deployments: args.deployments_file.as_deref(),
+                replica_sets: args.replica_sets_file.as_deref(),
+            },
+        )?)
+    } else if args.all_contexts || !args.contexts.is_empty() {
+        let contexts = if args.all_contexts {
+            contexts()?
+        } else {
+            args.contexts.clone()
+        };
+
+        TargetKind::Clusters {
+            contexts,
+            parallel: args.parallel_clusters,
+        }
+    } else {
+        TargetKind::Cluster(args.context.clone())
+    };
+
+    let target = Target {
+        kind,
+        no_owner: args.no_owner,
+        namespaces: NamespaceSelector::new(args.namespace_regex.clone(), &args.exclude_namespaces),
+        containers: args.filter_container.clone(),
+        strict: args.strict,
+        impersonation: Impersonation {
+            user: args.as_user.clone(),
+            groups: args.as_groups.clone(),
+        },
+        cache: if args.cache && !args.no_cache {
+            Some(CacheOptions {
+                root: cache::default_root()?,
+                ttl: args.cache_ttl,
+                refresh: args.refresh,
+            })
+        } else {
+            None
+        },
+    };
+
+    let Some(interval) = args.watch_interval else {
+        return run_command(
+            args.command,
+            &target,
+            &output,
+            &details,
+            config.prices.as_ref(),
+        )
+        .await;
+    };
+
+    info!(
+        "Watching for changes every {}",
+        humantime::format_duration(interval)
+    );
+
+    loop {
+        if let Err(err) = run_command(
+            args.command.clone(),
+            &target,
+            &output,
+            &details,
+            config.prices.as_ref(),
+        )
+        .await
+        {
+            warn!("Failed to run command, trying again after the interval: {err:?}");
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+async fn run_command(
+    command: Command,
+    target: &Target,
+    output: &OutputOptions<'_>,
+    details: &DetailOptions,
+    prices: Option<&Prices>,
+) -> Result<()> {
+    match command {
         Command::MissingHealthProbes {
-            namespaces,
-            all_namespaces,
-        } => missing_health_probes(namespaces, all_namespaces).await,
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            missing,
+        } => {
+            target::run("missing-health-probes", target, output, |source| {
+                Box::pin(missing_health_probes(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    missing.clone(),
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::PodGracefulShutdownTest {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("pod-graceful-shutdown-test", target, output, |source| {
+                Box::pin(pod_graceful_shutdown_test(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
 
         Command::ResourceRequests {
-            namespaces,
-            all_namespaces,
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
             threshold,
             no_check_higher,
-        } => resource_requests(namespaces, all_namespaces, threshold, no_check_higher).await,
+            aggregate_by_owner,
+            flag_near_limits,
+            no_limits_only,
+            include_phases,
+            no_usage,
+            baseline_file,
+            aggregate_by_label,
+            project_hpa_max,
+            cost,
+            cpu_price_per_core_hour,
+            memory_price_per_gib_hour,
+            currency,
+        } => {
+            let baseline = baseline_file.as_deref().map(Baseline::read).transpose()?;
+
+            let prices = if cost {
+                let prices = Prices::merge(
+                    prices,
+                    cpu_price_per_core_hour,
+                    memory_price_per_gib_hour,
+                    currency,
+                );
+
+                if prices.is_empty() {
+                    return Err(eyre!(
+                        "--cost needs prices, set --cpu-price-per-core-hour and \
+                         --memory-price-per-gib-hour or the prices section of the config file"
+                    ));
+                }
+
+                Some(prices)
+            } else {
+                None
+            };
+
+            target::run("resource-requests", target, output, |source| {
+                let namespaces = namespaces.clone();
+                let details = details.clone();
+                let include_phases = include_phases.clone();
+                let baseline = baseline.clone();
+                let aggregate_by_label = aggregate_by_label.clone();
+                let prices = prices.clone();
+
+                Box::pin(async move {
+                    resource_requests(
+                        source,
+                        namespaces,
+                        all_namespaces,
+                        threshold,
+                        no_check_higher,
+                        details,
+                        aggregate_by_owner,
+                        flag_near_limits,
+                        no_limits_only,
+                        &include_phases,
+                        no_usage,
+                        baseline.as_ref(),
+                        aggregate_by_label.as_deref(),
+                        project_hpa_max,
+                        prices.as_ref(),
+                    )
+                    .await
+                })
+            })
+            .await
+        }
+
+        Command::RequestsDrift {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("requests-drift", target, output, |source| {
+                Box::pin(requests_drift(source, namespaces.clone(), all_namespaces))
+            })
+            .await
+        }
+
+        Command::ResourceRequestRightsizing {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("resource-request-rightsizing", target, output, |source| {
+                Box::pin(resource_request_rightsizing(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::TopOwners {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            rank_by,
+            top,
+        } => {
+            target::run("top-owners", target, output, |source| {
+                Box::pin(top_owners(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    rank_by,
+                    top,
+                ))
+            })
+            .await
+        }
 
         Command::ReadOnlyRootFilesystem {
-            namespaces,
-            all_namespaces,
-        } => readonly_root_filesystem(namespaces, all_namespaces).await,
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("read-only-root-filesystem", target, output, |source| {
+                Box::pin(readonly_root_filesystem(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::VolumeMountReadWrite {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            sensitive_paths,
+        } => {
+            target::run("volume-mount-read-write", target, output, |source| {
+                Box::pin(volume_mount_read_write(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    sensitive_paths.clone(),
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::ContainerWorkingDir {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            forbidden_path_prefixes,
+            flag_root_workdir,
+        } => {
+            target::run("container-working-dir", target, output, |source| {
+                Box::pin(container_working_dir(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    forbidden_path_prefixes.clone(),
+                    flag_root_workdir,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::ContainerPortNameCheck {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            require_names_for_ports,
+        } => {
+            target::run("container-port-name-check", target, output, |source| {
+                Box::pin(container_port_name_check(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    require_names_for_ports.clone(),
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::ContainerNameConventions {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            pattern,
+            max_length,
+        } => {
+            let conventions = NameConventions::new(pattern.as_deref(), max_length)
+                .context("invalid container name pattern")?;
+
+            target::run("container-name-conventions", target, output, |source| {
+                let namespaces = namespaces.clone();
+                let conventions = conventions.clone();
+                let details = details.clone();
+
+                Box::pin(async move {
+                    container_name_conventions(
+                        source,
+                        namespaces,
+                        all_namespaces,
+                        &conventions,
+                        details,
+                    )
+                    .await
+                })
+            })
+            .await
+        }
+
+        Command::LivenessReadinessConsistency {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("liveness-readiness-consistency", target, output, |source| {
+                Box::pin(liveness_readiness_consistency(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::NamespaceResourceBalance {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            max_namespace_pct,
+        } => {
+            target::run("namespace-resource-balance", target, output, |source| {
+                Box::pin(namespace_resource_balance(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    max_namespace_pct,
+                ))
+            })
+            .await
+        }
+
+        Command::NodeAllocatableSummary {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("node-allocatable-summary", target, output, |source| {
+                Box::pin(node_allocatable_summary(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::Fit {
+            cpu,
+            memory,
+            replicas,
+            node_selector,
+        } => {
+            target::run("fit", target, output, |source| {
+                Box::pin(fit(source, cpu, memory, replicas, node_selector.clone()))
+            })
+            .await
+        }
+
+        Command::ObjectSizes {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            warn_bytes,
+            warn_annotation_bytes,
+        } => {
+            target::run("object-sizes", target, output, |source| {
+                Box::pin(object_sizes(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    warn_bytes,
+                    warn_annotation_bytes,
+                ))
+            })
+            .await
+        }
+
+        Command::OrphanReplicasets {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("orphan-replicasets", target, output, |source| {
+                Box::pin(orphan_replicasets(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::ResourcePressureEvents => {
+            target::run("resource-pressure-events", target, output, |source| {
+                Box::pin(resource_pressure_events(source))
+            })
+            .await
+        }
+
+        Command::PodDnsPolicy {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("pod-dns-policy", target, output, |source| {
+                Box::pin(pod_dns_policy(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::PodDnsSearchDomains {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            max_search_domains,
+        } => {
+            target::run("pod-dns-search-domains", target, output, |source| {
+                Box::pin(pod_dns_search_domains(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    max_search_domains,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::PodIpFamily {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("pod-ip-family", target, output, |source| {
+                Box::pin(pod_ip_family(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::AffinityCheck {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("affinity-check", target, output, |source| {
+                Box::pin(affinity_check(source, namespaces.clone(), all_namespaces))
+            })
+            .await
+        }
+
+        Command::ControlPlanePods {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            ignore_namespaces,
+        } => {
+            target::run("control-plane-pods", target, output, |source| {
+                let namespaces = namespaces.clone();
+                let ignore_namespaces = ignore_namespaces.clone();
+
+                Box::pin(async move {
+                    control_plane_pods(source, namespaces, all_namespaces, &ignore_namespaces).await
+                })
+            })
+            .await
+        }
+
+        Command::DaemonsetNodeCoverage {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("daemonset-node-coverage", target, output, |source| {
+                Box::pin(daemonset_node_coverage(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::DeploymentImageConsistency {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("deployment-image-consistency", target, output, |source| {
+                Box::pin(deployment_image_consistency(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::EndpointReadiness {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            not_ready_for,
+            min_not_ready_pct,
+        } => {
+            target::run("endpoint-readiness", target, output, |source| {
+                Box::pin(endpoint_readiness(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    not_ready_for,
+                    min_not_ready_pct,
+                ))
+            })
+            .await
+        }
+
+        Command::JobParallelismCheck {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            flag_single_thread,
+            flag_over_parallel,
+            serial_completions,
+        } => {
+            let flags = ParallelismFlags {
+                single_thread: flag_single_thread,
+                over_parallel: flag_over_parallel,
+                serial_completions,
+            };
+
+            target::run("job-parallelism-check", target, output, |source| {
+                Box::pin(job_parallelism_check(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    flags,
+                ))
+            })
+            .await
+        }
+
+        Command::LeakingCronjobs {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            threshold,
+        } => {
+            target::run("leaking-cronjobs", target, output, |source| {
+                Box::pin(leaking_cronjobs(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    threshold,
+                ))
+            })
+            .await
+        }
+
+        Command::PodCompletionRate {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            since,
+            min_success_rate,
+        } => {
+            target::run("pod-completion-rate", target, output, |source| {
+                Box::pin(pod_completion_rate(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    since,
+                    min_success_rate,
+                ))
+            })
+            .await
+        }
+
+        Command::ReadinessGates {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            unsatisfied_for,
+        } => {
+            target::run("readiness-gates", target, output, |source| {
+                Box::pin(readiness_gates(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    unsatisfied_for,
+                ))
+            })
+            .await
+        }
+
+        Command::StatefulsetPodManagementPolicy {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            flag_parallel,
+            flag_burst,
+        } => {
+            let flags = PolicyFlags {
+                parallel: flag_parallel,
+                burst: flag_burst,
+            };
+
+            target::run(
+                "statefulset-pod-management-policy",
+                target,
+                output,
+                |source| {
+                    Box::pin(statefulset_pod_management_policy(
+                        source,
+                        namespaces.clone(),
+                        all_namespaces,
+                        flags,
+                    ))
+                },
+            )
+            .await
+        }
+
+        Command::TerminationMessages {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("termination-messages", target, output, |source| {
+                Box::pin(termination_messages(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::TinyRequests {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            min_cpu,
+            min_memory,
+            ignore_images,
+        } => {
+            target::run("tiny-requests", target, output, |source| {
+                let namespaces = namespaces.clone();
+                let ignore_images = ignore_images.clone();
+
+                Box::pin(async move {
+                    tiny_requests(
+                        source,
+                        namespaces,
+                        all_namespaces,
+                        min_cpu,
+                        min_memory,
+                        &ignore_images,
+                    )
+                    .await
+                })
+            })
+            .await
+        }
+
+        Command::TokenExpiryCheck {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            warn_within,
+        } => {
+            target::run("token-expiry-check", target, output, |source| {
+                Box::pin(token_expiry_check(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    warn_within,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::TokenLifetimes {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            max_expiration_seconds,
+        } => {
+            target::run("token-lifetimes", target, output, |source| {
+                Box::pin(token_lifetimes(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    max_expiration_seconds,
+                ))
+            })
+            .await
+        }
+
+        Command::UpdateStrategies {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            stalled_after,
+        } => {
+            target::run("update-strategies", target, output, |source| {
+                Box::pin(update_strategies(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    stalled_after,
+                ))
+            })
+            .await
+        }
+
+        Command::VersionSkew {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("version-skew", target, output, |source| {
+                Box::pin(version_skew(source, namespaces.clone(), all_namespaces))
+            })
+            .await
+        }
+
+        Command::StuckFinalizers {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            grace,
+        } => {
+            target::run("stuck-finalizers", target, output, |source| {
+                Box::pin(stuck_finalizers(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    grace,
+                ))
+            })
+            .await
+        }
+
+        Command::ContainerPortPolicy {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            allowed_port_range,
+        } => {
+            target::run("container-port-policy", target, output, |source| {
+                let namespaces = namespaces.clone();
+                let allowed_port_range = allowed_port_range.clone();
+                let details = details.clone();
+
+                Box::pin(async move {
+                    container_port_policy(
+                        source,
+                        namespaces,
+                        all_namespaces,
+                        &allowed_port_range,
+                        details,
+                    )
+                    .await
+                })
+            })
+            .await
+        }
+
+        Command::IngressTlsCheck {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("ingress-tls-check", target, output, |source| {
+                Box::pin(ingress_tls_check(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::DeprecatedAnnotations {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("deprecated-annotations", target, output, |source| {
+                Box::pin(deprecated_annotations(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::InitContainerHealth {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            max_duration,
+        } => {
+            target::run("init-container-health", target, output, |source| {
+                Box::pin(init_container_health(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    max_duration,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::RuntimeSocket {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("runtime-socket", target, output, |source| {
+                Box::pin(runtime_socket(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::SecretTypeCheck {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("secret-type-check", target, output, |source| {
+                Box::pin(secret_type_check(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                ))
+            })
+            .await
+        }
+
+        Command::ServiceAccountPrivilege {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("service-account-privilege", target, output, |source| {
+                Box::pin(service_account_privilege(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::ServiceExposure {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            pending_after,
+        } => {
+            target::run("service-exposure", target, output, |source| {
+                Box::pin(service_exposure(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    pending_after,
+                ))
+            })
+            .await
+        }
+
+        Command::ServiceLinks {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            min_services,
+        } => {
+            target::run("service-links", target, output, |source| {
+                Box::pin(service_links(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    min_services,
+                ))
+            })
+            .await
+        }
+
+        Command::SidecarInjectionCheck {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            expected_sidecar,
+            injection_annotation,
+        } => {
+            let expected = SidecarExpectation {
+                sidecar: expected_sidecar,
+                annotation: injection_annotation,
+            };
+
+            target::run("sidecar-injection-check", target, output, |source| {
+                let namespaces = namespaces.clone();
+                let expected = expected.clone();
+                let details = details.clone();
+
+                Box::pin(async move {
+                    sidecar_injection_check(source, namespaces, all_namespaces, &expected, details)
+                        .await
+                })
+            })
+            .await
+        }
+
+        Command::PodAntiColocate {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            group_by_label,
+            max_per_node,
+        } => {
+            target::run("pod-anti-colocate", target, output, |source| {
+                let namespaces = namespaces.clone();
+                let group_by_label = group_by_label.clone();
+
+                Box::pin(async move {
+                    pod_anti_colocate(
+                        source,
+                        namespaces,
+                        all_namespaces,
+                        &group_by_label,
+                        max_per_node,
+                    )
+                    .await
+                })
+            })
+            .await
+        }
+
+        Command::PodCpuThrottlingIndicator {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("pod-cpu-throttling-indicator", target, output, |source| {
+                Box::pin(pod_cpu_throttling_indicator(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+
+        Command::ContainerScratchImage {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+            distroless_patterns,
+        } => {
+            target::run("container-scratch-image", target, output, |source| {
+                let namespaces = namespaces.clone();
+                let distroless_patterns = distroless_patterns.clone();
+
+                Box::pin(async move {
+                    container_scratch_image(
+                        source,
+                        namespaces,
+                        all_namespaces,
+                        &distroless_patterns,
+                    )
+                    .await
+                })
+            })
+            .await
+        }
+
+        Command::Version => {
+            let server_version = target::server_version(target).await.unwrap_or_else(|err| {
+                warn!("Failed to get the version of the api server: {err:?}");
+                None
+            });
+
+            let out = serde_json::to_string_pretty(&Version {
+                tool_version: Meta::TOOL_VERSION,
+                git_sha: env!("GIT_SHA"),
+                k8s_openapi_version: env!("K8S_OPENAPI_VERSION"),
+                server_version,
+            })?;
+
+            println!("{out}");
+
+            Ok(())
+        }
+
+        Command::Contexts => {
+            let out = serde_json::to_string_pretty(&context_list()?)?;
+
+            println!("{out}");
+
+            Ok(())
+        }
+
+        Command::Config {
+            generate_default: _,
+        } => {
+            print!("{}", Config::generate_default());
+
+            Ok(())
+        }
+
+        Command::Schema { command } => {
+            let schemas = schema::schemas();
+
+            let out = match command {
+                Some(command) => serde_json::to_string_pretty(
+                    schemas
+                        .get(command.as_str())
+                        .ok_or_else(|| eyre!("no schema for command {command}"))?,
+                )?,
+
+                None => serde_json::to_string_pretty(&schemas)?,
+            };
+
+            println!("{out}");
+
+            Ok(())
+        }
+
+        Command::ContainerProbePortMismatch {
+            namespaces:
+                NamespaceSelection {
+                    namespaces,
+                    all_namespaces,
+                },
+        } => {
+            target::run("container-probe-port-mismatch", target, output, |source| {
+                Box::pin(container_probe_port_mismatch(
+                    source,
+                    namespaces.clone(),
+                    all_namespaces,
+                    details.clone(),
+                ))
+            })
+            .await
+        }
+    }
+}
+
+/// The namespace selection options are global while `--namespaces` belongs to
+/// the subcommands, so clap can not check the conflict between them itself.
+fn check_namespace_selection(args: &Args, matches: &ArgMatches) {
+    let Some((_, command)) = matches.subcommand() else {
+        return;
+    };
+
+    let namespaces_given = command
+        .try_get_many::<String>("namespaces")
+        .ok()
+        .flatten()
+        .is_some_and(|mut namespaces| namespaces.next().is_some());
+
+    if args.namespace_regex.is_some() && namespaces_given {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "the argument '--namespace-regex' cannot be used with '--namespaces', select \
+                 the namespaces with either of them",
+            )
+            .exit();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::panic)]
+mod test {
+    use clap::Parser;
+
+    use super::{Args, Command, NamespaceSelection};
+
+    fn namespaces(args: &[&str]) -> Result<NamespaceSelection, clap::Error> {
+        let args = Args::try_parse_from(
+            ["k8s-tools", "missing-health-probes"]
+                .into_iter()
+                .chain(args.iter().copied()),
+        )?;
+
+        let Command::MissingHealthProbes { namespaces, .. } = args.command else {
+            panic!("unexpected command {:?}", args.command);
+        };
+
+        Ok(namespaces)
+    }
+
+    #[test]
+    fn namespace_selection() {
+        let testcases: [(&[&str], &[&str]); 4] = [
+            (&[], &[]),
+            (&["-n", "web"], &["web"]),
+            (&["--namespace", "web"], &["web"]),
+            (&["--namespaces", "a,b", "-n", "c"], &["a", "b", "c"]),
+        ];
+
+        for (args, expected) in testcases {
+            let selection = namespaces(args).unwrap();
+
+            assert_eq!(expected, selection.namespaces, "{args:?}");
+            assert!(!selection.all_namespaces);
+        }
+
+        assert!(namespaces(&["--all-namespaces"]).unwrap().all_namespaces);
+        assert!(namespaces(&["-n", "web", "--all-namespaces"]).is_err());
+    }
+
+    #[test]
+    fn no_usage_conflicts_with_usage_filters() {
+        let parse = |args: &[&str]| {
+            Args::try_parse_from(
+                ["k8s-tools", "resource-requests", "--no-usage"]
+                    .into_iter()
+                    .chain(args.iter().copied()),
+            )
+        };
+
+        assert!(parse(&[]).is_ok());
+        assert!(parse(&["--threshold", "10"]).is_err());
+        assert!(parse(&["--no-check-higher"]).is_err());
+        assert!(parse(&["--flag-near-limits"]).is_err());
+    }
+
+    #[test]
+    fn impersonation() {
+        let parse = |args: &[&str]| {
+            Args::try_parse_from(
+                ["k8s-tools", "missing-health-probes"]
+                    .into_iter()
+                    .chain(args.iter().copied()),
+            )
+        };
+
+        let args = parse(&[
+            "--as",
+            "system:serviceaccount:team-a:auditor",
+            "--as-group",
+            "team-a",
+            "--as-group",
+            "auditors",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            Some("system:serviceaccount:team-a:auditor"),
+            args.as_user.as_deref()
+        );
+        assert_eq!(vec!["team-a", "auditors"], args.as_groups);
+
+        assert!(parse(&["--as-group", "team-a"]).is_err());
+        assert!(parse(&["--as", "auditor", "--from-file", "pods.json"]).is_err());
+    }
+
+    #[test]
+    fn fit() {
+        let args = Args::try_parse_from([
+            "k8s-tools",
+            "fit",
+            "--cpu",
+            "500m",
+            "--memory",
+            "1Gi",
+            "--replicas",
+            "40",
+            "--node-selector",
+            "pool=general,zone=a",
+        ])
+        .unwrap();
+
+        let Command::Fit {
+            cpu,
+            memory,
+            replicas,
+            node_selector,
+        } = args.command
+        else {
+            panic!("unexpected command {:?}", args.command);
+        };
+
+        assert_eq!(500, cpu.to_milliseconds());
+        assert_eq!(1024 * 1024 * 1024, memory.to_bytes());
+        assert_eq!(Some(40), replicas);
+        assert_eq!(vec!["pool=general", "zone=a"], node_selector);
+
+        let args =
+            Args::try_parse_from(["k8s-tools", "fit", "--cpu", "0.5", "--memory", "1G"]).unwrap();
+
+        let Command::Fit { cpu, memory, .. } = args.command else {
+            panic!("unexpected command {:?}", args.command);
+        };
+
+        assert_eq!(500, cpu.to_milliseconds());
+        assert_eq!(1_000_000_000, memory.to_bytes());
     }
 }
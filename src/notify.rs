@@ -0,0 +1,539 @@
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+
+use clap::{Args, ValueEnum};
+use eyre::{bail, Context, Result};
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::json;
+
+use k8s_tools::commands::{Finding, Report, Severity, Summary};
+
+/// Maximum size of a notification payload in bytes. Findings are dropped from
+/// the payload until it fits.
+const MAX_PAYLOAD_BYTES: usize = 32 * 1024;
+
+/// How often sending a notification is attempted before giving up.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry, doubled for every further retry.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Time to wait for the webhook to answer a single attempt, so a webhook that
+/// never responds does not hang the run.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time to wait for the connection to the webhook to be established.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of blocks in a slack message.
+const SLACK_MAX_BLOCKS: usize = 50;
+
+/// Blocks of a slack message that are not findings: the header, the
+/// namespace and severity sections and the note about omitted findings.
+const SLACK_SUMMARY_BLOCKS: usize = 4;
+
+/// Maximum number of characters of the text of a slack section block.
+const SLACK_MAX_SECTION_CHARS: usize = 3000;
+
+/// Maximum number of characters of the text of a slack header block.
+const SLACK_MAX_HEADER_CHARS: usize = 150;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum NotifyFormat {
+    /// Plain json summary of the run.
+    #[default]
+    Json,
+
+    /// Slack compatible message with blocks.
+    Slack,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct NotifyOptions {
+    /// Send a summary of the findings to the given webhook url.
+    #[arg(long = "notify-webhook", global = true)]
+    webhook: Option<String>,
+
+    /// Format of the notification payload.
+    #[arg(long = "notify-format", global = true, value_enum, default_value_t)]
+    format: NotifyFormat,
+
+    /// Send a notification even if there are no findings.
+    #[arg(long = "notify-always", global = true)]
+    always: bool,
+
+    /// Print the notification payload to stderr instead of sending it.
+    #[arg(long = "notify-dry-run", global = true)]
+    dry_run: bool,
+
+    /// Number of findings to include in the notification.
+    #[arg(long = "notify-top", global = true, default_value = "10")]
+    top: usize,
+
+    /// Url of the full report, linked from the notification when findings
+    /// had to be left out.
+    #[arg(long = "notify-report-url", global = true)]
+    report_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Payload {
+    check: String,
+    context: Option<String>,
+    total: usize,
+    namespaces: BTreeMap<String, usize>,
+    severities: BTreeMap<Severity, usize>,
+    findings: Vec<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report_url: Option<String>,
+}
+
+pub(crate) async fn notify<R: Report>(
+    check: &str,
+    context: Option<String>,
+    report: &R,
+    options: &NotifyOptions,
+) -> Result<()> {
+    if options.webhook.is_none() && !options.dry_run {
+        return Ok(());
+    }
+
+    let findings = report.findings();
+
+    if findings.is_empty() && !options.always {
+        info!("Not sending notification as there are no findings");
+        return Ok(());
+    }
+
+    let payload = build_payload(
+        check,
+        context,
+        &findings,
+        options.top,
+        options.report_url.clone(),
+    )?;
+    let body = render_capped(payload, options.format)?;
+
+    if options.dry_run {
+        eprintln!("{body}");
+        return Ok(());
+    }
+
+    let url = options
+        .webhook
+        .as_ref()
+        .expect("webhook url is checked above");
+
+    send(url, body).await
+}
+
+fn build_payload<F: Finding>(
+    check: &str,
+    context: Option<String>,
+    findings: &[&F],
+    top: usize,
+    report_url: Option<String>,
+) -> Result<Payload> {
+    let Summary {
+        total,
+        namespaces,
+        severities,
+        ..
+    } = Summary::new(findings);
+
+    let top_findings = findings
+        .iter()
+        .take(top)
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()
+        .context("failed to serialize findings")?;
+
+    let omitted = findings.len() - top_findings.len();
+    let note = (omitted > 0).then(|| omitted_note(omitted));
+
+    Ok(Payload {
+        check: check.to_string(),
+        context,
+        total,
+        namespaces,
+        severities,
+        findings: top_findings,
+        note,
+        report_url,
+    })
+}
+
+fn omitted_note(omitted: usize) -> String {
+    format!("{omitted} more findings not shown, see the full report for details")
+}
+
+/// Render the payload in the given format, dropping findings until it fits
+/// into `MAX_PAYLOAD_BYTES`. Slack messages also only keep as many findings as
+/// fit into the block limit of slack.
+fn render_capped(mut payload: Payload, format: NotifyFormat) -> Result<String> {
+    let max_findings = SLACK_MAX_BLOCKS - SLACK_SUMMARY_BLOCKS;
+
+    if format == NotifyFormat::Slack && payload.findings.len() > max_findings {
+        payload.findings.truncate(max_findings);
+        payload.note = Some(omitted_note(payload.total - payload.findings.len()));
+    }
+
+    loop {
+        let body = render(&payload, format)?;
+
+        if body.len() <= MAX_PAYLOAD_BYTES || payload.findings.is_empty() {
+            return Ok(body);
+        }
+
+        payload.findings.pop();
+        payload.note = Some(omitted_note(payload.total - payload.findings.len()));
+    }
+}
+
+fn render(payload: &Payload, format: NotifyFormat) -> Result<String> {
+    let body = match format {
+        NotifyFormat::Json => serde_json::to_string(payload)?,
+        NotifyFormat::Slack => serde_json::to_string(&slack_message(payload))?,
+    };
+
+    Ok(body)
+}
+
+/// Build a slack message with a block per finding. Texts are shortened to the
+/// limits of slack, a section listing too many namespaces ends with the number
+/// of namespaces that are not shown.
+fn slack_message(payload: &Payload) -> serde_json::Value {
+    let title = format!(
+        "{}: {} findings in {}",
+        payload.check,
+        payload.total,
+        payload.context.as_deref().unwrap_or("unknown context")
+    );
+
+    let header = truncate(&title, SLACK_MAX_HEADER_CHARS);
+
+    let namespaces = section_list(
+        "*Findings per namespace*",
+        payload
+            .namespaces
+            .iter()
+            .map(|(namespace, count)| format!("• `{namespace}`: {count}")),
+    );
+
+    let severities = section_list(
+        "*Findings per severity*",
+        payload
+            .severities
+            .iter()
+            .rev()
+            .map(|(severity, count)| format!("• {severity}: {count}")),
+    );
+
+    let mut blocks = vec![
+        json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": header },
+        }),
+        json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": namespaces },
+        }),
+        json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": severities },
+        }),
+    ];
+
+    blocks.extend(payload.findings.iter().map(|finding| {
+        // Leave room for the backticks of the code block.
+        let finding = truncate(&finding.to_string(), SLACK_MAX_SECTION_CHARS - 6);
+
+        json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("```{finding}```") },
+        })
+    }));
+
+    if let Some(note) = &payload.note {
+        let note = match &payload.report_url {
+            Some(url) => format!("_{note}_: <{url}|full report>"),
+            None => format!("_{note}_"),
+        };
+
+        blocks.push(json!({
+            "type": "context",
+            "elements": [{ "type": "mrkdwn", "text": note }],
+        }));
+    }
+
+    json!({ "text": title, "blocks": blocks })
+}
+
+/// Join the title and the lines of a section, lines that do not fit into
+/// `SLACK_MAX_SECTION_CHARS` are replaced by the number of omitted lines.
+fn section_list(title: &str, lines: impl ExactSizeIterator<Item = String>) -> String {
+    let total = lines.len();
+    let mut text = title.to_string();
+
+    for (shown, line) in lines.enumerate() {
+        // Every line leaves room to count the lines after it.
+        let remaining = total - shown - 1;
+        let reserved = if remaining > 0 {
+            format!("\n+{remaining} more").chars().count()
+        } else {
+            0
+        };
+
+        if text.chars().count() + 1 + line.chars().count() + reserved > SLACK_MAX_SECTION_CHARS {
+            write!(text, "\n+{} more", total - shown).expect("writing to a string can not fail");
+            break;
+        }
+
+        text.push('\n');
+        text.push_str(&line);
+    }
+
+    text
+}
+
+/// Shorten the text to at most `max_chars` characters, ending it with `…` if
+/// it had to be cut.
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let mut truncated = text.chars().take(max_chars - 1).collect::<String>();
+    truncated.push('…');
+
+    truncated
+}
+
+/// Post the body to the webhook. Connection errors, timeouts and server errors
+/// are retried with a backoff, any other status fails right away.
+async fn send(url: &str, body: String) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .context("failed to create http client")?;
+
+    let mut backoff = INITIAL_BACKOFF;
+    let mut error = String::new();
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let response = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone())
+            .send()
+            .await;
+
+        error = match response {
+            Ok(response) if response.status().is_success() => {
+                info!("Sent notification to webhook");
+                return Ok(());
+            }
+
+            Ok(response) if !response.status().is_server_error() => {
+                bail!(
+                    "webhook rejected notification with status {}",
+                    response.status()
+                );
+            }
+
+            Ok(response) => format!("webhook returned {}", response.status()),
+            Err(err) => format!("failed to send notification: {err}"),
+        };
+
+        if attempt < MAX_ATTEMPTS {
+            warn!("{error}, retrying in {backoff:?}");
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    bail!("failed to send notification after {MAX_ATTEMPTS} attempts: {error}")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use serde::Serialize;
+
+    use super::{
+        build_payload, render_capped, NotifyFormat, MAX_PAYLOAD_BYTES, SLACK_MAX_BLOCKS,
+        SLACK_MAX_HEADER_CHARS, SLACK_MAX_SECTION_CHARS,
+    };
+    use k8s_tools::commands::{Finding, Severity};
+
+    #[derive(Debug, Serialize)]
+    struct TestFinding {
+        namespace: String,
+        message: String,
+        severity: Severity,
+    }
+
+    impl Finding for TestFinding {
+        const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+        fn namespace(&self) -> &str {
+            &self.namespace
+        }
+
+        fn severity(&self) -> Severity {
+            self.severity
+        }
+
+        fn set_severity(&mut self, severity: Severity) {
+            self.severity = severity;
+        }
+    }
+
+    fn findings(count: usize, message_len: usize) -> Vec<TestFinding> {
+        (0..count)
+            .map(|i| TestFinding {
+                namespace: format!("namespace-{}", i % 2),
+                message: "x".repeat(message_len),
+                severity: if i % 5 == 0 {
+                    Severity::High
+                } else {
+                    Severity::Low
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn payload_counts() {
+        let findings = findings(5, 1);
+        let findings = findings.iter().collect::<Vec<_>>();
+
+        let payload = build_payload("check", None, &findings, 2, None).unwrap();
+
+        assert_eq!(5, payload.total);
+        assert_eq!(2, payload.findings.len());
+        assert_eq!(Some(&3), payload.namespaces.get("namespace-0"));
+        assert_eq!(Some(&2), payload.namespaces.get("namespace-1"));
+        assert_eq!(Some(&1), payload.severities.get(&Severity::High));
+        assert_eq!(Some(&4), payload.severities.get(&Severity::Low));
+        assert!(payload.note.is_some());
+    }
+
+    #[test]
+    fn payload_capped() {
+        let findings = findings(20, 4096);
+        let findings = findings.iter().collect::<Vec<_>>();
+
+        for format in [NotifyFormat::Json, NotifyFormat::Slack] {
+            let payload = build_payload("check", None, &findings, 20, None).unwrap();
+            let body = render_capped(payload, format).unwrap();
+
+            assert!(body.len() <= MAX_PAYLOAD_BYTES);
+            assert!(body.contains("more findings not shown"));
+        }
+    }
+
+    #[test]
+    fn slack_limits() {
+        let many = (0..200)
+            .map(|i| TestFinding {
+                namespace: format!("namespace-{i:03}-{}", "n".repeat(40)),
+                message: "x".to_string(),
+                severity: Severity::Low,
+            })
+            .collect::<Vec<_>>();
+        let many = many.iter().collect::<Vec<_>>();
+
+        let payload = build_payload("check", Some("c".repeat(300)), &many, 200, None).unwrap();
+        let body = render_capped(payload, NotifyFormat::Slack).unwrap();
+        let message: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        let blocks = message["blocks"].as_array().unwrap();
+        assert_eq!(SLACK_MAX_BLOCKS, blocks.len());
+
+        let text = |block: &serde_json::Value| block["text"]["text"].as_str().unwrap().to_string();
+
+        assert_eq!(SLACK_MAX_HEADER_CHARS, text(&blocks[0]).chars().count());
+        assert!(text(&blocks[0]).ends_with('…'));
+
+        let namespaces = text(&blocks[1]);
+        assert!(namespaces.chars().count() <= SLACK_MAX_SECTION_CHARS);
+        assert!(namespaces.ends_with(" more"));
+        assert!(namespaces.contains("\n+"));
+
+        let note = blocks[SLACK_MAX_BLOCKS - 1]["elements"][0]["text"]
+            .as_str()
+            .unwrap();
+        assert!(note.contains("154 more findings not shown"));
+
+        // A single finding that is too long for a section is cut.
+        let long = findings(1, 5000);
+        let long = long.iter().collect::<Vec<_>>();
+
+        let payload = build_payload("check", None, &long, 1, None).unwrap();
+        let body = render_capped(payload, NotifyFormat::Slack).unwrap();
+        let message: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        let finding = text(&message["blocks"][3]);
+        assert_eq!(SLACK_MAX_SECTION_CHARS, finding.chars().count());
+        assert!(finding.ends_with("…```"));
+    }
+
+    #[test]
+    fn report_url() {
+        let findings = findings(5, 1);
+        let findings = findings.iter().collect::<Vec<_>>();
+        let url = "https://reports.example.com/run/1";
+
+        let payload = build_payload("check", None, &findings, 2, Some(url.to_string())).unwrap();
+        let body = render_capped(payload, NotifyFormat::Json).unwrap();
+        let message: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(url, message["report_url"]);
+
+        let payload = build_payload("check", None, &findings, 2, Some(url.to_string())).unwrap();
+        let body = render_capped(payload, NotifyFormat::Slack).unwrap();
+        let message: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        let blocks = message["blocks"].as_array().unwrap();
+        let note = blocks[blocks.len() - 1]["elements"][0]["text"]
+            .as_str()
+            .unwrap();
+
+        assert!(note.starts_with("_3 more findings not shown"));
+        assert!(note.ends_with("<https://reports.example.com/run/1|full report>"));
+    }
+
+    #[tokio::test]
+    async fn send_retries_connection_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            // Close the first connection without answering.
+            drop(listener.accept().unwrap());
+
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).unwrap();
+
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .unwrap();
+        });
+
+        super::send(&url, "{}".to_string()).await.unwrap();
+
+        server.join().unwrap();
+    }
+}
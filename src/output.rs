@@ -0,0 +1,171 @@
+use eyre::Result;
+use k8s_openapi::chrono::Utc;
+use log::info;
+use serde::Serialize;
+
+use k8s_tools::{
+    commands::{Report, SeverityOptions, SortBy, Summary},
+    document::{Document, Envelope, Meta},
+};
+
+use crate::{
+    notify::{self, NotifyOptions},
+    watch::{Changes, Watch},
+};
+
+/// Options controlling how reports are emitted.
+#[derive(Debug)]
+pub(crate) struct OutputOptions<'a> {
+    pub(crate) notify: &'a NotifyOptions,
+    pub(crate) severity: SeverityOptions,
+
+    /// Order the findings are printed in, the order of the check if unset.
+    pub(crate) sort: Option<SortBy>,
+
+    /// Only emit findings that changed since the previous run.
+    pub(crate) watch: Option<Watch>,
+
+    /// What is printed of the report.
+    pub(crate) mode: OutputMode,
+
+    /// Title and description the output is wrapped in.
+    pub(crate) envelope: EnvelopeOptions,
+}
+
+/// Title and description to wrap the output in, the output is printed as is
+/// if neither is set.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EnvelopeOptions {
+    pub(crate) title: Option<String>,
+    pub(crate) description: Option<String>,
+}
+
+impl EnvelopeOptions {
+    /// Serialize the document, wrapped in an [`Envelope`] if a title or
+    /// description is set.
+    fn to_json<D: Serialize>(&self, document: &D) -> Result<String> {
+        if self.title.is_none() && self.description.is_none() {
+            return Ok(serde_json::to_string_pretty(document)?);
+        }
+
+        Ok(serde_json::to_string_pretty(&Envelope {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            generated_at: Utc::now().to_rfc3339(),
+            data: document,
+        })?)
+    }
+}
+
+/// What is printed of the report of a check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum OutputMode {
+    /// The full report.
+    #[default]
+    Report,
+
+    /// Only the number of findings per namespace, owner and severity.
+    Summary,
+
+    /// Only the total number of findings.
+    Count,
+}
+
+/// Apply the severity options to the report of a check, print it together
+/// with the metadata of the run and send out notifications for it if they are
+/// configured.
+pub(crate) async fn emit<R: Report>(
+    meta: Meta,
+    mut report: R,
+    options: &OutputOptions<'_>,
+) -> Result<()> {
+    let check = meta.command.clone();
+    let context = meta.context.clone();
+
+    options.severity.apply(&check, &mut report);
+
+    if let Some(sort) = options.sort {
+        sort.apply(&mut report);
+    }
+
+    if let Some(watch) = &options.watch {
+        return emit_changes(meta, watch.changes(&check, report), options).await;
+    }
+
+    match options.mode {
+        OutputMode::Report => {
+            let out = options.envelope.to_json(&Document {
+                meta,
+                report: &report,
+            })?;
+
+            println!("{out}");
+        }
+
+        OutputMode::Summary => {
+            let out = options.envelope.to_json(&Document {
+                meta,
+                report: Summary::new(&report.findings()),
+            })?;
+
+            println!("{out}");
+        }
+
+        OutputMode::Count => println!("{}", report.findings().len()),
+    }
+
+    notify::notify(&check, context, &report, options.notify).await
+}
+
+/// Print only the findings that appeared or disappeared since the previous
+/// run and notify about the new ones.
+async fn emit_changes<R: Report>(
+    meta: Meta,
+    changes: Changes<R>,
+    options: &OutputOptions<'_>,
+) -> Result<()> {
+    let check = meta.command.clone();
+    let context = meta.context.clone();
+
+    if changes.is_empty() {
+        info!("No findings appeared or disappeared since the previous run");
+        return Ok(());
+    }
+
+    let out = options.envelope.to_json(&Document {
+        meta,
+        report: &changes,
+    })?;
+
+    println!("{out}");
+
+    notify::notify(&check, context, &changes.appeared, options.notify).await
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde_json::{json, Value};
+
+    use super::EnvelopeOptions;
+
+    #[test]
+    fn envelope() {
+        let document = json!({ "meta": {}, "report": [] });
+
+        let plain = EnvelopeOptions::default().to_json(&document).unwrap();
+        assert_eq!(document, serde_json::from_str::<Value>(&plain).unwrap());
+
+        let options = EnvelopeOptions {
+            title: Some("Nightly audit".to_string()),
+            description: None,
+        };
+
+        let wrapped: Value = serde_json::from_str(&options.to_json(&document).unwrap()).unwrap();
+
+        assert_eq!("Nightly audit", wrapped["title"]);
+        assert_eq!(Value::Null, wrapped["description"]);
+        assert!(wrapped["generated_at"].is_string());
+        assert_eq!(document, wrapped["data"]);
+    }
+}
@@ -0,0 +1,176 @@
+//! JSON schemas of the output documents of the commands.
+
+use std::collections::BTreeMap;
+
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+
+use crate::{
+    commands::{
+        affinity_check::DeadAffinityTerm, container_name_conventions::ContainerNameViolation,
+        container_port_name_check::ContainerPortNameCheck, container_port_policy::ContainerPort,
+        container_probe_port_mismatch::ProbePortMismatch, container_scratch_image::ScratchImage,
+        container_working_dir::ContainerWorkingDir, control_plane_pods::ControlPlanePod,
+        daemonset_node_coverage::UncoveredNode, deployment_image_consistency::InconsistentImages,
+        deprecated_annotations::DeprecatedAnnotationUsage, endpoint_readiness::ServiceReadiness,
+        fit, ingress_tls_check::IngressWithoutTls, init_container_health::StuckInitContainer,
+        job_parallelism_check::JobParallelism, leaking_cronjobs::LeakingCronJob,
+        liveness_readiness_consistency::InconsistentProbes, missing_health_probes,
+        namespace_resource_balance, node_allocatable_summary::NodeAllocatable,
+        object_sizes::LargeObject, orphan_replicasets::OrphanReplicaSet,
+        pod_anti_colocate::ColocatedPods, pod_completion_rate::PodCompletionRate,
+        pod_cpu_throttling_indicator::ContainerCpuThrottling, pod_dns_policy::DnsPolicyFinding,
+        pod_dns_search_domains::ExcessiveSearchDomains,
+        pod_graceful_shutdown_test::GracefulShutdown, pod_ip_family::PodIpFamily,
+        readiness_gates::UnsatisfiedReadinessGate, readonly_root_filesystem,
+        requests_drift::RequestsDrift, resource_pressure_events::NodePressureEvents,
+        resource_request_rightsizing::ResourceRequestRightsizing, resource_requests,
+        runtime_socket::RuntimeSocketMount, secret_type_check::SecretProblem,
+        service_account_privilege::ServiceAccountPrivilege, service_exposure,
+        service_links::ServiceLinks, sidecar_injection_check::MissingSidecar,
+        statefulset_pod_management_policy::PodManagementPolicyFinding,
+        stuck_finalizers::StuckObject, termination_messages::TerminationMessage,
+        tiny_requests::TinyRequest, token_expiry_check::ExpiringToken,
+        token_lifetimes::TokenLifetime, top_owners, update_strategies::UpdateStrategyFinding,
+        version_skew::VersionSkew, volume_mount_read_write::WritableVolumeMount, Finding, Report,
+        Severity,
+    },
+    document::Document,
+};
+
+/// A check together with what is known about its output without running it.
+#[derive(Debug)]
+pub struct Check {
+    /// Json schema of the output document of the check.
+    pub schema: RootSchema,
+
+    /// Highest severity the check assigns to its findings unless it is
+    /// overridden in the config.
+    pub default_severity: Severity,
+}
+
+fn check<R: Report + JsonSchema>() -> Check {
+    Check {
+        schema: schema_for!(Document<R>),
+        default_severity: R::Finding::DEFAULT_SEVERITY,
+    }
+}
+
+/// Returns the json schema of the output document of every command keyed by
+/// the name of the command. The report of the command is wrapped in a
+/// [`Document`] together with the metadata of the run.
+pub fn schemas() -> BTreeMap<&'static str, RootSchema> {
+    checks()
+        .into_iter()
+        .map(|(name, check)| (name, check.schema))
+        .collect()
+}
+
+/// Returns every check keyed by the name of its command.
+#[allow(clippy::too_many_lines)]
+pub fn checks() -> BTreeMap<&'static str, Check> {
+    BTreeMap::from([
+        ("affinity-check", check::<Vec<DeadAffinityTerm>>()),
+        (
+            "container-name-conventions",
+            check::<Vec<ContainerNameViolation>>(),
+        ),
+        (
+            "container-port-name-check",
+            check::<Vec<ContainerPortNameCheck>>(),
+        ),
+        ("container-port-policy", check::<Vec<ContainerPort>>()),
+        (
+            "container-probe-port-mismatch",
+            check::<Vec<ProbePortMismatch>>(),
+        ),
+        ("container-scratch-image", check::<Vec<ScratchImage>>()),
+        ("container-working-dir", check::<Vec<ContainerWorkingDir>>()),
+        ("control-plane-pods", check::<Vec<ControlPlanePod>>()),
+        ("daemonset-node-coverage", check::<Vec<UncoveredNode>>()),
+        (
+            "deployment-image-consistency",
+            check::<Vec<InconsistentImages>>(),
+        ),
+        (
+            "deprecated-annotations",
+            check::<Vec<DeprecatedAnnotationUsage>>(),
+        ),
+        ("endpoint-readiness", check::<Vec<ServiceReadiness>>()),
+        ("fit", check::<fit::Output>()),
+        ("ingress-tls-check", check::<Vec<IngressWithoutTls>>()),
+        ("init-container-health", check::<Vec<StuckInitContainer>>()),
+        ("job-parallelism-check", check::<Vec<JobParallelism>>()),
+        ("leaking-cronjobs", check::<Vec<LeakingCronJob>>()),
+        (
+            "liveness-readiness-consistency",
+            check::<Vec<InconsistentProbes>>(),
+        ),
+        (
+            "missing-health-probes",
+            check::<missing_health_probes::Output>(),
+        ),
+        (
+            "namespace-resource-balance",
+            check::<namespace_resource_balance::Output>(),
+        ),
+        ("node-allocatable-summary", check::<Vec<NodeAllocatable>>()),
+        ("object-sizes", check::<Vec<LargeObject>>()),
+        ("orphan-replicasets", check::<Vec<OrphanReplicaSet>>()),
+        ("pod-anti-colocate", check::<Vec<ColocatedPods>>()),
+        ("pod-completion-rate", check::<Vec<PodCompletionRate>>()),
+        (
+            "pod-cpu-throttling-indicator",
+            check::<Vec<ContainerCpuThrottling>>(),
+        ),
+        ("pod-dns-policy", check::<Vec<DnsPolicyFinding>>()),
+        (
+            "pod-dns-search-domains",
+            check::<Vec<ExcessiveSearchDomains>>(),
+        ),
+        (
+            "pod-graceful-shutdown-test",
+            check::<Vec<GracefulShutdown>>(),
+        ),
+        ("pod-ip-family", check::<Vec<PodIpFamily>>()),
+        ("readiness-gates", check::<Vec<UnsatisfiedReadinessGate>>()),
+        (
+            "read-only-root-filesystem",
+            check::<readonly_root_filesystem::Output>(),
+        ),
+        ("requests-drift", check::<Vec<RequestsDrift>>()),
+        (
+            "resource-pressure-events",
+            check::<Vec<NodePressureEvents>>(),
+        ),
+        (
+            "resource-request-rightsizing",
+            check::<Vec<ResourceRequestRightsizing>>(),
+        ),
+        ("resource-requests", check::<resource_requests::Output>()),
+        ("runtime-socket", check::<Vec<RuntimeSocketMount>>()),
+        ("secret-type-check", check::<Vec<SecretProblem>>()),
+        (
+            "service-account-privilege",
+            check::<Vec<ServiceAccountPrivilege>>(),
+        ),
+        ("service-exposure", check::<service_exposure::Output>()),
+        ("service-links", check::<Vec<ServiceLinks>>()),
+        ("sidecar-injection-check", check::<Vec<MissingSidecar>>()),
+        (
+            "statefulset-pod-management-policy",
+            check::<Vec<PodManagementPolicyFinding>>(),
+        ),
+        ("stuck-finalizers", check::<Vec<StuckObject>>()),
+        ("termination-messages", check::<Vec<TerminationMessage>>()),
+        ("tiny-requests", check::<Vec<TinyRequest>>()),
+        ("token-expiry-check", check::<Vec<ExpiringToken>>()),
+        ("token-lifetimes", check::<Vec<TokenLifetime>>()),
+        ("top-owners", check::<top_owners::Output>()),
+        ("update-strategies", check::<Vec<UpdateStrategyFinding>>()),
+        ("version-skew", check::<Vec<VersionSkew>>()),
+        (
+            "volume-mount-read-write",
+            check::<Vec<WritableVolumeMount>>(),
+        ),
+    ])
+}
@@ -0,0 +1,2729 @@
+//! Sources the commands read pods, owners and metrics from.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use eyre::{eyre, Context, Result};
+use k8s_openapi::api::{
+    apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet},
+    autoscaling::v2::HorizontalPodAutoscaler,
+    batch::v1::{CronJob, Job},
+    core::v1::{
+        ConfigMap, Event, Namespace, Node, PersistentVolumeClaim, Pod, Secret, Service,
+        ServiceAccount,
+    },
+    discovery::v1::EndpointSlice,
+    networking::v1::Ingress,
+    rbac::v1::{ClusterRole, ClusterRoleBinding, RoleBinding},
+};
+use kube::Client;
+use log::{info, warn};
+use regex::Regex;
+
+use crate::api::{
+    config, event_node, extract_owner, get_all_pod_metrics, get_cluster_role_bindings,
+    get_cluster_roles, get_config_maps, get_cronjobs, get_daemonsets, get_deployments,
+    get_endpoint_slices, get_horizontal_pod_autoscalers, get_ingresses, get_jobs,
+    get_namespace_list, get_namespaces, get_node_events, get_nodes, get_owner_chain_sync,
+    get_persistent_volume_claims, get_pod_owner, get_pod_resource_usage, get_pods,
+    get_replica_sets, get_role_bindings, get_secrets, get_server_version, get_service_accounts,
+    get_services, get_statefulsets, get_vertical_pod_autoscalers, index_pod_metrics, server_info,
+    Impersonation, Listed, Owner, OwnerCache, PodMetrics, ServerInfo, VerticalPodAutoscaler,
+};
+use crate::document::NamespaceError;
+
+/// Version of the api server when it is not known, for example for pods read
+/// from files.
+pub const UNKNOWN_SERVER_VERSION: &str = "unknown";
+
+/// Where the commands get their pods, owners and metrics from.
+#[async_trait]
+pub trait Source: Send + Sync {
+    /// Get the pods of the given namespaces, the current namespace if none are
+    /// given or of all namespaces.
+    async fn pods(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>>;
+
+    /// Get the top level owner of the pod.
+    fn pod_owner(&self, pod: &Pod) -> Option<Owner>;
+
+    /// Get all owners of the pod from the direct owner up to the top level
+    /// owner.
+    fn owner_chain(&self, pod: &Pod) -> Vec<Owner>;
+
+    /// Whether the container with the given name should be checked. Commands
+    /// skip the other containers when they look at the containers of a pod one
+    /// by one.
+    fn checks_container(&self, name: &str) -> bool;
+
+    /// Get the current resource usage of the pod if it is known.
+    async fn pod_resource_usage(&self, namespace: &str, pod: &str) -> Result<Option<PodMetrics>>;
+
+    /// Get the current resource usage of all pods that it is known for, keyed
+    /// by the namespace and name of the pod.
+    async fn all_pod_resource_usage(&self) -> Result<HashMap<(String, String), PodMetrics>>;
+
+    /// Get all nodes of the cluster.
+    async fn nodes(&self) -> Result<Vec<Node>>;
+
+    /// Get the version of the api server, for example `v1.29.2`.
+    async fn server_version(&self) -> Result<String>;
+
+    /// Get the names of all namespaces.
+    async fn namespaces(&self) -> Result<Vec<String>>;
+
+    /// Get all namespaces with their status.
+    async fn namespace_list(&self) -> Result<Vec<Namespace>>;
+
+    /// Get the warning events that happened on the nodes of the cluster.
+    async fn node_events(&self) -> Result<Vec<Event>>;
+
+    /// Get the statefulsets of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn statefulsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<StatefulSet>>;
+
+    /// Get the daemonsets of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn daemonsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<DaemonSet>>;
+
+    /// Get the jobs of the given namespaces, the current namespace if none are
+    /// given or of all namespaces.
+    async fn jobs(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Job>>;
+
+    /// Get the ingresses of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn ingresses(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Ingress>>;
+
+    /// Get the cronjobs of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn cronjobs(&self, namespaces: Vec<String>, all_namespaces: bool)
+        -> Result<Vec<CronJob>>;
+
+    /// Get the services of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn services(&self, namespaces: Vec<String>, all_namespaces: bool)
+        -> Result<Vec<Service>>;
+
+    /// Get the secrets of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn secrets(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Secret>>;
+
+    /// Get the service accounts of the given namespaces, the current namespace
+    /// if none are given or of all namespaces.
+    async fn service_accounts(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ServiceAccount>>;
+
+    /// Get the persistent volume claims of the given namespaces, the current
+    /// namespace if none are given or of all namespaces.
+    async fn persistent_volume_claims(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<PersistentVolumeClaim>>;
+
+    /// Get the endpoint slices of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn endpoint_slices(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<EndpointSlice>>;
+
+    /// Get the config maps of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn config_maps(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ConfigMap>>;
+
+    /// Get the role bindings of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn role_bindings(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<RoleBinding>>;
+
+    /// Get all cluster roles of the cluster.
+    async fn cluster_roles(&self) -> Result<Vec<ClusterRole>>;
+
+    /// Get all cluster role bindings of the cluster.
+    async fn cluster_role_bindings(&self) -> Result<Vec<ClusterRoleBinding>>;
+
+    /// Get the vertical pod autoscalers of the given namespaces, the current
+    /// namespace if none are given or of all namespaces.
+    async fn vertical_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<VerticalPodAutoscaler>>;
+
+    /// Get the horizontal pod autoscalers of the given namespaces, the current
+    /// namespace if none are given or of all namespaces.
+    async fn horizontal_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<HorizontalPodAutoscaler>>;
+
+    /// Get the deployments of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn deployments(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Deployment>>;
+
+    /// Get the replica sets of the given namespaces, the current namespace if
+    /// none are given or of all namespaces.
+    async fn replica_sets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ReplicaSet>>;
+}
+
+/// Reads everything from a kubernetes cluster.
+#[derive(Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ClusterSource {
+    client: Client,
+    context: Option<String>,
+    server: String,
+
+    /// Namespaces that were skipped because they could not be read.
+    errors: Arc<Mutex<BTreeSet<NamespaceError>>>,
+
+    owners: Arc<OwnerCache>,
+}
+
+impl ClusterSource {
+    /// Connect to the cluster of the given kubeconfig context, the current
+    /// context is used if none is given. All requests impersonate the given
+    /// user and groups.
+    pub async fn new(context: Option<&str>, impersonation: &Impersonation) -> Result<Self> {
+        let config = config(context, impersonation).await?;
+        let server = config.cluster_url.to_string();
+        let client = Client::try_from(config).context("failed to create kubernetes client")?;
+
+        Ok(Self {
+            client,
+            context: context.map(ToString::to_string),
+            server,
+            errors: Arc::default(),
+            owners: Arc::default(),
+        })
+    }
+
+    /// Url of the api server of the cluster.
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    /// Version and served apis of the api server of the cluster.
+    pub async fn server_info(&self) -> Result<ServerInfo> {
+        server_info(&self.client).await
+    }
+
+    /// Namespaces that were skipped so far because they could not be read.
+    pub fn namespace_errors(&self) -> Vec<NamespaceError> {
+        self.errors
+            .lock()
+            .expect("failed to lock namespace errors")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn record<K>(&self, (objects, errors): Listed<K>) -> Vec<K> {
+        self.errors
+            .lock()
+            .expect("failed to lock namespace errors")
+            .extend(errors);
+
+        objects
+    }
+}
+
+impl std::fmt::Debug for ClusterSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClusterSource")
+            .field("context", &self.context)
+            .field("server", &self.server)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Source for ClusterSource {
+    async fn pods(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>> {
+        let listed = get_pods(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    fn pod_owner(&self, pod: &Pod) -> Option<Owner> {
+        get_pod_owner(&self.client, &self.owners, pod)
+    }
+
+    fn owner_chain(&self, pod: &Pod) -> Vec<Owner> {
+        get_owner_chain_sync(&self.client, pod).unwrap_or_else(|err| {
+            warn!("Failed to get owner chain of pod: {err:?}");
+            Vec::new()
+        })
+    }
+
+    fn checks_container(&self, _name: &str) -> bool {
+        true
+    }
+
+    async fn pod_resource_usage(&self, namespace: &str, pod: &str) -> Result<Option<PodMetrics>> {
+        get_pod_resource_usage(&self.client, namespace, pod).await
+    }
+
+    async fn all_pod_resource_usage(&self) -> Result<HashMap<(String, String), PodMetrics>> {
+        get_all_pod_metrics(&self.client).await
+    }
+
+    async fn nodes(&self) -> Result<Vec<Node>> {
+        get_nodes(&self.client).await
+    }
+
+    async fn server_version(&self) -> Result<String> {
+        get_server_version(&self.client).await
+    }
+
+    async fn namespaces(&self) -> Result<Vec<String>> {
+        get_namespaces(&self.client).await
+    }
+
+    async fn namespace_list(&self) -> Result<Vec<Namespace>> {
+        get_namespace_list(&self.client).await
+    }
+
+    async fn node_events(&self) -> Result<Vec<Event>> {
+        get_node_events(&self.client).await
+    }
+
+    async fn statefulsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<StatefulSet>> {
+        let listed = get_statefulsets(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn daemonsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<DaemonSet>> {
+        let listed = get_daemonsets(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn jobs(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Job>> {
+        let listed = get_jobs(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn ingresses(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Ingress>> {
+        let listed = get_ingresses(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn cronjobs(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<CronJob>> {
+        let listed = get_cronjobs(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn services(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Service>> {
+        let listed = get_services(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn secrets(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Secret>> {
+        let listed = get_secrets(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn service_accounts(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ServiceAccount>> {
+        let listed = get_service_accounts(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn persistent_volume_claims(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<PersistentVolumeClaim>> {
+        let listed = get_persistent_volume_claims(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn endpoint_slices(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<EndpointSlice>> {
+        let listed = get_endpoint_slices(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn config_maps(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ConfigMap>> {
+        let listed = get_config_maps(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn role_bindings(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<RoleBinding>> {
+        let listed = get_role_bindings(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn cluster_roles(&self) -> Result<Vec<ClusterRole>> {
+        get_cluster_roles(&self.client).await
+    }
+
+    async fn cluster_role_bindings(&self) -> Result<Vec<ClusterRoleBinding>> {
+        get_cluster_role_bindings(&self.client).await
+    }
+
+    async fn vertical_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<VerticalPodAutoscaler>> {
+        let listed = get_vertical_pod_autoscalers(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn horizontal_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<HorizontalPodAutoscaler>> {
+        let listed =
+            get_horizontal_pod_autoscalers(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn deployments(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Deployment>> {
+        let listed = get_deployments(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+
+    async fn replica_sets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ReplicaSet>> {
+        let listed = get_replica_sets(&self.client, namespaces, all_namespaces).await?;
+        Ok(self.record(listed))
+    }
+}
+
+/// Reads pods and optionally metrics and nodes from json dumps, for example created with
+/// `kubectl get pods --all-namespaces -o json`.
+#[derive(Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct FileSource {
+    pods: Vec<Pod>,
+    metrics: Option<Vec<PodMetrics>>,
+    nodes: Option<Vec<Node>>,
+    namespaces: Option<Vec<Namespace>>,
+    statefulsets: Option<Vec<StatefulSet>>,
+    daemonsets: Option<Vec<DaemonSet>>,
+    jobs: Option<Vec<Job>>,
+    ingresses: Option<Vec<Ingress>>,
+    cronjobs: Option<Vec<CronJob>>,
+    services: Option<Vec<Service>>,
+    secrets: Option<Vec<Secret>>,
+    service_accounts: Option<Vec<ServiceAccount>>,
+    persistent_volume_claims: Option<Vec<PersistentVolumeClaim>>,
+    endpoint_slices: Option<Vec<EndpointSlice>>,
+    events: Option<Vec<Event>>,
+    config_maps: Option<Vec<ConfigMap>>,
+    role_bindings: Option<Vec<RoleBinding>>,
+    cluster_roles: Option<Vec<ClusterRole>>,
+    cluster_role_bindings: Option<Vec<ClusterRoleBinding>>,
+    vertical_pod_autoscalers: Option<Vec<VerticalPodAutoscaler>>,
+    horizontal_pod_autoscalers: Option<Vec<HorizontalPodAutoscaler>>,
+    deployments: Option<Vec<Deployment>>,
+    replica_sets: Option<Vec<ReplicaSet>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ItemList<T> {
+    items: Vec<T>,
+}
+
+impl FileSource {
+    /// Open the given pod list and the optional lists of the other objects. A
+    /// path of `-` reads from stdin.
+    #[allow(clippy::too_many_lines)]
+    pub fn open(pods: &Path, optional: OptionalFiles<'_>) -> Result<Self> {
+        let OptionalFiles {
+            metrics,
+            nodes,
+            namespaces,
+            statefulsets,
+            daemonsets,
+            jobs,
+            ingresses,
+            cronjobs,
+            services,
+            secrets,
+            service_accounts,
+            persistent_volume_claims,
+            endpoint_slices,
+            events,
+            config_maps,
+            role_bindings,
+            cluster_roles,
+            cluster_role_bindings,
+            vertical_pod_autoscalers,
+            horizontal_pod_autoscalers,
+            deployments,
+            replica_sets,
+        } = optional;
+
+        let pods = open_reader(pods).context("failed to open pods file")?;
+
+        let metrics = metrics
+            .map(open_reader)
+            .transpose()
+            .context("failed to open metrics file")?;
+
+        let mut source = Self::from_readers(pods, metrics)?;
+
+        if let Some(nodes) = nodes {
+            source = source.with_nodes(open_reader(nodes).context("failed to open nodes file")?)?;
+        }
+
+        if let Some(namespaces) = namespaces {
+            source = source.with_namespaces(
+                open_reader(namespaces).context("failed to open namespaces file")?,
+            )?;
+        }
+
+        if let Some(statefulsets) = statefulsets {
+            source = source.with_statefulsets(
+                open_reader(statefulsets).context("failed to open statefulsets file")?,
+            )?;
+        }
+
+        if let Some(daemonsets) = daemonsets {
+            source = source.with_daemonsets(
+                open_reader(daemonsets).context("failed to open daemonsets file")?,
+            )?;
+        }
+
+        if let Some(jobs) = jobs {
+            source = source.with_jobs(open_reader(jobs).context("failed to open jobs file")?)?;
+        }
+
+        if let Some(ingresses) = ingresses {
+            source = source
+                .with_ingresses(open_reader(ingresses).context("failed to open ingresses file")?)?;
+        }
+
+        if let Some(cronjobs) = cronjobs {
+            source = source
+                .with_cronjobs(open_reader(cronjobs).context("failed to open cronjobs file")?)?;
+        }
+
+        if let Some(services) = services {
+            source = source
+                .with_services(open_reader(services).context("failed to open services file")?)?;
+        }
+
+        if let Some(secrets) = secrets {
+            source = source
+                .with_secrets(open_reader(secrets).context("failed to open secrets file")?)?;
+        }
+
+        if let Some(service_accounts) = service_accounts {
+            source = source.with_service_accounts(
+                open_reader(service_accounts).context("failed to open service accounts file")?,
+            )?;
+        }
+
+        if let Some(persistent_volume_claims) = persistent_volume_claims {
+            source = source.with_persistent_volume_claims(
+                open_reader(persistent_volume_claims)
+                    .context("failed to open persistent volume claims file")?,
+            )?;
+        }
+
+        if let Some(endpoint_slices) = endpoint_slices {
+            source = source.with_endpoint_slices(
+                open_reader(endpoint_slices).context("failed to open endpoint slices file")?,
+            )?;
+        }
+
+        if let Some(events) = events {
+            source =
+                source.with_events(open_reader(events).context("failed to open events file")?)?;
+        }
+
+        if let Some(config_maps) = config_maps {
+            source = source.with_config_maps(
+                open_reader(config_maps).context("failed to open config maps file")?,
+            )?;
+        }
+
+        if let Some(role_bindings) = role_bindings {
+            source = source.with_role_bindings(
+                open_reader(role_bindings).context("failed to open role bindings file")?,
+            )?;
+        }
+
+        if let Some(cluster_roles) = cluster_roles {
+            source = source.with_cluster_roles(
+                open_reader(cluster_roles).context("failed to open cluster roles file")?,
+            )?;
+        }
+
+        if let Some(cluster_role_bindings) = cluster_role_bindings {
+            source = source.with_cluster_role_bindings(
+                open_reader(cluster_role_bindings)
+                    .context("failed to open cluster role bindings file")?,
+            )?;
+        }
+
+        if let Some(vertical_pod_autoscalers) = vertical_pod_autoscalers {
+            source = source.with_vertical_pod_autoscalers(
+                open_reader(vertical_pod_autoscalers)
+                    .context("failed to open vertical pod autoscalers file")?,
+            )?;
+        }
+
+        if let Some(horizontal_pod_autoscalers) = horizontal_pod_autoscalers {
+            source = source.with_horizontal_pod_autoscalers(
+                open_reader(horizontal_pod_autoscalers)
+                    .context("failed to open horizontal pod autoscalers file")?,
+            )?;
+        }
+
+        if let Some(deployments) = deployments {
+            source = source.with_deployments(
+                open_reader(deployments).context("failed to open deployments file")?,
+            )?;
+        }
+
+        if let Some(replica_sets) = replica_sets {
+            source = source.with_replica_sets(
+                open_reader(replica_sets).context("failed to open replica sets file")?,
+            )?;
+        }
+
+        Ok(source)
+    }
+
+    /// Read the pod list and metrics list from the given readers.
+    pub fn from_readers(pods: impl Read, metrics: Option<impl Read>) -> Result<Self> {
+        let pods = serde_json::from_reader::<_, ItemList<_>>(pods)
+            .context("failed to parse pod list")?
+            .items;
+
+        let metrics = metrics
+            .map(serde_json::from_reader::<_, ItemList<_>>)
+            .transpose()
+            .context("failed to parse pod metrics list")?
+            .map(|list| list.items);
+
+        Ok(Self {
+            pods,
+            metrics,
+            nodes: None,
+            namespaces: None,
+            statefulsets: None,
+            daemonsets: None,
+            jobs: None,
+            ingresses: None,
+            cronjobs: None,
+            services: None,
+            secrets: None,
+            service_accounts: None,
+            persistent_volume_claims: None,
+            endpoint_slices: None,
+            events: None,
+            config_maps: None,
+            role_bindings: None,
+            cluster_roles: None,
+            cluster_role_bindings: None,
+            vertical_pod_autoscalers: None,
+            horizontal_pod_autoscalers: None,
+            deployments: None,
+            replica_sets: None,
+        })
+    }
+
+    /// Read the node list from the given reader, for example created with
+    /// `kubectl get nodes -o json`.
+    pub fn with_nodes(self, nodes: impl Read) -> Result<Self> {
+        let nodes = serde_json::from_reader::<_, ItemList<_>>(nodes)
+            .context("failed to parse node list")?
+            .items;
+
+        Ok(Self {
+            nodes: Some(nodes),
+            ..self
+        })
+    }
+
+    /// Read the namespace list from the given reader, for example created with
+    /// `kubectl get namespaces -o json`.
+    pub fn with_namespaces(self, namespaces: impl Read) -> Result<Self> {
+        let namespaces = serde_json::from_reader::<_, ItemList<_>>(namespaces)
+            .context("failed to parse namespace list")?
+            .items;
+
+        Ok(Self {
+            namespaces: Some(namespaces),
+            ..self
+        })
+    }
+
+    /// Read the statefulset list from the given reader, for example created
+    /// with `kubectl get statefulsets --all-namespaces -o json`.
+    pub fn with_statefulsets(self, statefulsets: impl Read) -> Result<Self> {
+        let statefulsets = serde_json::from_reader::<_, ItemList<_>>(statefulsets)
+            .context("failed to parse statefulset list")?
+            .items;
+
+        Ok(Self {
+            statefulsets: Some(statefulsets),
+            ..self
+        })
+    }
+
+    /// Read the daemonset list from the given reader, for example created with
+    /// `kubectl get daemonsets --all-namespaces -o json`.
+    pub fn with_daemonsets(self, daemonsets: impl Read) -> Result<Self> {
+        let daemonsets = serde_json::from_reader::<_, ItemList<_>>(daemonsets)
+            .context("failed to parse daemonset list")?
+            .items;
+
+        Ok(Self {
+            daemonsets: Some(daemonsets),
+            ..self
+        })
+    }
+
+    /// Read the job list from the given reader, for example created with
+    /// `kubectl get jobs --all-namespaces -o json`.
+    pub fn with_jobs(self, jobs: impl Read) -> Result<Self> {
+        let jobs = serde_json::from_reader::<_, ItemList<_>>(jobs)
+            .context("failed to parse job list")?
+            .items;
+
+        Ok(Self {
+            jobs: Some(jobs),
+            ..self
+        })
+    }
+
+    /// Read the ingress list from the given reader, for example created with
+    /// `kubectl get ingresses --all-namespaces -o json`.
+    pub fn with_ingresses(self, ingresses: impl Read) -> Result<Self> {
+        let ingresses = serde_json::from_reader::<_, ItemList<_>>(ingresses)
+            .context("failed to parse ingress list")?
+            .items;
+
+        Ok(Self {
+            ingresses: Some(ingresses),
+            ..self
+        })
+    }
+
+    /// Read the cronjob list from the given reader, for example created with
+    /// `kubectl get cronjobs --all-namespaces -o json`.
+    pub fn with_cronjobs(self, cronjobs: impl Read) -> Result<Self> {
+        let cronjobs = serde_json::from_reader::<_, ItemList<_>>(cronjobs)
+            .context("failed to parse cronjob list")?
+            .items;
+
+        Ok(Self {
+            cronjobs: Some(cronjobs),
+            ..self
+        })
+    }
+
+    /// Read the service list from the given reader, for example created with
+    /// `kubectl get services --all-namespaces -o json`.
+    pub fn with_services(self, services: impl Read) -> Result<Self> {
+        let services = serde_json::from_reader::<_, ItemList<_>>(services)
+            .context("failed to parse service list")?
+            .items;
+
+        Ok(Self {
+            services: Some(services),
+            ..self
+        })
+    }
+
+    /// Read the secret list from the given reader, for example created with
+    /// `kubectl get secrets --all-namespaces -o json`.
+    pub fn with_secrets(self, secrets: impl Read) -> Result<Self> {
+        let secrets = serde_json::from_reader::<_, ItemList<_>>(secrets)
+            .context("failed to parse secret list")?
+            .items;
+
+        Ok(Self {
+            secrets: Some(secrets),
+            ..self
+        })
+    }
+
+    /// Read the service account list from the given reader, for example created
+    /// with `kubectl get serviceaccounts --all-namespaces -o json`.
+    pub fn with_service_accounts(self, service_accounts: impl Read) -> Result<Self> {
+        let service_accounts = serde_json::from_reader::<_, ItemList<_>>(service_accounts)
+            .context("failed to parse service account list")?
+            .items;
+
+        Ok(Self {
+            service_accounts: Some(service_accounts),
+            ..self
+        })
+    }
+
+    /// Read the persistent volume claim list from the given reader, for example created with
+    /// `kubectl get persistentvolumeclaims --all-namespaces -o json`.
+    pub fn with_persistent_volume_claims(
+        self,
+        persistent_volume_claims: impl Read,
+    ) -> Result<Self> {
+        let persistent_volume_claims =
+            serde_json::from_reader::<_, ItemList<_>>(persistent_volume_claims)
+                .context("failed to parse persistent volume claim list")?
+                .items;
+
+        Ok(Self {
+            persistent_volume_claims: Some(persistent_volume_claims),
+            ..self
+        })
+    }
+
+    /// Read the endpoint slice list from the given reader, for example created
+    /// with `kubectl get endpointslices --all-namespaces -o json`.
+    pub fn with_endpoint_slices(self, endpoint_slices: impl Read) -> Result<Self> {
+        let endpoint_slices = serde_json::from_reader::<_, ItemList<_>>(endpoint_slices)
+            .context("failed to parse endpoint slice list")?
+            .items;
+
+        Ok(Self {
+            endpoint_slices: Some(endpoint_slices),
+            ..self
+        })
+    }
+
+    /// Read the event list from the given reader, for example created with
+    /// `kubectl get events --all-namespaces -o json`.
+    pub fn with_events(self, events: impl Read) -> Result<Self> {
+        let events = serde_json::from_reader::<_, ItemList<_>>(events)
+            .context("failed to parse event list")?
+            .items;
+
+        Ok(Self {
+            events: Some(events),
+            ..self
+        })
+    }
+
+    /// Read the config map list from the given reader, for example created
+    /// with `kubectl get configmaps --all-namespaces -o json`.
+    pub fn with_config_maps(self, config_maps: impl Read) -> Result<Self> {
+        let config_maps = serde_json::from_reader::<_, ItemList<_>>(config_maps)
+            .context("failed to parse config map list")?
+            .items;
+
+        Ok(Self {
+            config_maps: Some(config_maps),
+            ..self
+        })
+    }
+
+    /// Read the role binding list from the given reader, for example created
+    /// with `kubectl get rolebindings --all-namespaces -o json`.
+    pub fn with_role_bindings(self, role_bindings: impl Read) -> Result<Self> {
+        let role_bindings = serde_json::from_reader::<_, ItemList<_>>(role_bindings)
+            .context("failed to parse role binding list")?
+            .items;
+
+        Ok(Self {
+            role_bindings: Some(role_bindings),
+            ..self
+        })
+    }
+
+    /// Read the cluster role list from the given reader, for example created
+    /// with `kubectl get clusterroles -o json`.
+    pub fn with_cluster_roles(self, cluster_roles: impl Read) -> Result<Self> {
+        let cluster_roles = serde_json::from_reader::<_, ItemList<_>>(cluster_roles)
+            .context("failed to parse cluster role list")?
+            .items;
+
+        Ok(Self {
+            cluster_roles: Some(cluster_roles),
+            ..self
+        })
+    }
+
+    /// Read the cluster role binding list from the given reader, for example
+    /// created with `kubectl get clusterrolebindings -o json`.
+    pub fn with_cluster_role_bindings(self, cluster_role_bindings: impl Read) -> Result<Self> {
+        let cluster_role_bindings =
+            serde_json::from_reader::<_, ItemList<_>>(cluster_role_bindings)
+                .context("failed to parse cluster role binding list")?
+                .items;
+
+        Ok(Self {
+            cluster_role_bindings: Some(cluster_role_bindings),
+            ..self
+        })
+    }
+
+    /// Read the vertical pod autoscaler list from the given reader, for example
+    /// created with `kubectl get verticalpodautoscalers --all-namespaces -o
+    /// json`.
+    pub fn with_vertical_pod_autoscalers(
+        self,
+        vertical_pod_autoscalers: impl Read,
+    ) -> Result<Self> {
+        let vertical_pod_autoscalers =
+            serde_json::from_reader::<_, ItemList<_>>(vertical_pod_autoscalers)
+                .context("failed to parse vertical pod autoscaler list")?
+                .items;
+
+        Ok(Self {
+            vertical_pod_autoscalers: Some(vertical_pod_autoscalers),
+            ..self
+        })
+    }
+
+    /// Read the horizontal pod autoscaler list from the given reader, for
+    /// example created with `kubectl get hpa --all-namespaces -o json`.
+    pub fn with_horizontal_pod_autoscalers(
+        self,
+        horizontal_pod_autoscalers: impl Read,
+    ) -> Result<Self> {
+        let horizontal_pod_autoscalers =
+            serde_json::from_reader::<_, ItemList<_>>(horizontal_pod_autoscalers)
+                .context("failed to parse horizontal pod autoscaler list")?
+                .items;
+
+        Ok(Self {
+            horizontal_pod_autoscalers: Some(horizontal_pod_autoscalers),
+            ..self
+        })
+    }
+
+    /// Read the deployment list from the given reader, for example created with
+    /// `kubectl get deployments --all-namespaces -o json`.
+    pub fn with_deployments(self, deployments: impl Read) -> Result<Self> {
+        let deployments = serde_json::from_reader::<_, ItemList<_>>(deployments)
+            .context("failed to parse deployment list")?
+            .items;
+
+        Ok(Self {
+            deployments: Some(deployments),
+            ..self
+        })
+    }
+
+    /// Read the replica set list from the given reader, for example created
+    /// with `kubectl get replicasets --all-namespaces -o json`.
+    pub fn with_replica_sets(self, replica_sets: impl Read) -> Result<Self> {
+        let replica_sets = serde_json::from_reader::<_, ItemList<_>>(replica_sets)
+            .context("failed to parse replica set list")?
+            .items;
+
+        Ok(Self {
+            replica_sets: Some(replica_sets),
+            ..self
+        })
+    }
+}
+
+/// Paths of the files next to the pod list a [`FileSource`] can read.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OptionalFiles<'a> {
+    /// Pod metrics list.
+    pub metrics: Option<&'a Path>,
+
+    /// Node list.
+    pub nodes: Option<&'a Path>,
+
+    /// Namespace list.
+    pub namespaces: Option<&'a Path>,
+
+    /// Statefulset list.
+    pub statefulsets: Option<&'a Path>,
+
+    /// Daemonset list.
+    pub daemonsets: Option<&'a Path>,
+
+    /// Job list.
+    pub jobs: Option<&'a Path>,
+
+    /// Ingress list.
+    pub ingresses: Option<&'a Path>,
+
+    /// Cronjob list.
+    pub cronjobs: Option<&'a Path>,
+
+    /// Service list.
+    pub services: Option<&'a Path>,
+
+    /// Secret list.
+    pub secrets: Option<&'a Path>,
+
+    /// Service account list.
+    pub service_accounts: Option<&'a Path>,
+
+    /// Persistent volume claim list.
+    pub persistent_volume_claims: Option<&'a Path>,
+
+    /// Endpoint slice list.
+    pub endpoint_slices: Option<&'a Path>,
+
+    /// Event list.
+    pub events: Option<&'a Path>,
+
+    /// Config map list.
+    pub config_maps: Option<&'a Path>,
+
+    /// Role binding list.
+    pub role_bindings: Option<&'a Path>,
+
+    /// Cluster role list.
+    pub cluster_roles: Option<&'a Path>,
+
+    /// Cluster role binding list.
+    pub cluster_role_bindings: Option<&'a Path>,
+
+    /// Vertical pod autoscaler list.
+    pub vertical_pod_autoscalers: Option<&'a Path>,
+
+    /// Horizontal pod autoscaler list.
+    pub horizontal_pod_autoscalers: Option<&'a Path>,
+
+    /// Deployment list.
+    pub deployments: Option<&'a Path>,
+
+    /// Replica set list.
+    pub replica_sets: Option<&'a Path>,
+}
+
+/// Objects of the file that belong to the given namespaces, all objects if no
+/// namespaces are given.
+fn in_namespaces<K>(objects: &[K], namespaces: &[String], all_namespaces: bool) -> Vec<K>
+where
+    K: kube::Resource + Clone,
+{
+    if all_namespaces || namespaces.is_empty() {
+        return objects.to_vec();
+    }
+
+    objects
+        .iter()
+        .filter(|object| {
+            object
+                .meta()
+                .namespace
+                .as_ref()
+                .is_some_and(|namespace| namespaces.contains(namespace))
+        })
+        .cloned()
+        .collect()
+}
+
+fn open_reader(path: &Path) -> Result<Box<dyn Read>> {
+    if path == Path::new("-") {
+        return Ok(Box::new(std::io::stdin().lock()));
+    }
+
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+    Ok(Box::new(BufReader::new(file)))
+}
+
+#[async_trait]
+impl Source for FileSource {
+    async fn pods(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>> {
+        if !all_namespaces && namespaces.is_empty() {
+            info!("No namespaces given, using all pods from the file");
+        }
+
+        Ok(in_namespaces(&self.pods, &namespaces, all_namespaces))
+    }
+
+    /// Owners of other objects can not be looked up from the file so this
+    /// only returns the direct controller of the pod.
+    fn pod_owner(&self, pod: &Pod) -> Option<Owner> {
+        let namespace = pod.metadata.namespace.as_deref().unwrap_or_default();
+
+        extract_owner(pod).map(|owner_reference| Owner::new(owner_reference, namespace))
+    }
+
+    /// Same as [`FileSource::pod_owner`] the chain only contains the direct
+    /// controller of the pod.
+    fn owner_chain(&self, pod: &Pod) -> Vec<Owner> {
+        self.pod_owner(pod).into_iter().collect()
+    }
+
+    fn checks_container(&self, _name: &str) -> bool {
+        true
+    }
+
+    async fn pod_resource_usage(&self, namespace: &str, pod: &str) -> Result<Option<PodMetrics>> {
+        let usage = self.metrics.as_ref().and_then(|metrics| {
+            metrics
+                .iter()
+                .find(|metrics| {
+                    metrics.metadata.namespace.as_deref() == Some(namespace)
+                        && metrics.metadata.name.as_deref() == Some(pod)
+                })
+                .cloned()
+        });
+
+        Ok(usage)
+    }
+
+    async fn all_pod_resource_usage(&self) -> Result<HashMap<(String, String), PodMetrics>> {
+        Ok(index_pod_metrics(self.metrics.iter().flatten().cloned()))
+    }
+
+    async fn nodes(&self) -> Result<Vec<Node>> {
+        self.nodes
+            .clone()
+            .ok_or_else(|| eyre!("no node list given, use --nodes-file"))
+    }
+
+    /// The files do not say which api server they were read from.
+    async fn server_version(&self) -> Result<String> {
+        Ok(UNKNOWN_SERVER_VERSION.to_string())
+    }
+
+    /// Only namespaces that contain at least one pod of the file are known.
+    async fn namespaces(&self) -> Result<Vec<String>> {
+        let namespaces = self
+            .pods
+            .iter()
+            .filter_map(|pod| pod.metadata.namespace.clone())
+            .collect::<BTreeSet<_>>();
+
+        Ok(namespaces.into_iter().collect())
+    }
+
+    async fn namespace_list(&self) -> Result<Vec<Namespace>> {
+        self.namespaces
+            .clone()
+            .ok_or_else(|| eyre!("no namespace list given, use --namespaces-file"))
+    }
+
+    /// Only warning events that name their node are returned, like
+    /// [`get_node_events`] does for a cluster.
+    async fn node_events(&self) -> Result<Vec<Event>> {
+        let events = self
+            .events
+            .as_ref()
+            .ok_or_else(|| eyre!("no event list given, use --events-file"))?;
+
+        Ok(events
+            .iter()
+            .filter(|event| {
+                event.type_.as_deref() == Some("Warning") && event_node(event).is_some()
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn statefulsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<StatefulSet>> {
+        let statefulsets = self
+            .statefulsets
+            .as_ref()
+            .ok_or_else(|| eyre!("no statefulset list given, use --statefulsets-file"))?;
+
+        Ok(in_namespaces(statefulsets, &namespaces, all_namespaces))
+    }
+
+    async fn daemonsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<DaemonSet>> {
+        let daemonsets = self
+            .daemonsets
+            .as_ref()
+            .ok_or_else(|| eyre!("no daemonset list given, use --daemonsets-file"))?;
+
+        Ok(in_namespaces(daemonsets, &namespaces, all_namespaces))
+    }
+
+    async fn jobs(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Job>> {
+        let jobs = self
+            .jobs
+            .as_ref()
+            .ok_or_else(|| eyre!("no job list given, use --jobs-file"))?;
+
+        Ok(in_namespaces(jobs, &namespaces, all_namespaces))
+    }
+
+    async fn ingresses(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Ingress>> {
+        let ingresses = self
+            .ingresses
+            .as_ref()
+            .ok_or_else(|| eyre!("no ingress list given, use --ingresses-file"))?;
+
+        Ok(in_namespaces(ingresses, &namespaces, all_namespaces))
+    }
+
+    async fn cronjobs(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<CronJob>> {
+        let cronjobs = self
+            .cronjobs
+            .as_ref()
+            .ok_or_else(|| eyre!("no cronjob list given, use --cronjobs-file"))?;
+
+        Ok(in_namespaces(cronjobs, &namespaces, all_namespaces))
+    }
+
+    async fn services(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Service>> {
+        let services = self
+            .services
+            .as_ref()
+            .ok_or_else(|| eyre!("no service list given, use --services-file"))?;
+
+        Ok(in_namespaces(services, &namespaces, all_namespaces))
+    }
+
+    async fn secrets(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Secret>> {
+        let secrets = self
+            .secrets
+            .as_ref()
+            .ok_or_else(|| eyre!("no secret list given, use --secrets-file"))?;
+
+        Ok(in_namespaces(secrets, &namespaces, all_namespaces))
+    }
+
+    async fn service_accounts(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ServiceAccount>> {
+        let service_accounts = self
+            .service_accounts
+            .as_ref()
+            .ok_or_else(|| eyre!("no service account list given, use --service-accounts-file"))?;
+
+        Ok(in_namespaces(service_accounts, &namespaces, all_namespaces))
+    }
+
+    async fn persistent_volume_claims(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<PersistentVolumeClaim>> {
+        let persistent_volume_claims = self.persistent_volume_claims.as_ref().ok_or_else(|| {
+            eyre!("no persistent volume claim list given, use --persistent-volume-claims-file")
+        })?;
+
+        Ok(in_namespaces(
+            persistent_volume_claims,
+            &namespaces,
+            all_namespaces,
+        ))
+    }
+
+    async fn endpoint_slices(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<EndpointSlice>> {
+        let endpoint_slices = self
+            .endpoint_slices
+            .as_ref()
+            .ok_or_else(|| eyre!("no endpoint slice list given, use --endpoint-slices-file"))?;
+
+        Ok(in_namespaces(endpoint_slices, &namespaces, all_namespaces))
+    }
+
+    async fn config_maps(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ConfigMap>> {
+        let config_maps = self
+            .config_maps
+            .as_ref()
+            .ok_or_else(|| eyre!("no config map list given, use --config-maps-file"))?;
+
+        Ok(in_namespaces(config_maps, &namespaces, all_namespaces))
+    }
+
+    async fn role_bindings(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<RoleBinding>> {
+        let role_bindings = self
+            .role_bindings
+            .as_ref()
+            .ok_or_else(|| eyre!("no role binding list given, use --role-bindings-file"))?;
+
+        Ok(in_namespaces(role_bindings, &namespaces, all_namespaces))
+    }
+
+    async fn cluster_roles(&self) -> Result<Vec<ClusterRole>> {
+        self.cluster_roles
+            .clone()
+            .ok_or_else(|| eyre!("no cluster role list given, use --cluster-roles-file"))
+    }
+
+    async fn cluster_role_bindings(&self) -> Result<Vec<ClusterRoleBinding>> {
+        self.cluster_role_bindings.clone().ok_or_else(|| {
+            eyre!("no cluster role binding list given, use --cluster-role-bindings-file")
+        })
+    }
+
+    async fn vertical_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<VerticalPodAutoscaler>> {
+        let vertical_pod_autoscalers = self.vertical_pod_autoscalers.as_ref().ok_or_else(|| {
+            eyre!("no vertical pod autoscaler list given, use --vertical-pod-autoscalers-file")
+        })?;
+
+        Ok(in_namespaces(
+            vertical_pod_autoscalers,
+            &namespaces,
+            all_namespaces,
+        ))
+    }
+
+    async fn horizontal_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<HorizontalPodAutoscaler>> {
+        let horizontal_pod_autoscalers =
+            self.horizontal_pod_autoscalers.as_ref().ok_or_else(|| {
+                eyre!(
+                    "no horizontal pod autoscaler list given, use \
+                     --horizontal-pod-autoscalers-file"
+                )
+            })?;
+
+        Ok(in_namespaces(
+            horizontal_pod_autoscalers,
+            &namespaces,
+            all_namespaces,
+        ))
+    }
+
+    async fn deployments(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Deployment>> {
+        let deployments = self
+            .deployments
+            .as_ref()
+            .ok_or_else(|| eyre!("no deployment list given, use --deployments-file"))?;
+
+        Ok(in_namespaces(deployments, &namespaces, all_namespaces))
+    }
+
+    async fn replica_sets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ReplicaSet>> {
+        let replica_sets = self
+            .replica_sets
+            .as_ref()
+            .ok_or_else(|| eyre!("no replica set list given, use --replica-sets-file"))?;
+
+        Ok(in_namespaces(replica_sets, &namespaces, all_namespaces))
+    }
+}
+
+/// What a command read from a source.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Examined {
+    /// Namespace and name of every pod returned to the command.
+    pub pods: BTreeSet<(String, String)>,
+
+    /// Whether the command asked for the resource usage of pods.
+    pub metrics_requested: bool,
+
+    /// Whether the resource usage was known for at least one pod.
+    pub metrics_found: bool,
+
+    /// Namespaces selected by a [`NamespaceSelector`] if one was used.
+    pub selected_namespaces: Option<BTreeSet<String>>,
+
+    /// Namespaces that were skipped because they could not be read.
+    pub namespace_errors: Vec<NamespaceError>,
+
+    /// Time the oldest response read from the on-disk cache was fetched.
+    pub cached_at: Option<String>,
+}
+
+impl Examined {
+    /// Number of namespaces the examined pods belong to.
+    pub fn namespaces(&self) -> usize {
+        self.pods
+            .iter()
+            .map(|(namespace, _)| namespace)
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    /// Notes about missing data that the report of the command is affected
+    /// by.
+    pub fn notes(&self) -> Vec<String> {
+        if self.metrics_requested && !self.metrics_found {
+            vec!["no pod metrics were available, usage values are missing".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Wraps another source and records what the command read from it.
+#[allow(clippy::module_name_repetitions)]
+pub struct RecordingSource<'a> {
+    inner: &'a dyn Source,
+    examined: Mutex<Examined>,
+}
+
+impl<'a> RecordingSource<'a> {
+    /// Record everything read from the given source.
+    pub fn new(inner: &'a dyn Source) -> Self {
+        Self {
+            inner,
+            examined: Mutex::default(),
+        }
+    }
+
+    /// Everything the command read so far.
+    pub fn examined(&self) -> Examined {
+        self.examined
+            .lock()
+            .expect("failed to lock examined")
+            .clone()
+    }
+}
+
+impl std::fmt::Debug for RecordingSource<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordingSource")
+            .field("examined", &self.examined)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Source for RecordingSource<'_> {
+    async fn pods(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>> {
+        let pods = self.inner.pods(namespaces, all_namespaces).await?;
+
+        self.examined
+            .lock()
+            .expect("failed to lock examined")
+            .pods
+            .extend(pods.iter().map(|pod| {
+                (
+                    pod.metadata.namespace.clone().unwrap_or_default(),
+                    pod.metadata.name.clone().unwrap_or_default(),
+                )
+            }));
+
+        Ok(pods)
+    }
+
+    fn pod_owner(&self, pod: &Pod) -> Option<Owner> {
+        self.inner.pod_owner(pod)
+    }
+
+    fn owner_chain(&self, pod: &Pod) -> Vec<Owner> {
+        self.inner.owner_chain(pod)
+    }
+
+    fn checks_container(&self, name: &str) -> bool {
+        self.inner.checks_container(name)
+    }
+
+    async fn pod_resource_usage(&self, namespace: &str, pod: &str) -> Result<Option<PodMetrics>> {
+        let usage = self.inner.pod_resource_usage(namespace, pod).await?;
+
+        let mut examined = self.examined.lock().expect("failed to lock examined");
+        examined.metrics_requested = true;
+        examined.metrics_found |= usage.is_some();
+
+        Ok(usage)
+    }
+
+    async fn all_pod_resource_usage(&self) -> Result<HashMap<(String, String), PodMetrics>> {
+        let usage = self.inner.all_pod_resource_usage().await?;
+
+        let mut examined = self.examined.lock().expect("failed to lock examined");
+        examined.metrics_requested = true;
+        examined.metrics_found |= !usage.is_empty();
+
+        Ok(usage)
+    }
+
+    async fn nodes(&self) -> Result<Vec<Node>> {
+        self.inner.nodes().await
+    }
+
+    async fn server_version(&self) -> Result<String> {
+        self.inner.server_version().await
+    }
+
+    async fn namespaces(&self) -> Result<Vec<String>> {
+        self.inner.namespaces().await
+    }
+
+    async fn namespace_list(&self) -> Result<Vec<Namespace>> {
+        self.inner.namespace_list().await
+    }
+
+    async fn node_events(&self) -> Result<Vec<Event>> {
+        self.inner.node_events().await
+    }
+
+    async fn statefulsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<StatefulSet>> {
+        self.inner.statefulsets(namespaces, all_namespaces).await
+    }
+
+    async fn daemonsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<DaemonSet>> {
+        self.inner.daemonsets(namespaces, all_namespaces).await
+    }
+
+    async fn jobs(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Job>> {
+        self.inner.jobs(namespaces, all_namespaces).await
+    }
+
+    async fn ingresses(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Ingress>> {
+        self.inner.ingresses(namespaces, all_namespaces).await
+    }
+
+    async fn cronjobs(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<CronJob>> {
+        self.inner.cronjobs(namespaces, all_namespaces).await
+    }
+
+    async fn services(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Service>> {
+        self.inner.services(namespaces, all_namespaces).await
+    }
+
+    async fn secrets(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Secret>> {
+        self.inner.secrets(namespaces, all_namespaces).await
+    }
+
+    async fn service_accounts(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ServiceAccount>> {
+        self.inner
+            .service_accounts(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn persistent_volume_claims(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<PersistentVolumeClaim>> {
+        self.inner
+            .persistent_volume_claims(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn endpoint_slices(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<EndpointSlice>> {
+        self.inner.endpoint_slices(namespaces, all_namespaces).await
+    }
+
+    async fn config_maps(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ConfigMap>> {
+        self.inner.config_maps(namespaces, all_namespaces).await
+    }
+
+    async fn role_bindings(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<RoleBinding>> {
+        self.inner.role_bindings(namespaces, all_namespaces).await
+    }
+
+    async fn cluster_roles(&self) -> Result<Vec<ClusterRole>> {
+        self.inner.cluster_roles().await
+    }
+
+    async fn cluster_role_bindings(&self) -> Result<Vec<ClusterRoleBinding>> {
+        self.inner.cluster_role_bindings().await
+    }
+
+    async fn vertical_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<VerticalPodAutoscaler>> {
+        self.inner
+            .vertical_pod_autoscalers(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn horizontal_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<HorizontalPodAutoscaler>> {
+        self.inner
+            .horizontal_pod_autoscalers(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn deployments(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Deployment>> {
+        self.inner.deployments(namespaces, all_namespaces).await
+    }
+
+    async fn replica_sets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ReplicaSet>> {
+        self.inner.replica_sets(namespaces, all_namespaces).await
+    }
+}
+
+/// Wraps another source and skips resolving the owners of pods.
+pub struct WithoutOwners<'a> {
+    inner: &'a dyn Source,
+}
+
+impl<'a> WithoutOwners<'a> {
+    /// Skip owner resolution for the given source.
+    pub fn new(inner: &'a dyn Source) -> Self {
+        Self { inner }
+    }
+}
+
+impl std::fmt::Debug for WithoutOwners<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WithoutOwners").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Source for WithoutOwners<'_> {
+    async fn pods(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>> {
+        self.inner.pods(namespaces, all_namespaces).await
+    }
+
+    fn pod_owner(&self, _pod: &Pod) -> Option<Owner> {
+        None
+    }
+
+    fn owner_chain(&self, _pod: &Pod) -> Vec<Owner> {
+        Vec::new()
+    }
+
+    fn checks_container(&self, name: &str) -> bool {
+        self.inner.checks_container(name)
+    }
+
+    async fn pod_resource_usage(&self, namespace: &str, pod: &str) -> Result<Option<PodMetrics>> {
+        self.inner.pod_resource_usage(namespace, pod).await
+    }
+
+    async fn all_pod_resource_usage(&self) -> Result<HashMap<(String, String), PodMetrics>> {
+        self.inner.all_pod_resource_usage().await
+    }
+
+    async fn nodes(&self) -> Result<Vec<Node>> {
+        self.inner.nodes().await
+    }
+
+    async fn server_version(&self) -> Result<String> {
+        self.inner.server_version().await
+    }
+
+    async fn namespaces(&self) -> Result<Vec<String>> {
+        self.inner.namespaces().await
+    }
+
+    async fn namespace_list(&self) -> Result<Vec<Namespace>> {
+        self.inner.namespace_list().await
+    }
+
+    async fn node_events(&self) -> Result<Vec<Event>> {
+        self.inner.node_events().await
+    }
+
+    async fn statefulsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<StatefulSet>> {
+        self.inner.statefulsets(namespaces, all_namespaces).await
+    }
+
+    async fn daemonsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<DaemonSet>> {
+        self.inner.daemonsets(namespaces, all_namespaces).await
+    }
+
+    async fn jobs(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Job>> {
+        self.inner.jobs(namespaces, all_namespaces).await
+    }
+
+    async fn ingresses(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Ingress>> {
+        self.inner.ingresses(namespaces, all_namespaces).await
+    }
+
+    async fn cronjobs(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<CronJob>> {
+        self.inner.cronjobs(namespaces, all_namespaces).await
+    }
+
+    async fn services(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Service>> {
+        self.inner.services(namespaces, all_namespaces).await
+    }
+
+    async fn secrets(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Secret>> {
+        self.inner.secrets(namespaces, all_namespaces).await
+    }
+
+    async fn service_accounts(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ServiceAccount>> {
+        self.inner
+            .service_accounts(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn persistent_volume_claims(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<PersistentVolumeClaim>> {
+        self.inner
+            .persistent_volume_claims(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn endpoint_slices(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<EndpointSlice>> {
+        self.inner.endpoint_slices(namespaces, all_namespaces).await
+    }
+
+    async fn config_maps(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ConfigMap>> {
+        self.inner.config_maps(namespaces, all_namespaces).await
+    }
+
+    async fn role_bindings(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<RoleBinding>> {
+        self.inner.role_bindings(namespaces, all_namespaces).await
+    }
+
+    async fn cluster_roles(&self) -> Result<Vec<ClusterRole>> {
+        self.inner.cluster_roles().await
+    }
+
+    async fn cluster_role_bindings(&self) -> Result<Vec<ClusterRoleBinding>> {
+        self.inner.cluster_role_bindings().await
+    }
+
+    async fn vertical_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<VerticalPodAutoscaler>> {
+        self.inner
+            .vertical_pod_autoscalers(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn horizontal_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<HorizontalPodAutoscaler>> {
+        self.inner
+            .horizontal_pod_autoscalers(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn deployments(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Deployment>> {
+        self.inner.deployments(namespaces, all_namespaces).await
+    }
+
+    async fn replica_sets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ReplicaSet>> {
+        self.inner.replica_sets(namespaces, all_namespaces).await
+    }
+}
+
+/// Narrows down the namespaces the commands look at.
+#[derive(Debug, Clone, Default)]
+pub struct NamespaceSelector {
+    regex: Option<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl NamespaceSelector {
+    /// Select namespaces matching the regex that do not match any of the
+    /// exclude globs. Globs support `*` and `?`.
+    pub fn new(regex: Option<Regex>, exclude: &[String]) -> Self {
+        let exclude = exclude.iter().map(|glob| glob_to_regex(glob)).collect();
+
+        Self { regex, exclude }
+    }
+
+    /// Whether the selector selects every namespace.
+    pub fn is_empty(&self) -> bool {
+        self.regex.is_none() && self.exclude.is_empty()
+    }
+
+    /// Whether the namespace is selected.
+    pub fn matches(&self, namespace: &str) -> bool {
+        let included = match &self.regex {
+            Some(regex) => regex.is_match(namespace),
+            None => true,
+        };
+
+        included && !self.exclude.iter().any(|glob| glob.is_match(namespace))
+    }
+}
+
+/// Regex matching the whole string against a glob with `*` and `?`.
+pub(crate) fn glob_to_regex(glob: &str) -> Regex {
+    let pattern = glob
+        .split('*')
+        .map(|part| {
+            part.split('?')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect::<Vec<_>>()
+        .join(".*");
+
+    Regex::new(&format!("^{pattern}$")).expect("escaped glob is a valid regex")
+}
+
+/// Wraps another source and only returns pods of the namespaces selected by a
+/// [`NamespaceSelector`]. The namespaces are listed first and the pods are
+/// then requested per selected namespace.
+pub struct SelectedNamespaces<'a> {
+    inner: &'a dyn Source,
+    selector: &'a NamespaceSelector,
+    selected: Mutex<BTreeSet<String>>,
+}
+
+impl<'a> SelectedNamespaces<'a> {
+    /// Apply the selector to the objects of the given source.
+    pub fn new(inner: &'a dyn Source, selector: &'a NamespaceSelector) -> Self {
+        Self {
+            inner,
+            selector,
+            selected: Mutex::default(),
+        }
+    }
+
+    /// Without explicit namespaces the selector is applied to all namespaces.
+    async fn select(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<String>> {
+        let candidates = if all_namespaces || namespaces.is_empty() {
+            self.inner.namespaces().await?
+        } else {
+            namespaces
+        };
+
+        let selected = candidates
+            .into_iter()
+            .filter(|namespace| self.selector.matches(namespace))
+            .collect::<Vec<_>>();
+
+        info!("Selected namespaces: {}", selected.join(", "));
+
+        self.selected
+            .lock()
+            .expect("failed to lock selected namespaces")
+            .extend(selected.iter().cloned());
+
+        Ok(selected)
+    }
+
+    /// All namespaces that were selected so far.
+    pub fn selected(&self) -> BTreeSet<String> {
+        self.selected
+            .lock()
+            .expect("failed to lock selected namespaces")
+            .clone()
+    }
+}
+
+impl std::fmt::Debug for SelectedNamespaces<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SelectedNamespaces")
+            .field("selector", &self.selector)
+            .field("selected", &self.selected)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Source for SelectedNamespaces<'_> {
+    async fn pods(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.pods(selected, false).await
+    }
+
+    fn pod_owner(&self, pod: &Pod) -> Option<Owner> {
+        self.inner.pod_owner(pod)
+    }
+
+    fn owner_chain(&self, pod: &Pod) -> Vec<Owner> {
+        self.inner.owner_chain(pod)
+    }
+
+    fn checks_container(&self, name: &str) -> bool {
+        self.inner.checks_container(name)
+    }
+
+    async fn pod_resource_usage(&self, namespace: &str, pod: &str) -> Result<Option<PodMetrics>> {
+        self.inner.pod_resource_usage(namespace, pod).await
+    }
+
+    async fn all_pod_resource_usage(&self) -> Result<HashMap<(String, String), PodMetrics>> {
+        self.inner.all_pod_resource_usage().await
+    }
+
+    async fn nodes(&self) -> Result<Vec<Node>> {
+        self.inner.nodes().await
+    }
+
+    async fn server_version(&self) -> Result<String> {
+        self.inner.server_version().await
+    }
+
+    async fn namespaces(&self) -> Result<Vec<String>> {
+        let namespaces = self.inner.namespaces().await?;
+
+        Ok(namespaces
+            .into_iter()
+            .filter(|namespace| self.selector.matches(namespace))
+            .collect())
+    }
+
+    async fn namespace_list(&self) -> Result<Vec<Namespace>> {
+        let namespaces = self.inner.namespace_list().await?;
+
+        Ok(namespaces
+            .into_iter()
+            .filter(|namespace| {
+                namespace
+                    .metadata
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| self.selector.matches(name))
+            })
+            .collect())
+    }
+
+    async fn node_events(&self) -> Result<Vec<Event>> {
+        self.inner.node_events().await
+    }
+
+    async fn statefulsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<StatefulSet>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.statefulsets(selected, false).await
+    }
+
+    async fn daemonsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<DaemonSet>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.daemonsets(selected, false).await
+    }
+
+    async fn jobs(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Job>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.jobs(selected, false).await
+    }
+
+    async fn ingresses(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Ingress>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.ingresses(selected, false).await
+    }
+
+    async fn cronjobs(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<CronJob>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.cronjobs(selected, false).await
+    }
+
+    async fn services(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Service>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.services(selected, false).await
+    }
+
+    async fn secrets(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Secret>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.secrets(selected, false).await
+    }
+
+    async fn service_accounts(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ServiceAccount>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.service_accounts(selected, false).await
+    }
+
+    async fn persistent_volume_claims(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<PersistentVolumeClaim>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.persistent_volume_claims(selected, false).await
+    }
+
+    async fn endpoint_slices(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<EndpointSlice>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.endpoint_slices(selected, false).await
+    }
+
+    async fn config_maps(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ConfigMap>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.config_maps(selected, false).await
+    }
+
+    async fn role_bindings(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<RoleBinding>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.role_bindings(selected, false).await
+    }
+
+    async fn cluster_roles(&self) -> Result<Vec<ClusterRole>> {
+        self.inner.cluster_roles().await
+    }
+
+    async fn cluster_role_bindings(&self) -> Result<Vec<ClusterRoleBinding>> {
+        self.inner.cluster_role_bindings().await
+    }
+
+    async fn vertical_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<VerticalPodAutoscaler>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.vertical_pod_autoscalers(selected, false).await
+    }
+
+    async fn horizontal_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<HorizontalPodAutoscaler>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.horizontal_pod_autoscalers(selected, false).await
+    }
+
+    async fn deployments(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Deployment>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.deployments(selected, false).await
+    }
+
+    async fn replica_sets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ReplicaSet>> {
+        let selected = self.select(namespaces, all_namespaces).await?;
+
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.inner.replica_sets(selected, false).await
+    }
+}
+
+/// Wraps another source and only checks the containers whose name matches a
+/// regex. Pods without a matching container are skipped, the spec of the other
+/// pods is kept whole so checks can still compare their containers.
+pub struct FilteredContainers<'a> {
+    inner: &'a dyn Source,
+    regex: &'a Regex,
+}
+
+impl<'a> FilteredContainers<'a> {
+    /// Filter the containers of the pods of the given source.
+    pub fn new(inner: &'a dyn Source, regex: &'a Regex) -> Self {
+        Self { inner, regex }
+    }
+}
+
+impl std::fmt::Debug for FilteredContainers<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilteredContainers")
+            .field("regex", &self.regex)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Source for FilteredContainers<'_> {
+    async fn pods(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Pod>> {
+        let pods = self.inner.pods(namespaces, all_namespaces).await?;
+
+        Ok(pods
+            .into_iter()
+            .filter(|pod| {
+                pod.spec.iter().any(|spec| {
+                    spec.init_containers
+                        .iter()
+                        .flatten()
+                        .chain(&spec.containers)
+                        .any(|container| self.checks_container(&container.name))
+                })
+            })
+            .collect())
+    }
+
+    fn pod_owner(&self, pod: &Pod) -> Option<Owner> {
+        self.inner.pod_owner(pod)
+    }
+
+    fn owner_chain(&self, pod: &Pod) -> Vec<Owner> {
+        self.inner.owner_chain(pod)
+    }
+
+    fn checks_container(&self, name: &str) -> bool {
+        self.regex.is_match(name) && self.inner.checks_container(name)
+    }
+
+    async fn pod_resource_usage(&self, namespace: &str, pod: &str) -> Result<Option<PodMetrics>> {
+        self.inner.pod_resource_usage(namespace, pod).await
+    }
+
+    async fn all_pod_resource_usage(&self) -> Result<HashMap<(String, String), PodMetrics>> {
+        self.inner.all_pod_resource_usage().await
+    }
+
+    async fn nodes(&self) -> Result<Vec<Node>> {
+        self.inner.nodes().await
+    }
+
+    async fn server_version(&self) -> Result<String> {
+        self.inner.server_version().await
+    }
+
+    async fn namespaces(&self) -> Result<Vec<String>> {
+        self.inner.namespaces().await
+    }
+
+    async fn namespace_list(&self) -> Result<Vec<Namespace>> {
+        self.inner.namespace_list().await
+    }
+
+    async fn node_events(&self) -> Result<Vec<Event>> {
+        self.inner.node_events().await
+    }
+
+    async fn statefulsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<StatefulSet>> {
+        self.inner.statefulsets(namespaces, all_namespaces).await
+    }
+
+    async fn daemonsets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<DaemonSet>> {
+        self.inner.daemonsets(namespaces, all_namespaces).await
+    }
+
+    async fn jobs(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Job>> {
+        self.inner.jobs(namespaces, all_namespaces).await
+    }
+
+    async fn ingresses(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Ingress>> {
+        self.inner.ingresses(namespaces, all_namespaces).await
+    }
+
+    async fn cronjobs(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<CronJob>> {
+        self.inner.cronjobs(namespaces, all_namespaces).await
+    }
+
+    async fn services(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Service>> {
+        self.inner.services(namespaces, all_namespaces).await
+    }
+
+    async fn secrets(&self, namespaces: Vec<String>, all_namespaces: bool) -> Result<Vec<Secret>> {
+        self.inner.secrets(namespaces, all_namespaces).await
+    }
+
+    async fn service_accounts(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ServiceAccount>> {
+        self.inner
+            .service_accounts(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn persistent_volume_claims(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<PersistentVolumeClaim>> {
+        self.inner
+            .persistent_volume_claims(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn endpoint_slices(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<EndpointSlice>> {
+        self.inner.endpoint_slices(namespaces, all_namespaces).await
+    }
+
+    async fn config_maps(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ConfigMap>> {
+        self.inner.config_maps(namespaces, all_namespaces).await
+    }
+
+    async fn role_bindings(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<RoleBinding>> {
+        self.inner.role_bindings(namespaces, all_namespaces).await
+    }
+
+    async fn cluster_roles(&self) -> Result<Vec<ClusterRole>> {
+        self.inner.cluster_roles().await
+    }
+
+    async fn cluster_role_bindings(&self) -> Result<Vec<ClusterRoleBinding>> {
+        self.inner.cluster_role_bindings().await
+    }
+
+    async fn vertical_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<VerticalPodAutoscaler>> {
+        self.inner
+            .vertical_pod_autoscalers(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn horizontal_pod_autoscalers(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<HorizontalPodAutoscaler>> {
+        self.inner
+            .horizontal_pod_autoscalers(namespaces, all_namespaces)
+            .await
+    }
+
+    async fn deployments(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<Deployment>> {
+        self.inner.deployments(namespaces, all_namespaces).await
+    }
+
+    async fn replica_sets(
+        &self,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+    ) -> Result<Vec<ReplicaSet>> {
+        self.inner.replica_sets(namespaces, all_namespaces).await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use regex::Regex;
+
+    use super::{
+        FileSource, FilteredContainers, NamespaceSelector, RecordingSource, SelectedNamespaces,
+        Source, WithoutOwners,
+    };
+    use crate::api::Owner;
+
+    const PODS: &str = include_str!("../resources/fixtures/pods.json");
+    const METRICS: &str = include_str!("../resources/fixtures/metrics.json");
+
+    fn source() -> FileSource {
+        FileSource::from_readers(PODS.as_bytes(), Some(METRICS.as_bytes())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn pods_namespaces() {
+        let source = source();
+
+        assert_eq!(3, source.pods(Vec::new(), true).await.unwrap().len());
+        assert_eq!(3, source.pods(Vec::new(), false).await.unwrap().len());
+
+        let pods = source.pods(vec!["web".to_string()], false).await.unwrap();
+        assert_eq!(2, pods.len());
+    }
+
+    #[tokio::test]
+    async fn pod_owner() {
+        let source = source();
+        let pods = source.pods(Vec::new(), true).await.unwrap();
+
+        let owners = pods
+            .iter()
+            .map(|pod| source.pod_owner(pod))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                Some(Owner {
+                    name: "frontend-7d9c8b7f5".to_string(),
+                    kind: "ReplicaSet".to_string(),
+                    uid: "0b6b7a3e-5d0e-4f4a-9d3c-1f0e2a3b4c5d".to_string(),
+                    namespace: "web".to_string(),
+                }),
+                None,
+                None,
+            ],
+            owners
+        );
+    }
+
+    #[tokio::test]
+    async fn pod_resource_usage() {
+        let source = source();
+
+        let usage = source
+            .pod_resource_usage("web", "frontend-7d9c8b7f5-abcde")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!("frontend", usage.containers[0].name);
+
+        assert!(source
+            .pod_resource_usage("web", "missing")
+            .await
+            .unwrap()
+            .is_none());
+
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+
+        assert!(source
+            .pod_resource_usage("web", "frontend-7d9c8b7f5-abcde")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn all_pod_resource_usage() {
+        let usage = source().all_pod_resource_usage().await.unwrap();
+
+        let key = ("web".to_string(), "frontend-7d9c8b7f5-abcde".to_string());
+        assert_eq!("frontend", usage[&key].containers[0].name);
+
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+
+        assert!(source.all_pod_resource_usage().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn recording_source() {
+        let source = FileSource::from_readers(PODS.as_bytes(), None::<&[u8]>).unwrap();
+        let recording = RecordingSource::new(&source);
+
+        recording
+            .pods(vec!["web".to_string()], false)
+            .await
+            .unwrap();
+        recording
+            .pod_resource_usage("web", "frontend-7d9c8b7f5-abcde")
+            .await
+            .unwrap();
+
+        let examined = recording.examined();
+
+        assert_eq!(2, examined.pods.len());
+        assert_eq!(1, examined.namespaces());
+        assert!(examined.metrics_requested);
+        assert!(!examined.metrics_found);
+        assert_eq!(1, examined.notes().len());
+    }
+
+    #[tokio::test]
+    async fn without_owners() {
+        let source = source();
+        let without_owners = WithoutOwners::new(&source);
+
+        let pods = without_owners.pods(Vec::new(), true).await.unwrap();
+        assert_eq!(3, pods.len());
+
+        assert!(pods
+            .iter()
+            .all(|pod| without_owners.pod_owner(pod).is_none()
+                && without_owners.owner_chain(pod).is_empty()));
+    }
+
+    #[test]
+    fn namespace_selector() {
+        let selector = NamespaceSelector::new(
+            Some(Regex::new("^(kube|web)").unwrap()),
+            &["kube-*".to_string(), "web-?".to_string()],
+        );
+
+        assert!(selector.matches("web"));
+        assert!(selector.matches("web-12"));
+        assert!(!selector.matches("web-1"));
+        assert!(!selector.matches("kube-system"));
+        assert!(!selector.matches("batch"));
+
+        assert!(NamespaceSelector::default().is_empty());
+        assert!(NamespaceSelector::default().matches("anything"));
+    }
+
+    #[tokio::test]
+    async fn selected_namespaces() {
+        let source = source();
+        let selector = NamespaceSelector::new(None, &["b*".to_string()]);
+        let selected = SelectedNamespaces::new(&source, &selector);
+
+        let pods = selected.pods(Vec::new(), true).await.unwrap();
+
+        assert_eq!(2, pods.len());
+        assert_eq!(
+            vec!["web".to_string()],
+            selected.selected().into_iter().collect::<Vec<_>>()
+        );
+
+        let pods = selected
+            .pods(vec!["batch".to_string()], false)
+            .await
+            .unwrap();
+        assert!(pods.is_empty());
+    }
+
+    #[tokio::test]
+    async fn filtered_containers() {
+        let source = source();
+        let regex = Regex::new("^(frontend|api)$").unwrap();
+        let filtered = FilteredContainers::new(&source, &regex);
+
+        let pods = filtered.pods(Vec::new(), true).await.unwrap();
+
+        let containers = pods
+            .iter()
+            .map(|pod| {
+                (
+                    pod.metadata.name.as_deref().unwrap(),
+                    pod.spec
+                        .as_ref()
+                        .unwrap()
+                        .containers
+                        .iter()
+                        .map(|container| container.name.as_str())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // The sidecar is kept in the spec but not checked.
+        assert_eq!(
+            vec![
+                ("frontend-7d9c8b7f5-abcde", vec!["frontend", "sidecar"]),
+                ("api-0", vec!["api"]),
+            ],
+            containers
+        );
+
+        assert!(filtered.checks_container("frontend"));
+        assert!(!filtered.checks_container("sidecar"));
+        assert!(source.checks_container("sidecar"));
+    }
+}
@@ -0,0 +1,516 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+    sync::Mutex,
+    thread,
+    time::Instant,
+};
+
+use eyre::{bail, Result};
+use futures::future::LocalBoxFuture;
+use k8s_openapi::chrono::{DateTime, Utc};
+use log::{info, warn};
+use regex::Regex;
+
+use k8s_tools::{
+    api::{current_context, missing_apis, Impersonation},
+    cache::{Cache, CacheOptions, CachedSource},
+    commands::{ClusterReport, ClusterReports, Report},
+    document::{Meta, NamespaceError},
+    source::{
+        ClusterSource, Examined, FileSource, FilteredContainers, NamespaceSelector,
+        RecordingSource, SelectedNamespaces, Source, WithoutOwners,
+    },
+};
+
+use crate::output::{self, OutputOptions};
+
+/// What the commands are run against and how the sources are read.
+#[derive(Debug)]
+pub(crate) struct Target {
+    pub(crate) kind: TargetKind,
+
+    /// Skip resolving the owners of pods.
+    pub(crate) no_owner: bool,
+
+    /// Narrows down the namespaces the pods are read from.
+    pub(crate) namespaces: NamespaceSelector,
+
+    /// Only keep the containers with a matching name.
+    pub(crate) containers: Option<Regex>,
+
+    /// Fail the run if some namespaces could not be read.
+    pub(crate) strict: bool,
+
+    /// Read pods, owners and metrics of clusters through the on-disk cache.
+    pub(crate) cache: Option<CacheOptions>,
+
+    /// User and groups all requests to clusters are sent as.
+    pub(crate) impersonation: Impersonation,
+}
+
+impl Target {
+    /// Impersonation recorded in the metadata of the output, `None` if the
+    /// requests are not impersonated.
+    fn impersonation(&self) -> Option<Impersonation> {
+        (!self.impersonation.is_empty()).then(|| self.impersonation.clone())
+    }
+}
+
+/// What the commands are run against.
+#[derive(Debug)]
+pub(crate) enum TargetKind {
+    /// Pods and metrics read from files.
+    File(FileSource),
+
+    /// A single cluster, the current context is used if no context is given.
+    Cluster(Option<String>),
+
+    /// Multiple clusters, the reports are nested by context name.
+    Clusters {
+        contexts: Vec<String>,
+        parallel: NonZeroUsize,
+    },
+}
+
+/// Start of a run used to fill in the metadata of the output document.
+struct Run<'a> {
+    check: &'a str,
+    started_at: DateTime<Utc>,
+    started: Instant,
+}
+
+impl<'a> Run<'a> {
+    fn start(check: &'a str) -> Self {
+        Self {
+            check,
+            started_at: Utc::now(),
+            started: Instant::now(),
+        }
+    }
+
+    fn meta(
+        &self,
+        context: Option<String>,
+        server: Option<String>,
+        namespaces_examined: usize,
+        pods_examined: usize,
+        selected_namespaces: Option<Vec<String>>,
+        notes: Vec<String>,
+    ) -> Meta {
+        Meta {
+            tool_version: Meta::TOOL_VERSION.to_string(),
+            command: self.check.to_string(),
+            args: std::env::args().skip(1).collect(),
+            context,
+            server,
+            server_version: None,
+            impersonation: None,
+            cached_at: None,
+            timestamp: self.started_at.to_rfc3339(),
+            duration_seconds: self.started.elapsed().as_secs_f64(),
+            namespaces_examined,
+            pods_examined,
+            selected_namespaces,
+            notes,
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// Run the command against the target and emit its report.
+#[allow(clippy::too_many_lines)]
+pub(crate) async fn run<R, F>(
+    check: &str,
+    target: &Target,
+    options: &OutputOptions<'_>,
+    command: F,
+) -> Result<()>
+where
+    R: Report + Send,
+    F: for<'a> Fn(&'a dyn Source) -> LocalBoxFuture<'a, Result<R>> + Sync,
+{
+    let run = Run::start(check);
+
+    match &target.kind {
+        TargetKind::File(source) => {
+            let (report, examined) = run_recorded(source, target, &command).await?;
+
+            let mut notes = vec!["pods were read from a file instead of a cluster".to_string()];
+            notes.extend(examined.notes());
+            notes.extend(report.notes());
+            notes.extend(owner_notes(target.no_owner));
+
+            let meta = run.meta(
+                None,
+                None,
+                examined.namespaces(),
+                examined.pods.len(),
+                selected_namespaces(&examined),
+                notes,
+            );
+
+            output::emit(meta, report, options).await
+        }
+
+        TargetKind::Cluster(context) => {
+            let source = ClusterSource::new(context.as_deref(), &target.impersonation).await?;
+            let preflight = preflight(check, &source).await;
+            let (report, examined) =
+                run_on_cluster(&source, context.as_deref(), target, &command).await?;
+
+            let mut notes = preflight.notes;
+            notes.extend(examined.notes());
+            notes.extend(report.notes());
+            notes.extend(owner_notes(target.no_owner));
+
+            let mut meta = run.meta(
+                context.clone().or_else(current_context),
+                Some(source.server().to_string()),
+                examined.namespaces(),
+                examined.pods.len(),
+                selected_namespaces(&examined),
+                notes,
+            );
+            meta.server_version = preflight.version;
+            meta.impersonation = target.impersonation();
+            meta.cached_at = examined.cached_at;
+            meta.errors = examined.namespace_errors;
+
+            let errors = meta.errors.len();
+            output::emit(meta, report, options).await?;
+
+            fail_if_strict(target, errors)
+        }
+
+        TargetKind::Clusters { contexts, parallel } => {
+            let results = run_clusters(check, contexts, *parallel, target, &command);
+
+            let mut namespaces_examined = 0;
+            let mut pods_examined = 0;
+            let mut selected: Option<BTreeSet<String>> = None;
+            let mut notes = owner_notes(target.no_owner);
+            let mut errors = Vec::new();
+            let mut cached_at: Option<String> = None;
+            let mut reports = BTreeMap::new();
+
+            for (context, result) in results {
+                let report = match result {
+                    Ok((report, examined, preflight)) => {
+                        namespaces_examined += examined.namespaces();
+                        pods_examined += examined.pods.len();
+
+                        if let Some(namespaces) = &examined.selected_namespaces {
+                            selected
+                                .get_or_insert_with(BTreeSet::new)
+                                .extend(namespaces.iter().cloned());
+                        }
+
+                        notes.extend(
+                            preflight
+                                .notes
+                                .into_iter()
+                                .chain(examined.notes())
+                                .chain(report.notes())
+                                .map(|note| format!("{context}: {note}")),
+                        );
+
+                        cached_at = match (cached_at, examined.cached_at) {
+                            (Some(a), Some(b)) => Some(a.min(b)),
+                            (a, b) => a.or(b),
+                        };
+
+                        errors.extend(examined.namespace_errors.into_iter().map(|error| {
+                            NamespaceError {
+                                context: Some(context.clone()),
+                                ..error
+                            }
+                        }));
+
+                        ClusterReport {
+                            report: Some(report),
+                            server_version: preflight.version,
+                            errors: Vec::new(),
+                        }
+                    }
+
+                    Err(err) => ClusterReport {
+                        report: None,
+                        server_version: None,
+                        errors: vec![format!("{err:#}")],
+                    },
+                };
+
+                reports.insert(context, report);
+            }
+
+            let mut meta = run.meta(
+                Some(contexts.join(",")),
+                None,
+                namespaces_examined,
+                pods_examined,
+                selected.map(|namespaces| namespaces.into_iter().collect()),
+                notes,
+            );
+            meta.impersonation = target.impersonation();
+            meta.cached_at = cached_at;
+            meta.errors = errors;
+
+            let errors = meta.errors.len();
+            output::emit(meta, ClusterReports(reports), options).await?;
+
+            fail_if_strict(target, errors)
+        }
+    }
+}
+
+/// Report of a single cluster with what was examined to create it.
+type ClusterResult<R> = Result<(R, Examined, Preflight)>;
+
+/// Run the command against every context, `parallel` contexts at a time. The
+/// results are in the order of the contexts.
+///
+/// This is a workaround: `Source::pod_owner` and `Source::owner_chain` are
+/// synchronous and block the thread they run on until the owners are fetched,
+/// so polling all contexts from one task would check them one after another.
+/// Each context therefore gets its own thread, which means
+/// `--parallel-clusters` threads blocked on owner lookups at the same time.
+/// Once owner resolution is async this can go back to polling the contexts
+/// concurrently.
+fn run_clusters<R, F>(
+    check: &str,
+    contexts: &[String],
+    parallel: NonZeroUsize,
+    target: &Target,
+    command: &F,
+) -> Vec<(String, ClusterResult<R>)>
+where
+    R: Send,
+    F: for<'a> Fn(&'a dyn Source) -> LocalBoxFuture<'a, Result<R>> + Sync,
+{
+    let handle = tokio::runtime::Handle::current();
+    let pending = Mutex::new(contexts.iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(contexts.len()));
+
+    tokio::task::block_in_place(|| {
+        thread::scope(|scope| {
+            for _ in 0..parallel.get().min(contexts.len()) {
+                scope.spawn(|| loop {
+                    let Some((index, context)) =
+                        pending.lock().expect("failed to lock contexts").next()
+                    else {
+                        break;
+                    };
+
+                    info!("Running {check} against context {context}");
+
+                    let result = handle.block_on(run_cluster(check, context, target, command));
+
+                    if let Err(err) = &result {
+                        warn!("Failed to run {check} against context {context}: {err:?}");
+                    }
+
+                    results.lock().expect("failed to lock results").push((
+                        index,
+                        context.clone(),
+                        result,
+                    ));
+                });
+            }
+        });
+    });
+
+    let mut results = results.into_inner().expect("failed to lock results");
+    results.sort_by_key(|(index, _, _)| *index);
+
+    results
+        .into_iter()
+        .map(|(_, context, result)| (context, result))
+        .collect()
+}
+
+async fn run_cluster<R, F>(
+    check: &str,
+    context: &str,
+    target: &Target,
+    command: &F,
+) -> ClusterResult<R>
+where
+    F: for<'a> Fn(&'a dyn Source) -> LocalBoxFuture<'a, Result<R>>,
+{
+    let source = ClusterSource::new(Some(context), &target.impersonation).await?;
+    let preflight = preflight(check, &source).await;
+    let (report, examined) = run_on_cluster(&source, Some(context), target, command).await?;
+
+    Ok((report, examined, preflight))
+}
+
+/// Run the command against a cluster, through the on-disk cache if it is
+/// enabled.
+async fn run_on_cluster<R, F>(
+    source: &ClusterSource,
+    context: Option<&str>,
+    target: &Target,
+    command: &F,
+) -> Result<(R, Examined)>
+where
+    F: for<'a> Fn(&'a dyn Source) -> LocalBoxFuture<'a, Result<R>>,
+{
+    let cached = match &target.cache {
+        Some(options) => {
+            let mut context = context
+                .map(ToString::to_string)
+                .or_else(current_context)
+                .unwrap_or_else(|| "in-cluster".to_string());
+
+            // Impersonated users see different objects, their responses are
+            // cached separately.
+            if !target.impersonation.is_empty() {
+                let impersonation = target
+                    .impersonation
+                    .user
+                    .iter()
+                    .chain(&target.impersonation.groups)
+                    .map(String::as_str)
+                    .collect::<Vec<_>>();
+
+                context = format!("{context}-as-{}", impersonation.join(","));
+            }
+
+            Some(CachedSource::new(source, Cache::new(options, &context)?))
+        }
+
+        None => None,
+    };
+
+    let recorded: &dyn Source = match &cached {
+        Some(cached) => cached,
+        None => source,
+    };
+
+    let (report, mut examined) = run_recorded(recorded, target, command).await?;
+    examined.namespace_errors = source.namespace_errors();
+    examined.cached_at = cached.and_then(|cached| cached.fetched_at());
+
+    Ok((report, examined))
+}
+
+/// What is known about the api server before the check runs.
+#[derive(Debug, Default)]
+struct Preflight {
+    version: Option<String>,
+
+    /// Warnings about apis the check needs that are not served.
+    notes: Vec<String>,
+}
+
+/// Look up the version and the served apis of the api server so missing apis
+/// are reported up front instead of failing halfway through the check. The
+/// check still runs if the api server can not be queried, for example when
+/// discovery is not allowed.
+async fn preflight(check: &str, source: &ClusterSource) -> Preflight {
+    match source.server_info().await {
+        Ok(server) => {
+            let notes = missing_apis(check, &server);
+
+            for note in &notes {
+                warn!("{note}");
+            }
+
+            Preflight {
+                version: Some(server.version),
+                notes,
+            }
+        }
+
+        Err(err) => {
+            warn!("Failed to query the api server before running {check}: {err:?}");
+            Preflight::default()
+        }
+    }
+}
+
+/// Version of the api server of the target, `None` for files.
+pub(crate) async fn server_version(target: &Target) -> Result<Option<String>> {
+    let context = match &target.kind {
+        TargetKind::File(_) => return Ok(None),
+        TargetKind::Cluster(context) => context.clone(),
+        TargetKind::Clusters { contexts, .. } => contexts.first().cloned(),
+    };
+
+    let source = ClusterSource::new(context.as_deref(), &target.impersonation).await?;
+    let server = source.server_info().await?;
+
+    Ok(Some(server.version))
+}
+
+/// Run the command while recording what it reads from the source.
+async fn run_recorded<R, F>(
+    source: &dyn Source,
+    target: &Target,
+    command: &F,
+) -> Result<(R, Examined)>
+where
+    F: for<'a> Fn(&'a dyn Source) -> LocalBoxFuture<'a, Result<R>>,
+{
+    let without_owners = WithoutOwners::new(source);
+
+    let source: &dyn Source = if target.no_owner {
+        &without_owners
+    } else {
+        source
+    };
+
+    let selected = SelectedNamespaces::new(source, &target.namespaces);
+
+    let source: &dyn Source = if target.namespaces.is_empty() {
+        source
+    } else {
+        &selected
+    };
+
+    let filtered = target
+        .containers
+        .as_ref()
+        .map(|regex| FilteredContainers::new(source, regex));
+
+    let source: &dyn Source = match &filtered {
+        Some(filtered) => filtered,
+        None => source,
+    };
+
+    let recording = RecordingSource::new(source);
+    let report = command(&recording).await?;
+
+    let mut examined = recording.examined();
+    if !target.namespaces.is_empty() {
+        examined.selected_namespaces = Some(selected.selected());
+    }
+
+    Ok((report, examined))
+}
+
+fn selected_namespaces(examined: &Examined) -> Option<Vec<String>> {
+    examined
+        .selected_namespaces
+        .as_ref()
+        .map(|namespaces| namespaces.iter().cloned().collect())
+}
+
+fn owner_notes(no_owner: bool) -> Vec<String> {
+    if no_owner {
+        vec!["owner resolution was skipped, owners are missing".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+/// With `--strict` a run that skipped namespaces fails after its report was
+/// printed.
+fn fail_if_strict(target: &Target, errors: usize) -> Result<()> {
+    if target.strict && errors > 0 {
+        bail!("{errors} namespaces could not be read");
+    }
+
+    Ok(())
+}
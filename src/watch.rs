@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use k8s_tools::commands::Report;
+
+/// Findings seen by the previous runs of a check when running with
+/// `--watch-interval`.
+#[derive(Debug, Default)]
+pub(crate) struct Watch {
+    seen: Mutex<BTreeMap<String, Value>>,
+}
+
+/// Changes of the findings since the previous run.
+#[derive(Debug, Serialize)]
+pub(crate) struct Changes<R> {
+    /// Findings that were not part of the previous run.
+    pub(crate) appeared: R,
+
+    /// Findings of the previous run that are gone.
+    pub(crate) disappeared: Vec<Value>,
+}
+
+impl<R: Report> Changes<R> {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.appeared.findings().is_empty() && self.disappeared.is_empty()
+    }
+}
+
+impl Watch {
+    /// Remove all findings from the report that were already seen by the
+    /// previous run and collect the findings that disappeared since then.
+    pub(crate) fn changes<R: Report>(&self, check: &str, mut report: R) -> Changes<R> {
+        let mut seen = self.seen.lock().expect("failed to lock seen findings");
+        let mut current = BTreeMap::new();
+
+        report.retain_findings(&mut |finding| {
+            let value = serde_json::to_value(&*finding).expect("failed to serialize finding");
+            let key = finding_key(check, &value);
+            let new = !seen.contains_key(&key);
+
+            current.insert(key, value);
+            new
+        });
+
+        let previous = std::mem::replace(&mut *seen, current);
+
+        let disappeared = previous
+            .into_iter()
+            .filter(|(key, _)| !seen.contains_key(key))
+            .map(|(_, value)| value)
+            .collect();
+
+        Changes {
+            appeared: report,
+            disappeared,
+        }
+    }
+}
+
+/// Findings are identified by the check, their namespace and the names of the
+/// objects they refer to (`pod_name`, `container_name` and so on) so changing
+/// values like the resource usage do not count as a new finding.
+fn finding_key(check: &str, finding: &Value) -> String {
+    let mut key = check.to_string();
+
+    if let Some(fields) = finding.as_object() {
+        for (field, value) in fields {
+            if field == "namespace" || field.ends_with("_name") {
+                key.push_str(&format!("/{field}={value}"));
+            }
+        }
+    }
+
+    key
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use serde::Serialize;
+
+    use super::Watch;
+    use k8s_tools::commands::{Finding, Severity};
+
+    #[derive(Debug, Serialize)]
+    struct TestFinding {
+        namespace: String,
+        pod_name: String,
+        container_name: String,
+        restarts: usize,
+        severity: Severity,
+    }
+
+    impl Finding for TestFinding {
+        const DEFAULT_SEVERITY: Severity = Severity::Medium;
+
+        fn namespace(&self) -> &str {
+            &self.namespace
+        }
+
+        fn severity(&self) -> Severity {
+            self.severity
+        }
+
+        fn set_severity(&mut self, severity: Severity) {
+            self.severity = severity;
+        }
+    }
+
+    fn finding(pod_name: &str, container_name: &str, restarts: usize) -> TestFinding {
+        TestFinding {
+            namespace: "test".to_string(),
+            pod_name: pod_name.to_string(),
+            container_name: container_name.to_string(),
+            restarts,
+            severity: Severity::Medium,
+        }
+    }
+
+    #[test]
+    fn changes() {
+        let watch = Watch::default();
+
+        let changes = watch.changes(
+            "check",
+            vec![finding("a", "app", 0), finding("b", "app", 0)],
+        );
+        assert_eq!(2, changes.appeared.len());
+        assert!(changes.disappeared.is_empty());
+
+        // Other values than the names do not make a finding new.
+        let changes = watch.changes(
+            "check",
+            vec![finding("a", "app", 3), finding("b", "app", 0)],
+        );
+        assert!(changes.is_empty());
+
+        let changes = watch.changes(
+            "check",
+            vec![finding("a", "app", 3), finding("a", "sidecar", 0)],
+        );
+        assert_eq!(
+            vec!["sidecar"],
+            changes
+                .appeared
+                .iter()
+                .map(|finding| finding.container_name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, changes.disappeared.len());
+        assert_eq!("b", changes.disappeared[0]["pod_name"]);
+
+        // The same finding of another check is a different finding.
+        let changes = watch.changes("other", vec![finding("a", "app", 3)]);
+        assert_eq!(1, changes.appeared.len());
+        assert_eq!(2, changes.disappeared.len());
+    }
+}
@@ -0,0 +1,139 @@
+#![allow(clippy::unwrap_used)]
+
+use k8s_tools::{
+    commands::{
+        missing_health_probes::{missing_health_probes, MissingProbes},
+        readonly_root_filesystem::readonly_root_filesystem,
+        resource_requests::resource_requests,
+        DetailOptions, PodPhase,
+    },
+    source::FileSource,
+};
+use serde_json::json;
+
+const PODS: &str = include_str!("../resources/fixtures/pods.json");
+const METRICS: &str = include_str!("../resources/fixtures/metrics.json");
+
+fn source() -> FileSource {
+    FileSource::from_readers(PODS.as_bytes(), Some(METRICS.as_bytes())).unwrap()
+}
+
+#[tokio::test]
+async fn missing_health_probes_offline() {
+    let report = missing_health_probes(
+        &source(),
+        Vec::new(),
+        true,
+        vec![MissingProbes::Both],
+        DetailOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut report = serde_json::to_value(report).unwrap();
+    let report = report.as_object_mut().unwrap();
+    assert!(report.remove("generated_at").unwrap().is_string());
+    assert_eq!(json!("unknown"), report.remove("k8s_version").unwrap());
+
+    let owner = json!({
+        "name": "frontend-7d9c8b7f5",
+        "kind": "ReplicaSet",
+        "uid": "0b6b7a3e-5d0e-4f4a-9d3c-1f0e2a3b4c5d",
+        "namespace": "web",
+    });
+
+    assert_eq!(
+        json!({
+            "containers": [{
+                "namespace": "web",
+                "pod_name": "frontend-7d9c8b7f5-abcde",
+                "owner": owner,
+                "container_name": "frontend",
+                "liveness_probe": null,
+                "readiness_probe": null,
+                "missing": "both",
+                "ready": null,
+                "severity": "medium",
+                "node": "node-a",
+                "created": null,
+                "age": null,
+            }],
+            "summary": {
+                "namespaces": [
+                    { "namespace": "web", "liveness_only": 0, "readiness_only": 0, "both": 1 },
+                ],
+                "owners": [
+                    { "owner": owner, "liveness_only": 0, "readiness_only": 0, "both": 1 },
+                ],
+            },
+        }),
+        json!(report)
+    );
+}
+
+#[tokio::test]
+async fn readonly_root_filesystem_offline() {
+    let report = readonly_root_filesystem(
+        &source(),
+        vec!["web".to_string()],
+        false,
+        DetailOptions {
+            labels: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let report = serde_json::to_value(report).unwrap();
+    let findings = report["containers"].as_array().unwrap();
+    let containers = findings
+        .iter()
+        .map(|finding| finding["container_name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec!["frontend", "sidecar", "api"], containers);
+    assert_eq!(json!({ "app": "frontend" }), findings[0]["labels"]);
+    assert_eq!(json!("node-a"), findings[0]["node"]);
+    assert_eq!(json!("unknown"), report["k8s_version"]);
+}
+
+#[tokio::test]
+async fn resource_requests_offline() {
+    let report = resource_requests(
+        &source(),
+        Vec::new(),
+        true,
+        None,
+        false,
+        DetailOptions::default(),
+        false,
+        false,
+        false,
+        &[PodPhase::Running],
+        false,
+        None,
+        None,
+        false,
+        None,
+    )
+    .await
+    .unwrap();
+
+    let report = serde_json::to_value(report).unwrap();
+    let pods = report["pods"].as_array().unwrap();
+
+    assert_eq!(1, pods.len());
+
+    let frontend = pods
+        .iter()
+        .find(|pod| pod["container_name"] == "frontend")
+        .unwrap();
+
+    assert_eq!(100, frontend["resources"]["requests"]["cpu_milliseconds"]);
+    assert_eq!(25, frontend["resources"]["usage"]["cpu_milliseconds"]);
+    assert_eq!(
+        json!("ReplicaSet"),
+        report["total"]["owners"][0]["owner"]["kind"]
+    );
+}
@@ -0,0 +1,110 @@
+#![allow(clippy::unwrap_used)]
+
+use jsonschema::JSONSchema;
+use k8s_tools::{
+    api::Impersonation,
+    commands::{
+        missing_health_probes::{missing_health_probes, MissingProbes},
+        readonly_root_filesystem::readonly_root_filesystem,
+        resource_requests::resource_requests,
+        DetailOptions, PodPhase,
+    },
+    document::{Document, Meta, NamespaceError},
+    schema::schemas,
+    source::FileSource,
+};
+use serde::Serialize;
+
+const PODS: &str = include_str!("../resources/fixtures/pods.json");
+const METRICS: &str = include_str!("../resources/fixtures/metrics.json");
+
+fn source() -> FileSource {
+    FileSource::from_readers(PODS.as_bytes(), Some(METRICS.as_bytes())).unwrap()
+}
+
+fn assert_valid(command: &str, report: impl Serialize) {
+    let schema = serde_json::to_value(&schemas()[command]).unwrap();
+    let schema = JSONSchema::compile(&schema).unwrap();
+
+    let meta = Meta {
+        tool_version: Meta::TOOL_VERSION.to_string(),
+        command: command.to_string(),
+        args: vec![command.to_string(), "--all-namespaces".to_string()],
+        context: None,
+        server: None,
+        server_version: Some("v1.29.2".to_string()),
+        impersonation: Some(Impersonation {
+            user: Some("system:serviceaccount:team-a:auditor".to_string()),
+            groups: vec!["team-a".to_string()],
+        }),
+        cached_at: Some("2024-01-01T00:00:00+00:00".to_string()),
+        timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+        duration_seconds: 0.5,
+        namespaces_examined: 2,
+        pods_examined: 3,
+        selected_namespaces: Some(vec!["default".to_string(), "web".to_string()]),
+        notes: Vec::new(),
+        errors: vec![NamespaceError {
+            context: None,
+            namespace: "team-b".to_string(),
+            error: "failed to list pods: forbidden".to_string(),
+        }],
+    };
+
+    let report = serde_json::to_value(Document { meta, report }).unwrap();
+
+    let errors = match schema.validate(&report) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.map(|error| error.to_string()).collect(),
+    };
+
+    assert!(
+        errors.is_empty(),
+        "{command} output does not match its schema: {errors:?}"
+    );
+}
+
+#[tokio::test]
+async fn outputs_match_schema() {
+    let details = DetailOptions {
+        labels: true,
+        ..Default::default()
+    };
+
+    let report = missing_health_probes(
+        &source(),
+        Vec::new(),
+        true,
+        MissingProbes::ALL.to_vec(),
+        details.clone(),
+    )
+    .await
+    .unwrap();
+    assert_valid("missing-health-probes", report);
+
+    let report = readonly_root_filesystem(&source(), Vec::new(), true, details.clone())
+        .await
+        .unwrap();
+    assert_valid("read-only-root-filesystem", report);
+
+    let report = resource_requests(
+        &source(),
+        Vec::new(),
+        true,
+        None,
+        false,
+        details,
+        true,
+        false,
+        false,
+        &[PodPhase::Running],
+        false,
+        None,
+        None,
+        false,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_valid("resource-requests", report);
+}